@@ -1,4 +1,5 @@
-use lib_utils::envs::{get_env_b64u_as_u8s, get_env_parse};
+use lib_utils::b64::b64u_decode;
+use lib_utils::envs::{get_env, get_env_b64u_as_u8s, get_env_parse};
 use std::sync::OnceLock;
 
 pub fn auth_config() -> &'static AuthConfig {
@@ -20,10 +21,20 @@ pub struct AuthConfig {
 	pub TOKEN_KEY: Vec<u8>,
 	pub ACCESS_TOKEN_TTL: i64,
     pub REFRESH_TOKEN_TTL: i64,
+    /// The ttl of the "mfa pending" tokens, in seconds (`MFA_TOKEN_TTL`, 300 by default).
+    pub MFA_TOKEN_TTL: i64,
+
+	// -- Token Keyring (optional, see token::keyring)
+	pub JWT_KEYS: Vec<(String, Vec<u8>)>,
+	pub JWT_PRIMARY_KID: Option<String>,
+	/// Verify the tokens without `kid` with the HS256 key, during a migration to asymmetric keys.
+	pub JWT_HS256_FALLBACK: bool,
 
 	// -- Verification & Reset
     pub RESET_TOKEN_TTL_MIN: i64,
     pub VERIFY_TOKEN_TTL_MIN: i64,
+    /// `LOGIN_LINK_TTL_MIN`, 15 by default.
+    pub LOGIN_LINK_TTL_MIN: i64,
 }
 
 impl AuthConfig {
@@ -36,10 +47,48 @@ impl AuthConfig {
             TOKEN_KEY: get_env_b64u_as_u8s("SERVICE_TOKEN_KEY")?,
             ACCESS_TOKEN_TTL: get_env_parse("ACCESS_TOKEN_TTL")?,
             REFRESH_TOKEN_TTL: get_env_parse("REFRESH_TOKEN_TTL")?,
+            MFA_TOKEN_TTL: get_env_parse_or("MFA_TOKEN_TTL", 300)?,
+
+			// -- Token Keyring
+			JWT_KEYS: get_env_jwt_keys("SERVICE_JWT_KEYS")?,
+			JWT_PRIMARY_KID: get_env("SERVICE_JWT_PRIMARY_KID").ok(),
+			JWT_HS256_FALLBACK: get_env_parse_or("SERVICE_JWT_HS256_FALLBACK", false)?,
 
 			// -- Verification & Reset
             RESET_TOKEN_TTL_MIN: get_env_parse("RESET_TOKEN_TTL_MIN")?,
             VERIFY_TOKEN_TTL_MIN: get_env_parse("VERIFY_TOKEN_TTL_MIN")?,
+            LOGIN_LINK_TTL_MIN: get_env_parse_or("LOGIN_LINK_TTL_MIN", 15)?,
 		})
 	}
-}
\ No newline at end of file
+}
+
+/// Parse the env, or the default if the env is not set.
+fn get_env_parse_or<T: std::str::FromStr>(
+	name: &'static str,
+	default: T,
+) -> lib_utils::envs::Result<T> {
+	match get_env_parse(name) {
+		Err(lib_utils::envs::Error::MissingEnv(_)) => Ok(default),
+		res => res,
+	}
+}
+
+/// Parse the `kid=b64u_der,kid=b64u_der` keys (empty if the env is not set).
+fn get_env_jwt_keys(name: &'static str) -> lib_utils::envs::Result<Vec<(String, Vec<u8>)>> {
+	let Ok(val) = get_env(name) else {
+		return Ok(Vec::new());
+	};
+
+	val.split(',')
+		.map(str::trim)
+		.filter(|entry| !entry.is_empty())
+		.map(|entry| {
+			let (kid, der_b64u) = entry
+				.split_once('=')
+				.ok_or(lib_utils::envs::Error::WrongFormat(name))?;
+			let der = b64u_decode(der_b64u.trim())
+				.map_err(|_| lib_utils::envs::Error::WrongFormat(name))?;
+			Ok((kid.trim().to_string(), der))
+		})
+		.collect()
+}
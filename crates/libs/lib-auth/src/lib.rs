@@ -1,6 +1,9 @@
 mod config;
+pub mod oidc;
+pub mod onetime;
 pub mod pwd;
 pub mod token;
+pub mod totp;
 
 // Re-export for easier access
 pub use config::auth_config;
\ No newline at end of file
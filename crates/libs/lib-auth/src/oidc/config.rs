@@ -0,0 +1,87 @@
+use super::{Error, Result};
+use std::env;
+use std::sync::OnceLock;
+
+/// The OIDC providers config, loaded on first use.
+/// Note: Returns the error (e.g., a missing provider env) rather than panicking,
+///       so a misconfigured provider only fails the OIDC routes.
+pub fn oidc_config() -> Result<&'static OidcConfig> {
+	static INSTANCE: OnceLock<OidcConfig> = OnceLock::new();
+
+	if let Some(config) = INSTANCE.get() {
+		return Ok(config);
+	}
+	let config = OidcConfig::load_from_env()?;
+
+	Ok(INSTANCE.get_or_init(|| config))
+}
+
+#[allow(non_snake_case)]
+pub struct OidcConfig {
+	/// From `SERVICE_OIDC_PROVIDERS` (e.g., "google,apple"), empty if not set.
+	pub PROVIDERS: Vec<OidcProvider>,
+}
+
+/// An OpenID Connect provider (the app being the relying party).
+///
+/// Loaded from the `SERVICE_OIDC_{NAME}_*` envs, for example, for "google":
+/// `SERVICE_OIDC_GOOGLE_ISSUER`, `_CLIENT_ID`, `_CLIENT_SECRET` (optional for public clients),
+/// `_AUTH_URL`, `_TOKEN_URL`, `_JWKS_URL`, `_REDIRECT_URL`, and `_SCOPES` (optional).
+#[derive(Clone, Debug)]
+pub struct OidcProvider {
+	pub name: String,
+	pub issuer: String,
+	pub client_id: String,
+	pub client_secret: Option<String>,
+	pub auth_url: String,
+	pub token_url: String,
+	pub jwks_url: String,
+	pub redirect_url: String,
+	pub scopes: String,
+}
+
+impl OidcConfig {
+	fn load_from_env() -> Result<OidcConfig> {
+		let names = env::var("SERVICE_OIDC_PROVIDERS").unwrap_or_default();
+
+		let providers = names
+			.split(',')
+			.map(str::trim)
+			.filter(|name| !name.is_empty())
+			.map(OidcProvider::load_from_env)
+			.collect::<Result<Vec<_>>>()?;
+
+		Ok(OidcConfig {
+			PROVIDERS: providers,
+		})
+	}
+
+	pub fn provider(&self, name: &str) -> Result<&OidcProvider> {
+		self.PROVIDERS
+			.iter()
+			.find(|p| p.name == name)
+			.ok_or_else(|| Error::ProviderUnknown(name.to_string()))
+	}
+}
+
+impl OidcProvider {
+	fn load_from_env(name: &str) -> Result<OidcProvider> {
+		let prefix = format!("SERVICE_OIDC_{}", name.to_uppercase());
+		let get = |key: &str| {
+			let env_name = format!("{prefix}_{key}");
+			env::var(&env_name).map_err(|_| Error::ConfigMissingEnv(env_name))
+		};
+
+		Ok(OidcProvider {
+			name: name.to_string(),
+			issuer: get("ISSUER")?,
+			client_id: get("CLIENT_ID")?,
+			client_secret: get("CLIENT_SECRET").ok(),
+			auth_url: get("AUTH_URL")?,
+			token_url: get("TOKEN_URL")?,
+			jwks_url: get("JWKS_URL")?,
+			redirect_url: get("REDIRECT_URL")?,
+			scopes: get("SCOPES").unwrap_or_else(|_| "openid email profile".to_string()),
+		})
+	}
+}
@@ -0,0 +1,36 @@
+use serde::Serialize;
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[derive(Debug, Serialize)]
+pub enum Error {
+	// -- Config
+	ConfigMissingEnv(String),
+	ProviderUnknown(String),
+
+	// -- Flow
+	/// Error returned by the provider on the redirect (e.g., "access_denied").
+	ProviderError(String),
+	TokenRequestFail(String),
+	TokenResponseNoIdToken,
+	JwksFetchFail(String),
+
+	// -- ID Token
+	IdTokenAlgNotSupported,
+	IdTokenKidNotFound,
+	IdTokenInvalid(String),
+	IdTokenNonceMismatch,
+}
+
+// region:    --- Error Boilerplate
+impl core::fmt::Display for Error {
+	fn fmt(
+		&self,
+		fmt: &mut core::fmt::Formatter,
+	) -> core::result::Result<(), core::fmt::Error> {
+		write!(fmt, "{self:?}")
+	}
+}
+
+impl std::error::Error for Error {}
+// endregion: --- Error Boilerplate
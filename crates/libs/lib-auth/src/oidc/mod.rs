@@ -0,0 +1,454 @@
+//! OpenID Connect relying party (social login).
+//!
+//! - Authorization code flow with PKCE (S256), `state` and `nonce`.
+//! - ID token validated against the provider JWKS (cached, refreshed on unknown `kid`,
+//!   at most once per `JWKS_REFETCH_MIN_INTERVAL`).
+//!
+//! Note: The flow state (state, nonce, pkce verifier) is persisted by the caller,
+//!       between `authorization_request` and `exchange_code`.
+
+// region:    --- Modules
+
+mod config;
+mod error;
+
+pub use self::config::{oidc_config, OidcConfig, OidcProvider};
+pub use self::error::{Error, Result};
+
+use jsonwebtoken::jwk::{Jwk, JwkSet};
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use lib_utils::b64::b64u_encode;
+use rand::RngCore;
+use reqwest::Url;
+use serde::{Deserialize, Deserializer};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+// endregion: --- Modules
+
+/// Only asymmetric algorithms (the client secret is never used as a verification key).
+const ID_TOKEN_ALGS: &[Algorithm] = &[
+	Algorithm::RS256,
+	Algorithm::RS384,
+	Algorithm::RS512,
+	Algorithm::PS256,
+	Algorithm::ES256,
+	Algorithm::ES384,
+	Algorithm::EdDSA,
+];
+
+const JWKS_CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+/// So that tokens with made up `kid`s cannot make us refetch the provider JWKS on every request.
+const JWKS_REFETCH_MIN_INTERVAL: Duration = Duration::from_secs(60);
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+// region:    --- Authorization Request
+
+/// The provider authorization redirect, with the values to keep until the callback.
+pub struct AuthRequest {
+	pub url: String,
+	pub state: String,
+	pub nonce: String,
+	pub pkce_verifier: String,
+}
+
+pub fn authorization_request(provider: &OidcProvider) -> Result<AuthRequest> {
+	let state = random_b64u();
+	let nonce = random_b64u();
+	let pkce_verifier = random_b64u();
+	let pkce_challenge = b64u_encode(Sha256::digest(pkce_verifier.as_bytes()));
+
+	let mut url = Url::parse(&provider.auth_url)
+		.map_err(|_| Error::ConfigMissingEnv(format!("{} auth url (invalid)", provider.name)))?;
+	url.query_pairs_mut()
+		.append_pair("response_type", "code")
+		.append_pair("client_id", &provider.client_id)
+		.append_pair("redirect_uri", &provider.redirect_url)
+		.append_pair("scope", &provider.scopes)
+		.append_pair("state", &state)
+		.append_pair("nonce", &nonce)
+		.append_pair("code_challenge", &pkce_challenge)
+		.append_pair("code_challenge_method", "S256");
+
+	Ok(AuthRequest {
+		url: url.into(),
+		state,
+		nonce,
+		pkce_verifier,
+	})
+}
+
+// endregion: --- Authorization Request
+
+// region:    --- Code Exchange
+
+/// The validated ID token claims used by the app.
+#[derive(Debug, Clone, Deserialize)]
+pub struct IdTokenClaims {
+	pub iss: String,
+	pub sub: String,
+	#[serde(default)]
+	pub nonce: Option<String>,
+	#[serde(default)]
+	pub email: Option<String>,
+	#[serde(default, deserialize_with = "bool_or_string")]
+	pub email_verified: bool,
+	#[serde(default)]
+	pub name: Option<String>,
+	#[serde(default)]
+	pub preferred_username: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+	id_token: Option<String>,
+}
+
+/// Exchange the authorization code (with the PKCE verifier) for the ID token,
+/// and validate it (signature, issuer, audience, expiration, nonce).
+pub async fn exchange_code(
+	provider: &OidcProvider,
+	code: &str,
+	pkce_verifier: &str,
+	nonce: &str,
+) -> Result<IdTokenClaims> {
+	let mut form = vec![
+		("grant_type", "authorization_code"),
+		("code", code),
+		("redirect_uri", provider.redirect_url.as_str()),
+		("client_id", provider.client_id.as_str()),
+		("code_verifier", pkce_verifier),
+	];
+	if let Some(client_secret) = provider.client_secret.as_deref() {
+		form.push(("client_secret", client_secret));
+	}
+
+	let res = http_client()
+		.post(&provider.token_url)
+		.form(&form)
+		.send()
+		.await
+		.map_err(|ex| Error::TokenRequestFail(ex.to_string()))?;
+
+	let status = res.status();
+	if !status.is_success() {
+		let body = res.text().await.unwrap_or_default();
+		return Err(Error::TokenRequestFail(format!("{status} - {body}")));
+	}
+
+	let token_res: TokenResponse = res
+		.json()
+		.await
+		.map_err(|ex| Error::TokenRequestFail(ex.to_string()))?;
+	let id_token = token_res.id_token.ok_or(Error::TokenResponseNoIdToken)?;
+
+	validate_id_token(provider, &id_token, nonce).await
+}
+
+pub async fn validate_id_token(
+	provider: &OidcProvider,
+	id_token: &str,
+	nonce: &str,
+) -> Result<IdTokenClaims> {
+	let header =
+		decode_header(id_token).map_err(|ex| Error::IdTokenInvalid(ex.to_string()))?;
+	if !ID_TOKEN_ALGS.contains(&header.alg) {
+		return Err(Error::IdTokenAlgNotSupported);
+	}
+
+	let jwk = find_jwk(provider, header.kid.as_deref()).await?;
+	let key = DecodingKey::from_jwk(&jwk).map_err(|ex| Error::IdTokenInvalid(ex.to_string()))?;
+
+	let mut validation = Validation::new(header.alg);
+	validation.set_issuer(&[&provider.issuer]);
+	validation.set_audience(&[&provider.client_id]);
+	validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+
+	let claims = decode::<IdTokenClaims>(id_token, &key, &validation)
+		.map_err(|ex| Error::IdTokenInvalid(ex.to_string()))?
+		.claims;
+
+	if claims.nonce.as_deref() != Some(nonce) {
+		return Err(Error::IdTokenNonceMismatch);
+	}
+
+	Ok(claims)
+}
+
+// endregion: --- Code Exchange
+
+// region:    --- Provider JWKS
+
+fn jwks_cache() -> &'static Mutex<HashMap<String, (Instant, JwkSet)>> {
+	static INSTANCE: OnceLock<Mutex<HashMap<String, (Instant, JwkSet)>>> = OnceLock::new();
+	INSTANCE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+async fn find_jwk(provider: &OidcProvider, kid: Option<&str>) -> Result<Jwk> {
+	// -- From the cache (if still fresh)
+	{
+		let cache = jwks_cache().lock().unwrap_or_else(|err| err.into_inner());
+		if let Some((fetched_at, jwks)) = cache.get(&provider.jwks_url) {
+			if fetched_at.elapsed() < JWKS_CACHE_TTL
+				&& let Some(jwk) = select_jwk(jwks, kid)
+			{
+				return Ok(jwk);
+			}
+			// Unknown kid, but just fetched.
+			if fetched_at.elapsed() < JWKS_REFETCH_MIN_INTERVAL {
+				return Err(Error::IdTokenKidNotFound);
+			}
+		}
+	}
+
+	// -- Refresh (expired, or unknown kid after a provider key rotation)
+	let jwks: JwkSet = http_client()
+		.get(&provider.jwks_url)
+		.send()
+		.await
+		.and_then(|res| res.error_for_status())
+		.map_err(|ex| Error::JwksFetchFail(ex.to_string()))?
+		.json()
+		.await
+		.map_err(|ex| Error::JwksFetchFail(ex.to_string()))?;
+
+	let jwk = select_jwk(&jwks, kid);
+	jwks_cache()
+		.lock()
+		.unwrap_or_else(|err| err.into_inner())
+		.insert(provider.jwks_url.clone(), (Instant::now(), jwks));
+
+	jwk.ok_or(Error::IdTokenKidNotFound)
+}
+
+fn select_jwk(jwks: &JwkSet, kid: Option<&str>) -> Option<Jwk> {
+	match kid {
+		Some(kid) => jwks.find(kid).cloned(),
+		// Note: Without kid, only accepted when there is no ambiguity.
+		None if jwks.keys.len() == 1 => jwks.keys.first().cloned(),
+		None => None,
+	}
+}
+
+// endregion: --- Provider JWKS
+
+// region:    --- Support
+
+fn http_client() -> &'static reqwest::Client {
+	static INSTANCE: OnceLock<reqwest::Client> = OnceLock::new();
+	INSTANCE.get_or_init(|| {
+		reqwest::Client::builder()
+			.timeout(HTTP_TIMEOUT)
+			.build()
+			.unwrap_or_default()
+	})
+}
+
+fn random_b64u() -> String {
+	let mut bytes = [0u8; 32];
+	rand::rng().fill_bytes(&mut bytes);
+	b64u_encode(bytes)
+}
+
+/// Some providers send `email_verified` as a string ("true").
+fn bool_or_string<'de, D: Deserializer<'de>>(deserializer: D) -> core::result::Result<bool, D::Error> {
+	#[derive(Deserialize)]
+	#[serde(untagged)]
+	enum BoolOrString {
+		Bool(bool),
+		String(String),
+	}
+
+	Ok(match BoolOrString::deserialize(deserializer)? {
+		BoolOrString::Bool(val) => val,
+		BoolOrString::String(val) => val.eq_ignore_ascii_case("true"),
+	})
+}
+
+// endregion: --- Support
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	pub type Result<T> = core::result::Result<T, Error>;
+	pub type Error = Box<dyn std::error::Error>; // For tests.
+
+	use super::*;
+	use crate::token::keyring::{generate_ed25519_keypair, Keyring};
+	use axum::routing::{get, post};
+	use axum::{Form, Json, Router};
+	use std::sync::atomic::{AtomicUsize, Ordering};
+	use std::sync::Arc;
+	use jsonwebtoken::{encode, EncodingKey, Header};
+	use serde::Serialize;
+
+	const FX_CLIENT_ID: &str = "mapster-test";
+	const FX_NONCE: &str = "fx-nonce";
+
+	#[derive(Serialize)]
+	struct FxIdTokenClaims {
+		iss: String,
+		sub: &'static str,
+		aud: &'static str,
+		exp: i64,
+		nonce: &'static str,
+		email: &'static str,
+		email_verified: &'static str,
+	}
+
+	#[derive(Deserialize)]
+	struct FxTokenForm {
+		code: String,
+		code_verifier: String,
+	}
+
+	/// Local mock IdP, with the token and jwks endpoints. Returns the provider config.
+	async fn fx_mock_idp() -> Result<OidcProvider> {
+		let keypair = generate_ed25519_keypair()?;
+		let keyring = Keyring::new(
+			b"unused",
+			&[("mock-k1".to_string(), keypair.private_pkcs8.clone())],
+			None,
+			false,
+		)?;
+		let jwks = keyring.jwks();
+
+		let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+		let issuer = format!("http://{}", listener.local_addr()?);
+
+		let token_issuer = issuer.clone();
+		let token_handler = move |Form(form): Form<FxTokenForm>| async move {
+			assert_eq!(form.code, "fx-code");
+			assert!(!form.code_verifier.is_empty());
+
+			let claims = FxIdTokenClaims {
+				iss: token_issuer,
+				sub: "mock-user-1",
+				aud: FX_CLIENT_ID,
+				exp: chrono::Utc::now().timestamp() + 60,
+				nonce: FX_NONCE,
+				email: "mock-user-1@example.com",
+				email_verified: "true",
+			};
+			let mut header = Header::new(Algorithm::EdDSA);
+			header.kid = Some("mock-k1".to_string());
+			let id_token = encode(
+				&header,
+				&claims,
+				&EncodingKey::from_ed_der(&keypair.private_pkcs8),
+			)
+			.unwrap();
+
+			Json(serde_json::json!({"id_token": id_token, "token_type": "Bearer"}))
+		};
+
+		let app = Router::new()
+			.route("/token", post(token_handler))
+			.route("/jwks", get(move || async move { Json(jwks) }));
+		tokio::spawn(async move { axum::serve(listener, app).await });
+
+		Ok(OidcProvider {
+			name: "mock".to_string(),
+			client_id: FX_CLIENT_ID.to_string(),
+			client_secret: None,
+			auth_url: format!("{issuer}/authorize"),
+			token_url: format!("{issuer}/token"),
+			jwks_url: format!("{issuer}/jwks"),
+			redirect_url: "http://localhost:8080/api/oidc/mock/callback".to_string(),
+			scopes: "openid email".to_string(),
+			issuer,
+		})
+	}
+
+	#[test]
+	fn test_authorization_request_pkce_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let fx_provider = OidcProvider {
+			name: "mock".to_string(),
+			issuer: "https://idp.example.com".to_string(),
+			client_id: FX_CLIENT_ID.to_string(),
+			client_secret: None,
+			auth_url: "https://idp.example.com/authorize".to_string(),
+			token_url: "https://idp.example.com/token".to_string(),
+			jwks_url: "https://idp.example.com/jwks".to_string(),
+			redirect_url: "http://localhost:8080/api/oidc/mock/callback".to_string(),
+			scopes: "openid email".to_string(),
+		};
+
+		// -- Exec
+		let auth_req = authorization_request(&fx_provider)?;
+
+		// -- Check
+		let url = Url::parse(&auth_req.url)?;
+		let params: HashMap<_, _> = url.query_pairs().into_owned().collect();
+		let challenge = b64u_encode(Sha256::digest(auth_req.pkce_verifier.as_bytes()));
+		assert_eq!(params.get("code_challenge"), Some(&challenge));
+		assert_eq!(params.get("code_challenge_method").map(String::as_str), Some("S256"));
+		assert_eq!(params.get("state"), Some(&auth_req.state));
+		assert_eq!(params.get("nonce"), Some(&auth_req.nonce));
+
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn test_exchange_code_mock_idp_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let fx_provider = fx_mock_idp().await?;
+
+		// -- Exec
+		let claims = exchange_code(&fx_provider, "fx-code", "fx-verifier", FX_NONCE).await?;
+
+		// -- Check
+		assert_eq!(claims.sub, "mock-user-1");
+		assert_eq!(claims.email.as_deref(), Some("mock-user-1@example.com"));
+		assert!(claims.email_verified);
+
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn test_exchange_code_err_nonce_mismatch() -> Result<()> {
+		// -- Setup & Fixtures
+		let fx_provider = fx_mock_idp().await?;
+
+		// -- Exec
+		let res = exchange_code(&fx_provider, "fx-code", "fx-verifier", "other-nonce").await;
+
+		// -- Check
+		assert!(matches!(res, Err(super::Error::IdTokenNonceMismatch)));
+
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn test_find_jwk_err_kid_unknown_refetch_limited() -> Result<()> {
+		// -- Setup & Fixtures
+		let mut fx_provider = fx_mock_idp().await?;
+		let fx_fetch_count = Arc::new(AtomicUsize::new(0));
+		let fetch_count = fx_fetch_count.clone();
+		let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+		fx_provider.jwks_url = format!("http://{}/jwks", listener.local_addr()?);
+		let app = Router::new().route(
+			"/jwks",
+			get(move || async move {
+				fetch_count.fetch_add(1, Ordering::SeqCst);
+				Json(JwkSet { keys: Vec::new() })
+			}),
+		);
+		tokio::spawn(async move { axum::serve(listener, app).await });
+
+		// -- Exec
+		let res_1 = find_jwk(&fx_provider, Some("unknown-kid-1")).await;
+		let res_2 = find_jwk(&fx_provider, Some("unknown-kid-2")).await;
+
+		// -- Check
+		assert!(matches!(res_1, Err(super::Error::IdTokenKidNotFound)));
+		assert!(matches!(res_2, Err(super::Error::IdTokenKidNotFound)));
+		assert_eq!(fx_fetch_count.load(Ordering::SeqCst), 1);
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
@@ -0,0 +1,132 @@
+//! One-time tokens sent by email (email verification, password reset, login link,
+//! account unlock).
+//!
+//! The clear token is only in the email. The db stores its keyed hash
+//! (HMAC-SHA256 with the token key), so a db read does not give usable tokens.
+//!
+//! The hash is bound to a `TokenPurpose`, so a token of one purpose can never
+//! match the hash of another purpose.
+//! Format: `#h1#_b64u_hmac_` (the prefix tells hashed values from legacy plaintext ones).
+
+// region:    --- Modules
+
+mod error;
+
+pub use self::error::{Error, Result};
+
+use crate::auth_config;
+use hmac::{Hmac, Mac};
+use lib_utils::b64::{b64u_decode, b64u_encode};
+use rand::RngCore;
+use sha2::Sha256;
+
+// endregion: --- Modules
+
+const TOKEN_LEN: usize = 32;
+/// Prefix of the stored hashes (legacy plaintext tokens do not have it).
+pub const HASH_PREFIX: &str = "#h1#";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenPurpose {
+	EmailVerification,
+	PasswordReset,
+	LoginLink,
+	AccountUnlock,
+}
+
+impl TokenPurpose {
+	fn as_str(&self) -> &'static str {
+		match self {
+			TokenPurpose::EmailVerification => "email_verification",
+			TokenPurpose::PasswordReset => "password_reset",
+			TokenPurpose::LoginLink => "login_link",
+			TokenPurpose::AccountUnlock => "account_unlock",
+		}
+	}
+}
+
+/// A new random token (256 bits, b64u encoded), to be sent to the user.
+pub fn generate_token() -> String {
+	let mut bytes = [0u8; TOKEN_LEN];
+	rand::rng().fill_bytes(&mut bytes);
+	b64u_encode(bytes)
+}
+
+/// Keyed hash of a token for a purpose, as stored in the db.
+pub fn hash_token(purpose: TokenPurpose, token: &str) -> Result<String> {
+	let hmac_sha256 = new_hmac(purpose, token)?;
+
+	Ok(format!(
+		"{HASH_PREFIX}{}",
+		b64u_encode(hmac_sha256.finalize().into_bytes())
+	))
+}
+
+/// Verify a token against a stored hash, in constant time.
+pub fn verify_token(purpose: TokenPurpose, token: &str, token_hash: &str) -> Result<()> {
+	let hash_bytes = token_hash
+		.strip_prefix(HASH_PREFIX)
+		.and_then(|b64u| b64u_decode(b64u).ok())
+		.ok_or(Error::TokenHashInvalidFormat)?;
+
+	new_hmac(purpose, token)?
+		.verify_slice(&hash_bytes)
+		.map_err(|_| Error::TokenNotMatching)
+}
+
+fn new_hmac(purpose: TokenPurpose, token: &str) -> Result<Hmac<Sha256>> {
+	let mut hmac_sha256 = Hmac::<Sha256>::new_from_slice(&auth_config().TOKEN_KEY)
+		.map_err(|_| Error::HmacFailNewFromSlice)?;
+	hmac_sha256.update(purpose.as_str().as_bytes());
+	hmac_sha256.update(b":");
+	hmac_sha256.update(token.as_bytes());
+
+	Ok(hmac_sha256)
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	pub type Result<T> = core::result::Result<T, Error>;
+	pub type Error = Box<dyn std::error::Error>; // For tests.
+
+	use super::*;
+
+	#[test]
+	fn test_hash_token_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let fx_token = generate_token();
+		let fx_other_token = generate_token();
+
+		// -- Exec
+		let hash = hash_token(TokenPurpose::PasswordReset, &fx_token)?;
+
+		// -- Check
+		assert!(hash.starts_with(HASH_PREFIX));
+		assert_eq!(hash, hash_token(TokenPurpose::PasswordReset, &fx_token)?);
+		assert_ne!(hash, hash_token(TokenPurpose::PasswordReset, &fx_other_token)?);
+		verify_token(TokenPurpose::PasswordReset, &fx_token, &hash)?;
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_verify_token_err_other_purpose() -> Result<()> {
+		// -- Setup & Fixtures
+		let fx_token = generate_token();
+		let fx_hash = hash_token(TokenPurpose::EmailVerification, &fx_token)?;
+
+		// -- Exec
+		let res = verify_token(TokenPurpose::PasswordReset, &fx_token, &fx_hash);
+
+		// -- Check
+		assert!(matches!(res, Err(super::Error::TokenNotMatching)));
+		assert_ne!(
+			fx_hash,
+			hash_token(TokenPurpose::PasswordReset, &fx_token)?
+		);
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
@@ -15,7 +15,14 @@ pub enum Error {
 	TokenDecodeFailed,
 	TokenCreationFailed,
 	Unauthorized,
-	InvalidSubject
+	InvalidSubject,
+
+	// -- Keyring
+	KeyringKeyInvalid { kid: String },
+	KeyringKidDuplicate { kid: String },
+	KeyringPrimaryKeyNotFound { kid: String },
+	KeyringKeyNotForSigning,
+	KeyGenFail,
 }
 
 // region:    --- Error Boilerplate
@@ -0,0 +1,289 @@
+//! JWT keyring: the primary (signing) key and all the verification keys, by `kid`.
+//!
+//! - Asymmetric keys (EdDSA/Ed25519 and RS256) are configured with `SERVICE_JWT_KEYS`, as
+//!   `kid=b64u_der` entries (comma separated). The DER is either a PKCS#8 private key
+//!   (sign & verify) or a SPKI public key (verify only, e.g., a retired key).
+//! - `SERVICE_JWT_PRIMARY_KID` selects the signing key (default: the first private key).
+//! - The HS256 `SERVICE_TOKEN_KEY` is the signing key when no asymmetric private key is configured.
+//!   Otherwise, it only verifies the tokens without `kid` (issued before the asymmetric keys) when
+//!   `SERVICE_JWT_HS256_FALLBACK=true`, to be turned off once those tokens have expired.
+//!
+//! Other services only need the public keys, from the JWKS (`/.well-known/jwks.json`).
+
+use super::{Error, Result};
+use crate::config::{auth_config, AuthConfig};
+use ed25519_dalek::pkcs8::{DecodePrivateKey, DecodePublicKey, EncodePrivateKey, EncodePublicKey};
+use ed25519_dalek::{SigningKey, VerifyingKey};
+use jsonwebtoken::jwk::{
+	AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, KeyAlgorithm,
+	OctetKeyPairParameters, OctetKeyPairType, PublicKeyUse, RSAKeyParameters, RSAKeyType,
+};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey};
+use lib_utils::b64::b64u_encode;
+use rand::RngCore;
+use rsa::pkcs1::EncodeRsaPrivateKey;
+use rsa::traits::PublicKeyParts;
+use rsa::{RsaPrivateKey, RsaPublicKey};
+use std::sync::OnceLock;
+
+pub use jsonwebtoken::jwk::JwkSet;
+
+pub fn keyring() -> &'static Keyring {
+	static INSTANCE: OnceLock<Keyring> = OnceLock::new();
+
+	INSTANCE.get_or_init(|| {
+		Keyring::from_config(auth_config()).unwrap_or_else(|ex| {
+			panic!("FATAL - WHILE LOADING JWT KEYRING - Cause: {ex:?}")
+		})
+	})
+}
+
+// region:    --- Keyring
+
+pub struct Keyring {
+	keys: Vec<JwtKey>,
+	primary_idx: usize,
+}
+
+impl Keyring {
+	fn from_config(config: &AuthConfig) -> Result<Self> {
+		Self::new(
+			&config.TOKEN_KEY,
+			&config.JWT_KEYS,
+			config.JWT_PRIMARY_KID.as_deref(),
+			config.JWT_HS256_FALLBACK,
+		)
+	}
+
+	/// Note: `hs256_fallback` keeps the HS256 key for verification when an asymmetric key signs
+	///       (always kept when none can sign, since it is then the signing key).
+	pub fn new(
+		hs256_secret: &[u8],
+		keys: &[(String, Vec<u8>)],
+		primary_kid: Option<&str>,
+		hs256_fallback: bool,
+	) -> Result<Self> {
+		let mut jwt_keys: Vec<JwtKey> = Vec::with_capacity(keys.len() + 1);
+		for (kid, der) in keys {
+			if jwt_keys.iter().any(|k| k.kid() == Some(kid.as_str())) {
+				return Err(Error::KeyringKidDuplicate { kid: kid.to_string() });
+			}
+			jwt_keys.push(JwtKey::from_der(kid, der)?);
+		}
+
+		if hs256_fallback || !jwt_keys.iter().any(JwtKey::can_sign) {
+			jwt_keys.push(JwtKey::hs256(hs256_secret));
+		}
+
+		let primary_idx = match primary_kid {
+			Some(primary_kid) => jwt_keys
+				.iter()
+				.position(|k| k.kid() == Some(primary_kid) && k.can_sign())
+				.ok_or_else(|| Error::KeyringPrimaryKeyNotFound {
+					kid: primary_kid.to_string(),
+				})?,
+			// Note: The first private asymmetric key, otherwise the HS256 one (last).
+			None => jwt_keys
+				.iter()
+				.position(JwtKey::can_sign)
+				.ok_or(Error::KeyringKeyNotForSigning)?,
+		};
+
+		Ok(Keyring {
+			keys: jwt_keys,
+			primary_idx,
+		})
+	}
+
+	/// The key used to sign the new tokens.
+	pub fn primary(&self) -> &JwtKey {
+		&self.keys[self.primary_idx]
+	}
+
+	/// The verification key for a token header `kid`
+	/// (`None` is the HS256 key, if signing or `hs256_fallback`).
+	pub fn find(&self, kid: Option<&str>) -> Option<&JwtKey> {
+		self.keys.iter().find(|k| k.kid() == kid)
+	}
+
+	/// The public keys, for the other services to validate the tokens.
+	pub fn jwks(&self) -> JwkSet {
+		JwkSet {
+			keys: self.keys.iter().filter_map(|k| k.jwk.clone()).collect(),
+		}
+	}
+}
+
+// endregion: --- Keyring
+
+// region:    --- JwtKey
+
+pub struct JwtKey {
+	kid: Option<String>,
+	alg: Algorithm,
+	encoding_key: Option<EncodingKey>,
+	decoding_key: DecodingKey,
+	/// Public JWK (None for HS256).
+	jwk: Option<Jwk>,
+}
+
+impl JwtKey {
+	fn hs256(secret: &[u8]) -> Self {
+		JwtKey {
+			kid: None,
+			alg: Algorithm::HS256,
+			encoding_key: Some(EncodingKey::from_secret(secret)),
+			decoding_key: DecodingKey::from_secret(secret),
+			jwk: None,
+		}
+	}
+
+	/// From a PKCS#8 private key or SPKI public key DER, Ed25519 or RSA.
+	fn from_der(kid: &str, der: &[u8]) -> Result<Self> {
+		if let Ok(signing_key) = SigningKey::from_pkcs8_der(der) {
+			let mut key = Self::ed25519(kid, &signing_key.verifying_key());
+			key.encoding_key = Some(EncodingKey::from_ed_der(der));
+			Ok(key)
+		} else if let Ok(verifying_key) = VerifyingKey::from_public_key_der(der) {
+			Ok(Self::ed25519(kid, &verifying_key))
+		} else if let Ok(private_key) = RsaPrivateKey::from_pkcs8_der(der) {
+			// Note: jsonwebtoken takes the RSA private key as PKCS#1.
+			let pkcs1_der = private_key
+				.to_pkcs1_der()
+				.map_err(|_| Error::KeyringKeyInvalid { kid: kid.to_string() })?;
+			let mut key = Self::rs256(kid, &private_key.to_public_key());
+			key.encoding_key = Some(EncodingKey::from_rsa_der(pkcs1_der.as_bytes()));
+			Ok(key)
+		} else if let Ok(public_key) = RsaPublicKey::from_public_key_der(der) {
+			Ok(Self::rs256(kid, &public_key))
+		} else {
+			Err(Error::KeyringKeyInvalid { kid: kid.to_string() })
+		}
+	}
+
+	fn ed25519(kid: &str, verifying_key: &VerifyingKey) -> Self {
+		let x = b64u_encode(verifying_key.as_bytes());
+		let jwk = Jwk {
+			common: jwk_common(kid, KeyAlgorithm::EdDSA),
+			algorithm: AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+				key_type: OctetKeyPairType::OctetKeyPair,
+				curve: EllipticCurve::Ed25519,
+				x,
+			}),
+		};
+
+		JwtKey {
+			kid: Some(kid.to_string()),
+			alg: Algorithm::EdDSA,
+			encoding_key: None,
+			// Note: For EdDSA, the decoding key is the raw public key bytes.
+			decoding_key: DecodingKey::from_ed_der(verifying_key.as_bytes()),
+			jwk: Some(jwk),
+		}
+	}
+
+	fn rs256(kid: &str, public_key: &RsaPublicKey) -> Self {
+		let n = public_key.n().to_bytes_be();
+		let e = public_key.e().to_bytes_be();
+		let jwk = Jwk {
+			common: jwk_common(kid, KeyAlgorithm::RS256),
+			algorithm: AlgorithmParameters::RSA(RSAKeyParameters {
+				key_type: RSAKeyType::RSA,
+				n: b64u_encode(&n),
+				e: b64u_encode(&e),
+			}),
+		};
+
+		JwtKey {
+			kid: Some(kid.to_string()),
+			alg: Algorithm::RS256,
+			encoding_key: None,
+			decoding_key: DecodingKey::from_rsa_raw_components(&n, &e),
+			jwk: Some(jwk),
+		}
+	}
+
+	pub fn kid(&self) -> Option<&str> {
+		self.kid.as_deref()
+	}
+
+	pub fn alg(&self) -> Algorithm {
+		self.alg
+	}
+
+	pub fn can_sign(&self) -> bool {
+		self.encoding_key.is_some()
+	}
+
+	pub(super) fn encoding_key(&self) -> Result<&EncodingKey> {
+		self.encoding_key.as_ref().ok_or(Error::KeyringKeyNotForSigning)
+	}
+
+	pub(super) fn decoding_key(&self) -> &DecodingKey {
+		&self.decoding_key
+	}
+}
+
+fn jwk_common(kid: &str, key_algorithm: KeyAlgorithm) -> CommonParameters {
+	CommonParameters {
+		public_key_use: Some(PublicKeyUse::Signature),
+		key_algorithm: Some(key_algorithm),
+		key_id: Some(kid.to_string()),
+		..Default::default()
+	}
+}
+
+// endregion: --- JwtKey
+
+// region:    --- Key Generation
+
+/// A generated keypair, DER encoded (PKCS#8 private key, SPKI public key).
+pub struct KeyPairDer {
+	pub private_pkcs8: Vec<u8>,
+	pub public_spki: Vec<u8>,
+}
+
+pub fn generate_ed25519_keypair() -> Result<KeyPairDer> {
+	let mut seed = [0u8; 32];
+	rand::rng().fill_bytes(&mut seed);
+	let signing_key = SigningKey::from_bytes(&seed);
+
+	let private_pkcs8 = signing_key
+		.to_pkcs8_der()
+		.map_err(|_| Error::KeyGenFail)?
+		.as_bytes()
+		.to_vec();
+	let public_spki = signing_key
+		.verifying_key()
+		.to_public_key_der()
+		.map_err(|_| Error::KeyGenFail)?
+		.into_vec();
+
+	Ok(KeyPairDer {
+		private_pkcs8,
+		public_spki,
+	})
+}
+
+pub fn generate_rsa_keypair(bits: usize) -> Result<KeyPairDer> {
+	let private_key = RsaPrivateKey::new(&mut rsa::rand_core::OsRng, bits)
+		.map_err(|_| Error::KeyGenFail)?;
+
+	let private_pkcs8 = private_key
+		.to_pkcs8_der()
+		.map_err(|_| Error::KeyGenFail)?
+		.as_bytes()
+		.to_vec();
+	let public_spki = private_key
+		.to_public_key()
+		.to_public_key_der()
+		.map_err(|_| Error::KeyGenFail)?
+		.into_vec();
+
+	Ok(KeyPairDer {
+		private_pkcs8,
+		public_spki,
+	})
+}
+
+// endregion: --- Key Generation
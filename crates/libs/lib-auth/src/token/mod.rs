@@ -1,12 +1,12 @@
 use chrono::{Duration, Utc};
-use jsonwebtoken::{
-    decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation
-};
+use jsonwebtoken::{decode, decode_header, encode, Header, Validation};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use crate::config::auth_config;
 mod error;
+pub mod keyring;
 pub use self::error::{Error, Result};
+pub use self::keyring::{keyring, JwtKey, Keyring};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TokenClaims {
@@ -14,40 +14,71 @@ pub struct TokenClaims {
     pub iat: usize,
     pub exp: usize,
     pub salt: String,
-    pub typ: String, // "access" or "refresh"
+    pub typ: String, // "access", "refresh" or "mfa_pending"
+    /// Session (refresh token family) id.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<String>,
+    /// Refresh token id, rotated on every refresh (only on "refresh" tokens).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jti: Option<String>,
+}
+
+impl TokenClaims {
+    pub fn sid_uuid(&self) -> Result<Uuid> {
+        parse_claim_uuid(self.sid.as_deref())
+    }
+
+    pub fn jti_uuid(&self) -> Result<Uuid> {
+        parse_claim_uuid(self.jti.as_deref())
+    }
+}
+
+fn parse_claim_uuid(val: Option<&str>) -> Result<Uuid> {
+    val.and_then(|v| Uuid::parse_str(v).ok())
+        .ok_or(Error::InvalidToken)
 }
 
 // region:    --- Web Token Gen and Validation
 
-pub fn generate_web_tokens(user: &str, salt: Uuid) -> Result<(String, String)> {
-	let access_token = generate_access_token(user, salt,)?;
-	let refresh_token = generate_refresh_token(user, salt)?;
+/// Generates the access and refresh tokens for a session.
+/// `sid` is the session (token family) id, and `jti` the id of the current refresh token of this family.
+pub fn generate_web_tokens(user: &str, salt: Uuid, sid: Uuid, jti: Uuid) -> Result<(String, String)> {
+	let access_token = generate_access_token(user, salt, sid)?;
+	let refresh_token = generate_refresh_token(user, salt, sid, jti)?;
 	Ok((access_token, refresh_token))
 }
 
-fn generate_access_token(user: &str, salt: Uuid) -> Result<String> {
+pub fn generate_access_token(user: &str, salt: Uuid, sid: Uuid) -> Result<String> {
+    let config = &auth_config();
+    create_jwt_token(user, keyring().primary(), config.ACCESS_TOKEN_TTL, salt, "access", Some(sid), None)
+}
+
+fn generate_refresh_token(user: &str, salt: Uuid, sid: Uuid, jti: Uuid) -> Result<String> {
     let config = &auth_config();
-    create_jwt_token(user, &config.TOKEN_KEY, config.ACCESS_TOKEN_TTL, salt, "access")
+    create_jwt_token(user, keyring().primary(), config.REFRESH_TOKEN_TTL, salt, "refresh", Some(sid), Some(jti))
 }
 
-fn generate_refresh_token(user: &str, salt: Uuid) -> Result<String> {
+/// Short-lived token returned by the login when the second factor is still to be verified.
+/// It carries no session, so it cannot be used as an access token.
+pub fn generate_mfa_pending_token(user: &str, salt: Uuid) -> Result<String> {
     let config = &auth_config();
-    create_jwt_token(user, &config.TOKEN_KEY, config.REFRESH_TOKEN_TTL, salt, "refresh")
+    create_jwt_token(user, keyring().primary(), config.MFA_TOKEN_TTL, salt, "mfa_pending", None, None)
 }
 
 pub fn validate_web_token(token: &String) -> Result<TokenClaims> {
-	let config = &auth_config();
-	decode_jwt_token(token, &config.TOKEN_KEY)
+	decode_jwt_token(token, keyring())
 }
 
 // endregion: --- Web Token Gen and Validation
 
 fn create_jwt_token(
     user_id: &str,
-    secret: &[u8],
+    key: &JwtKey,
     expires_in_seconds: i64,
     salt: Uuid,
     typ: &str,
+    sid: Option<Uuid>,
+    jti: Option<Uuid>,
 ) -> Result<String> {
     if user_id.is_empty() {
         return Err(Error::InvalidSubject);
@@ -62,26 +93,155 @@ fn create_jwt_token(
         exp,
         salt: salt.to_string(),
         typ: typ.to_string(),
+        sid: sid.map(|v| v.to_string()),
+        jti: jti.map(|v| v.to_string()),
     };
 
+    let mut header = Header::new(key.alg());
+    header.kid = key.kid().map(|kid| kid.to_string());
+
     encode(
-        &Header::default(),
+        &header,
         &claims, 
-        &EncodingKey::from_secret(secret)
+        key.encoding_key()?
     ).map_err(Error::from)
 }
 
 fn decode_jwt_token<T: Into <String>> (
     token: T,
-    secret: &[u8]
+    keyring: &Keyring
 ) -> Result<TokenClaims> {
 	let token = token.into();
 
+    // -- Find the verification key (by kid)
+    let header = decode_header(&token).map_err(Error::from)?;
+    let key = keyring
+        .find(header.kid.as_deref())
+        .ok_or(Error::InvalidToken)?;
+
+    // Note: The validation only accepts the algorithm of the key (header `alg` must match).
     let token_decoded = decode::<TokenClaims>(
         &token,
-        &DecodingKey::from_secret(secret),
-        &Validation::new(Algorithm::HS256),
+        key.decoding_key(),
+        &Validation::new(key.alg()),
     ).map_err(Error::from)?;
 
 	Ok(token_decoded.claims)
 }
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	pub type Result<T> = core::result::Result<T, Error>;
+	pub type Error = Box<dyn std::error::Error>; // For tests.
+
+	use super::*;
+
+	#[test]
+	fn test_refresh_token_claims_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let fx_keyring = Keyring::new(b"some-test-secret-for-token-claims", &[], None, false)?;
+		let fx_salt = Uuid::new_v4();
+		let fx_sid = Uuid::new_v4();
+		let fx_jti = Uuid::new_v4();
+
+		// -- Exec
+		let token = create_jwt_token("demo1", fx_keyring.primary(), 60, fx_salt, "refresh", Some(fx_sid), Some(fx_jti))?;
+		let claims = decode_jwt_token(token, &fx_keyring)?;
+
+		// -- Check
+		assert_eq!(claims.typ, "refresh");
+		assert_eq!(claims.sid_uuid()?, fx_sid);
+		assert_eq!(claims.jti_uuid()?, fx_jti);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_access_token_has_no_jti() -> Result<()> {
+		// -- Setup & Fixtures
+		let fx_keyring = Keyring::new(b"some-test-secret-for-token-claims", &[], None, false)?;
+		let fx_sid = Uuid::new_v4();
+
+		// -- Exec
+		let token = create_jwt_token("demo1", fx_keyring.primary(), 60, Uuid::new_v4(), "access", Some(fx_sid), None)?;
+		let claims = decode_jwt_token(token, &fx_keyring)?;
+
+		// -- Check
+		assert_eq!(claims.sid_uuid()?, fx_sid);
+		assert!(matches!(claims.jti_uuid(), Err(super::Error::InvalidToken)));
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_keyring_rotation_eddsa_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let fx_secret = b"some-test-secret-for-token-claims";
+		let fx_old = keyring::generate_ed25519_keypair()?;
+		let fx_new = keyring::generate_ed25519_keypair()?;
+		let fx_old_keys = vec![("k-old".to_string(), fx_old.private_pkcs8.clone())];
+		let old_keyring = Keyring::new(fx_secret, &fx_old_keys, None, false)?;
+		let token_old = create_jwt_token("demo1", old_keyring.primary(), 60, Uuid::new_v4(), "access", None, None)?;
+
+		// -- Exec
+		// New primary key, the old one is only kept for verification (public key).
+		let fx_keys = vec![
+			("k-new".to_string(), fx_new.private_pkcs8),
+			("k-old".to_string(), fx_old.public_spki),
+		];
+		let keyring = Keyring::new(fx_secret, &fx_keys, Some("k-new"), false)?;
+		let token_new = create_jwt_token("demo1", keyring.primary(), 60, Uuid::new_v4(), "access", None, None)?;
+
+		// -- Check
+		assert_eq!(decode_header(&token_new)?.kid.as_deref(), Some("k-new"));
+		assert_eq!(decode_jwt_token(token_new, &keyring)?.sub, "demo1");
+		assert_eq!(decode_jwt_token(token_old, &keyring)?.sub, "demo1");
+		assert_eq!(keyring.jwks().keys.len(), 2);
+		// A verification only key cannot be the primary.
+		assert!(Keyring::new(fx_secret, &fx_keys, Some("k-old"), false).is_err());
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_keyring_hs256_fallback_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let fx_secret = b"some-test-secret-for-token-claims";
+		let hs256_keyring = Keyring::new(fx_secret, &[], None, false)?;
+		let token_hs256 = create_jwt_token("demo1", hs256_keyring.primary(), 60, Uuid::new_v4(), "access", None, None)?;
+		let fx_keys = vec![("k-1".to_string(), keyring::generate_ed25519_keypair()?.private_pkcs8)];
+
+		// -- Exec
+		let keyring_fallback = Keyring::new(fx_secret, &fx_keys, None, true)?;
+		let keyring_no_fallback = Keyring::new(fx_secret, &fx_keys, None, false)?;
+
+		// -- Check
+		assert_eq!(keyring_fallback.primary().kid(), Some("k-1"));
+		assert_eq!(decode_jwt_token(token_hs256.clone(), &keyring_fallback)?.sub, "demo1");
+		assert!(decode_jwt_token(token_hs256, &keyring_no_fallback).is_err());
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_keyring_new_err_kid_duplicate() -> Result<()> {
+		// -- Setup & Fixtures
+		let fx_keys = vec![
+			("k-1".to_string(), keyring::generate_ed25519_keypair()?.private_pkcs8),
+			("k-1".to_string(), keyring::generate_ed25519_keypair()?.public_spki),
+		];
+
+		// -- Exec
+		let res = Keyring::new(b"some-test-secret-for-token-claims", &fx_keys, None, false);
+
+		// -- Check
+		assert!(
+			matches!(res, Err(super::Error::KeyringKidDuplicate { ref kid }) if kid == "k-1"),
+			"should be KeyringKidDuplicate"
+		);
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
@@ -0,0 +1,267 @@
+//! TOTP (RFC 6238) second factor, and the recovery codes.
+//!
+//! Uses the authenticator apps defaults (HMAC-SHA1, 6 digits, 30 seconds steps).
+
+// region:    --- Modules
+
+mod error;
+
+pub use self::error::{Error, Result};
+
+use crate::config::auth_config;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use lib_utils::b32::{b32_decode, b32_encode};
+use lib_utils::b64::b64u_encode;
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use rand::RngCore;
+use sha1::Sha1;
+use sha2::Sha256;
+use subtle::ConstantTimeEq;
+
+// endregion: --- Modules
+
+pub const TOTP_ISSUER: &str = "Mapster";
+
+const TOTP_DIGITS: u32 = 6;
+const TOTP_STEP_SEC: i64 = 30;
+/// Number of steps accepted before/after the current one (clock skew).
+const TOTP_SKEW_STEPS: i64 = 1;
+/// 160 bits, as recommended by RFC 4226 for HMAC-SHA1.
+const TOTP_SECRET_LEN: usize = 20;
+
+const RECOVERY_CODES_COUNT: usize = 10;
+/// 48 bits, which gives 10 base32 chars.
+const RECOVERY_CODE_LEN: usize = 6;
+
+// region:    --- TOTP
+
+/// Generate a new random TOTP secret (base32 encoded).
+pub fn generate_secret() -> String {
+	let mut secret = [0u8; TOTP_SECRET_LEN];
+	rand::rng().fill_bytes(&mut secret);
+	b32_encode(secret)
+}
+
+/// The `otpauth://` URI to be shown as a QR code to the authenticator app.
+pub fn provisioning_uri(secret: &str, account_name: &str) -> String {
+	let issuer = utf8_percent_encode(TOTP_ISSUER, NON_ALPHANUMERIC);
+	let account_name = utf8_percent_encode(account_name, NON_ALPHANUMERIC);
+
+	format!(
+		"otpauth://totp/{issuer}:{account_name}?secret={secret}&issuer={issuer}&algorithm=SHA1&digits={TOTP_DIGITS}&period={TOTP_STEP_SEC}"
+	)
+}
+
+/// The TOTP code of the secret at a given unix time (e.g., for tests and dev tools).
+pub fn generate_code(secret: &str, unix_time: i64) -> Result<String> {
+	let key = b32_decode(secret).map_err(|_| Error::SecretInvalid)?;
+	let code = hotp(&key, (unix_time / TOTP_STEP_SEC) as u64)?;
+	Ok(format!("{code:0width$}", width = TOTP_DIGITS as usize))
+}
+
+/// Verify a TOTP code against the secret for the current time (with the skew window).
+///
+/// Returns the matched time step, to be stored as the new `last_step`, so that a code
+/// cannot be replayed (any step <= `last_step` is rejected).
+pub fn verify_code(secret: &str, code: &str, last_step: Option<i64>) -> Result<i64> {
+	verify_code_at(secret, code, last_step, Utc::now().timestamp())
+}
+
+fn verify_code_at(
+	secret: &str,
+	code: &str,
+	last_step: Option<i64>,
+	unix_time: i64,
+) -> Result<i64> {
+	let key = b32_decode(secret).map_err(|_| Error::SecretInvalid)?;
+
+	let code = code.trim();
+	if code.len() != TOTP_DIGITS as usize || !code.chars().all(|c| c.is_ascii_digit()) {
+		return Err(Error::CodeInvalid);
+	}
+	let code: u32 = code.parse().map_err(|_| Error::CodeInvalid)?;
+
+	let current_step = unix_time / TOTP_STEP_SEC;
+	for step in (current_step - TOTP_SKEW_STEPS)..=(current_step + TOTP_SKEW_STEPS) {
+		if hotp(&key, step as u64)? == code {
+			if last_step.is_some_and(|last_step| step <= last_step) {
+				return Err(Error::CodeReused);
+			}
+			return Ok(step);
+		}
+	}
+
+	Err(Error::CodeInvalid)
+}
+
+/// HOTP (RFC 4226) value for a counter.
+fn hotp(key: &[u8], counter: u64) -> Result<u32> {
+	let mut hmac_sha1 =
+		Hmac::<Sha1>::new_from_slice(key).map_err(|_| Error::HmacFailNewFromSlice)?;
+	hmac_sha1.update(&counter.to_be_bytes());
+	let hash = hmac_sha1.finalize().into_bytes();
+
+	// -- Dynamic truncation
+	let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+	let bin_code = u32::from_be_bytes([
+		hash[offset] & 0x7f,
+		hash[offset + 1],
+		hash[offset + 2],
+		hash[offset + 3],
+	]);
+
+	Ok(bin_code % 10u32.pow(TOTP_DIGITS))
+}
+
+// endregion: --- TOTP
+
+// region:    --- Recovery Codes
+
+/// Generate a new set of single-use recovery codes (e.g., "k3jd9-a8xq2").
+/// Only their hash (see `hash_recovery_code`) should be stored.
+pub fn generate_recovery_codes() -> Vec<String> {
+	(0..RECOVERY_CODES_COUNT)
+		.map(|_| {
+			let mut bytes = [0u8; RECOVERY_CODE_LEN];
+			rand::rng().fill_bytes(&mut bytes);
+			let code = b32_encode(bytes).to_lowercase();
+			let (left, right) = code.split_at(code.len() / 2);
+			format!("{left}-{right}")
+		})
+		.collect()
+}
+
+/// Keyed hash (HMAC-SHA256 with the pwd key) of a recovery code, b64u encoded.
+/// The code is normalized first, so "K3JD9 A8XQ2" matches "k3jd9-a8xq2".
+pub fn hash_recovery_code(code: &str) -> Result<String> {
+	let code: String = code
+		.chars()
+		.filter(|c| c.is_ascii_alphanumeric())
+		.map(|c| c.to_ascii_lowercase())
+		.collect();
+
+	let mut hmac_sha256 = Hmac::<Sha256>::new_from_slice(&auth_config().PWD_KEY)
+		.map_err(|_| Error::HmacFailNewFromSlice)?;
+	hmac_sha256.update(code.as_bytes());
+
+	Ok(b64u_encode(hmac_sha256.finalize().into_bytes()))
+}
+
+/// Find a recovery code in the stored hashes (see `hash_recovery_code`).
+/// Returns the index of the matched hash, if any.
+///
+/// Note: The hashes are compared in constant time, and all of them are scanned,
+///       so the timing does not leak a partial match.
+pub fn find_recovery_code(code: &str, hashes: &[&str]) -> Result<Option<usize>> {
+	let code_hash = hash_recovery_code(code)?;
+
+	let mut found = None;
+	for (idx, hash) in hashes.iter().enumerate() {
+		if bool::from(hash.as_bytes().ct_eq(code_hash.as_bytes())) && found.is_none() {
+			found = Some(idx);
+		}
+	}
+
+	Ok(found)
+}
+
+// endregion: --- Recovery Codes
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	pub type Result<T> = core::result::Result<T, Error>;
+	pub type Error = Box<dyn std::error::Error>; // For tests.
+
+	use super::*;
+
+	// RFC 6238 Appendix B secret ("12345678901234567890"), SHA1 vectors (last 6 digits).
+	const FX_RFC_SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+	#[test]
+	fn test_verify_code_rfc6238_vectors_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let fx_vectors = [
+			(59, "287082"),
+			(1111111109, "081804"),
+			(1111111111, "050471"),
+			(1234567890, "005924"),
+			(2000000000, "279037"),
+		];
+
+		// -- Exec & Check
+		for (time, code) in fx_vectors {
+			let step = verify_code_at(FX_RFC_SECRET, code, None, time)?;
+			assert_eq!(step, time / TOTP_STEP_SEC);
+		}
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_verify_code_skew_window() -> Result<()> {
+		// -- Setup & Fixtures
+		let fx_time = 1111111109; // code "081804" at step 37037036
+
+		// -- Exec & Check
+		// One step later is still accepted (clock skew).
+		verify_code_at(FX_RFC_SECRET, "081804", None, fx_time + TOTP_STEP_SEC)?;
+		// Two steps later is not.
+		let res = verify_code_at(FX_RFC_SECRET, "081804", None, fx_time + 2 * TOTP_STEP_SEC);
+		assert!(matches!(res, Err(super::Error::CodeInvalid)));
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_verify_code_err_reused() -> Result<()> {
+		// -- Setup & Fixtures
+		let fx_time = 1111111109;
+		let step = verify_code_at(FX_RFC_SECRET, "081804", None, fx_time)?;
+
+		// -- Exec
+		let res = verify_code_at(FX_RFC_SECRET, "081804", Some(step), fx_time);
+
+		// -- Check
+		assert!(matches!(res, Err(super::Error::CodeReused)));
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_recovery_codes_format() -> Result<()> {
+		// -- Exec
+		let codes = generate_recovery_codes();
+
+		// -- Check
+		assert_eq!(codes.len(), RECOVERY_CODES_COUNT);
+		for code in codes {
+			assert_eq!(code.len(), 11, "code '{code}' should be 'xxxxx-xxxxx'");
+			assert_eq!(code.chars().nth(5), Some('-'));
+		}
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_find_recovery_code_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let codes = generate_recovery_codes();
+		let hashes = codes
+			.iter()
+			.map(|code| hash_recovery_code(code))
+			.collect::<super::Result<Vec<_>>>()?;
+		let hashes: Vec<&str> = hashes.iter().map(String::as_str).collect();
+
+		// -- Exec & Check
+		assert_eq!(find_recovery_code(&codes[3], &hashes)?, Some(3));
+		let fx_code = codes[5].to_uppercase().replace('-', " ");
+		assert_eq!(find_recovery_code(&fx_code, &hashes)?, Some(5));
+		assert_eq!(find_recovery_code("aaaaa-aaaaa", &hashes)?, None);
+		assert_eq!(find_recovery_code(&codes[0], &[])?, None);
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
@@ -4,7 +4,10 @@ mod dev_db;
 use tokio::sync::OnceCell;
 use tracing::info;
 
-use crate::{ctx::Ctx, model::{self, post::{Post, PostBmc, PostForCreate}, ModelManager}};
+use crate::ctx::{Ctx, Role};
+use crate::model::{self, post::{Post, PostBmc, PostForCreate}, ModelManager};
+use crate::model::place::{PlaceBmc, PlaceForCreate};
+use crate::model::user::{UserBmc, UserForCreate};
 
 // endregion: ---- Modules
 
@@ -55,6 +58,7 @@ pub async fn seed_posts(
                 thumbnail_url: None,
                 media_count: None,
                 has_video: None,
+                lang: None,
             }
         )
         .await?;
@@ -64,4 +68,54 @@ pub async fn seed_posts(
     }
     
     Ok(posts)
-}
\ No newline at end of file
+}
+
+/// Seed the places (e.g., "cafe" category, in Singapore), returns their ids.
+pub async fn seed_places(
+    ctx: &Ctx,
+    mm: &ModelManager,
+    names: &[&str],
+) -> model::Result<Vec<i64>> {
+    let places_c = names
+        .iter()
+        .map(|name| PlaceForCreate {
+            name: name.to_string(),
+            category: "cafe".to_string(),
+            lat: 1.2839,
+            lon: 103.8515,
+            street: None,
+            city: Some("Singapore".to_string()),
+            region: None,
+            postal_code: None,
+            country_code: Some("SG".to_string()),
+            opening_hours: None,
+            descriptions: None,
+            source: None,
+            source_id: None,
+        })
+        .collect();
+
+    PlaceBmc::create_many(ctx, mm, places_c).await
+}
+
+/// Create a user with this role, and returns its ctx (to act as this user).
+pub async fn seed_user(
+    mm: &ModelManager,
+    username: &str,
+    role: Role,
+) -> model::Result<Ctx> {
+    let root_ctx = Ctx::root_ctx();
+    let id = UserBmc::create(
+        &root_ctx,
+        mm,
+        UserForCreate {
+            username: username.to_string(),
+            pwd_clear: format!("{username} pwd"),
+            email: format!("{username}@example.com"),
+        },
+    )
+    .await?;
+    UserBmc::update_role(&root_ctx, mm, id, role).await?;
+
+    Ok(Ctx::new(id)?.add_role(role))
+}
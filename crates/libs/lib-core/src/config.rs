@@ -1,4 +1,5 @@
 use std::sync::OnceLock;
+use ipnet::IpNet;
 use lib_utils::envs::get_env;
 
 // region: --- Core Config
@@ -16,9 +17,14 @@ pub fn core_config() -> &'static CoreConfig {
 pub struct CoreConfig {
 	// -- Db
 	pub DB_URL: String,
+	/// `SERVICE_MIGRATIONS_DIR`, "sql/migrations" by default.
+	pub MIGRATIONS_DIR: String,
 
 	// -- Web
 	pub WEB_FOLDER: String,
+	/// The reverse proxies allowed to set `X-Forwarded-For` and the geo headers
+	/// (empty if not behind a proxy).
+	pub TRUSTED_PROXIES: Vec<IpNet>,
 }
 
 impl CoreConfig {
@@ -26,10 +32,31 @@ impl CoreConfig {
 		Ok(CoreConfig {
 			// -- Db
 			DB_URL: get_env("SERVICE_DB_URL")?,
+			MIGRATIONS_DIR: get_env("SERVICE_MIGRATIONS_DIR")
+				.unwrap_or_else(|_| "sql/migrations".to_string()),
 
 			// -- Web
 			WEB_FOLDER: get_env("SERVICE_WEB_FOLDER")?,
+			TRUSTED_PROXIES: get_env_ip_nets("SERVICE_TRUSTED_PROXIES")?,
 		})
 	}
 }
+
+/// Parse the `ip_or_cidr,ip_or_cidr` list (e.g., "10.0.0.0/8,192.168.1.2"), empty if the env is not set.
+fn get_env_ip_nets(name: &'static str) -> lib_utils::envs::Result<Vec<IpNet>> {
+	let Ok(val) = get_env(name) else {
+		return Ok(Vec::new());
+	};
+
+	val.split(',')
+		.map(str::trim)
+		.filter(|entry| !entry.is_empty())
+		.map(|entry| {
+			entry
+				.parse::<IpNet>()
+				.or_else(|_| entry.parse::<std::net::IpAddr>().map(IpNet::from))
+				.map_err(|_| lib_utils::envs::Error::WrongFormat(name))
+		})
+		.collect()
+}
 // endregion: --- Core Config
@@ -1,3 +1,4 @@
+use crate::ctx::Permission;
 use serde::Serialize;
 
 pub type Result<T> = core::result::Result<T, Error>;
@@ -5,6 +6,10 @@ pub type Result<T> = core::result::Result<T, Error>;
 #[derive(Debug, Serialize)]
 pub enum Error {
 	CtxCannotNewRootCtx,
+	PermissionDenied {
+		user_id: i64,
+		permission: Permission,
+	},
 }
 
 // region:    --- Error Boilerplate
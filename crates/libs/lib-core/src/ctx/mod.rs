@@ -1,29 +1,59 @@
 // region: ---- Modules
 
 mod error;
+mod role;
 
 pub use self::error::{Error, Result};
+pub use self::role::{Permission, Role};
 
 // endregion: ---- Modules
 
 #[derive(Clone, Debug)]
 pub struct Ctx {
     user_id: i64,
+
+    /// The session (refresh token family) of the request, if any.
+    session_id: Option<i64>,
+
+    role: Role,
 }
 
 // Constructors
 impl Ctx {
     pub fn root_ctx() -> Self {
-        Ctx { user_id: 0 }
+        Ctx {
+            user_id: 0,
+            session_id: None,
+            role: Role::Admin,
+        }
     }
 
     pub fn new(user_id: i64) -> Result<Self> {
         if user_id == 0 {
             Err(Error::CtxCannotNewRootCtx)
         } else {
-            Ok(Self { user_id })
+            Ok(Self {
+                user_id,
+                session_id: None,
+                role: Role::User,
+            })
         }
     }
+
+    /// Note: For the session_id, we use a "add/with" pattern,
+    ///       as it is set after the Ctx is created (once the session is validated).
+    pub fn add_session_id(&self, session_id: i64) -> Ctx {
+        let mut ctx = self.clone();
+        ctx.session_id = Some(session_id);
+        ctx
+    }
+
+    /// Note: The role is loaded with the user (`Role::User` until then).
+    pub fn add_role(&self, role: Role) -> Ctx {
+        let mut ctx = self.clone();
+        ctx.role = role;
+        ctx
+    }
 }
 
 // Property Accessors
@@ -31,4 +61,31 @@ impl Ctx {
     pub fn user_id(&self) -> i64 {
         self.user_id
     }
+
+    pub fn session_id(&self) -> Option<i64> {
+        self.session_id
+    }
+
+    pub fn role(&self) -> Role {
+        self.role
+    }
+}
+
+// Permissions
+impl Ctx {
+    pub fn has_permission(&self, permission: Permission) -> bool {
+        self.role.has_permission(permission)
+    }
+
+    /// Returns `Error::PermissionDenied` if the ctx role does not have this permission.
+    pub fn require(&self, permission: Permission) -> Result<()> {
+        if self.has_permission(permission) {
+            Ok(())
+        } else {
+            Err(Error::PermissionDenied {
+                user_id: self.user_id,
+                permission,
+            })
+        }
+    }
 }
\ No newline at end of file
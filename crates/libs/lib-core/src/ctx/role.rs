@@ -0,0 +1,76 @@
+use serde::{Deserialize, Serialize};
+
+/// The role of a user (one per user), giving its permissions.
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, sqlx::Type, derive_more::Display, Deserialize, Serialize,
+)]
+#[sqlx(type_name = "user_role")]
+pub enum Role {
+    Admin,
+    Moderator,
+    Creator,
+    User,
+}
+
+// Covert custom Role into sea_query::Value
+impl From<Role> for sea_query::Value {
+    fn from(val: Role) -> Self {
+        val.to_string().into()
+    }
+}
+
+/// The permissions checked by the Bmcs (through the Ctx) and the web extractors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum Permission {
+    // -- Post
+    PostCreate,
+    PostUpdate,
+    PostDelete,
+
+    // -- Moderation
+    /// Update and delete the content (posts, media) owned by other users.
+    ContentModerate,
+
+    // -- Place
+    /// Create the places (shared by all the users, e.g., to tag them on the posts).
+    PlaceCreate,
+    /// Update and delete the places (shared by all the users).
+    PlaceManage,
+
+    // -- User
+    /// Manage the users (e.g., change their role).
+    UserManage,
+}
+
+impl Role {
+    pub fn permissions(&self) -> &'static [Permission] {
+        use Permission::*;
+
+        match self {
+            Role::Admin => &[
+                PostCreate,
+                PostUpdate,
+                PostDelete,
+                ContentModerate,
+                PlaceCreate,
+                PlaceManage,
+                UserManage,
+            ],
+            Role::Moderator => &[
+                PostCreate,
+                PostUpdate,
+                PostDelete,
+                ContentModerate,
+                PlaceCreate,
+                PlaceManage,
+            ],
+            // Note: The post writes are limited to the owner (see `PostBmc::has_owner_id`).
+            Role::Creator => &[PostCreate, PostUpdate, PostDelete, PlaceCreate],
+            Role::User => &[PostCreate, PostUpdate, PostDelete],
+        }
+    }
+
+    pub fn has_permission(&self, permission: Permission) -> bool {
+        self.permissions().contains(&permission)
+    }
+}
@@ -0,0 +1,30 @@
+use serde::Serialize;
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[derive(Debug, Serialize)]
+pub enum Error {
+	LatOutOfRange(f64),
+	LonOutOfRange(f64),
+	RadiusInvalid {
+		radius_km: f64,
+		max: f64,
+	},
+	BBoxMinLatOverMaxLat {
+		min_lat: f64,
+		max_lat: f64,
+	},
+}
+
+// region:    --- Error Boilerplate
+impl core::fmt::Display for Error {
+	fn fmt(
+		&self,
+		fmt: &mut core::fmt::Formatter,
+	) -> core::result::Result<(), core::fmt::Error> {
+		write!(fmt, "{self:?}")
+	}
+}
+
+impl std::error::Error for Error {}
+// endregion: --- Error Boilerplate
@@ -0,0 +1,448 @@
+//! Geo types and functions for the proximity queries, on plain Postgres (no extension).
+//!
+//! - The entities with coordinates have a `geohash` column (generated, see the
+//!   `0008_geohash` migration), indexed for the prefix scans.
+//! - A query area (bbox, or the bbox around a point) is covered by a few geohash
+//!   prefixes (see `geohash_cover`), then refined by the exact bbox and the haversine
+//!   distance.
+
+// region: ---- Modules
+
+mod error;
+
+pub use self::error::{Error, Result};
+
+use serde::{Deserialize, Serialize};
+
+// endregion: ---- Modules
+
+// region:    --- Consts
+
+/// The mean earth radius (km), for the haversine distance.
+pub const EARTH_RADIUS_KM: f64 = 6371.0088;
+
+/// The max radius of a `Near` query (km).
+pub const NEAR_RADIUS_MAX_KM: f64 = 1000.;
+
+/// The precision of the geohash column (~3.7 cm cells).
+pub const GEOHASH_PRECISION: usize = 12;
+
+const GEOHASH_BASE32: &[u8] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+
+// endregion: --- Consts
+
+// region:    --- Location
+
+/// A WGS 84 location (degrees), validated.
+/// Note: Deserialized from `{"lat": .., "lon": ..}` (or `"lng"`).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "LocationRaw")]
+pub struct Location {
+	lat: f64,
+	lon: f64,
+}
+
+#[derive(Deserialize)]
+struct LocationRaw {
+	lat: f64,
+	#[serde(alias = "lng")]
+	lon: f64,
+}
+
+impl TryFrom<LocationRaw> for Location {
+	type Error = Error;
+
+	fn try_from(raw: LocationRaw) -> Result<Self> {
+		Location::new(raw.lat, raw.lon)
+	}
+}
+
+impl Location {
+	pub fn new(lat: f64, lon: f64) -> Result<Self> {
+		validate_lat(lat)?;
+		validate_lon(lon)?;
+
+		Ok(Location { lat, lon })
+	}
+
+	pub fn lat(&self) -> f64 {
+		self.lat
+	}
+
+	pub fn lon(&self) -> f64 {
+		self.lon
+	}
+
+	/// The great-circle (haversine) distance to the other location, in km.
+	pub fn distance_km(&self, other: &Location) -> f64 {
+		let d_lat = (other.lat - self.lat).to_radians();
+		let d_lon = (other.lon - self.lon).to_radians();
+		let a = (d_lat / 2.).sin().powi(2)
+			+ self.lat.to_radians().cos() * other.lat.to_radians().cos() * (d_lon / 2.).sin().powi(2);
+
+		// Note: Clamped, the rounding can exceed 1 for near antipodal locations.
+		2. * EARTH_RADIUS_KM * a.sqrt().min(1.).asin()
+	}
+
+	pub fn geohash(&self, precision: usize) -> String {
+		geohash_encode(self.lat, self.lon, precision)
+	}
+}
+
+fn validate_lat(lat: f64) -> Result<()> {
+	if (-90. ..=90.).contains(&lat) {
+		Ok(())
+	} else {
+		Err(Error::LatOutOfRange(lat))
+	}
+}
+
+fn validate_lon(lon: f64) -> Result<()> {
+	if (-180. ..=180.).contains(&lon) {
+		Ok(())
+	} else {
+		Err(Error::LonOutOfRange(lon))
+	}
+}
+
+// endregion: --- Location
+
+// region:    --- Near
+
+/// The area within `radius_km` of a location (e.g., the `$near` filter operator).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "NearRaw")]
+pub struct Near {
+	#[serde(flatten)]
+	center: Location,
+	radius_km: f64,
+}
+
+#[derive(Deserialize)]
+struct NearRaw {
+	#[serde(flatten)]
+	center: Location,
+	radius_km: f64,
+}
+
+impl TryFrom<NearRaw> for Near {
+	type Error = Error;
+
+	fn try_from(raw: NearRaw) -> Result<Self> {
+		Near::new(raw.center, raw.radius_km)
+	}
+}
+
+impl Near {
+	pub fn new(center: Location, radius_km: f64) -> Result<Self> {
+		if !(radius_km > 0. && radius_km <= NEAR_RADIUS_MAX_KM) {
+			return Err(Error::RadiusInvalid {
+				radius_km,
+				max: NEAR_RADIUS_MAX_KM,
+			});
+		}
+
+		Ok(Near { center, radius_km })
+	}
+
+	pub fn center(&self) -> Location {
+		self.center
+	}
+
+	pub fn radius_km(&self) -> f64 {
+		self.radius_km
+	}
+
+	/// The bbox containing the area (for the index scan, before the distance refinement).
+	pub fn bbox(&self) -> BBox {
+		let Location { lat, lon } = self.center;
+		let d_lat = (self.radius_km / EARTH_RADIUS_KM).to_degrees();
+		let (min_lat, max_lat) = (lat - d_lat, lat + d_lat);
+
+		// Note: All the longitudes when the area contains a pole.
+		let d_lon = (self.radius_km / (EARTH_RADIUS_KM * lat.to_radians().cos())).to_degrees();
+		let (min_lon, max_lon) = if min_lat <= -90. || max_lat >= 90. || d_lon >= 180. {
+			(-180., 180.)
+		} else {
+			(wrap_lon(lon - d_lon), wrap_lon(lon + d_lon))
+		};
+
+		BBox {
+			min_lat: min_lat.max(-90.),
+			min_lon,
+			max_lat: max_lat.min(90.),
+			max_lon,
+		}
+	}
+}
+
+fn wrap_lon(lon: f64) -> f64 {
+	if lon < -180. {
+		lon + 360.
+	} else if lon > 180. {
+		lon - 360.
+	} else {
+		lon
+	}
+}
+
+// endregion: --- Near
+
+// region:    --- BBox
+
+/// A bounding box (degrees), e.g., the visible area of the map.
+/// `min_lon` is the west edge, so it is greater than `max_lon` when the box
+/// crosses the antimeridian (e.g., Fiji).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "BBoxRaw")]
+pub struct BBox {
+	pub min_lat: f64,
+	pub min_lon: f64,
+	pub max_lat: f64,
+	pub max_lon: f64,
+}
+
+#[derive(Deserialize)]
+struct BBoxRaw {
+	min_lat: f64,
+	#[serde(alias = "min_lng")]
+	min_lon: f64,
+	max_lat: f64,
+	#[serde(alias = "max_lng")]
+	max_lon: f64,
+}
+
+impl TryFrom<BBoxRaw> for BBox {
+	type Error = Error;
+
+	fn try_from(raw: BBoxRaw) -> Result<Self> {
+		BBox::new(raw.min_lat, raw.min_lon, raw.max_lat, raw.max_lon)
+	}
+}
+
+impl BBox {
+	pub fn new(min_lat: f64, min_lon: f64, max_lat: f64, max_lon: f64) -> Result<Self> {
+		validate_lat(min_lat)?;
+		validate_lat(max_lat)?;
+		validate_lon(min_lon)?;
+		validate_lon(max_lon)?;
+		if min_lat > max_lat {
+			return Err(Error::BBoxMinLatOverMaxLat { min_lat, max_lat });
+		}
+
+		Ok(BBox {
+			min_lat,
+			min_lon,
+			max_lat,
+			max_lon,
+		})
+	}
+
+	/// The longitude ranges (min, max) of the bbox, two if it crosses the antimeridian.
+	pub fn lon_ranges(&self) -> Vec<(f64, f64)> {
+		if self.min_lon <= self.max_lon {
+			vec![(self.min_lon, self.max_lon)]
+		} else {
+			vec![(self.min_lon, 180.), (-180., self.max_lon)]
+		}
+	}
+}
+
+// endregion: --- BBox
+
+// region:    --- Geohash
+
+/// The geohash of the location, with `precision` characters.
+/// Note: Same as the `geohash_encode` sql function (of the `geohash` columns).
+pub fn geohash_encode(lat: f64, lon: f64, precision: usize) -> String {
+	let (mut lat_range, mut lon_range) = ((-90., 90.), (-180., 180.));
+	let mut hash = String::with_capacity(precision);
+	let (mut ch, mut bit, mut even) = (0, 0, true);
+
+	while hash.len() < precision {
+		let (range, val): (&mut (f64, f64), f64) = if even {
+			(&mut lon_range, lon)
+		} else {
+			(&mut lat_range, lat)
+		};
+		let mid = (range.0 + range.1) / 2.;
+		ch <<= 1;
+		if val >= mid {
+			ch |= 1;
+			range.0 = mid;
+		} else {
+			range.1 = mid;
+		}
+		even = !even;
+
+		bit += 1;
+		if bit == 5 {
+			hash.push(GEOHASH_BASE32[ch] as char);
+			(ch, bit) = (0, 0);
+		}
+	}
+
+	hash
+}
+
+/// The geohash prefixes of the cells covering the bbox, at the finest precision
+/// with at most `max_cells` cells. Empty if the bbox needs more than `max_cells`
+/// cells of precision 1 (e.g., most of the world).
+pub fn geohash_cover(bbox: &BBox, max_cells: usize) -> Vec<String> {
+	for precision in (1..=GEOHASH_PRECISION).rev() {
+		let cells: Vec<_> = bbox
+			.lon_ranges()
+			.into_iter()
+			.map(|(min_lon, max_lon)| CellRange::new(precision, bbox, min_lon, max_lon))
+			.collect();
+		let count: usize = cells.iter().map(CellRange::count).sum();
+		if count > max_cells {
+			continue;
+		}
+
+		let mut hashes: Vec<String> = cells.iter().flat_map(|cells| cells.hashes(precision)).collect();
+		hashes.sort();
+		hashes.dedup();
+		return hashes;
+	}
+
+	Vec::new()
+}
+
+/// The (lat, lon) cell indexes of a bbox (one longitude range) at a precision.
+struct CellRange {
+	lat_cells: (i64, i64),
+	lon_cells: (i64, i64),
+	cell_lat: f64,
+	cell_lon: f64,
+}
+
+impl CellRange {
+	fn new(precision: usize, bbox: &BBox, min_lon: f64, max_lon: f64) -> Self {
+		// Note: The bits alternate, starting with the longitude.
+		let bits = 5 * precision as i32;
+		let (lon_bits, lat_bits) = ((bits + 1) / 2, bits / 2);
+		let (lat_count, lon_count) = (2_i64.pow(lat_bits as u32), 2_i64.pow(lon_bits as u32));
+		let cell_lat = 180. / lat_count as f64;
+		let cell_lon = 360. / lon_count as f64;
+
+		let index = |val: f64, origin: f64, cell: f64, count: i64| {
+			(((val - origin) / cell).floor() as i64).clamp(0, count - 1)
+		};
+
+		CellRange {
+			lat_cells: (
+				index(bbox.min_lat, -90., cell_lat, lat_count),
+				index(bbox.max_lat, -90., cell_lat, lat_count),
+			),
+			lon_cells: (
+				index(min_lon, -180., cell_lon, lon_count),
+				index(max_lon, -180., cell_lon, lon_count),
+			),
+			cell_lat,
+			cell_lon,
+		}
+	}
+
+	fn count(&self) -> usize {
+		let lat = self.lat_cells.1 - self.lat_cells.0 + 1;
+		let lon = self.lon_cells.1 - self.lon_cells.0 + 1;
+
+		(lat * lon) as usize
+	}
+
+	fn hashes(&self, precision: usize) -> Vec<String> {
+		let mut hashes = Vec::with_capacity(self.count());
+		for lat_i in self.lat_cells.0..=self.lat_cells.1 {
+			for lon_i in self.lon_cells.0..=self.lon_cells.1 {
+				// Note: The hash of the cell center.
+				let lat = -90. + (lat_i as f64 + 0.5) * self.cell_lat;
+				let lon = -180. + (lon_i as f64 + 0.5) * self.cell_lon;
+				hashes.push(geohash_encode(lat, lon, precision));
+			}
+		}
+
+		hashes
+	}
+}
+
+// endregion: --- Geohash
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	pub type Result<T> = core::result::Result<T, Error>;
+	pub type Error = Box<dyn std::error::Error>; // For tests.
+
+	use super::*;
+
+	#[test]
+	fn test_location_validate() -> Result<()> {
+		assert!(Location::new(1.28, 103.85).is_ok());
+		assert!(matches!(Location::new(91., 0.), Err(super::Error::LatOutOfRange(_))));
+		assert!(matches!(Location::new(0., -181.), Err(super::Error::LonOutOfRange(_))));
+		assert!(matches!(Location::new(f64::NAN, 0.), Err(super::Error::LatOutOfRange(_))));
+
+		let location: Location = serde_json::from_str(r#"{"lat": 1.28, "lng": 103.85}"#)?;
+		assert_eq!(location, Location::new(1.28, 103.85)?);
+		assert!(serde_json::from_str::<Location>(r#"{"lat": 100, "lon": 0}"#).is_err());
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_distance_km() -> Result<()> {
+		let paris = Location::new(48.8566, 2.3522)?;
+		let london = Location::new(51.5074, -0.1278)?;
+
+		let distance = paris.distance_km(&london);
+
+		assert!((distance - 343.5).abs() < 1., "distance: {distance}");
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_geohash_encode() -> Result<()> {
+		// Note: The reference example of the geohash article.
+		assert_eq!(geohash_encode(57.64911, 10.40744, 11), "u4pruydqqvj");
+		assert_eq!(Location::new(1.2839, 103.8515)?.geohash(6), "w21z71");
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_geohash_cover() -> Result<()> {
+		let near = Near::new(Location::new(1.2839, 103.8515)?, 2.)?;
+
+		let cover = geohash_cover(&near.bbox(), 16);
+
+		assert!(!cover.is_empty() && cover.len() <= 16);
+		let center_hash = near.center().geohash(GEOHASH_PRECISION);
+		assert!(cover.iter().any(|prefix| center_hash.starts_with(prefix)));
+		// a corner of the bbox is covered too
+		let bbox = near.bbox();
+		let corner_hash = geohash_encode(bbox.max_lat, bbox.min_lon, GEOHASH_PRECISION);
+		assert!(cover.iter().any(|prefix| corner_hash.starts_with(prefix)));
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_near_bbox_antimeridian() -> Result<()> {
+		let near = Near::new(Location::new(-17.7, 179.9)?, 50.)?;
+
+		let bbox = near.bbox();
+
+		assert!(bbox.min_lon > bbox.max_lon, "bbox: {bbox:?}");
+		assert_eq!(bbox.lon_ranges().len(), 2);
+		let cover = geohash_cover(&bbox, 16);
+		let east = geohash_encode(-17.7, -179.8, GEOHASH_PRECISION);
+		assert!(cover.iter().any(|prefix| east.starts_with(prefix)));
+
+		Ok(())
+	}
+}
+
+// endregion: --- Tests
@@ -1,6 +1,9 @@
 pub mod config;
 pub mod ctx;
+pub mod geo;
+pub mod migration;
 pub mod model;
+pub mod throttle;
 
 // #[cfg(test)] // Commented during early development.
 pub mod _dev_utils;
\ No newline at end of file
@@ -0,0 +1,56 @@
+use serde::Serialize;
+use serde_with::{serde_as, DisplayFromStr};
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[serde_as]
+#[derive(Debug, Serialize)]
+pub enum Error {
+	// -- Files
+	MigrationDirNotFound(String),
+	FileNameInvalid(String),
+	DuplicateVersion(i64),
+	UpFileMissing(i64),
+
+	// -- Db
+	/// The up file of an applied migration was changed.
+	ChecksumMismatch {
+		version: i64,
+		name: String,
+	},
+	/// Applied in the db, but no migration file (e.g., db ahead of the code).
+	AppliedMigrationUnknown(i64),
+	DownFileMissing(i64),
+	PendingMigrations(Vec<String>),
+
+	// -- Externals
+	Io(#[serde_as(as = "DisplayFromStr")] std::io::Error),
+	Sqlx(#[serde_as(as = "DisplayFromStr")] sqlx::Error),
+}
+
+// region:    --- Froms
+impl From<std::io::Error> for Error {
+	fn from(val: std::io::Error) -> Self {
+		Self::Io(val)
+	}
+}
+
+impl From<sqlx::Error> for Error {
+	fn from(val: sqlx::Error) -> Self {
+		Self::Sqlx(val)
+	}
+}
+// endregion: --- Froms
+
+// region:    --- Error Boilerplate
+impl core::fmt::Display for Error {
+	fn fmt(
+		&self,
+		fmt: &mut core::fmt::Formatter,
+	) -> core::result::Result<(), core::fmt::Error> {
+		write!(fmt, "{self:?}")
+	}
+}
+
+impl std::error::Error for Error {}
+// endregion: --- Error Boilerplate
@@ -0,0 +1,419 @@
+//! Versioned schema migrations.
+//!
+//! - Files `{version}_{name}.up.sql`, with an optional `{version}_{name}.down.sql`
+//!   (e.g., `0002_soft_delete.up.sql`), applied in version order.
+//! - Each migration runs as a whole file (functions and dollar-quoted bodies are fine)
+//!   in its own transaction, and is recorded in the `schema_migration` table with the
+//!   checksum of its up file.
+//! - An applied migration must not be edited (`Error::ChecksumMismatch`), add a new one.
+//!
+//! Applied by `_dev_utils` for dev and tests, and by the `db-migrate` tool otherwise.
+//! The web-server refuses to start with pending migrations.
+
+// region:    --- Modules
+
+mod error;
+
+pub use self::error::{Error, Result};
+
+use crate::model::ModelManager;
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+use sqlx::{Executor, Pool, Postgres, Transaction};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+use tracing::info;
+
+// endregion: --- Modules
+
+/// Note: Same key for all the instances, so only one applies the migrations at a time.
+const MIGRATION_LOCK_KEY: i64 = 0x006d_6170_7374_6572; // "mapster"
+
+const SQL_CREATE_MIGRATION_TABLE: &str = r#"
+CREATE TABLE IF NOT EXISTS schema_migration (
+    version BIGINT PRIMARY KEY,
+    name VARCHAR(255) NOT NULL,
+    checksum VARCHAR(64) NOT NULL,
+    applied_at TIMESTAMPTZ NOT NULL DEFAULT now()
+)"#;
+
+// region:    --- Types
+
+pub struct Migration {
+	pub version: i64,
+	pub name: String,
+	/// sha256 (hex) of the up file.
+	pub checksum: String,
+	up_sql: String,
+	down_sql: Option<String>,
+}
+
+pub struct MigrationStatus {
+	pub version: i64,
+	pub name: String,
+	/// None if pending.
+	pub applied_at: Option<DateTime<Utc>>,
+}
+
+#[derive(sqlx::FromRow)]
+struct AppliedMigration {
+	version: i64,
+	name: String,
+	checksum: String,
+	applied_at: DateTime<Utc>,
+}
+
+// endregion: --- Types
+
+// region:    --- Migrator
+
+pub struct Migrator {
+	/// Sorted by version.
+	migrations: Vec<Migration>,
+}
+
+impl Migrator {
+	/// Load the migration files of a directory (e.g., `sql/migrations`).
+	pub fn from_dir(dir: impl AsRef<Path>) -> Result<Self> {
+		let dir = dir.as_ref();
+		if !dir.is_dir() {
+			return Err(Error::MigrationDirNotFound(dir.display().to_string()));
+		}
+
+		let mut ups: BTreeMap<i64, (String, String)> = BTreeMap::new();
+		let mut downs: HashMap<i64, String> = HashMap::new();
+
+		for entry in fs::read_dir(dir)? {
+			let path = entry?.path();
+			let Some(file_name) = path.file_name().and_then(|n| n.to_str()) else {
+				continue;
+			};
+
+			let (stem, is_up) = if let Some(stem) = file_name.strip_suffix(".up.sql") {
+				(stem, true)
+			} else if let Some(stem) = file_name.strip_suffix(".down.sql") {
+				(stem, false)
+			} else {
+				continue;
+			};
+
+			let (version, name) = parse_file_stem(stem)
+				.ok_or_else(|| Error::FileNameInvalid(file_name.to_string()))?;
+			let sql = fs::read_to_string(&path)?;
+
+			let duplicate = if is_up {
+				ups.insert(version, (name, sql)).is_some()
+			} else {
+				downs.insert(version, sql).is_some()
+			};
+			if duplicate {
+				return Err(Error::DuplicateVersion(version));
+			}
+		}
+
+		if let Some(version) = downs.keys().find(|version| !ups.contains_key(version)) {
+			return Err(Error::UpFileMissing(*version));
+		}
+
+		let migrations = ups
+			.into_iter()
+			.map(|(version, (name, up_sql))| Migration {
+				version,
+				name,
+				checksum: checksum(&up_sql),
+				down_sql: downs.remove(&version),
+				up_sql,
+			})
+			.collect();
+
+		Ok(Migrator { migrations })
+	}
+
+	pub fn migrations(&self) -> &[Migration] {
+		&self.migrations
+	}
+
+	/// All the migrations, with their applied time (None if pending).
+	pub async fn status(&self, mm: &ModelManager) -> Result<Vec<MigrationStatus>> {
+		let applied = self.applied(mm.dbx().db()).await?;
+
+		let status = self
+			.migrations
+			.iter()
+			.map(|migration| MigrationStatus {
+				version: migration.version,
+				name: migration.name.clone(),
+				applied_at: applied.get(&migration.version).map(|a| a.applied_at),
+			})
+			.collect();
+
+		Ok(status)
+	}
+
+	pub async fn pending(&self, mm: &ModelManager) -> Result<Vec<&Migration>> {
+		let applied = self.applied(mm.dbx().db()).await?;
+
+		Ok(self
+			.migrations
+			.iter()
+			.filter(|migration| !applied.contains_key(&migration.version))
+			.collect())
+	}
+
+	/// Returns `Error::PendingMigrations` if some migrations are not applied
+	/// (e.g., on server startup).
+	pub async fn check_no_pending(&self, mm: &ModelManager) -> Result<()> {
+		let pending = self.pending(mm).await?;
+		if pending.is_empty() {
+			return Ok(());
+		}
+
+		Err(Error::PendingMigrations(
+			pending
+				.into_iter()
+				.map(|migration| format!("{:04}_{}", migration.version, migration.name))
+				.collect(),
+		))
+	}
+
+	/// Apply the pending migrations (up to `to_version` if set).
+	/// Returns the applied versions.
+	pub async fn up(&self, mm: &ModelManager, to_version: Option<i64>) -> Result<Vec<i64>> {
+		let db = mm.dbx().db();
+		let pending: Vec<&Migration> = self
+			.pending(mm)
+			.await?
+			.into_iter()
+			.filter(|migration| to_version.is_none_or(|to| migration.version <= to))
+			.collect();
+
+		let mut applied = Vec::new();
+		for migration in pending {
+			let mut txn = begin_locked_txn(db).await?;
+
+			// Note: Could have been applied by another instance while waiting for the lock.
+			let already_applied = sqlx::query("SELECT 1 FROM schema_migration WHERE version = $1")
+				.bind(migration.version)
+				.fetch_optional(&mut *txn)
+				.await?
+				.is_some();
+			if already_applied {
+				continue;
+			}
+
+			// Note: `Executor::execute` (boxed future) rather than `RawSql::execute`,
+			//       which is not `Send` for the callers (higher-ranked lifetime error).
+			txn.execute(sqlx::raw_sql(&migration.up_sql)).await?;
+			sqlx::query("INSERT INTO schema_migration (version, name, checksum) VALUES ($1, $2, $3)")
+				.bind(migration.version)
+				.bind(&migration.name)
+				.bind(&migration.checksum)
+				.execute(&mut *txn)
+				.await?;
+			txn.commit().await?;
+
+			info!("{:<12} - up {:04}_{}", "MIGRATION", migration.version, migration.name);
+			applied.push(migration.version);
+		}
+
+		Ok(applied)
+	}
+
+	/// Roll back the last `steps` applied migrations, in reverse order.
+	/// Returns the rolled back versions.
+	pub async fn down(&self, mm: &ModelManager, steps: usize) -> Result<Vec<i64>> {
+		let db = mm.dbx().db();
+		let applied = self.applied(db).await?;
+
+		let to_rollback: Vec<&Migration> = self
+			.migrations
+			.iter()
+			.rev()
+			.filter(|migration| applied.contains_key(&migration.version))
+			.take(steps)
+			.collect();
+
+		// Note: Checked before any rollback, so it does not stop halfway.
+		if let Some(migration) = to_rollback.iter().find(|m| m.down_sql.is_none()) {
+			return Err(Error::DownFileMissing(migration.version));
+		}
+
+		let mut rolled_back = Vec::new();
+		for migration in to_rollback {
+			let Some(down_sql) = &migration.down_sql else {
+				continue;
+			};
+
+			let mut txn = begin_locked_txn(db).await?;
+			txn.execute(sqlx::raw_sql(down_sql)).await?;
+			sqlx::query("DELETE FROM schema_migration WHERE version = $1")
+				.bind(migration.version)
+				.execute(&mut *txn)
+				.await?;
+			txn.commit().await?;
+
+			info!("{:<12} - down {:04}_{}", "MIGRATION", migration.version, migration.name);
+			rolled_back.push(migration.version);
+		}
+
+		Ok(rolled_back)
+	}
+
+	/// The applied migrations by version, checked against the migration files.
+	async fn applied(&self, db: &Pool<Postgres>) -> Result<HashMap<i64, AppliedMigration>> {
+		let mut txn = begin_locked_txn(db).await?;
+		txn.execute(sqlx::raw_sql(SQL_CREATE_MIGRATION_TABLE)).await?;
+		let applied: Vec<AppliedMigration> = sqlx::query_as(
+			"SELECT version, name, checksum, applied_at FROM schema_migration ORDER BY version",
+		)
+		.fetch_all(&mut *txn)
+		.await?;
+		txn.commit().await?;
+
+		for applied in applied.iter() {
+			let migration = self
+				.migrations
+				.iter()
+				.find(|migration| migration.version == applied.version)
+				.ok_or(Error::AppliedMigrationUnknown(applied.version))?;
+			if migration.checksum != applied.checksum {
+				return Err(Error::ChecksumMismatch {
+					version: applied.version,
+					name: applied.name.clone(),
+				});
+			}
+		}
+
+		Ok(applied.into_iter().map(|a| (a.version, a)).collect())
+	}
+}
+
+// endregion: --- Migrator
+
+// region:    --- Support
+
+/// Begin a transaction holding the migration lock (released on commit or rollback).
+async fn begin_locked_txn(db: &Pool<Postgres>) -> Result<Transaction<'static, Postgres>> {
+	let mut txn = db.begin().await?;
+	sqlx::query("SELECT pg_advisory_xact_lock($1)")
+		.bind(MIGRATION_LOCK_KEY)
+		.execute(&mut *txn)
+		.await?;
+
+	Ok(txn)
+}
+
+/// e.g., "0002_soft_delete" -> (2, "soft_delete")
+fn parse_file_stem(stem: &str) -> Option<(i64, String)> {
+	let (version, name) = stem.split_once('_')?;
+	if version.is_empty() || !version.chars().all(|c| c.is_ascii_digit()) || name.is_empty() {
+		return None;
+	}
+
+	Some((version.parse().ok()?, name.to_string()))
+}
+
+fn checksum(content: &str) -> String {
+	Sha256::digest(content.as_bytes())
+		.iter()
+		.map(|b| format!("{b:02x}"))
+		.collect()
+}
+
+// endregion: --- Support
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	pub type Result<T> = core::result::Result<T, Error>;
+	pub type Error = Box<dyn std::error::Error>; // For tests.
+
+	use super::*;
+	use crate::_dev_utils;
+	use crate::ctx::{Ctx, Role};
+	use crate::model::user::{User, UserBmc};
+	use serial_test::serial;
+
+	#[test]
+	fn test_parse_file_stem() {
+		assert_eq!(
+			parse_file_stem("0002_soft_delete"),
+			Some((2, "soft_delete".to_string()))
+		);
+		assert_eq!(parse_file_stem("v2_soft_delete"), None);
+		assert_eq!(parse_file_stem("0002"), None);
+		assert_eq!(parse_file_stem("0002_"), None);
+	}
+
+	#[test]
+	fn test_from_dir_ok_and_err_up_missing() -> Result<()> {
+		// -- Setup & Fixtures
+		let dir = std::env::temp_dir().join(format!("migration-test-{}", std::process::id()));
+		fs::create_dir_all(&dir)?;
+		fs::write(dir.join("0002_second.up.sql"), "SELECT 2;")?;
+		fs::write(dir.join("0001_first.up.sql"), "SELECT 1;")?;
+		fs::write(dir.join("0001_first.down.sql"), "SELECT -1;")?;
+		fs::write(dir.join("README.md"), "not a migration")?;
+
+		// -- Exec
+		let migrator = Migrator::from_dir(&dir)?;
+		fs::write(dir.join("0003_orphan.down.sql"), "SELECT -3;")?;
+		let res = Migrator::from_dir(&dir);
+
+		// -- Check
+		let versions: Vec<i64> = migrator.migrations().iter().map(|m| m.version).collect();
+		assert_eq!(versions, vec![1, 2]);
+		assert!(migrator.migrations()[0].down_sql.is_some());
+		assert!(migrator.migrations()[1].down_sql.is_none());
+		assert_eq!(migrator.migrations()[0].checksum, checksum("SELECT 1;"));
+		assert!(matches!(res, Err(super::Error::UpFileMissing(3))));
+
+		// -- Clean
+		fs::remove_dir_all(&dir)?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_up_then_no_pending_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let migrator = Migrator::from_dir("../../../sql/migrations")?;
+
+		// -- Exec
+		// Note: Already applied by `init_test`, so nothing to apply.
+		let applied = migrator.up(&mm, None).await?;
+		let status = migrator.status(&mm).await?;
+
+		// -- Check
+		assert!(applied.is_empty());
+		migrator.check_no_pending(&mm).await?;
+		assert_eq!(status.len(), migrator.migrations().len());
+		assert!(status.iter().all(|s| s.applied_at.is_some()));
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_up_ok_root_user() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let root_ctx = Ctx::root_ctx();
+
+		// -- Exec
+		// Note: Created by the migrations (the dev seed only has the demo users).
+		let root: User = UserBmc::get(&root_ctx, &mm, 0).await?;
+
+		// -- Check
+		assert_eq!(root.username, "root");
+		assert_eq!(root.role, Role::Admin);
+
+		Ok(())
+	}
+}
+
+// endregion: --- Tests
@@ -0,0 +1,173 @@
+// region: ---- Modules
+use crate::ctx::Ctx;
+use crate::model::base::DbBmc;
+use crate::model::ModelManager;
+use crate::model::Result;
+use chrono::{DateTime, Utc};
+use modql::field::{Fields, HasSeaFields, SeaFields};
+use sea_query::{Expr, Iden, Order, PostgresQueryBuilder, Query, SimpleExpr};
+use sea_query_binder::SqlxBinder;
+use serde::Serialize;
+use serde_json::Value as Json;
+use sqlx::FromRow;
+// endregion: ---- Modules
+
+// region:    --- AuditLog Types
+
+/// A write of an entity, for the Bmcs with `DbBmc::has_audit_log`
+/// (recorded by the base crud functions).
+#[derive(Clone, Fields, FromRow, Debug, Serialize)]
+pub struct AuditLogEntry {
+	pub id: i64,
+	pub entity: String,
+	pub entity_id: i64,
+	pub op: String,
+	pub changes: Option<Json>,
+	pub actor_id: i64,
+	pub ctime: DateTime<Utc>,
+}
+
+#[derive(Clone, Copy, Debug, derive_more::Display)]
+pub enum AuditOp {
+	#[display("create")]
+	Create,
+	#[display("update")]
+	Update,
+	#[display("delete")]
+	Delete,
+}
+
+#[derive(Iden)]
+enum AuditLogIden {
+	Id,
+	Entity,
+	EntityId,
+	Op,
+	Changes,
+	ActorId,
+}
+
+// endregion: --- AuditLog Types
+
+// region:    --- AuditLogBmc
+pub struct AuditLogBmc;
+
+impl DbBmc for AuditLogBmc {
+	const TABLE: &'static str = "audit_log";
+
+	/// Append only, the actor is `actor_id`.
+	fn has_timestamps() -> bool {
+		false
+	}
+}
+
+impl AuditLogBmc {
+	/// Record a write of an `MC` entity by the ctx user.
+	/// Note: Must be called with the mm (transaction) of the write.
+	pub(in crate::model) async fn record<MC>(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		entity_id: i64,
+		op: AuditOp,
+		changes: Option<Json>,
+	) -> Result<()>
+	where
+		MC: DbBmc,
+	{
+		// -- Build query
+		let mut query = Query::insert();
+		query
+			.into_table(Self::table_ref())
+			.columns([
+				AuditLogIden::Entity,
+				AuditLogIden::EntityId,
+				AuditLogIden::Op,
+				AuditLogIden::Changes,
+				AuditLogIden::ActorId,
+			])
+			.values([
+				MC::TABLE.into(),
+				entity_id.into(),
+				op.to_string().into(),
+				changes.into(),
+				ctx.user_id().into(),
+			])?;
+
+		// -- Exec query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		mm.dbx().execute(sqlx::query_with(&sql, values)).await?;
+
+		Ok(())
+	}
+
+	/// The history of an `MC` entity, oldest first.
+	/// Note: The permission is checked by the caller (e.g., `PostBmc::history`).
+	pub async fn list_for_entity<MC>(
+		_ctx: &Ctx,
+		mm: &ModelManager,
+		entity_id: i64,
+	) -> Result<Vec<AuditLogEntry>>
+	where
+		MC: DbBmc,
+	{
+		// -- Build query
+		let mut query = Query::select();
+		query
+			.from(Self::table_ref())
+			.columns(AuditLogEntry::sea_column_refs())
+			.and_where(Expr::col(AuditLogIden::Entity).eq(MC::TABLE))
+			.and_where(Expr::col(AuditLogIden::EntityId).eq(entity_id))
+			.order_by(AuditLogIden::Id, Order::Asc);
+
+		// -- Exec query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let sqlx_query = sqlx::query_as_with::<_, AuditLogEntry, _>(&sql, values);
+		let entries = mm.dbx().fetch_all(sqlx_query).await?;
+
+		Ok(entries)
+	}
+
+	/// Clear the changes (fields sent) of the writes of an actor, keeping who, when,
+	/// and what operation. On user erasure, since the changes can hold its content.
+	pub async fn clear_changes_for_actor(
+		_ctx: &Ctx,
+		mm: &ModelManager,
+		actor_id: i64,
+	) -> Result<u64> {
+		// -- Build query
+		let mut query = Query::update();
+		query
+			.table(Self::table_ref())
+			.values([(AuditLogIden::Changes, Option::<Json>::None.into())])
+			.and_where(Expr::col(AuditLogIden::ActorId).eq(actor_id))
+			.and_where(Expr::col(AuditLogIden::Changes).is_not_null());
+
+		// -- Exec query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let count = mm.dbx().execute(sqlx::query_with(&sql, values)).await?;
+
+		Ok(count)
+	}
+}
+
+/// The fields sent to a write, as a json object (column -> value).
+pub(in crate::model) fn fields_to_json(fields: &SeaFields) -> Json {
+	let changes = fields
+		.clone()
+		.zip()
+		.map(|(iden, value)| (iden.to_string(), expr_to_json(&value)))
+		.collect();
+
+	Json::Object(changes)
+}
+
+fn expr_to_json(expr: &SimpleExpr) -> Json {
+	match expr {
+		SimpleExpr::Value(value) => sea_query::sea_value_to_json_value(value),
+		// e.g., a postgres enum value (see `UserBmc::update_role`)
+		SimpleExpr::AsEnum(_, expr) => expr_to_json(expr),
+		other => Json::String(format!("{other:?}")),
+	}
+}
+
+// endregion: --- AuditLogBmc
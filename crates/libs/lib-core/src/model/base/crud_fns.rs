@@ -1,15 +1,17 @@
 #![allow(unused)]
 
 use crate::ctx::Ctx;
+use crate::model::base::page::{Cursor, PageOrder, PageRow, PAGE_ID, PAGE_KEY};
 use crate::model::base::{
-	prep_fields_for_create, prep_fields_for_update, CommonIden, DbBmc,
-	LIST_LIMIT_DEFAULT, LIST_LIMIT_MAX,
+	not_deleted_cond, owner_scope, prep_fields_for_create, prep_fields_for_update, CommonIden, DbBmc,
+	Page, PageOptions, LIST_LIMIT_DEFAULT, LIST_LIMIT_MAX, PAGE_LIMIT_DEFAULT,
 };
+use crate::model::audit_log::{fields_to_json, AuditLogBmc, AuditOp};
 use crate::model::ModelManager;
 use crate::model::{Error, Result};
-use modql::field::HasSeaFields;
+use modql::field::{HasSeaFields, SeaField, SeaFields};
 use modql::filter::{FilterGroups, ListOptions};
-use sea_query::{Condition, Expr, PostgresQueryBuilder, Query};
+use sea_query::{Alias, Condition, Expr, PostgresQueryBuilder, Query, SimpleExpr};
 use sea_query_binder::SqlxBinder;
 use sqlx::postgres::PgRow;
 use sqlx::FromRow;
@@ -28,6 +30,7 @@ where
 
 	// -- Extract fields (name / sea-query value expression)
 	let mut fields = data.not_none_sea_fields();
+	let changes = MC::has_audit_log().then(|| fields_to_json(&fields));
 	prep_fields_for_create::<MC>(&mut fields, user_id);
 
 	// -- Build query
@@ -41,12 +44,22 @@ where
 
 	// -- Exec query
 	let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
-	let sqlx_query = sqlx::query_as_with::<_, (i64,), _>(&sql, values);
-	// NOTE: For now, we will use the _txn for all create.
-	//       We could have a with_txn as function argument if perf is an issue (it should not be)
-	let (id,) = mm.dbx().fetch_one(sqlx_query).await?;
-
-	Ok(id)
+	audit_txn::<MC, _, _, _>(mm, |mm| {
+		let sql = sql.clone();
+		let values = values.clone();
+		let changes = changes.clone();
+		async move {
+			let sqlx_query = sqlx::query_as_with::<_, (i64,), _>(&sql, values);
+			// NOTE: For now, we will use the _txn for all create.
+			//       We could have a with_txn as function argument if perf is an issue (it should not be)
+			let (id,) = mm.dbx().fetch_one(sqlx_query).await?;
+			if MC::has_audit_log() {
+				AuditLogBmc::record::<MC>(ctx, &mm, id, AuditOp::Create, changes).await?;
+			}
+			Ok(id)
+		}
+	})
+	.await
 }
 
 pub async fn create_many<MC, E>(
@@ -59,13 +72,16 @@ where
 	E: HasSeaFields,
 {
 	let user_id = ctx.user_id();
-	let mut ids = Vec::with_capacity(data.len());
 
 	// Prepare insert query
 	let mut query = Query::insert();
+	let mut changes = Vec::with_capacity(data.len());
 
 	for item in data {
 		let mut fields = item.not_none_sea_fields();
+		if MC::has_audit_log() {
+			changes.push(fields_to_json(&fields));
+		}
 		prep_fields_for_create::<MC>(&mut fields, user_id);
 		let (columns, sea_values) = fields.for_sea_insert();
 
@@ -80,20 +96,28 @@ where
 
 	// Execute query
 	let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
-	let sqlx_query = sqlx::query_as_with::<_, (i64,), _>(&sql, values);
-
-	let rows = mm.dbx().fetch_all(sqlx_query).await?;
-
-	for row in rows {
-		let (id,): (i64,) = row;
-		ids.push(id);
-	}
-
-	Ok(ids)
+	audit_txn::<MC, _, _, _>(mm, |mm| {
+		let sql = sql.clone();
+		let values = values.clone();
+		let changes = changes.clone();
+		async move {
+			let sqlx_query = sqlx::query_as_with::<_, (i64,), _>(&sql, values);
+			let rows = mm.dbx().fetch_all(sqlx_query).await?;
+			let ids: Vec<i64> = rows.into_iter().map(|(id,)| id).collect();
+
+			// Note: The ids are returned in the order of the values.
+			for (id, changes) in ids.iter().zip(changes) {
+				AuditLogBmc::record::<MC>(ctx, &mm, *id, AuditOp::Create, Some(changes))
+					.await?;
+			}
+			Ok(ids)
+		}
+	})
+	.await
 }
 
 pub async fn get<MC, E>(
-    _ctx: &Ctx, 
+    ctx: &Ctx, 
     mm: &ModelManager, 
     id: i64
 ) -> Result<E>
@@ -108,6 +132,12 @@ where
 		.from(MC::table_ref())
 		.columns(E::sea_column_refs())
 		.and_where(Expr::col(CommonIden::Id).eq(id));
+	if let Some(not_deleted) = not_deleted_cond::<MC>() {
+		query.and_where(not_deleted);
+	}
+	if let Some(visible_cond) = MC::visible_cond(ctx) {
+		query.and_where(visible_cond);
+	}
 
 	// -- Exec query
 	let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
@@ -124,6 +154,39 @@ where
 	Ok(entity)
 }
 
+/// Check that the entity exists and that the ctx can write it (see `owner_scope`).
+/// e.g., before creating a child entity (the media of a post).
+pub async fn check_writable<MC>(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()>
+where
+	MC: DbBmc,
+{
+	// -- Build query
+	let mut query = Query::select();
+	query
+		.from(MC::table_ref())
+		.column(CommonIden::Id)
+		.and_where(Expr::col(CommonIden::Id).eq(id));
+	if let Some(not_deleted) = not_deleted_cond::<MC>() {
+		query.and_where(not_deleted);
+	}
+	if let Some(owner_cond) = owner_scope::<MC>(ctx) {
+		query.and_where(owner_cond);
+	}
+
+	// -- Exec query
+	let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+	let sqlx_query = sqlx::query_as_with::<_, (i64,), _>(&sql, values);
+	mm.dbx()
+		.fetch_optional(sqlx_query)
+		.await?
+		.ok_or(Error::EntityNotFound {
+			entity: MC::TABLE,
+			id,
+		})?;
+
+	Ok(())
+}
+
 pub async fn first<MC, E, F>(
 	ctx: &Ctx,
 	mm: &ModelManager,
@@ -162,7 +225,7 @@ where
 }
 
 pub async fn list<MC, E, F>(
-	_ctx: &Ctx,
+	ctx: &Ctx,
 	mm: &ModelManager,
 	filter: Option<F>,
 	list_options: Option<ListOptions>,
@@ -176,6 +239,12 @@ where
 	// -- Build the query
 	let mut query = Query::select();
 	query.from(MC::table_ref()).columns(E::sea_column_refs());
+	if let Some(not_deleted) = not_deleted_cond::<MC>() {
+		query.and_where(not_deleted);
+	}
+	if let Some(visible_cond) = MC::visible_cond(ctx) {
+		query.and_where(visible_cond);
+	}
 
 	// condition from filter
 	if let Some(filter) = filter {
@@ -196,8 +265,82 @@ where
 	Ok(entities)
 }
 
+/// List a page of entities after the cursor of `page_options` (keyset pagination,
+/// see the `page` module), ordered by its sort key and id.
+pub async fn list_page<MC, E, F>(
+	ctx: &Ctx,
+	mm: &ModelManager,
+	filter: Option<F>,
+	page_options: PageOptions,
+) -> Result<Page<E>>
+where
+	MC: DbBmc,
+	F: Into<FilterGroups>,
+	E: for<'r> FromRow<'r, PgRow> + Unpin + Send,
+	E: HasSeaFields,
+{
+	let PageOptions {
+		cursor,
+		limit,
+		order_by,
+	} = page_options;
+	let page_order = PageOrder::new::<MC>(order_by.as_deref())?;
+	let limit = compute_page_limit(limit)?;
+
+	// -- Build the query
+	let mut query = Query::select();
+	query
+		.from(MC::table_ref())
+		.columns(E::sea_column_refs())
+		.expr_as(Expr::col(page_order.iden.clone()), Alias::new(PAGE_KEY))
+		.expr_as(Expr::col(CommonIden::Id), Alias::new(PAGE_ID));
+	if let Some(not_deleted) = not_deleted_cond::<MC>() {
+		query.and_where(not_deleted);
+	}
+	if let Some(visible_cond) = MC::visible_cond(ctx) {
+		query.and_where(visible_cond);
+	}
+
+	// condition from filter
+	if let Some(filter) = filter {
+		let filters: FilterGroups = filter.into();
+		let cond: Condition = filters.try_into()?;
+		query.cond_where(cond);
+	}
+	// after the cursor
+	if let Some(cursor) = cursor {
+		let cursor = Cursor::decode(&cursor)?;
+		query.and_where(page_order.after_cond(&cursor)?);
+	}
+	// Note: One more row than the limit, to know if there is a next page.
+	query
+		.order_by(page_order.iden.clone(), page_order.order.clone())
+		.order_by(CommonIden::Id, page_order.order.clone())
+		.limit(limit as u64 + 1);
+
+	// -- Execute the query
+	let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+
+	let sqlx_query = sqlx::query_as_with::<_, PageRow<E>, _>(&sql, values);
+	let mut rows = mm.dbx().fetch_all(sqlx_query).await?;
+
+	// -- Build the page
+	let has_more = rows.len() as i64 > limit;
+	rows.truncate(limit as usize);
+	let next_cursor = rows
+		.last()
+		.filter(|_| has_more)
+		.map(|row| page_order.cursor(row.key.clone(), row.id).encode());
+
+	Ok(Page {
+		items: rows.into_iter().map(|row| row.entity).collect(),
+		next_cursor,
+		has_more,
+	})
+}
+
 pub async fn count<MC, F>(
-	_ctx: &Ctx,
+	ctx: &Ctx,
 	mm: &ModelManager,
 	filter: Option<F>,
 ) -> Result<i64>
@@ -211,6 +354,12 @@ where
 		.from(MC::table_ref())
 		.expr(Expr::col(sea_query::Asterisk).count())
 		.to_owned();
+	if let Some(not_deleted) = not_deleted_cond::<MC>() {
+		query.and_where(not_deleted);
+	}
+	if let Some(visible_cond) = MC::visible_cond(ctx) {
+		query.and_where(visible_cond);
+	}
 
 	// condition from filter
 	if let Some(filter) = filter {
@@ -243,6 +392,7 @@ where
 {
 	// -- Prep Fields
 	let mut fields = data.not_none_sea_fields();
+	let changes = MC::has_audit_log().then(|| fields_to_json(&fields));
 	prep_fields_for_update::<MC>(&mut fields, ctx.user_id());
 
 	// -- Build query
@@ -252,25 +402,136 @@ where
 		.table(MC::table_ref())
 		.values(fields)
 		.and_where(Expr::col(CommonIden::Id).eq(id));
+	if let Some(not_deleted) = not_deleted_cond::<MC>() {
+		query.and_where(not_deleted);
+	}
+	// Note: Not found when not owned, so the ids of the others are not disclosed.
+	if let Some(owner_cond) = owner_scope::<MC>(ctx) {
+		query.and_where(owner_cond);
+	}
 
 	// -- Execute query
 	let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
-	let sqlx_query = sqlx::query_with(&sql, values);
-	let count = mm.dbx().execute(sqlx_query).await?;
+	audit_txn::<MC, _, _, _>(mm, |mm| {
+		let sql = sql.clone();
+		let values = values.clone();
+		let changes = changes.clone();
+		async move {
+			let sqlx_query = sqlx::query_with(&sql, values);
+			let count = mm.dbx().execute(sqlx_query).await?;
+
+			// -- Check result
+			if count == 0 {
+				return Err(Error::EntityNotFound {
+					entity: MC::TABLE,
+					id,
+				});
+			}
+			if MC::has_audit_log() {
+				AuditLogBmc::record::<MC>(ctx, &mm, id, AuditOp::Update, changes).await?;
+			}
+			Ok(())
+		}
+	})
+	.await
+}
 
-	// -- Check result
-	if count == 0 {
-		Err(Error::EntityNotFound {
-			entity: MC::TABLE,
-			id,
-		})
-	} else {
-		Ok(())
+/// Update the entity only if its version is still `version` (optimistic concurrency,
+/// see `DbBmc::has_version`), and return its new version.
+/// Returns `Error::VersionConflict` (with the current version) if updated in between.
+pub async fn update_if_version<MC, E>(
+	ctx: &Ctx,
+	mm: &ModelManager,
+	id: i64,
+	version: i64,
+	data: E,
+) -> Result<i64>
+where
+	MC: DbBmc,
+	E: HasSeaFields,
+{
+	if !MC::has_version() {
+		return Err(Error::VersionNotSupported { entity: MC::TABLE });
+	}
+
+	// -- Prep Fields
+	let mut fields = data.not_none_sea_fields();
+	let changes = MC::has_audit_log().then(|| fields_to_json(&fields));
+	prep_fields_for_update::<MC>(&mut fields, ctx.user_id());
+
+	// -- Build query
+	let fields = fields.for_sea_update();
+	let mut query = Query::update();
+	query
+		.table(MC::table_ref())
+		.values(fields)
+		.and_where(Expr::col(CommonIden::Id).eq(id))
+		.and_where(Expr::col(CommonIden::Version).eq(version))
+		.returning_col(CommonIden::Version);
+	if let Some(not_deleted) = not_deleted_cond::<MC>() {
+		query.and_where(not_deleted);
+	}
+	if let Some(owner_cond) = owner_scope::<MC>(ctx) {
+		query.and_where(owner_cond);
+	}
+
+	// -- Execute query
+	let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+	audit_txn::<MC, _, _, _>(mm, |mm| {
+		let sql = sql.clone();
+		let values = values.clone();
+		let changes = changes.clone();
+		async move {
+			let sqlx_query = sqlx::query_as_with::<_, (i64,), _>(&sql, values);
+			let Some((new_version,)) = mm.dbx().fetch_optional(sqlx_query).await? else {
+				// Note: Not updated, either a version conflict or not found (or not owned).
+				return Err(match current_version::<MC>(ctx, &mm, id).await? {
+					Some(current_version) => Error::VersionConflict {
+						entity: MC::TABLE,
+						id,
+						current_version,
+					},
+					None => Error::EntityNotFound {
+						entity: MC::TABLE,
+						id,
+					},
+				});
+			};
+			if MC::has_audit_log() {
+				AuditLogBmc::record::<MC>(ctx, &mm, id, AuditOp::Update, changes).await?;
+			}
+			Ok(new_version)
+		}
+	})
+	.await
+}
+
+/// The current version of the entity, if writable by the ctx (see `update_if_version`).
+async fn current_version<MC>(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<Option<i64>>
+where
+	MC: DbBmc,
+{
+	let mut query = Query::select();
+	query
+		.from(MC::table_ref())
+		.column(CommonIden::Version)
+		.and_where(Expr::col(CommonIden::Id).eq(id));
+	if let Some(not_deleted) = not_deleted_cond::<MC>() {
+		query.and_where(not_deleted);
+	}
+	if let Some(owner_cond) = owner_scope::<MC>(ctx) {
+		query.and_where(owner_cond);
 	}
+
+	let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+	let sqlx_query = sqlx::query_as_with::<_, (i64,), _>(&sql, values);
+	let version = mm.dbx().fetch_optional(sqlx_query).await?;
+
+	Ok(version.map(|(version,)| version))
 }
 
 pub async fn delete<MC>(
-    _ctx: &Ctx, 
+    ctx: &Ctx, 
     mm: &ModelManager, 
     id: i64
 ) -> Result<()>
@@ -282,25 +543,84 @@ where
 	query
 		.from_table(MC::table_ref())
 		.and_where(Expr::col(CommonIden::Id).eq(id));
+	if let Some(owner_cond) = owner_scope::<MC>(ctx) {
+		query.and_where(owner_cond);
+	}
 
 	// -- Execute query
 	let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
-	let sqlx_query = sqlx::query_with(&sql, values);
-	let count = mm.dbx().execute(sqlx_query).await?;
+	audit_txn::<MC, _, _, _>(mm, |mm| {
+		let sql = sql.clone();
+		let values = values.clone();
+		async move {
+			let sqlx_query = sqlx::query_with(&sql, values);
+			let count = mm.dbx().execute(sqlx_query).await?;
+
+			// -- Check result
+			if count == 0 {
+				return Err(Error::EntityNotFound {
+					entity: MC::TABLE,
+					id,
+				});
+			}
+			if MC::has_audit_log() {
+				AuditLogBmc::record::<MC>(ctx, &mm, id, AuditOp::Delete, None).await?;
+			}
+			Ok(())
+		}
+	})
+	.await
+}
 
-	// -- Check result
-	if count == 0 {
-		Err(Error::EntityNotFound {
-			entity: MC::TABLE,
-			id,
-		})
-	} else {
-		Ok(())
+/// Soft delete an entity (sets `deleted`, with mid/mtime), for the Bmcs with
+/// `has_soft_delete`. The row is then hidden by the reads.
+pub async fn soft_delete<MC>(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()>
+where
+	MC: DbBmc,
+{
+	// -- Prep Fields
+	let mut fields = SeaFields::new(vec![SeaField::new(CommonIden::Deleted, true)]);
+	prep_fields_for_update::<MC>(&mut fields, ctx.user_id());
+
+	// -- Build query
+	let fields = fields.for_sea_update();
+	let mut query = Query::update();
+	query
+		.table(MC::table_ref())
+		.values(fields)
+		.and_where(Expr::col(CommonIden::Id).eq(id))
+		.and_where(Expr::col(CommonIden::Deleted).eq(false));
+	if let Some(owner_cond) = owner_scope::<MC>(ctx) {
+		query.and_where(owner_cond);
 	}
+
+	// -- Execute query
+	let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+	audit_txn::<MC, _, _, _>(mm, |mm| {
+		let sql = sql.clone();
+		let values = values.clone();
+		async move {
+			let sqlx_query = sqlx::query_with(&sql, values);
+			let count = mm.dbx().execute(sqlx_query).await?;
+
+			// -- Check result
+			if count == 0 {
+				return Err(Error::EntityNotFound {
+					entity: MC::TABLE,
+					id,
+				});
+			}
+			if MC::has_audit_log() {
+				AuditLogBmc::record::<MC>(ctx, &mm, id, AuditOp::Delete, None).await?;
+			}
+			Ok(())
+		}
+	})
+	.await
 }
 
 pub async fn delete_many<MC>(
-	_ctx: &Ctx,
+	ctx: &Ctx,
 	mm: &ModelManager,
 	ids: Vec<i64>,
 ) -> Result<u64>
@@ -316,23 +636,94 @@ where
 	query
 		.from_table(MC::table_ref())
 		.and_where(Expr::col(CommonIden::Id).is_in(ids.clone()));
+	if let Some(owner_cond) = owner_scope::<MC>(ctx) {
+		query.and_where(owner_cond);
+	}
 
 	// -- Execute query
 	let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
-	let sqlx_query = sqlx::query_with(&sql, values);
-	let result = mm.dbx().execute(sqlx_query).await?;
+	audit_txn::<MC, _, _, _>(mm, |mm| {
+		let sql = sql.clone();
+		let values = values.clone();
+		let ids = ids.clone();
+		async move {
+			let sqlx_query = sqlx::query_with(&sql, values);
+			let result = mm.dbx().execute(sqlx_query).await?;
+
+			// -- Check result
+			if result as usize != ids.len() {
+				return Err(Error::EntityNotFound {
+					entity: MC::TABLE,
+					id: 0, // Using 0 because multiple IDs could be not found, you may want to improve error handling here
+				});
+			}
+			if MC::has_audit_log() {
+				for id in ids.iter() {
+					AuditLogBmc::record::<MC>(ctx, &mm, *id, AuditOp::Delete, None).await?;
+				}
+			}
+			Ok(result)
+		}
+	})
+	.await
+}
 
-	// -- Check result
-	if result as usize != ids.len() {
-		Err(Error::EntityNotFound {
-			entity: MC::TABLE,
-			id: 0, // Using 0 because multiple IDs could be not found, you may want to improve error handling here
-		})
+/// Delete all the entities matching a condition, and returns the number deleted.
+/// e.g., the sessions of a user on erasure.
+/// Note: Not scoped by the ctx, so the caller must check the permission.
+pub async fn delete_where<MC>(
+	ctx: &Ctx,
+	mm: &ModelManager,
+	cond: SimpleExpr,
+) -> Result<u64>
+where
+	MC: DbBmc,
+{
+	// -- Build query
+	let mut query = Query::delete();
+	query
+		.from_table(MC::table_ref())
+		.and_where(cond)
+		.returning_col(CommonIden::Id);
+
+	// -- Execute query
+	let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+	audit_txn::<MC, _, _, _>(mm, |mm| {
+		let sql = sql.clone();
+		let values = values.clone();
+		async move {
+			let sqlx_query = sqlx::query_as_with::<_, (i64,), _>(&sql, values);
+			let ids = mm.dbx().fetch_all(sqlx_query).await?;
+			if MC::has_audit_log() {
+				for (id,) in ids.iter() {
+					AuditLogBmc::record::<MC>(ctx, &mm, *id, AuditOp::Delete, None).await?;
+				}
+			}
+			Ok(ids.len() as u64)
+		}
+	})
+	.await
+}
+
+// region:    --- Audit Txn
+
+/// Run the write `f` in a transaction if `MC::has_audit_log`, so the audit row is
+/// written in the same transaction as the entity (nested in the caller transaction, if any).
+async fn audit_txn<MC, F, Fut, T>(mm: &ModelManager, f: F) -> Result<T>
+where
+	MC: DbBmc,
+	F: Fn(ModelManager) -> Fut,
+	Fut: Future<Output = Result<T>> + Send,
+{
+	if MC::has_audit_log() {
+		mm.transaction(f).await
 	} else {
-		Ok(result)
+		f(mm.clone()).await
 	}
 }
 
+// endregion: --- Audit Txn
+
 pub fn compute_list_options(
 	list_options: Option<ListOptions>,
 ) -> Result<ListOptions> {
@@ -360,4 +751,15 @@ pub fn compute_list_options(
 			order_bys: Some("id".into()),
 		})
 	}
-}
\ No newline at end of file
+}
+/// The limit of a page, validated (at least 1, at most `LIST_LIMIT_MAX`).
+pub(in crate::model) fn compute_page_limit(limit: Option<i64>) -> Result<i64> {
+	match limit {
+		Some(limit) if limit > LIST_LIMIT_MAX => Err(Error::ListLimitOverMax {
+			max: LIST_LIMIT_MAX,
+			actual: limit,
+		}),
+		Some(limit) => Ok(limit.max(1)),
+		None => Ok(PAGE_LIMIT_DEFAULT),
+	}
+}
@@ -56,6 +56,15 @@ macro_rules! generate_common_bmc_fns {
 					base::list::<Self, _, _>(ctx, mm, filter, list_options).await
 				}
 
+				pub async fn list_page(
+					ctx: &Ctx,
+					mm: &ModelManager,
+					filter: Option<Vec<$filter>>,
+					page_options: base::PageOptions,
+				) -> Result<base::Page<$entity>> {
+					base::list_page::<Self, _, _>(ctx, mm, filter, page_options).await
+				}
+
 				pub async fn count(
 					ctx: &Ctx,
 					mm: &ModelManager,
@@ -74,6 +83,16 @@ macro_rules! generate_common_bmc_fns {
 				) -> Result<()> {
 					base::update::<Self, _>(ctx, mm, id, entity_u).await
 				}
+
+				pub async fn update_if_version(
+					ctx: &Ctx,
+					mm: &ModelManager,
+					id: i64,
+					version: i64,
+					entity_u: $for_update,
+				) -> Result<i64> {
+					base::update_if_version::<Self, _>(ctx, mm, id, version, entity_u).await
+				}
 			)?
 
 				pub async fn delete(
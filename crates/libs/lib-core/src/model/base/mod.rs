@@ -2,14 +2,18 @@
 
 mod crud_fns;
 mod macros;
+mod page;
 mod utils;
 
 // -- Flatten hierarchy for user code.
 pub use crud_fns::*;
+pub use page::{Page, PageKeyType, PageOptions};
+pub(in crate::model) use page::OffsetCursor;
 pub use utils::*;
 
+use crate::ctx::Ctx;
 use modql::SIden;
-use sea_query::{Iden, IntoIden, TableRef};
+use sea_query::{Iden, IntoIden, SimpleExpr, TableRef};
 
 // endregion: --- Modules
 
@@ -17,6 +21,7 @@ use sea_query::{Iden, IntoIden, TableRef};
 
 const LIST_LIMIT_DEFAULT: i64 = 1000;
 const LIST_LIMIT_MAX: i64 = 5000;
+const PAGE_LIMIT_DEFAULT: i64 = 50;
 
 // endregion: --- Consts
 
@@ -26,6 +31,8 @@ const LIST_LIMIT_MAX: i64 = 5000;
 pub enum CommonIden {
 	Id,
 	OwnerId,
+	Deleted,
+	Version,
 }
 
 #[derive(Iden)]
@@ -62,9 +69,59 @@ pub trait DbBmc {
 
 	/// Specifies if the entity table managed by this BMC
 	/// has an `owner_id` column that needs to be set on create (by default ctx.user_id).
+	/// The writes (update, delete) are then scoped to the ctx user, unless its role
+	/// can moderate (see `owner_scope`).
 	///
 	/// default: false
 	fn has_owner_id() -> bool {
 		false
 	}
+
+	/// Specifies that the entity table has a `deleted` column (soft delete, see `base::soft_delete`).
+	/// The soft-deleted rows are then hidden by the reads (get, first, list, count)
+	/// and the updates.
+	///
+	/// default: false
+	fn has_soft_delete() -> bool {
+		false
+	}
+
+	/// Specifies that the entity table has a `version` column, incremented by each update,
+	/// for the optimistic concurrency of `base::update_if_version` (e.g., two devices
+	/// editing the same post).
+	///
+	/// default: false
+	fn has_version() -> bool {
+		false
+	}
+
+	/// Specifies that the writes of the base crud functions (create, update, delete)
+	/// are recorded in the `audit_log` (actor, operation, and the fields sent),
+	/// in the same transaction as the write. See `AuditLogBmc::list_for_entity`.
+	///
+	/// default: false
+	fn has_audit_log() -> bool {
+		false
+	}
+
+	/// Specifies the columns a page can be ordered by (see `base::list_page`), with their type.
+	/// They must be NOT NULL columns of the entity table.
+	///
+	/// default: id, and ctime, mtime if `has_timestamps`
+	fn page_order_columns() -> Vec<(&'static str, PageKeyType)> {
+		let mut columns = vec![("id", PageKeyType::Int)];
+		if Self::has_timestamps() {
+			columns.push(("ctime", PageKeyType::Time));
+			columns.push(("mtime", PageKeyType::Time));
+		}
+		columns
+	}
+
+	/// Specifies the condition of the rows visible to the ctx, applied by the reads
+	/// (get, first, list, count). e.g., the unpublished posts only visible to their owner.
+	///
+	/// default: None (all rows visible)
+	fn visible_cond(_ctx: &Ctx) -> Option<SimpleExpr> {
+		None
+	}
 }
\ No newline at end of file
@@ -0,0 +1,249 @@
+//! Keyset (cursor) pagination of the base list (see `base::list_page`).
+//!
+//! The page is ordered by a sort key column and the id (tie breaker), and the next page
+//! starts after the `(sort key, id)` of the last item, given by an opaque cursor
+//! (only valid for the order it was taken in).
+//! Unlike the offset of `ListOptions`, it stays fast and consistent for deep pages
+//! (e.g., the infinite-scroll feed), even with rows inserted in between.
+//!
+//! Note: The sort key columns are allow-listed by `DbBmc::page_order_columns`, which must be
+//!       NOT NULL (rows with a null key are skipped) and of a `PageKeyType`.
+
+use crate::model::base::{CommonIden, DbBmc};
+use crate::model::{Error, Result};
+use chrono::{DateTime, Utc};
+use lib_utils::b64::{b64u_decode, b64u_encode};
+use sea_query::{Alias, DynIden, Expr, IntoIden, Order, SimpleExpr, Value};
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgRow;
+use sqlx::{Column, FromRow, Row, TypeInfo};
+
+/// The alias of the sort key and id columns selected for the cursor.
+pub(super) const PAGE_KEY: &str = "__page_key";
+pub(super) const PAGE_ID: &str = "__page_id";
+
+// region:    --- PageOptions
+
+/// The options of a page (e.g., from the query string of a list endpoint).
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PageOptions {
+	/// The `next_cursor` of the previous page (None for the first page).
+	pub cursor: Option<String>,
+	pub limit: Option<i64>,
+	/// The sort key column, with a `!` prefix for descending (e.g., "!ctime").
+	/// Must be the same for all the pages of a cursor. default: "id"
+	pub order_by: Option<String>,
+}
+
+/// The type of a sort key column (see `DbBmc::page_order_columns`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageKeyType {
+	/// INT2, INT4, INT8
+	Int,
+	/// TEXT, VARCHAR
+	Text,
+	/// TIMESTAMPTZ
+	Time,
+}
+
+/// The sort key of the page, validated against the allow-listed columns.
+pub(super) struct PageOrder {
+	pub iden: DynIden,
+	pub order: Order,
+	pub key_type: PageKeyType,
+	/// The column, with a `!` prefix for descending (e.g., "!ctime"), as in the cursors.
+	order_by: String,
+}
+
+impl PageOrder {
+	pub fn new<MC>(order_by: Option<&str>) -> Result<Self>
+	where
+		MC: DbBmc,
+	{
+		let order_by = order_by.unwrap_or("id");
+		let (name, order) = match order_by.strip_prefix('!') {
+			Some(name) => (name, Order::Desc),
+			None => (order_by, Order::Asc),
+		};
+
+		let key_type = MC::page_order_columns()
+			.into_iter()
+			.find(|(column, _)| *column == name)
+			.map(|(_, key_type)| key_type)
+			.ok_or_else(|| Error::PageOrderByInvalid {
+				order_by: order_by.to_string(),
+			})?;
+
+		Ok(PageOrder {
+			iden: Alias::new(name).into_iden(),
+			order,
+			key_type,
+			order_by: order_by.to_string(),
+		})
+	}
+
+	/// The cursor of a row (its sort key and id), in this order.
+	pub fn cursor(&self, key: CursorKey, id: i64) -> Cursor {
+		Cursor {
+			key,
+			id,
+			order_by: self.order_by.clone(),
+		}
+	}
+
+	/// The condition of the rows after the cursor, in this order.
+	/// Fails if the cursor was taken in another order (column or direction).
+	pub fn after_cond(&self, cursor: &Cursor) -> Result<SimpleExpr> {
+		if cursor.order_by != self.order_by || cursor.key.key_type() != self.key_type {
+			return Err(Error::PageCursorInvalid);
+		}
+
+		let row = Expr::tuple([
+			Expr::col(self.iden.clone()).into(),
+			Expr::col(CommonIden::Id).into(),
+		]);
+		let after = Expr::tuple([
+			Expr::value(cursor.key.clone().into_value()),
+			Expr::value(cursor.id),
+		]);
+		let cond = match self.order {
+			Order::Desc => row.lt(after),
+			_ => row.gt(after),
+		};
+
+		Ok(cond)
+	}
+}
+
+// endregion: --- PageOptions
+
+// region:    --- Page
+
+/// A page of entities, with the cursor of the next one.
+#[derive(Debug, Serialize)]
+pub struct Page<E> {
+	pub items: Vec<E>,
+	/// The cursor of the next page (see `PageOptions::cursor`), None if no more items.
+	pub next_cursor: Option<String>,
+	pub has_more: bool,
+}
+
+/// An entity row with the sort key and id of its cursor (see `PageOrder::cursor`).
+pub(super) struct PageRow<E> {
+	pub entity: E,
+	pub key: CursorKey,
+	pub id: i64,
+}
+
+impl<'r, E> FromRow<'r, PgRow> for PageRow<E>
+where
+	E: FromRow<'r, PgRow>,
+{
+	fn from_row(row: &'r PgRow) -> sqlx::Result<Self> {
+		let entity = E::from_row(row)?;
+		let key = CursorKey::from_row(row)?;
+		let id = row.try_get(PAGE_ID)?;
+
+		Ok(PageRow { entity, key, id })
+	}
+}
+
+// endregion: --- Page
+
+// region:    --- Cursor
+
+/// The cursor, encoded as an opaque base64url token (of its json).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(super) struct Cursor {
+	#[serde(rename = "k")]
+	pub key: CursorKey,
+	pub id: i64,
+	/// The order of the page (see `PageOrder`).
+	#[serde(rename = "ob")]
+	pub order_by: String,
+}
+
+/// The value of the sort key in the cursor (of the supported column types).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(super) enum CursorKey {
+	Int(i64),
+	Text(String),
+	Time(DateTime<Utc>),
+}
+
+impl Cursor {
+	pub fn encode(&self) -> String {
+		// Note: Cannot fail, the cursor is a plain struct.
+		let json = serde_json::to_vec(self).unwrap_or_default();
+		b64u_encode(json)
+	}
+
+	pub fn decode(cursor: &str) -> Result<Self> {
+		b64u_decode(cursor)
+			.ok()
+			.and_then(|json| serde_json::from_slice(&json).ok())
+			.ok_or(Error::PageCursorInvalid)
+	}
+}
+
+impl CursorKey {
+	fn from_row(row: &PgRow) -> sqlx::Result<Self> {
+		let type_name = row.try_column(PAGE_KEY)?.type_info().name();
+		let key = match type_name {
+			"INT2" => CursorKey::Int(row.try_get::<i16, _>(PAGE_KEY)?.into()),
+			"INT4" => CursorKey::Int(row.try_get::<i32, _>(PAGE_KEY)?.into()),
+			"INT8" => CursorKey::Int(row.try_get(PAGE_KEY)?),
+			"TEXT" | "VARCHAR" => CursorKey::Text(row.try_get(PAGE_KEY)?),
+			"TIMESTAMPTZ" => CursorKey::Time(row.try_get(PAGE_KEY)?),
+			_ => {
+				return Err(sqlx::Error::Decode(
+					format!("page sort key type '{type_name}' not supported").into(),
+				))
+			}
+		};
+
+		Ok(key)
+	}
+
+	fn key_type(&self) -> PageKeyType {
+		match self {
+			CursorKey::Int(_) => PageKeyType::Int,
+			CursorKey::Text(_) => PageKeyType::Text,
+			CursorKey::Time(_) => PageKeyType::Time,
+		}
+	}
+
+	fn into_value(self) -> Value {
+		match self {
+			CursorKey::Int(val) => val.into(),
+			CursorKey::Text(val) => val.into(),
+			CursorKey::Time(val) => val.into(),
+		}
+	}
+}
+
+/// The cursor of a page by offset, for the orders without a keyset
+/// (e.g., the search relevance, see `PostBmc::search`).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub(in crate::model) struct OffsetCursor {
+	#[serde(rename = "o")]
+	pub offset: i64,
+}
+
+impl OffsetCursor {
+	pub fn encode(&self) -> String {
+		// Note: Cannot fail, the cursor is a plain struct.
+		let json = serde_json::to_vec(self).unwrap_or_default();
+		b64u_encode(json)
+	}
+
+	pub fn decode(cursor: &str) -> Result<Self> {
+		b64u_decode(cursor)
+			.ok()
+			.and_then(|json| serde_json::from_slice::<Self>(&json).ok())
+			.filter(|cursor| cursor.offset >= 0)
+			.ok_or(Error::PageCursorInvalid)
+	}
+}
+
+// endregion: --- Cursor
@@ -1,7 +1,8 @@
+use crate::ctx::{Ctx, Permission};
 use crate::model::base::{CommonIden, DbBmc, TimestampIden};
 use chrono::{DateTime, Utc};
 use modql::field::{SeaField, SeaFields};
-use sea_query::IntoIden;
+use sea_query::{Expr, IntoIden, SimpleExpr};
 
 /// This method must be called when a model controller intends to create its entity.
 pub fn prep_fields_for_create<MC>(fields: &mut SeaFields, user_id: i64)
@@ -24,6 +25,28 @@ where
 	if MC::has_timestamps() {
 		add_timestamps_for_update(fields, user_id);
 	}
+	if MC::has_version() {
+		let next_version = Expr::col(CommonIden::Version).add(1);
+		fields.push(SeaField::new(CommonIden::Version.into_iden(), next_version));
+	}
+}
+
+/// The owner condition of the writes (update, delete) for this ctx, if any.
+/// Note: A ctx with `Permission::ContentModerate` (elevated role) can write any entity.
+pub fn owner_scope<MC>(ctx: &Ctx) -> Option<SimpleExpr>
+where
+	MC: DbBmc,
+{
+	(MC::has_owner_id() && !ctx.has_permission(Permission::ContentModerate))
+		.then(|| Expr::col(CommonIden::OwnerId).eq(ctx.user_id()))
+}
+
+/// The condition hiding the soft-deleted rows, if the entity has soft delete.
+pub fn not_deleted_cond<MC>() -> Option<SimpleExpr>
+where
+	MC: DbBmc,
+{
+	MC::has_soft_delete().then(|| Expr::col(CommonIden::Deleted).eq(false))
 }
 
 /// Update the timestamps info for create
@@ -1,6 +1,8 @@
+use crate::ctx;
+use crate::geo;
 use crate::model::store::dbx;
 use derive_more::From;
-use lib_auth::pwd;
+use lib_auth::{onetime, pwd, totp};
 use serde::Serialize;
 use serde_with::{serde_as, DisplayFromStr};
 use sqlx::error::DatabaseError;
@@ -19,6 +21,18 @@ pub enum Error {
 		max: i64,
 		actual: i64,
 	},
+	PageCursorInvalid,
+	VersionConflict {
+		entity: &'static str,
+		id: i64,
+		current_version: i64,
+	},
+	VersionNotSupported {
+		entity: &'static str,
+	},
+	PageOrderByInvalid {
+		order_by: String,
+	},
 
 	CountFail,
 
@@ -36,15 +50,73 @@ pub enum Error {
     PasswordMismatch(String),
 	ResetTokenInvalid,
 	ResetTokenExpired,
+	LoginLinkTokenInvalid,
+	LoginLinkTokenExpired,
+	UnlockTokenInvalid,
+	UnlockTokenExpired,
+
+	// -- Media
+	MediaUploadMissing {
+		id: i64,
+	},
+	MediaUploadNotPending {
+		id: i64,
+	},
+	MediaTypeMismatch {
+		declared: String,
+		sniffed: String,
+	},
+	MediaSizeMismatch {
+		declared: i64,
+		actual: u64,
+	},
+	MediaUploadExpired {
+		id: i64,
+	},
+	/// The chunk does not start at the offset of the resumable upload.
+	MediaUploadOffsetConflict {
+		id: i64,
+		offset: i64,
+	},
+
+	// -- Session
+	SessionNotFound,
+	SessionRevoked,
+	SessionExpired,
+	SessionTokenReused {
+		session_id: i64,
+	},
+
+	// -- MFA
+	MfaAlreadyEnabled,
+	MfaNotEnabled,
+	MfaNotSetup,
+	MfaCodeInvalid,
+
+	// -- OIDC
+	OidcStateInvalid,
+	OidcEmailNotVerified,
+	OidcLinkRequiresLogin,
+	OidcIdentityAlreadyLinked,
 
 	// -- ModelManager
 	CantCreateModelManagerProvider(String),
 
 	// -- Modules
 	#[from]
+	Ctx(ctx::Error),
+	#[from]
 	Pwd(pwd::Error),
 	#[from]
+	Totp(totp::Error),
+	#[from]
+	Onetime(onetime::Error),
+	#[from]
 	Dbx(dbx::Error),
+	#[from]
+	Geo(geo::Error),
+	#[from]
+	Storage(lib_storage::Error),
 
 	// -- Externals
 	#[from]
@@ -78,6 +150,22 @@ impl Error {
 		}
 	}
 
+	/// If this Error is a postgres serialization failure (40001), e.g., the commit of a
+	/// serializable transaction in conflict. The transaction can be retried.
+	pub fn is_serialization_failure(&self) -> bool {
+		self.as_database_error()
+			.and_then(|db_error| db_error.code())
+			.is_some_and(|code| code == "40001")
+	}
+
+	/// If this Error is a postgres foreign key violation (23503), e.g., a reference to a
+	/// missing entity.
+	pub fn is_foreign_key_violation(&self) -> bool {
+		self.as_database_error()
+			.and_then(|db_error| db_error.code())
+			.is_some_and(|code| code == "23503")
+	}
+
 	/// A convenient function to return the eventual database error (Postgres)
 	/// if this Error is an SQLX Error that contains a database error.
 	pub fn as_database_error(&self) -> Option<&(dyn DatabaseError + 'static)> {
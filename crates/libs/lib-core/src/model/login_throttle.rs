@@ -0,0 +1,192 @@
+// region: ---- Modules
+use crate::ctx::Ctx;
+use crate::model::base::DbBmc;
+use crate::model::ModelManager;
+use crate::model::Result;
+use chrono::{DateTime, Utc};
+use modql::field::{Fields, HasSeaFields};
+use sea_query::{Alias, Expr, Iden, OnConflict, PostgresQueryBuilder, Query};
+use sea_query_binder::SqlxBinder;
+use sqlx::FromRow;
+// endregion: ---- Modules
+
+// region:    --- LoginThrottle Types
+
+/// The login failures counter of a key (account or ip), see `crate::throttle`.
+#[derive(Clone, Fields, FromRow, Debug)]
+pub struct LoginThrottleCounter {
+	pub failures: i32,
+	pub last_failure_at: DateTime<Utc>,
+	pub blocked_until: Option<DateTime<Utc>>,
+}
+
+#[derive(Iden)]
+enum LoginThrottleIden {
+	Key,
+	Failures,
+	LastFailureAt,
+	BlockedUntil,
+	Mtime,
+}
+
+// endregion: --- LoginThrottle Types
+
+// region:    --- LoginThrottleBmc
+pub struct LoginThrottleBmc;
+
+impl DbBmc for LoginThrottleBmc {
+	const TABLE: &'static str = "login_throttle";
+
+	/// System counters, no cid/mid.
+	fn has_timestamps() -> bool {
+		false
+	}
+}
+
+impl LoginThrottleBmc {
+	pub async fn get(
+		_ctx: &Ctx,
+		mm: &ModelManager,
+		key: &str,
+	) -> Result<Option<LoginThrottleCounter>> {
+		// -- Build query
+		let mut query = Query::select();
+		query
+			.from(Self::table_ref())
+			.columns(LoginThrottleCounter::sea_column_refs())
+			.and_where(Expr::col(LoginThrottleIden::Key).eq(key));
+
+		// -- Execute query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let sqlx_query = sqlx::query_as_with::<_, LoginThrottleCounter, _>(&sql, values);
+		let counter = mm.dbx().fetch_optional(sqlx_query).await?;
+
+		Ok(counter)
+	}
+
+	/// Increment the failures of a key (atomic upsert), and returns the new count.
+	/// The count restarts at 1 if the last failure is before `reset_before`.
+	pub async fn incr_failures(
+		_ctx: &Ctx,
+		mm: &ModelManager,
+		key: &str,
+		reset_before: DateTime<Utc>,
+	) -> Result<i32> {
+		let now = Utc::now();
+		let table = Alias::new(Self::TABLE);
+
+		// -- Build query
+		let failures = Expr::case(
+			Expr::col((table.clone(), LoginThrottleIden::LastFailureAt)).lt(reset_before),
+			1,
+		)
+		.finally(Expr::col((table, LoginThrottleIden::Failures)).add(1));
+
+		let mut query = Query::insert();
+		query
+			.into_table(Self::table_ref())
+			.columns([
+				LoginThrottleIden::Key,
+				LoginThrottleIden::Failures,
+				LoginThrottleIden::LastFailureAt,
+			])
+			.values([key.into(), 1.into(), now.into()])?
+			.on_conflict(
+				OnConflict::column(LoginThrottleIden::Key)
+					.value(LoginThrottleIden::Failures, failures)
+					.value(LoginThrottleIden::LastFailureAt, now)
+					.value(LoginThrottleIden::Mtime, now)
+					.to_owned(),
+			)
+			.returning_col(LoginThrottleIden::Failures);
+
+		// -- Exec query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let sqlx_query = sqlx::query_as_with::<_, (i32,), _>(&sql, values);
+		let (failures,) = mm.dbx().fetch_one(sqlx_query).await?;
+
+		Ok(failures)
+	}
+
+	pub async fn set_blocked_until(
+		_ctx: &Ctx,
+		mm: &ModelManager,
+		key: &str,
+		blocked_until: DateTime<Utc>,
+	) -> Result<()> {
+		// -- Build query
+		let mut query = Query::update();
+		query
+			.table(Self::table_ref())
+			.values([
+				(LoginThrottleIden::BlockedUntil, blocked_until.into()),
+				(LoginThrottleIden::Mtime, Utc::now().into()),
+			])
+			.and_where(Expr::col(LoginThrottleIden::Key).eq(key));
+
+		// -- Exec query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		mm.dbx().execute(sqlx::query_with(&sql, values)).await?;
+
+		Ok(())
+	}
+
+	/// Remove the counter of a key (e.g., on login success, or account unlock).
+	pub async fn reset(_ctx: &Ctx, mm: &ModelManager, key: &str) -> Result<()> {
+		// -- Build query
+		let mut query = Query::delete();
+		query
+			.from_table(Self::table_ref())
+			.and_where(Expr::col(LoginThrottleIden::Key).eq(key));
+
+		// -- Exec query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		mm.dbx().execute(sqlx::query_with(&sql, values)).await?;
+
+		Ok(())
+	}
+}
+
+// endregion: --- LoginThrottleBmc
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	pub type Result<T> = core::result::Result<T, Error>;
+	pub type Error = Box<dyn std::error::Error>; // For tests.
+
+	use super::*;
+	use crate::_dev_utils;
+	use chrono::Duration;
+	use serial_test::serial;
+
+	#[serial]
+	#[tokio::test]
+	async fn test_incr_failures_upsert_and_window_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_key = "user:test_incr_failures-01";
+		let window_start = Utc::now() - Duration::hours(1);
+
+		// -- Exec
+		let first = LoginThrottleBmc::incr_failures(&ctx, &mm, fx_key, window_start).await?;
+		let second = LoginThrottleBmc::incr_failures(&ctx, &mm, fx_key, window_start).await?;
+		// Last failure before the window, restarts at 1.
+		let restarted =
+			LoginThrottleBmc::incr_failures(&ctx, &mm, fx_key, Utc::now() + Duration::hours(1))
+				.await?;
+
+		// -- Check
+		assert_eq!((first, second, restarted), (1, 2, 1));
+
+		// -- Clean
+		LoginThrottleBmc::reset(&ctx, &mm, fx_key).await?;
+		assert!(LoginThrottleBmc::get(&ctx, &mm, fx_key).await?.is_none());
+
+		Ok(())
+	}
+}
+
+// endregion: --- Tests
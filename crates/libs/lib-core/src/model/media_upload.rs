@@ -0,0 +1,663 @@
+// region: ---- Modules
+use crate::ctx::{Ctx, Permission};
+use crate::model::base::{self, prep_fields_for_update, CommonIden, DbBmc};
+use crate::model::post::PostBmc;
+use crate::model::post_media::{
+	check_upload, PostImageForCreate, PostMediaBmc, PostMediaForCreate, SNIFF_LEN,
+};
+use crate::model::ModelManager;
+use crate::model::{Error, Result};
+use chrono::{DateTime, Utc};
+use lib_storage::config::media_config;
+use lib_storage::media;
+use lib_storage::StorageBackend;
+use lib_utils::mime::get_mime_from_bytes;
+use modql::field::{Fields, SeaField, SeaFields};
+use sea_query::{BinOper, Expr, Iden, PostgresQueryBuilder, Query, SimpleExpr};
+use sea_query_binder::SqlxBinder;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as Json;
+use sqlx::FromRow;
+use tracing::warn;
+// endregion: ---- Modules
+
+// region:    --- MediaUpload Types
+
+/// Time allowed to complete a resumable upload, then it is deleted with its chunks
+/// (see `MediaUploadBmc::delete_expired`).
+const MEDIA_UPLOAD_TTL_HOURS: i64 = 24;
+
+/// A resumable upload of a post media (e.g., the tus routes of lib-web).
+/// The file is received in chunks, staged in the storage, then assembled on completion
+/// as the file of a new media (see `MediaUploadBmc::complete`).
+#[derive(Clone, Fields, FromRow, Debug)]
+pub struct MediaUpload {
+	pub id: i64,
+	pub post_id: i64,
+	pub media_type: String,
+	/// The declared type, to check against the assembled file.
+	pub mime_type: String,
+	pub sort_order: i32,
+	pub alt_text: Option<String>,
+	/// The object of the file, assembled on completion.
+	pub storage_key: String,
+
+	// -- Progress
+	pub upload_length: i64,
+	pub upload_offset: i64,
+	/// The staged chunks, in order (see `MediaUpload::chunks`).
+	pub chunks: Json,
+	pub expires_at: DateTime<Utc>,
+	/// The media created on completion.
+	pub post_media_id: Option<i64>,
+}
+
+impl MediaUpload {
+	/// True when all the bytes are received.
+	pub fn is_complete(&self) -> bool {
+		self.upload_offset == self.upload_length
+	}
+
+	pub fn chunks(&self) -> Result<Vec<MediaUploadChunk>> {
+		serde_json::from_value(self.chunks.clone())
+			.map_err(|ex| Error::ValidationFail(ex.to_string()))
+	}
+}
+
+/// A staged chunk of an upload, an object of the storage.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MediaUploadChunk {
+	pub key: String,
+	pub size: i64,
+}
+
+pub struct MediaUploadForCreate {
+	pub post_id: i64,
+	pub mime_type: String,
+	pub upload_length: i64,
+	pub sort_order: Option<i32>,
+	pub alt_text: Option<String>,
+}
+
+#[derive(Fields)]
+struct MediaUploadForInsert {
+	post_id: i64,
+	media_type: String,
+	mime_type: String,
+	sort_order: i32,
+	alt_text: Option<String>,
+	storage_key: String,
+	upload_length: i64,
+	expires_at: DateTime<Utc>,
+}
+
+#[derive(Iden)]
+enum MediaUploadIden {
+	UploadLength,
+	UploadOffset,
+	Chunks,
+	ExpiresAt,
+	PostMediaId,
+}
+
+// endregion: --- MediaUpload Types
+
+// region:    --- MediaUploadBmc
+pub struct MediaUploadBmc;
+
+impl DbBmc for MediaUploadBmc {
+	const TABLE: &'static str = "media_upload";
+
+	fn has_owner_id() -> bool {
+		true
+	}
+
+	/// The uploads are only visible to their owner, until they expire.
+	fn visible_cond(ctx: &Ctx) -> Option<SimpleExpr> {
+		Some(
+			Expr::col(CommonIden::OwnerId)
+				.eq(ctx.user_id())
+				.and(Expr::col(MediaUploadIden::ExpiresAt).gt(Utc::now())),
+		)
+	}
+}
+
+impl MediaUploadBmc {
+	/// Create an upload of a media of the post, of the declared type and size
+	/// (checked as the direct uploads, see `PostMediaBmc::create_upload_slot`).
+	pub async fn create(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		upload_c: MediaUploadForCreate,
+	) -> Result<i64> {
+		ctx.require(Permission::PostUpdate)?;
+		base::check_writable::<PostBmc>(ctx, mm, upload_c.post_id).await?;
+		let (mime_type, media_type, extension) =
+			check_upload(&upload_c.mime_type, upload_c.upload_length)?;
+		let post_id = upload_c.post_id;
+
+		let upload_i = MediaUploadForInsert {
+			post_id,
+			media_type: media_type.to_string(),
+			mime_type: mime_type.to_string(),
+			sort_order: upload_c.sort_order.unwrap_or_default(),
+			alt_text: upload_c.alt_text,
+			storage_key: format!("posts/{post_id}/{}/original.{extension}", uuid::Uuid::new_v4()),
+			upload_length: upload_c.upload_length,
+			expires_at: Utc::now() + chrono::Duration::hours(MEDIA_UPLOAD_TTL_HOURS),
+		};
+
+		base::create::<Self, _>(ctx, mm, upload_i).await
+	}
+
+	pub async fn get(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<MediaUpload> {
+		base::get::<Self, _>(ctx, mm, id).await
+	}
+
+	/// Append the staged chunks at `offset`, and return the new offset.
+	/// Returns `Error::MediaUploadOffsetConflict` if `offset` is not the one of the upload
+	/// (e.g., a concurrent append), or if the chunks exceed its length.
+	pub async fn append_chunks(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		id: i64,
+		offset: i64,
+		chunks: &[MediaUploadChunk],
+	) -> Result<i64> {
+		// Note: Not found if not visible (not owned, or expired).
+		Self::get(ctx, mm, id).await?;
+
+		// -- Prep fields
+		let size: i64 = chunks.iter().map(|chunk| chunk.size).sum();
+		let chunks =
+			serde_json::to_value(chunks).map_err(|ex| Error::ValidationFail(ex.to_string()))?;
+		let mut fields = SeaFields::new(vec![
+			SeaField::new(
+				MediaUploadIden::UploadOffset,
+				Expr::col(MediaUploadIden::UploadOffset).add(size),
+			),
+			SeaField::new(
+				MediaUploadIden::Chunks,
+				Expr::col(MediaUploadIden::Chunks).binary(BinOper::Custom("||"), Expr::val(chunks)),
+			),
+		]);
+		prep_fields_for_update::<Self>(&mut fields, ctx.user_id());
+
+		// -- Build query
+		// Note: The offset condition makes the concurrent appends at the same offset fail,
+		//       but one.
+		let mut query = Query::update();
+		query
+			.table(Self::table_ref())
+			.values(fields.for_sea_update())
+			.and_where(Expr::col(CommonIden::Id).eq(id))
+			.and_where(Expr::col(CommonIden::OwnerId).eq(ctx.user_id()))
+			.and_where(Expr::col(MediaUploadIden::UploadOffset).eq(offset))
+			.and_where(
+				Expr::expr(Expr::col(MediaUploadIden::UploadOffset).add(size))
+					.lte(Expr::col(MediaUploadIden::UploadLength)),
+			)
+			.returning(Query::returning().columns([MediaUploadIden::UploadOffset]));
+
+		// -- Exec query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let sqlx_query = sqlx::query_as_with::<_, (i64,), _>(&sql, values);
+		let (new_offset,) = mm
+			.dbx()
+			.fetch_optional(sqlx_query)
+			.await?
+			.ok_or(Error::MediaUploadOffsetConflict { id, offset })?;
+
+		Ok(new_offset)
+	}
+
+	/// Complete the upload: assemble its chunks as the file of a new media, and return
+	/// its id. As the direct uploads (see `PostMediaBmc::complete_upload`), an image is
+	/// stored as its variants (the assembled file deleted), and set as the post cover if
+	/// it has none.
+	/// A file not of the declared type (sniffed) is deleted, with the upload.
+	/// Returns `Error::MediaUploadNotPending` if not all received, or already completed.
+	pub async fn complete(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		storage: &impl StorageBackend,
+		id: i64,
+	) -> Result<i64> {
+		ctx.require(Permission::PostUpdate)?;
+		let upload = Self::get(ctx, mm, id).await?;
+		if !upload.is_complete() || upload.post_media_id.is_some() {
+			return Err(Error::MediaUploadNotPending { id });
+		}
+		base::check_writable::<PostBmc>(ctx, mm, upload.post_id).await?;
+		let chunks = upload.chunks()?;
+
+		// -- Check the type
+		let prefix = upload_prefix(storage, &chunks, SNIFF_LEN).await?;
+		let sniffed = get_mime_from_bytes(&prefix, "");
+		if sniffed != upload.mime_type {
+			Self::delete(ctx, mm, id).await?;
+			Self::delete_chunks(storage, &chunks).await;
+			return Err(Error::MediaTypeMismatch {
+				declared: upload.mime_type,
+				sniffed,
+			});
+		}
+
+		// -- Assemble
+		// Note: The key is the one of the upload, so a concurrent completion stores the same.
+		let key = upload.storage_key.clone();
+		let chunk_keys: Vec<String> = chunks.iter().map(|chunk| chunk.key.clone()).collect();
+		let media_url = storage.assemble(&key, &chunk_keys).await?;
+
+		// -- Image variants
+		let image = if upload.media_type == "image" {
+			let data = storage.download(&key).await?;
+			let key_prefix = key.rsplit_once('/').map(|(prefix, _)| prefix).unwrap_or_default();
+			Some(media::process_image(storage, media_config(), key_prefix, &data).await?)
+		} else {
+			None
+		};
+
+		let post_media_id = mm
+			.transaction(|mm| {
+				let (upload, image, media_url) = (upload.clone(), image.clone(), media_url.clone());
+				async move {
+					// -- Create the media
+					let post_media_id = match image {
+						Some(image) => {
+							let image_c = PostImageForCreate {
+								post_id: upload.post_id,
+								sort_order: upload.sort_order,
+								alt_text: upload.alt_text,
+							};
+							let file_size = Some(upload.upload_length);
+							PostMediaBmc::create_processed_image(ctx, &mm, image_c, image, file_size)
+								.await?
+						}
+						None => {
+							let post_media_c = PostMediaForCreate {
+								post_id: upload.post_id,
+								media_url,
+								media_type: upload.media_type,
+								mime_type: upload.mime_type,
+								width: None,
+								height: None,
+								file_size: Some(upload.upload_length),
+								duration: None,
+								sort_order: upload.sort_order,
+								alt_text: upload.alt_text,
+							};
+							PostMediaBmc::create(ctx, &mm, post_media_c).await?
+						}
+					};
+
+					// -- Prep fields
+					let mut fields = SeaFields::new(vec![
+						SeaField::new(MediaUploadIden::PostMediaId, post_media_id),
+						SeaField::new(MediaUploadIden::Chunks, Json::Array(Vec::new())),
+					]);
+					prep_fields_for_update::<Self>(&mut fields, ctx.user_id());
+
+					// -- Build query
+					// Note: The conditions make the completion happen once.
+					let mut query = Query::update();
+					query
+						.table(Self::table_ref())
+						.values(fields.for_sea_update())
+						.and_where(Expr::col(CommonIden::Id).eq(id))
+						.and_where(Expr::col(CommonIden::OwnerId).eq(ctx.user_id()))
+						.and_where(
+							Expr::col(MediaUploadIden::UploadOffset)
+								.equals(MediaUploadIden::UploadLength),
+						)
+						.and_where(Expr::col(MediaUploadIden::PostMediaId).is_null());
+
+					// -- Exec query
+					let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+					let count = mm.dbx().execute(sqlx::query_with(&sql, values)).await?;
+					if count == 0 {
+						return Err(Error::MediaUploadNotPending { id });
+					}
+
+					Ok(post_media_id)
+				}
+			})
+			.await?;
+
+		// Note: The assembled image is deleted (its metadata, e.g., EXIF GPS, with it).
+		if image.is_some() {
+			storage.delete(&key).await?;
+		}
+		Self::delete_chunks(storage, &chunks).await;
+
+		Ok(post_media_id)
+	}
+
+	/// Delete the upload (its chunks are to delete from the storage, by the caller).
+	/// Its media, if completed, is kept.
+	pub async fn delete(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
+		base::delete::<Self>(ctx, mm, id).await
+	}
+
+	/// Delete the expired uploads, with their staged chunks (the media of the completed
+	/// ones are kept). Returns the number of uploads deleted.
+	/// Note: Expired, an upload is not visible anymore (see `visible_cond`), so not resumable.
+	pub async fn delete_expired(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		storage: &impl StorageBackend,
+	) -> Result<u64> {
+		ctx.require(Permission::ContentModerate)?;
+
+		// -- Build query
+		let mut query = Query::delete();
+		query
+			.from_table(Self::table_ref())
+			.and_where(Expr::col(MediaUploadIden::ExpiresAt).lt(Utc::now()))
+			.returning(Query::returning().columns([MediaUploadIden::Chunks]));
+
+		// -- Exec query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let sqlx_query = sqlx::query_as_with::<_, (Json,), _>(&sql, values);
+		let deleted = mm.dbx().fetch_all(sqlx_query).await?;
+
+		// Note: The rows are deleted first, so a failed chunk delete is only logged.
+		for (chunks,) in &deleted {
+			match serde_json::from_value::<Vec<MediaUploadChunk>>(chunks.clone()) {
+				Ok(chunks) => Self::delete_chunks(storage, &chunks).await,
+				Err(ex) => warn!("Failed to read the chunks of an expired upload: {ex:?}"),
+			}
+		}
+
+		Ok(deleted.len() as u64)
+	}
+
+	/// Delete the staged chunks from the storage (best effort, the failures are logged).
+	pub async fn delete_chunks(storage: &impl StorageBackend, chunks: &[MediaUploadChunk]) {
+		for chunk in chunks {
+			if let Err(ex) = storage.delete(&chunk.key).await {
+				warn!("Failed to delete the upload chunk '{}': {ex:?}", chunk.key);
+			}
+		}
+	}
+}
+
+/// The first `len` bytes of the upload (from its first chunks), e.g., to sniff its type.
+async fn upload_prefix(
+	storage: &impl StorageBackend,
+	chunks: &[MediaUploadChunk],
+	len: usize,
+) -> Result<Vec<u8>> {
+	let mut prefix = Vec::with_capacity(len);
+	for chunk in chunks {
+		if prefix.len() >= len {
+			break;
+		}
+		prefix.extend(storage.download_prefix(&chunk.key, len - prefix.len()).await?);
+	}
+
+	Ok(prefix)
+}
+
+// endregion: --- MediaUploadBmc
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	pub type Result<T> = core::result::Result<T, Error>;
+	pub type Error = Box<dyn std::error::Error>; // For tests.
+
+	use super::*;
+	use crate::_dev_utils;
+	use crate::model::Error as ModelError;
+	use image::codecs::png::PngEncoder;
+	use image::{DynamicImage, Rgb, RgbImage};
+	use lib_storage::media::ImageVariant;
+	use lib_storage::MemStorage;
+	use serial_test::serial;
+
+	fn fx_chunk(id: i64, offset: i64, size: i64) -> MediaUploadChunk {
+		MediaUploadChunk {
+			key: format!("uploads/{id}/{offset}"),
+			size,
+		}
+	}
+
+	/// Stage the chunk of `data` at `offset` in the storage.
+	async fn fx_stage(
+		storage: &MemStorage,
+		id: i64,
+		offset: i64,
+		data: &[u8],
+	) -> Result<MediaUploadChunk> {
+		let chunk = fx_chunk(id, offset, data.len() as i64);
+		storage.upload(&chunk.key, data).await?;
+
+		Ok(chunk)
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_append_chunks_err_offset_conflict() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_post = _dev_utils::seed_posts(
+			&ctx,
+			&mm,
+			&["test_append_chunks_err_offset_conflict post"],
+			&["test_append_chunks_err_offset_conflict"],
+		)
+		.await?
+		.remove(0);
+		let upload_c = MediaUploadForCreate {
+			post_id: fx_post.id,
+			mime_type: "video/mp4".to_string(),
+			upload_length: 100,
+			sort_order: None,
+			alt_text: None,
+		};
+		let id = MediaUploadBmc::create(&ctx, &mm, upload_c).await?;
+		MediaUploadBmc::append_chunks(&ctx, &mm, id, 0, &[fx_chunk(id, 0, 60)]).await?;
+
+		// -- Exec & Check
+		// Already appended at 0.
+		let res = MediaUploadBmc::append_chunks(&ctx, &mm, id, 0, &[fx_chunk(id, 0, 60)]).await;
+		assert!(matches!(
+			res,
+			Err(ModelError::MediaUploadOffsetConflict { offset: 0, .. })
+		));
+		// Over the length.
+		let res = MediaUploadBmc::append_chunks(&ctx, &mm, id, 60, &[fx_chunk(id, 60, 41)]).await;
+		assert!(matches!(res, Err(ModelError::MediaUploadOffsetConflict { .. })));
+
+		let upload = MediaUploadBmc::get(&ctx, &mm, id).await?;
+		assert_eq!(upload.upload_offset, 60);
+		assert_eq!(upload.chunks()?.len(), 1);
+
+		// -- Clean
+		PostBmc::delete(&ctx, &mm, fx_post.id).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_complete_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let storage = MemStorage::default();
+		let fx_post = _dev_utils::seed_posts(
+			&ctx,
+			&mm,
+			&["test_complete_ok post"],
+			&["test_complete_ok"],
+		)
+		.await?
+		.remove(0);
+		let mut fx_data = b"\x00\x00\x00\x18ftypisom\x00\x00\x02\x00isomiso2mp41".to_vec();
+		fx_data.resize(100, 0);
+		let upload_c = MediaUploadForCreate {
+			post_id: fx_post.id,
+			mime_type: "video/mp4".to_string(),
+			upload_length: 100,
+			sort_order: Some(2),
+			alt_text: None,
+		};
+		let id = MediaUploadBmc::create(&ctx, &mm, upload_c).await?;
+
+		// -- Exec & Check
+		// Not all received.
+		let chunks = [fx_stage(&storage, id, 0, &fx_data[..60]).await?];
+		MediaUploadBmc::append_chunks(&ctx, &mm, id, 0, &chunks).await?;
+		let res = MediaUploadBmc::complete(&ctx, &mm, &storage, id).await;
+		assert!(matches!(res, Err(ModelError::MediaUploadNotPending { .. })));
+
+		let fx_chunk_60 = fx_stage(&storage, id, 60, &fx_data[60..90]).await?;
+		let fx_chunk_90 = fx_stage(&storage, id, 90, &fx_data[90..]).await?;
+		let chunks = [fx_chunk_60, fx_chunk_90];
+		let offset = MediaUploadBmc::append_chunks(&ctx, &mm, id, 60, &chunks).await?;
+		assert_eq!(offset, 100);
+		let upload = MediaUploadBmc::get(&ctx, &mm, id).await?;
+		assert!(upload.is_complete());
+		let keys: Vec<String> = upload.chunks()?.into_iter().map(|chunk| chunk.key).collect();
+		assert_eq!(
+			keys,
+			[
+				format!("uploads/{id}/0"),
+				format!("uploads/{id}/60"),
+				format!("uploads/{id}/90")
+			]
+		);
+
+		let post_media_id = MediaUploadBmc::complete(&ctx, &mm, &storage, id).await?;
+		let upload = MediaUploadBmc::get(&ctx, &mm, id).await?;
+		assert_eq!(upload.post_media_id, Some(post_media_id));
+		assert!(upload.chunks()?.is_empty());
+		for key in &keys {
+			assert!(!storage.exists(key).await?, "chunk should be deleted");
+		}
+		assert_eq!(storage.download(&upload.storage_key).await?, fx_data);
+		let media = PostMediaBmc::get(&ctx, &mm, post_media_id).await?;
+		assert_eq!(media.media_type, "video");
+		assert_eq!(media.file_size, Some(100));
+		assert_eq!(media.sort_order, 2);
+
+		// Once.
+		let res = MediaUploadBmc::complete(&ctx, &mm, &storage, id).await;
+		assert!(matches!(res, Err(ModelError::MediaUploadNotPending { .. })));
+
+		// -- Clean
+		PostBmc::delete(&ctx, &mm, fx_post.id).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_complete_image_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let storage = MemStorage::default();
+		let fx_post = _dev_utils::seed_posts(
+			&ctx,
+			&mm,
+			&["test_complete_image_ok post"],
+			&["test_complete_image_ok"],
+		)
+		.await?
+		.remove(0);
+		let mut fx_data = Vec::new();
+		DynamicImage::ImageRgb8(RgbImage::from_pixel(48, 64, Rgb([255, 140, 0])))
+			.write_with_encoder(PngEncoder::new(&mut fx_data))?;
+		let upload_c = MediaUploadForCreate {
+			post_id: fx_post.id,
+			mime_type: "image/png".to_string(),
+			upload_length: fx_data.len() as i64,
+			sort_order: None,
+			alt_text: None,
+		};
+		let id = MediaUploadBmc::create(&ctx, &mm, upload_c).await?;
+		let chunks = [fx_stage(&storage, id, 0, &fx_data).await?];
+		MediaUploadBmc::append_chunks(&ctx, &mm, id, 0, &chunks).await?;
+
+		// -- Exec
+		let post_media_id = MediaUploadBmc::complete(&ctx, &mm, &storage, id).await?;
+
+		// -- Check
+		let media = PostMediaBmc::get(&ctx, &mm, post_media_id).await?;
+		assert_eq!(media.media_type, "image");
+		assert_eq!((media.width, media.height), (Some(48), Some(64)));
+		let variants: Vec<ImageVariant> = serde_json::from_value(media.variants)?;
+		assert_eq!(variants.len(), media_config().MEDIA_VARIANTS.len());
+		assert_eq!(media.media_url, variants.last().unwrap().webp_url);
+		let upload = MediaUploadBmc::get(&ctx, &mm, id).await?;
+		assert!(!storage.exists(&upload.storage_key).await?, "assembled file should be deleted");
+		assert!(!storage.exists(&chunks[0].key).await?, "chunk should be deleted");
+
+		let post = PostBmc::get(&ctx, &mm, fx_post.id).await?;
+		assert_eq!(post.cover_media_url.as_deref(), Some(media.media_url.as_str()));
+		assert!(post.thumbnail_url.is_some());
+
+		// -- Clean
+		PostBmc::delete(&ctx, &mm, fx_post.id).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_delete_expired_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let storage = MemStorage::default();
+		let fx_post = _dev_utils::seed_posts(
+			&ctx,
+			&mm,
+			&["test_delete_expired_ok post"],
+			&["test_delete_expired_ok"],
+		)
+		.await?
+		.remove(0);
+		let mut ids = Vec::new();
+		let mut chunks = Vec::new();
+		for _ in 0..2 {
+			let upload_c = MediaUploadForCreate {
+				post_id: fx_post.id,
+				mime_type: "video/mp4".to_string(),
+				upload_length: 100,
+				sort_order: None,
+				alt_text: None,
+			};
+			let id = MediaUploadBmc::create(&ctx, &mm, upload_c).await?;
+			let chunks_c = [fx_stage(&storage, id, 0, &[0; 60]).await?];
+			MediaUploadBmc::append_chunks(&ctx, &mm, id, 0, &chunks_c).await?;
+			ids.push(id);
+			chunks.extend(chunks_c);
+		}
+		let expire_sql =
+			"UPDATE media_upload SET expires_at = now() - interval '1 hour' WHERE id = $1";
+		mm.dbx().execute(sqlx::query(expire_sql).bind(ids[0])).await?;
+
+		// -- Exec
+		let count = MediaUploadBmc::delete_expired(&ctx, &mm, &storage).await?;
+
+		// -- Check
+		assert_eq!(count, 1);
+		assert!(!storage.exists(&chunks[0].key).await?, "expired chunk should be deleted");
+		assert!(storage.exists(&chunks[1].key).await?, "pending chunk should be kept");
+		MediaUploadBmc::get(&ctx, &mm, ids[1]).await?;
+		assert_eq!(MediaUploadBmc::delete_expired(&ctx, &mm, &storage).await?, 0);
+
+		// -- Clean
+		PostBmc::delete(&ctx, &mm, fx_post.id).await?;
+
+		Ok(())
+	}
+}
+
+// endregion: --- Tests
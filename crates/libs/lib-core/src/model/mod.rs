@@ -4,13 +4,30 @@ mod base;
 mod error;
 mod store;
 mod modql_utils;
+mod txn;
 
+pub mod audit_log;
+pub mod login_throttle;
+pub mod media_upload;
+pub mod oidc_login_state;
+pub mod place;
 pub mod post;
 pub mod post_media;
+pub mod search;
+pub mod session;
+pub mod trip;
+pub mod trip_day;
+pub mod trip_stop;
+pub mod um_change_log;
 pub mod user;
+pub mod user_identity;
 
 use crate::model::store::{dbx::Dbx, new_db_pool};
+pub use self::base::{Page, PageOptions};
 pub use self::error::{Error, Result};
+pub use self::modql_utils::{GeoOp, OpValsGeo};
+pub use self::store::dbx::IsolationLevel;
+pub use self::txn::TxnOptions;
 
 // endregion: ---- Modules
 
@@ -23,21 +40,14 @@ pub struct ModelManager {
 impl ModelManager {
     /// Constructor
 	pub async fn new() -> Result<Self> {
-        println!("DEBUG: ModelManager::new called");
         let db_pool = new_db_pool()
             .await
-            .map_err(|ex| {
-                println!("DEBUG: Failed to create db pool: {}", ex);
-                Error::CantCreateModelManagerProvider(ex.to_string())
-            })?;
-        println!("DEBUG: Db pool created successfully");
+            .map_err(|ex| Error::CantCreateModelManagerProvider(ex.to_string()))?;
         let dbx = Dbx::new(db_pool, false)?; // Пока используйте false
-        println!("DEBUG: Dbx created successfully");
         Ok(ModelManager { dbx })
     }
 
     pub fn new_with_txn(&self) -> Result<ModelManager> {
-        println!("DEBUG: new_with_txn called");
         let dbx = Dbx::new(self.dbx.db().clone(), true)?;
         Ok(ModelManager { dbx })
     }
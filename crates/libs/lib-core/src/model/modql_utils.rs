@@ -1,6 +1,9 @@
+use crate::geo::{geohash_cover, BBox, Location, Near};
 use chrono::{DateTime, Utc};
-use sea_query::Value;
-use modql::filter::{IntoSeaError, SeaResult};
+use sea_query::{ColumnRef, Condition, ConditionExpression, Expr, Iden, SimpleExpr, Value};
+use modql::filter::{IntoSeaError, OpValValue, SeaResult};
+use serde::de::{self, Deserializer};
+use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 
 pub fn time_to_sea_value(json_value: JsonValue) -> SeaResult<Value> {
@@ -18,3 +21,114 @@ pub fn time_to_sea_value(json_value: JsonValue) -> SeaResult<Value> {
     Ok(Value::ChronoDateTimeUtc(Some(Box::new(datetime))))
 }
 
+// region:    --- Geo Filter
+
+/// A geo operator of a location filter (see `OpValsGeo`), e.g.,
+/// `{"$near": {"lat": 1.28, "lon": 103.85, "radius_km": 2}}` or
+/// `{"$withinBbox": {"min_lat": 1.2, "min_lon": 103.6, "max_lat": 1.5, "max_lon": 104.1}}`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum GeoOp {
+    #[serde(rename = "$near")]
+    Near(Near),
+    #[serde(rename = "$withinBbox")]
+    WithinBbox(BBox),
+}
+
+/// The geo operators of a location filter node, for the entities with the `lat`, `lon`,
+/// and `geohash` columns, with `#[modql(to_sea_condition_fn = "geo_to_sea_condition")]`.
+/// Note: Named `OpVals..` for the `FilterNodes` derive. The operators are held as json
+///       values, since the modql operators cannot be extended.
+#[derive(Debug, Clone)]
+pub struct OpValsGeo(pub Vec<OpValValue>);
+
+impl From<GeoOp> for OpValsGeo {
+    fn from(op: GeoOp) -> Self {
+        // Note: Cannot fail, the locations are validated (finite numbers).
+        let op = serde_json::to_value(op).unwrap_or_default();
+        OpValsGeo(vec![OpValValue::Eq(op)])
+    }
+}
+
+impl<'de> Deserialize<'de> for OpValsGeo {
+    fn deserialize<D>(deserializer: D) -> core::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let ops = serde_json::Map::<String, JsonValue>::deserialize(deserializer)?;
+        let ops = ops
+            .into_iter()
+            .map(|(op, value)| {
+                let op = JsonValue::Object([(op, value)].into_iter().collect());
+                // Note: Validated here, so an invalid filter fails on deserialization.
+                GeoOp::deserialize(&op).map_err(de::Error::custom)?;
+                Ok(OpValValue::Eq(op))
+            })
+            .collect::<core::result::Result<Vec<_>, D::Error>>()?;
+
+        Ok(OpValsGeo(ops))
+    }
+}
+
+/// The `to_sea_condition_fn` of the `OpValsGeo` filter nodes.
+/// Note: The column of the node is not used (see `geo_cond`).
+pub fn geo_to_sea_condition(_col: &ColumnRef, op_value: OpValValue) -> SeaResult<ConditionExpression> {
+    let OpValValue::Eq(op) = op_value else {
+        return Err(IntoSeaError::custom("Invalid geo operator"));
+    };
+    let op: GeoOp = serde_json::from_value(op)?;
+
+    Ok(geo_cond(&op).into())
+}
+
+#[derive(Iden)]
+enum GeoIden {
+    Lat,
+    Lon,
+    Geohash,
+}
+
+/// The max number of geohash prefixes of a geo condition (see `geohash_cover`).
+const GEOHASH_COVER_MAX_CELLS: usize = 16;
+
+/// The condition of the rows in the area of the geo operator: the geohash prefixes
+/// covering its bbox (index scan), then the exact bbox and distance (refinement).
+pub fn geo_cond(op: &GeoOp) -> Condition {
+    let bbox = match op {
+        GeoOp::Near(near) => near.bbox(),
+        GeoOp::WithinBbox(bbox) => *bbox,
+    };
+    let mut cond = Condition::all();
+
+    // -- Geohash prefixes
+    let prefixes = geohash_cover(&bbox, GEOHASH_COVER_MAX_CELLS);
+    if !prefixes.is_empty() {
+        let prefixes_cond = prefixes.into_iter().fold(Condition::any(), |any, prefix| {
+            any.add(Expr::col(GeoIden::Geohash).like(format!("{prefix}%")))
+        });
+        cond = cond.add(prefixes_cond);
+    }
+
+    // -- Bbox
+    cond = cond.add(Expr::col(GeoIden::Lat).between(bbox.min_lat, bbox.max_lat));
+    let lon_cond = bbox
+        .lon_ranges()
+        .into_iter()
+        .fold(Condition::any(), |any, (min_lon, max_lon)| {
+            any.add(Expr::col(GeoIden::Lon).between(min_lon, max_lon))
+        });
+    cond = cond.add(lon_cond);
+
+    // -- Distance
+    if let GeoOp::Near(near) = op {
+        cond = cond.add(Expr::expr(distance_km_expr(near.center())).lte(near.radius_km()));
+    }
+
+    cond
+}
+
+/// The haversine distance (km) of the row (`lat`, `lon` columns) to the location.
+pub fn distance_km_expr(location: Location) -> SimpleExpr {
+    Expr::cust_with_values("haversine_km(lat, lon, $1, $2)", [location.lat(), location.lon()])
+}
+
+// endregion: --- Geo Filter
@@ -0,0 +1,131 @@
+// region: ---- Modules
+use crate::ctx::Ctx;
+use crate::model::base::{self, DbBmc};
+use crate::model::ModelManager;
+use crate::model::{Error, Result};
+use chrono::{DateTime, Utc};
+use modql::field::{Fields, HasSeaFields};
+use sea_query::{Expr, Iden, PostgresQueryBuilder, Query};
+use sea_query_binder::SqlxBinder;
+use sqlx::FromRow;
+// endregion: ---- Modules
+
+// region:    --- OidcLoginState Types
+
+/// Time allowed between the provider redirect and the callback.
+const OIDC_LOGIN_STATE_TTL_MIN: i64 = 10;
+
+/// The flow values of an OIDC login, kept until the provider callback.
+/// Single use (see `OidcLoginStateBmc::take`).
+#[derive(Clone, Fields, FromRow, Debug)]
+pub struct OidcLoginState {
+	pub id: i64,
+	pub state: String,
+	pub provider: String,
+	pub nonce: String,
+	pub pkce_verifier: String,
+	/// Set when the flow links the provider account to a logged in user.
+	pub link_user_id: Option<i64>,
+	pub expires_at: DateTime<Utc>,
+}
+
+pub struct OidcLoginStateForCreate {
+	pub state: String,
+	pub provider: String,
+	pub nonce: String,
+	pub pkce_verifier: String,
+	pub link_user_id: Option<i64>,
+}
+
+#[derive(Fields)]
+struct OidcLoginStateForInsert {
+	state: String,
+	provider: String,
+	nonce: String,
+	pkce_verifier: String,
+	link_user_id: Option<i64>,
+	expires_at: DateTime<Utc>,
+}
+
+#[derive(Iden)]
+enum OidcLoginStateIden {
+	State,
+	ExpiresAt,
+}
+
+// endregion: --- OidcLoginState Types
+
+// region:    --- OidcLoginStateBmc
+pub struct OidcLoginStateBmc;
+
+impl DbBmc for OidcLoginStateBmc {
+	const TABLE: &'static str = "oidc_login_state";
+}
+
+impl OidcLoginStateBmc {
+	pub async fn create(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		state_c: OidcLoginStateForCreate,
+	) -> Result<i64> {
+		let OidcLoginStateForCreate {
+			state,
+			provider,
+			nonce,
+			pkce_verifier,
+			link_user_id,
+		} = state_c;
+
+		// -- Clean the abandoned flows
+		let mut query = Query::delete();
+		query
+			.from_table(Self::table_ref())
+			.and_where(Expr::col(OidcLoginStateIden::ExpiresAt).lt(Utc::now()));
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		mm.dbx().execute(sqlx::query_with(&sql, values)).await?;
+
+		let state_fi = OidcLoginStateForInsert {
+			state,
+			provider,
+			nonce,
+			pkce_verifier,
+			link_user_id,
+			expires_at: Utc::now() + chrono::Duration::minutes(OIDC_LOGIN_STATE_TTL_MIN),
+		};
+
+		base::create::<Self, _>(ctx, mm, state_fi).await
+	}
+
+	/// Take (get and delete) the login state of a callback.
+	/// Returns `Error::OidcStateInvalid` if unknown, already used, or expired.
+	pub async fn take(
+		_ctx: &Ctx,
+		mm: &ModelManager,
+		state: &str,
+	) -> Result<OidcLoginState> {
+		// -- Build query
+		// Note: The delete makes the state single use, even with concurrent callbacks.
+		let mut query = Query::delete();
+		query
+			.from_table(Self::table_ref())
+			.and_where(Expr::col(OidcLoginStateIden::State).eq(state))
+			.returning(Query::returning().columns(OidcLoginState::sea_column_refs()));
+
+		// -- Exec query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let sqlx_query = sqlx::query_as_with::<_, OidcLoginState, _>(&sql, values);
+		let login_state = mm
+			.dbx()
+			.fetch_optional(sqlx_query)
+			.await?
+			.ok_or(Error::OidcStateInvalid)?;
+
+		if Utc::now() > login_state.expires_at {
+			return Err(Error::OidcStateInvalid);
+		}
+
+		Ok(login_state)
+	}
+}
+
+// endregion: --- OidcLoginStateBmc
@@ -0,0 +1,752 @@
+// region: ---- Modules
+use crate::ctx::{Ctx, Permission};
+use crate::geo::{BBox, Location, Near};
+use crate::model::base::{self, not_deleted_cond, CommonIden, DbBmc, Page, PageOptions};
+use crate::model::modql_utils::{
+	distance_km_expr, geo_cond, geo_to_sea_condition, GeoOp, OpValsGeo,
+};
+use crate::model::post::{Post, PostBmc};
+use crate::model::{Error, ModelManager, Result};
+use modql::field::{Fields, HasSeaFields};
+use modql::filter::{
+	FilterGroups, FilterNodes, ListOptions, OpValFloat64, OpValString, OpValsFloat64,
+	OpValsInt64, OpValsString,
+};
+use sea_query::{
+	Alias, Condition, Expr, Iden, IntoIden, JoinType, Order, PostgresQueryBuilder, Query,
+};
+use sea_query_binder::SqlxBinder;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as Json;
+use sqlx::FromRow;
+// endregion: ---- Modules
+
+// region:    --- Place Types
+
+/// A place (point of interest), e.g., a restaurant, a museum, or a viewpoint.
+#[derive(Debug, Clone, Fields, FromRow, Serialize)]
+pub struct Place {
+	pub id: i64,
+	pub name: String,
+	pub category: String,
+
+	// -- Coordinates (WGS 84, degrees)
+	pub lat: f64,
+	pub lon: f64,
+
+	// -- Address
+	pub street: Option<String>,
+	pub city: Option<String>,
+	pub region: Option<String>,
+	pub postal_code: Option<String>,
+	pub country_code: Option<String>,
+
+	/// By day, the open intervals, e.g., `{"mon": [["09:00", "17:00"]], "sun": []}`.
+	pub opening_hours: Option<Json>,
+	/// By language (ISO 639-1 code), e.g., `{"en": "...", "fr": "..."}`.
+	pub descriptions: Json,
+
+	/// The external source (e.g., "osm") and the place id in it, for the imports.
+	pub source: Option<String>,
+	pub source_id: Option<String>,
+}
+
+#[derive(Fields, Deserialize)]
+pub struct PlaceForCreate {
+	pub name: String,
+	pub category: String,
+	pub lat: f64,
+	pub lon: f64,
+	pub street: Option<String>,
+	pub city: Option<String>,
+	pub region: Option<String>,
+	pub postal_code: Option<String>,
+	pub country_code: Option<String>,
+	pub opening_hours: Option<Json>,
+	pub descriptions: Option<Json>,
+	pub source: Option<String>,
+	pub source_id: Option<String>,
+}
+
+#[derive(Fields, Default, Deserialize)]
+pub struct PlaceForUpdate {
+	pub name: Option<String>,
+	pub category: Option<String>,
+	pub lat: Option<f64>,
+	pub lon: Option<f64>,
+	pub street: Option<String>,
+	pub city: Option<String>,
+	pub region: Option<String>,
+	pub postal_code: Option<String>,
+	pub country_code: Option<String>,
+	pub opening_hours: Option<Json>,
+	pub descriptions: Option<Json>,
+}
+
+/// Note: For a bounding box, see `PlaceFilter::in_bbox`, or the `location` geo operators,
+///       e.g., `{"location": {"$near": {"lat": 1.28, "lon": 103.85, "radius_km": 2}}}`.
+#[derive(FilterNodes, Deserialize, Default, Debug)]
+pub struct PlaceFilter {
+	pub id: Option<OpValsInt64>,
+	pub name: Option<OpValsString>,
+	pub category: Option<OpValsString>,
+	pub lat: Option<OpValsFloat64>,
+	pub lon: Option<OpValsFloat64>,
+	pub city: Option<OpValsString>,
+	pub country_code: Option<OpValsString>,
+	pub source: Option<OpValsString>,
+	pub source_id: Option<OpValsString>,
+
+	/// The `$near` and `$withinBbox` operators (see `GeoOp`), on the geohash index.
+	#[modql(to_sea_condition_fn = "geo_to_sea_condition")]
+	pub location: Option<OpValsGeo>,
+}
+
+impl PlaceFilter {
+	/// The filters of the places in the bbox, and of one of the categories, if any.
+	/// Note: Two filters (or) when the bbox crosses the antimeridian.
+	pub fn in_bbox(bbox: BBox, categories: Option<Vec<String>>) -> Vec<PlaceFilter> {
+		bbox.lon_ranges()
+			.into_iter()
+			.map(|(min_lon, max_lon)| PlaceFilter {
+				lat: Some(OpValsFloat64(vec![
+					OpValFloat64::Gte(bbox.min_lat),
+					OpValFloat64::Lte(bbox.max_lat),
+				])),
+				lon: Some(OpValsFloat64(vec![
+					OpValFloat64::Gte(min_lon),
+					OpValFloat64::Lte(max_lon),
+				])),
+				category: categories
+					.clone()
+					.map(|categories| OpValsString(vec![OpValString::In(categories)])),
+				..Default::default()
+			})
+			.collect()
+	}
+}
+
+/// A place near a location, with its distance.
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct PlaceNear {
+	#[sqlx(flatten)]
+	#[serde(flatten)]
+	pub place: Place,
+	pub distance_km: f64,
+}
+
+/// A post near a location, with the distance of its nearest tagged place.
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct PostNear {
+	#[sqlx(flatten)]
+	#[serde(flatten)]
+	pub post: Post,
+	pub distance_km: f64,
+}
+
+#[derive(Iden)]
+enum PostPlaceIden {
+	#[iden = "post_place"]
+	Table,
+	PostId,
+	PlaceId,
+}
+
+// endregion: --- Place Types
+
+// region:    --- PlaceBmc
+
+pub struct PlaceBmc;
+
+impl DbBmc for PlaceBmc {
+	const TABLE: &'static str = "place";
+}
+
+// Note: The places are shared (not owned), so the creates require `Permission::PlaceCreate`,
+//       and the updates and deletes `Permission::PlaceManage`.
+impl PlaceBmc {
+	pub async fn create(ctx: &Ctx, mm: &ModelManager, place_c: PlaceForCreate) -> Result<i64> {
+		ctx.require(Permission::PlaceCreate)?;
+		check_location(Some(place_c.lat), Some(place_c.lon))?;
+		base::create::<Self, _>(ctx, mm, place_c).await
+	}
+
+	pub async fn create_many(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		places_c: Vec<PlaceForCreate>,
+	) -> Result<Vec<i64>> {
+		ctx.require(Permission::PlaceCreate)?;
+		for place_c in &places_c {
+			check_location(Some(place_c.lat), Some(place_c.lon))?;
+		}
+		base::create_many::<Self, _>(ctx, mm, places_c).await
+	}
+
+	pub async fn get(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<Place> {
+		base::get::<Self, _>(ctx, mm, id).await
+	}
+
+	pub async fn first(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		filter: Option<Vec<PlaceFilter>>,
+		list_options: Option<ListOptions>,
+	) -> Result<Option<Place>> {
+		base::first::<Self, _, _>(ctx, mm, filter, list_options).await
+	}
+
+	pub async fn list(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		filter: Option<Vec<PlaceFilter>>,
+		list_options: Option<ListOptions>,
+	) -> Result<Vec<Place>> {
+		base::list::<Self, _, _>(ctx, mm, filter, list_options).await
+	}
+
+	pub async fn list_page(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		filter: Option<Vec<PlaceFilter>>,
+		page_options: PageOptions,
+	) -> Result<Page<Place>> {
+		base::list_page::<Self, _, _>(ctx, mm, filter, page_options).await
+	}
+
+	pub async fn count(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		filter: Option<Vec<PlaceFilter>>,
+	) -> Result<i64> {
+		base::count::<Self, _>(ctx, mm, filter).await
+	}
+
+	pub async fn update(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		id: i64,
+		place_u: PlaceForUpdate,
+	) -> Result<()> {
+		ctx.require(Permission::PlaceManage)?;
+		check_location(place_u.lat, place_u.lon)?;
+		base::update::<Self, _>(ctx, mm, id, place_u).await
+	}
+
+	pub async fn delete(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
+		ctx.require(Permission::PlaceManage)?;
+		base::delete::<Self>(ctx, mm, id).await
+	}
+
+	pub async fn delete_many(ctx: &Ctx, mm: &ModelManager, ids: Vec<i64>) -> Result<u64> {
+		ctx.require(Permission::PlaceManage)?;
+		base::delete_many::<Self>(ctx, mm, ids).await
+	}
+
+	/// The places tagged on a post (see `PostBmc::set_places`), by name.
+	pub async fn list_for_post(ctx: &Ctx, mm: &ModelManager, post_id: i64) -> Result<Vec<Place>> {
+		// Note: The post must be visible to the ctx (not found otherwise).
+		PostBmc::get(ctx, mm, post_id).await?;
+
+		let post_places = Query::select()
+			.column(PostPlaceIden::PlaceId)
+			.from(PostPlaceIden::Table)
+			.and_where(Expr::col(PostPlaceIden::PostId).eq(post_id))
+			.to_owned();
+
+		let mut query = Query::select();
+		query
+			.from(Self::table_ref())
+			.columns(Place::sea_column_refs())
+			.and_where(Expr::col(CommonIden::Id).in_subquery(post_places))
+			.order_by(Alias::new("name"), Order::Asc);
+
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let sqlx_query = sqlx::query_as_with::<_, Place, _>(&sql, values);
+		let places = mm.dbx().fetch_all(sqlx_query).await?;
+
+		Ok(places)
+	}
+
+	/// The places within the radius of the location, the nearest first (e.g., for the map).
+	pub async fn list_near(
+		_ctx: &Ctx,
+		mm: &ModelManager,
+		near: Near,
+		filter: Option<Vec<PlaceFilter>>,
+		limit: Option<i64>,
+	) -> Result<Vec<PlaceNear>> {
+		let limit = base::compute_page_limit(limit)?;
+
+		let mut query = Query::select();
+		query
+			.from(Self::table_ref())
+			.columns(Place::sea_column_refs())
+			.expr_as(distance_km_expr(near.center()), Alias::new("distance_km"))
+			.cond_where(geo_cond(&GeoOp::Near(near)));
+		if let Some(filter) = filter {
+			let filters: FilterGroups = filter.into();
+			let cond: Condition = filters.try_into()?;
+			query.cond_where(cond);
+		}
+		query
+			.order_by(Alias::new("distance_km"), Order::Asc)
+			.order_by(CommonIden::Id, Order::Asc)
+			.limit(limit as u64);
+
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let sqlx_query = sqlx::query_as_with::<_, PlaceNear, _>(&sql, values);
+		let places = mm.dbx().fetch_all(sqlx_query).await?;
+
+		Ok(places)
+	}
+
+	/// Replace the places tagged on a post.
+	/// Note: The permission and the post are checked by the caller (see `PostBmc::set_places`).
+	pub(in crate::model) async fn set_for_post(
+		mm: &ModelManager,
+		post_id: i64,
+		place_ids: &[i64],
+	) -> Result<()> {
+		// -- Remove the current tags
+		let (sql, values) = Query::delete()
+			.from_table(PostPlaceIden::Table)
+			.and_where(Expr::col(PostPlaceIden::PostId).eq(post_id))
+			.build_sqlx(PostgresQueryBuilder);
+		mm.dbx().execute(sqlx::query_with(&sql, values)).await?;
+
+		if place_ids.is_empty() {
+			return Ok(());
+		}
+
+		// -- Insert the new ones
+		// Note: A missing place fails on its foreign key (see `PostBmc::set_places`).
+		let mut query = Query::insert();
+		query
+			.into_table(PostPlaceIden::Table)
+			.columns([
+				PostPlaceIden::PostId.into_iden(),
+				PostPlaceIden::PlaceId.into_iden(),
+			]);
+		for place_id in place_ids {
+			query.values([post_id.into(), (*place_id).into()])?;
+		}
+		query.on_conflict(
+			sea_query::OnConflict::columns([PostPlaceIden::PostId, PostPlaceIden::PlaceId])
+				.do_nothing()
+				.to_owned(),
+		);
+
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		mm.dbx().execute(sqlx::query_with(&sql, values)).await?;
+
+		Ok(())
+	}
+}
+
+/// Check the lat/lon of a place write (the ones set), as a `ValidationFail`.
+fn check_location(lat: Option<f64>, lon: Option<f64>) -> Result<()> {
+	// Note: 0 for the one not set (e.g., an update of the lat only), always valid.
+	Location::new(lat.unwrap_or(0.), lon.unwrap_or(0.))
+		.map(|_| ())
+		.map_err(|ex| Error::ValidationFail(ex.to_string()))
+}
+
+// endregion: --- PlaceBmc
+
+// region:    --- PostBmc Near
+
+impl PostBmc {
+	/// The posts visible to the ctx with a tagged place within the radius of the location,
+	/// the nearest first (by their nearest place).
+	pub async fn list_near(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		near: Near,
+		limit: Option<i64>,
+	) -> Result<Vec<PostNear>> {
+		let limit = base::compute_page_limit(limit)?;
+		let near_alias = Alias::new("near");
+
+		// -- The nearest place distance of each post
+		let post_distances = Query::select()
+			.expr_as(
+				Expr::col((PostPlaceIden::Table, PostPlaceIden::PostId)),
+				Alias::new("post_id"),
+			)
+			.expr_as(
+				Expr::expr(distance_km_expr(near.center())).min(),
+				Alias::new("distance_km"),
+			)
+			.from(PostPlaceIden::Table)
+			.inner_join(
+				PlaceBmc::table_ref(),
+				Expr::col((Alias::new(PlaceBmc::TABLE), CommonIden::Id))
+					.equals((PostPlaceIden::Table, PostPlaceIden::PlaceId)),
+			)
+			.cond_where(geo_cond(&GeoOp::Near(near)))
+			.group_by_col((PostPlaceIden::Table, PostPlaceIden::PostId))
+			.to_owned();
+
+		// -- The posts
+		let mut query = Query::select();
+		query
+			.from(Self::table_ref())
+			.columns(Post::sea_column_refs())
+			.column((near_alias.clone(), Alias::new("distance_km")))
+			.join_subquery(
+				JoinType::InnerJoin,
+				post_distances,
+				near_alias.clone(),
+				Expr::col((near_alias, Alias::new("post_id")))
+					.equals((Alias::new(Self::TABLE), CommonIden::Id)),
+			);
+		if let Some(not_deleted) = not_deleted_cond::<Self>() {
+			query.and_where(not_deleted);
+		}
+		if let Some(visible_cond) = Self::visible_cond(ctx) {
+			query.and_where(visible_cond);
+		}
+		query
+			.order_by(Alias::new("distance_km"), Order::Asc)
+			.order_by(CommonIden::Id, Order::Asc)
+			.limit(limit as u64);
+
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let sqlx_query = sqlx::query_as_with::<_, PostNear, _>(&sql, values);
+		let posts = mm.dbx().fetch_all(sqlx_query).await?;
+
+		Ok(posts)
+	}
+}
+
+// endregion: --- PostBmc Near
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	pub type Result<T> = core::result::Result<T, Error>;
+	pub type Error = Box<dyn std::error::Error>; // For tests.
+
+	use super::*;
+	use crate::_dev_utils;
+	use crate::ctx::{self, Role};
+	use crate::geo::{Location, GEOHASH_PRECISION};
+	use crate::model::user::UserBmc;
+	use serde_json::json;
+	use serial_test::serial;
+
+	fn fx_place_c(name: &str, category: &str, lat: f64, lon: f64) -> PlaceForCreate {
+		PlaceForCreate {
+			name: name.to_string(),
+			category: category.to_string(),
+			lat,
+			lon,
+			street: None,
+			city: None,
+			region: None,
+			postal_code: None,
+			country_code: None,
+			opening_hours: None,
+			descriptions: None,
+			source: None,
+			source_id: None,
+		}
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_create_get_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_place_c = PlaceForCreate {
+			city: Some("Singapore".to_string()),
+			country_code: Some("SG".to_string()),
+			opening_hours: Some(json!({"mon": [["10:00", "19:00"]]})),
+			descriptions: Some(json!({"en": "Botanic gardens", "fr": "Jardins botaniques"})),
+			source: Some("osm".to_string()),
+			source_id: Some("test_create_get_ok-1".to_string()),
+			..fx_place_c("test_create_get_ok Gardens", "park", 1.3138, 103.8159)
+		};
+
+		// -- Exec
+		let id = PlaceBmc::create(&ctx, &mm, fx_place_c).await?;
+
+		// -- Check
+		let place = PlaceBmc::get(&ctx, &mm, id).await?;
+		assert_eq!(place.name, "test_create_get_ok Gardens");
+		assert_eq!(place.country_code.as_deref(), Some("SG"));
+		assert_eq!(place.descriptions["fr"], "Jardins botaniques");
+		assert_eq!(place.opening_hours, Some(json!({"mon": [["10:00", "19:00"]]})));
+
+		// -- Clean
+		PlaceBmc::delete(&ctx, &mm, id).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_create_err_permission_denied() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::new(1000)?.add_role(Role::User);
+		let fx_place_c = fx_place_c("test_create_err_permission_denied", "park", 1.3, 103.8);
+
+		// -- Exec
+		let res = PlaceBmc::create(&ctx, &mm, fx_place_c).await;
+
+		// -- Check
+		assert!(matches!(
+			res,
+			Err(crate::model::Error::Ctx(ctx::Error::PermissionDenied {
+				permission: Permission::PlaceCreate,
+				..
+			}))
+		));
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_create_ok_creator_update_err_permission_denied() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let root_ctx = Ctx::root_ctx();
+		let ctx = _dev_utils::seed_user(&mm, "test_create_ok_creator-user-01", Role::Creator).await?;
+		let fx_name = "test_create_ok_creator_update_err_permission_denied";
+
+		// -- Exec
+		let id = PlaceBmc::create(&ctx, &mm, fx_place_c(fx_name, "park", 1.3, 103.8)).await?;
+		let place_u = PlaceForUpdate {
+			name: Some(format!("{fx_name} renamed")),
+			..Default::default()
+		};
+		let res_update = PlaceBmc::update(&ctx, &mm, id, place_u).await;
+		let res_delete = PlaceBmc::delete(&ctx, &mm, id).await;
+
+		// -- Check
+		assert_eq!(PlaceBmc::get(&ctx, &mm, id).await?.name, fx_name);
+		for res in [res_update, res_delete] {
+			assert!(matches!(
+				res,
+				Err(crate::model::Error::Ctx(ctx::Error::PermissionDenied {
+					permission: Permission::PlaceManage,
+					..
+				}))
+			));
+		}
+
+		// -- Clean
+		PlaceBmc::delete(&root_ctx, &mm, id).await?;
+		UserBmc::delete(&root_ctx, &mm, ctx.user_id()).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_create_update_err_location_invalid() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_name = "test_create_update_err_location_invalid";
+
+		// -- Exec & Check
+		let res = PlaceBmc::create(&ctx, &mm, fx_place_c(fx_name, "park", 91., 103.8)).await;
+		assert!(matches!(res, Err(crate::model::Error::ValidationFail(_))));
+
+		let fx_places_c = vec![
+			fx_place_c(fx_name, "park", 1.3, 103.8),
+			fx_place_c(fx_name, "park", 1.3, -180.5),
+		];
+		let res = PlaceBmc::create_many(&ctx, &mm, fx_places_c).await;
+		assert!(matches!(res, Err(crate::model::Error::ValidationFail(_))));
+
+		let id = PlaceBmc::create(&ctx, &mm, fx_place_c(fx_name, "park", 1.3, 103.8)).await?;
+		let place_u = PlaceForUpdate {
+			lon: Some(181.),
+			..Default::default()
+		};
+		let res = PlaceBmc::update(&ctx, &mm, id, place_u).await;
+		assert!(matches!(res, Err(crate::model::Error::ValidationFail(_))));
+
+		// -- Clean
+		PlaceBmc::delete(&ctx, &mm, id).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_list_in_bbox_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_places = [
+			fx_place_c("test_list_in_bbox_ok Suva cafe", "cafe", -18.14, 178.44),
+			fx_place_c("test_list_in_bbox_ok Taveuni museum", "museum", -16.85, -179.97),
+			fx_place_c("test_list_in_bbox_ok Taveuni cafe", "cafe", -16.86, -179.96),
+			fx_place_c("test_list_in_bbox_ok Paris cafe", "cafe", 48.85, 2.35),
+		];
+		let ids = PlaceBmc::create_many(&ctx, &mm, fx_places.into()).await?;
+
+		// -- Exec (Fiji, across the antimeridian)
+		let fx_bbox = BBox {
+			min_lat: -21.,
+			min_lon: 177.,
+			max_lat: -12.,
+			max_lon: -178.,
+		};
+		let filters = PlaceFilter::in_bbox(fx_bbox, Some(vec!["cafe".to_string()]));
+		let places = PlaceBmc::list(&ctx, &mm, Some(filters), None).await?;
+
+		// -- Check
+		let mut names: Vec<&str> = places.iter().map(|place| place.name.as_str()).collect();
+		names.sort();
+		assert_eq!(
+			names,
+			["test_list_in_bbox_ok Suva cafe", "test_list_in_bbox_ok Taveuni cafe"]
+		);
+
+		// -- Clean
+		PlaceBmc::delete_many(&ctx, &mm, ids).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_list_by_geo_filter_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_places = [
+			fx_place_c("test_list_by_geo_filter_ok Marina Bay", "viewpoint", 1.2839, 103.8515),
+			fx_place_c("test_list_by_geo_filter_ok Chinatown", "market", 1.2836, 103.8436),
+			fx_place_c("test_list_by_geo_filter_ok Changi", "airport", 1.3644, 103.9915),
+			fx_place_c("test_list_by_geo_filter_ok Johor Bahru", "market", 1.4927, 103.7414),
+		];
+		let ids = PlaceBmc::create_many(&ctx, &mm, fx_places.into()).await?;
+
+		// -- Exec & Check ($near)
+		let filter: Vec<PlaceFilter> = serde_json::from_value(json!([{
+			"name": {"$startsWith": "test_list_by_geo_filter_ok"},
+			"location": {"$near": {"lat": 1.2839, "lng": 103.8515, "radius_km": 5}}
+		}]))?;
+		let places = PlaceBmc::list(&ctx, &mm, Some(filter), None).await?;
+		let mut names: Vec<&str> = places.iter().map(|place| place.name.as_str()).collect();
+		names.sort();
+		assert_eq!(
+			names,
+			["test_list_by_geo_filter_ok Chinatown", "test_list_by_geo_filter_ok Marina Bay"]
+		);
+
+		// -- Exec & Check ($withinBbox, with another filter)
+		let filter: Vec<PlaceFilter> = serde_json::from_value(json!([{
+			"category": "market",
+			"location": {"$withinBbox": {
+				"min_lat": 1.2, "min_lon": 103.6, "max_lat": 1.6, "max_lon": 104.1
+			}}
+		}]))?;
+		let places = PlaceBmc::list(&ctx, &mm, Some(filter), None).await?;
+		let mut names: Vec<&str> = places.iter().map(|place| place.name.as_str()).collect();
+		names.sort();
+		assert_eq!(
+			names,
+			["test_list_by_geo_filter_ok Chinatown", "test_list_by_geo_filter_ok Johor Bahru"]
+		);
+
+		// -- Check (invalid location)
+		let res = serde_json::from_value::<Vec<PlaceFilter>>(json!([{
+			"location": {"$near": {"lat": 91, "lon": 103.8515, "radius_km": 5}}
+		}]));
+		assert!(res.is_err());
+
+		// -- Clean
+		PlaceBmc::delete_many(&ctx, &mm, ids).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_list_near_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_places = [
+			fx_place_c("test_list_near_ok Changi", "airport", 1.3644, 103.9915),
+			fx_place_c("test_list_near_ok Chinatown", "market", 1.2836, 103.8436),
+			fx_place_c("test_list_near_ok Marina Bay", "viewpoint", 1.2839, 103.8515),
+		];
+		let ids = PlaceBmc::create_many(&ctx, &mm, fx_places.into()).await?;
+		let fx_center = Location::new(1.2839, 103.8515)?;
+
+		// -- Exec
+		let near = Near::new(fx_center, 30.)?;
+		let places = PlaceBmc::list_near(&ctx, &mm, near, None, None).await?;
+
+		// -- Check
+		let places: Vec<&PlaceNear> = places
+			.iter()
+			.filter(|place| place.place.name.starts_with("test_list_near_ok"))
+			.collect();
+		let names: Vec<&str> = places.iter().map(|place| place.place.name.as_str()).collect();
+		assert_eq!(
+			names,
+			[
+				"test_list_near_ok Marina Bay",
+				"test_list_near_ok Chinatown",
+				"test_list_near_ok Changi"
+			]
+		);
+		// the distances match the ones computed in rust, and so the geohashes
+		for place in places {
+			let location = Location::new(place.place.lat, place.place.lon)?;
+			assert!((place.distance_km - fx_center.distance_km(&location)).abs() < 1e-6);
+			let sql = "SELECT geohash FROM place WHERE id = $1";
+			let (geohash,): (String,) = sqlx::query_as(sql)
+				.bind(place.place.id)
+				.fetch_one(mm.dbx().db())
+				.await?;
+			assert_eq!(geohash, location.geohash(GEOHASH_PRECISION));
+		}
+
+		// -- Clean
+		PlaceBmc::delete_many(&ctx, &mm, ids).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_haversine_km_ok_antipodal() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		// Note: Near antipodal, the haversine term rounds above 1.
+		let fx_from = Location::new(-60.74651755403846, 48.819076262161744)?;
+		let fx_to = Location::new(60.74651758377535, -131.18092370812846)?;
+
+		// -- Exec
+		let sql = "SELECT haversine_km($1, $2, $3, $4)";
+		let (distance_km,): (f64,) = sqlx::query_as(sql)
+			.bind(fx_from.lat())
+			.bind(fx_from.lon())
+			.bind(fx_to.lat())
+			.bind(fx_to.lon())
+			.fetch_one(mm.dbx().db())
+			.await?;
+
+		// -- Check
+		assert!((distance_km - fx_from.distance_km(&fx_to)).abs() < 1e-3);
+		assert!((distance_km - 20015.11).abs() < 0.01, "distance_km: {distance_km}");
+
+		Ok(())
+	}
+}
+
+// endregion: --- Tests
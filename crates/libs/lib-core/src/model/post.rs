@@ -1,16 +1,21 @@
-use crate::ctx::Ctx;
-use crate::model::base::{self, DbBmc};
+use crate::ctx::{Ctx, Permission};
+use crate::model::audit_log::{fields_to_json, AuditLogBmc, AuditLogEntry, AuditOp};
+use crate::model::place::PlaceBmc;
+use crate::model::base::{self, CommonIden, DbBmc, Page, PageKeyType, PageOptions};
 use modql::filter::{FilterNodes, ListOptions, OpValsBool, OpValsInt64, OpValsString};
 use serde::{Deserialize, Serialize};
-use crate::model::{Result, ModelManager};
+use crate::model::{Error, Result, ModelManager};
 use sqlx::FromRow;
-use modql::field::Fields;
+use modql::field::{Fields, SeaField, SeaFields};
+use sea_query::{Expr, Iden, PostgresQueryBuilder, Query, SimpleExpr};
+use sea_query_binder::SqlxBinder;
 
 // region: ---- Post Types
 
 #[derive(Debug, Clone, Fields, FromRow, Serialize)]
 pub struct Post {
     pub id: i64,
+    pub owner_id: i64,
     pub title: String,
     pub description: String,
     pub is_published: bool,
@@ -18,6 +23,10 @@ pub struct Post {
     pub thumbnail_url: Option<String>,
     pub media_count: i32,
     pub has_video: bool,
+    /// Incremented by each update (see `PostBmc::update_if_version`).
+    pub version: i64,
+    /// The language of the text (ISO 639-1 code), for the search (see `PostBmc::search`).
+    pub lang: String,
 }
 
 #[derive(Fields, Deserialize)]
@@ -29,24 +38,34 @@ pub struct PostForCreate {
     pub thumbnail_url: Option<String>,
     pub media_count: Option<i32>,
     pub has_video: Option<bool>,
+    pub lang: Option<String>,
 }
 
 #[derive(Fields, Default, Deserialize)]
 pub struct PostForUpdate {
     pub title: Option<String>,
     pub description: Option<String>,
-    pub is_published: Option<bool>
+    pub is_published: Option<bool>,
+    pub lang: Option<String>,
 }
 
 #[derive(FilterNodes, Deserialize, Default, Debug)]
 pub struct PostFilter {
     id: Option<OpValsInt64>,
+    owner_id: Option<OpValsInt64>,
     title: Option<OpValsString>,
     is_published: Option<OpValsBool>,
     has_video: Option<OpValsBool>,
     media_count: Option<OpValsInt64>,
 }
 
+#[derive(Iden)]
+enum PostIden {
+    IsPublished,
+    CoverMediaUrl,
+    ThumbnailUrl,
+}
+
 // endregion: ---- Post Types
 
 // region: ---- PostBmc
@@ -54,6 +73,36 @@ pub struct PostBmc;
 
 impl DbBmc for PostBmc {
     const TABLE: &'static str = "post";
+
+    fn has_owner_id() -> bool {
+        true
+    }
+
+    fn has_audit_log() -> bool {
+        true
+    }
+
+    fn has_version() -> bool {
+        true
+    }
+
+    fn page_order_columns() -> Vec<(&'static str, PageKeyType)> {
+        vec![
+            ("id", PageKeyType::Int),
+            ("title", PageKeyType::Text),
+            ("ctime", PageKeyType::Time),
+            ("mtime", PageKeyType::Time),
+        ]
+    }
+
+    /// The unpublished posts are only visible to their owner.
+    fn visible_cond(ctx: &Ctx) -> Option<SimpleExpr> {
+        Some(
+            Expr::col(PostIden::IsPublished)
+                .eq(true)
+                .or(Expr::col(CommonIden::OwnerId).eq(ctx.user_id())),
+        )
+    }
 }
 
 impl PostBmc {
@@ -62,6 +111,8 @@ impl PostBmc {
         mm: &ModelManager,
         post_c: PostForCreate,
     ) -> Result<i64> {
+        ctx.require(Permission::PostCreate)?;
+        validate_lang(post_c.lang.as_deref())?;
         base::create::<Self, _>(ctx, mm, post_c).await
     }
 
@@ -78,14 +129,155 @@ impl PostBmc {
         base::list::<Self, _, _>(ctx, mm, filters, list_options).await
     }
 
+    /// A page of posts after the cursor (e.g., the feed, with "!id" for the latest first).
+    pub async fn list_page(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        filters: Option<Vec<PostFilter>>,
+        page_options: PageOptions,
+    ) -> Result<Page<Post>> {
+        base::list_page::<Self, _, _>(ctx, mm, filters, page_options).await
+    }
+
     pub async fn update(ctx: &Ctx, mm: &ModelManager, id: i64, post_u: PostForUpdate) -> Result<()> {
+        ctx.require(Permission::PostUpdate)?;
+        validate_lang(post_u.lang.as_deref())?;
         base::update::<Self, _>(ctx, mm, id, post_u).await
     }
 
+    /// Update the post only if not updated since `version` (e.g., by another device),
+    /// and return its new version.
+    pub async fn update_if_version(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        id: i64,
+        version: i64,
+        post_u: PostForUpdate,
+    ) -> Result<i64> {
+        ctx.require(Permission::PostUpdate)?;
+        validate_lang(post_u.lang.as_deref())?;
+        base::update_if_version::<Self, _>(ctx, mm, id, version, post_u).await
+    }
+
     pub async fn delete(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
+        ctx.require(Permission::PostDelete)?;
         base::delete::<Self>(ctx, mm, id).await
     }
 
+    /// Tag the places of the post (replacing the current ones), see `PlaceBmc::list_for_post`.
+    pub async fn set_places(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        id: i64,
+        place_ids: Vec<i64>,
+    ) -> Result<()> {
+        ctx.require(Permission::PostUpdate)?;
+        base::check_writable::<Self>(ctx, mm, id).await?;
+
+        mm.transaction(|mm| {
+            let place_ids = place_ids.clone();
+            async move { PlaceBmc::set_for_post(&mm, id, &place_ids).await }
+        })
+        .await
+        .map_err(|err| {
+            if err.is_foreign_key_violation() {
+                Error::ValidationFail("place not found".to_string())
+            } else {
+                err
+            }
+        })
+    }
+
+    /// Set the cover and thumbnail urls of the post, each only if it has none (e.g., on
+    /// its first image, see `PostMediaBmc::create_image`). Versioned and audited as an
+    /// update, if changed.
+    /// Note: The permission is checked by the caller, in its transaction (for the audit).
+    pub(in crate::model) async fn set_cover_if_none(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        id: i64,
+        cover_media_url: &str,
+        thumbnail_url: &str,
+    ) -> Result<()> {
+        // -- Prep Fields
+        let changes = fields_to_json(&SeaFields::new(vec![
+            SeaField::new(PostIden::CoverMediaUrl, cover_media_url),
+            SeaField::new(PostIden::ThumbnailUrl, thumbnail_url),
+        ]));
+        let mut fields = SeaFields::new(vec![
+            SeaField::new(
+                PostIden::CoverMediaUrl,
+                Expr::cust_with_values("COALESCE(cover_media_url, $1)", [cover_media_url]),
+            ),
+            SeaField::new(
+                PostIden::ThumbnailUrl,
+                Expr::cust_with_values("COALESCE(thumbnail_url, $1)", [thumbnail_url]),
+            ),
+        ]);
+        base::prep_fields_for_update::<Self>(&mut fields, ctx.user_id());
+
+        // -- Build query
+        // Note: Only if one is missing, so the version is not bumped for nothing.
+        let mut query = Query::update();
+        query
+            .table(Self::table_ref())
+            .values(fields.for_sea_update())
+            .and_where(Expr::col(CommonIden::Id).eq(id))
+            .and_where(
+                Expr::col(PostIden::CoverMediaUrl)
+                    .is_null()
+                    .or(Expr::col(PostIden::ThumbnailUrl).is_null()),
+            );
+
+        // -- Exec query
+        let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+        let count = mm.dbx().execute(sqlx::query_with(&sql, values)).await?;
+        if count > 0 {
+            AuditLogBmc::record::<Self>(ctx, mm, id, AuditOp::Update, Some(changes)).await?;
+        }
+
+        Ok(())
+    }
+
+    /// The history of the writes of a post (who, when, what), for the moderators.
+    pub async fn history(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<Vec<AuditLogEntry>> {
+        ctx.require(Permission::ContentModerate)?;
+        AuditLogBmc::list_for_entity::<Self>(ctx, mm, id).await
+    }
+
+    /// Delete all the posts of an owner (their media cascade), on user erasure.
+    /// Note: The permission is checked by the caller (see `UserBmc::delete`).
+    pub async fn delete_for_owner(ctx: &Ctx, mm: &ModelManager, owner_id: i64) -> Result<u64> {
+        base::delete_where::<Self>(ctx, mm, Expr::col(CommonIden::OwnerId).eq(owner_id)).await
+    }
+
+}
+
+/// The ISO 639-1 language codes.
+const LANG_CODES: &[&str] = &[
+    "aa", "ab", "ae", "af", "ak", "am", "an", "ar", "as", "av", "ay", "az", "ba", "be", "bg",
+    "bi", "bm", "bn", "bo", "br", "bs", "ca", "ce", "ch", "co", "cr", "cs", "cu", "cv", "cy",
+    "da", "de", "dv", "dz", "ee", "el", "en", "eo", "es", "et", "eu", "fa", "ff", "fi", "fj",
+    "fo", "fr", "fy", "ga", "gd", "gl", "gn", "gu", "gv", "ha", "he", "hi", "ho", "hr", "ht",
+    "hu", "hy", "hz", "ia", "id", "ie", "ig", "ii", "ik", "io", "is", "it", "iu", "ja", "jv",
+    "ka", "kg", "ki", "kj", "kk", "kl", "km", "kn", "ko", "kr", "ks", "ku", "kv", "kw", "ky",
+    "la", "lb", "lg", "li", "ln", "lo", "lt", "lu", "lv", "mg", "mh", "mi", "mk", "ml", "mn",
+    "mr", "ms", "mt", "my", "na", "nb", "nd", "ne", "ng", "nl", "nn", "no", "nr", "nv", "ny",
+    "oc", "oj", "om", "or", "os", "pa", "pi", "pl", "ps", "pt", "qu", "rm", "rn", "ro", "ru",
+    "rw", "sa", "sc", "sd", "se", "sg", "si", "sk", "sl", "sm", "sn", "so", "sq", "sr", "ss",
+    "st", "su", "sv", "sw", "ta", "te", "tg", "th", "ti", "tk", "tl", "tn", "to", "tr", "ts",
+    "tt", "tw", "ty", "ug", "uk", "ur", "uz", "ve", "vi", "vo", "wa", "wo", "xh", "yi", "yo",
+    "za", "zh", "zu",
+];
+
+/// The language must be an ISO 639-1 code (e.g., "en"), if given.
+pub(in crate::model) fn validate_lang(lang: Option<&str>) -> Result<()> {
+    match lang {
+        Some(lang) if !LANG_CODES.contains(&lang) => {
+            Err(Error::ValidationFail(format!("lang '{lang}' invalid")))
+        }
+        _ => Ok(()),
+    }
 }
 
 // endregion: ---- PostBmc
@@ -95,6 +287,10 @@ impl PostBmc {
 mod tests {
     #[allow(unused)]
     use crate::_dev_utils;
+    use crate::ctx::Role;
+    use crate::geo::{Location, Near};
+    use crate::model::place::PostNear;
+    use crate::model::user::UserBmc;
     use crate::model::Error;
 
     use super::*;
@@ -125,6 +321,7 @@ mod tests {
             thumbnail_url: fx_thumbnail_url,
             media_count: fx_media_count,
             has_video: fx_has_video,
+            lang: None,
         };
 
         let id = PostBmc::create(&ctx, &mm, post_c).await?;
@@ -140,6 +337,35 @@ mod tests {
         Ok(())
     }
 
+    #[serial]
+    #[tokio::test]
+    async fn test_create_err_lang_invalid() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+
+        // -- Exec
+        let post_c = PostForCreate {
+            title: "test_create_err_lang_invalid title".to_string(),
+            description: "test_create_err_lang_invalid description".to_string(),
+            is_published: None,
+            cover_media_url: None,
+            thumbnail_url: None,
+            media_count: None,
+            has_video: None,
+            lang: Some("english".to_string()),
+        };
+        let res = PostBmc::create(&ctx, &mm, post_c).await;
+
+        // -- Check
+        assert!(
+            matches!(res, Err(Error::ValidationFail(_))),
+            "ValidationFail not matching"
+        );
+
+        Ok(())
+    }
+
     #[serial]
     #[tokio::test]
     async fn test_get_err_not_found() -> Result<()> {
@@ -255,6 +481,133 @@ mod tests {
        Ok(())
     }
 
+    #[serial]
+    #[tokio::test]
+    async fn test_list_page_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_titles = &[
+            "test_list_page_ok-post 03",
+            "test_list_page_ok-post 01",
+            "test_list_page_ok-post 05",
+            "test_list_page_ok-post 02",
+            "test_list_page_ok-post 04",
+        ];
+        _dev_utils::seed_posts(&ctx, &mm, fx_titles, fx_titles).await?;
+        let fx_filter = || -> Result<Vec<PostFilter>> {
+            Ok(serde_json::from_value(json!([{
+                "title": {"$startsWith": "test_list_page_ok"}
+            }]))?)
+        };
+
+        // -- Exec (by title, 2 per page)
+        let mut titles = Vec::new();
+        let mut cursor = None;
+        let mut pages = 0;
+        loop {
+            let page_options = PageOptions {
+                cursor,
+                limit: Some(2),
+                order_by: Some("title".to_string()),
+            };
+            let page = PostBmc::list_page(&ctx, &mm, Some(fx_filter()?), page_options).await?;
+            pages += 1;
+            titles.extend(page.items.into_iter().map(|post| post.title));
+            assert_eq!(page.has_more, page.next_cursor.is_some());
+            cursor = page.next_cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+
+        // -- Check
+        assert_eq!(pages, 3);
+        let mut fx_sorted = fx_titles.to_vec();
+        fx_sorted.sort();
+        assert_eq!(titles, fx_sorted);
+
+        // -- Exec & Check (latest first)
+        let page_options = PageOptions {
+            limit: Some(3),
+            order_by: Some("!id".to_string()),
+            ..Default::default()
+        };
+        let page = PostBmc::list_page(&ctx, &mm, Some(fx_filter()?), page_options).await?;
+        assert!(page.has_more);
+        assert_eq!(page.items[0].title, "test_list_page_ok-post 04");
+        let page_options = PageOptions {
+            cursor: page.next_cursor,
+            limit: Some(3),
+            order_by: Some("!id".to_string()),
+        };
+        let page = PostBmc::list_page(&ctx, &mm, Some(fx_filter()?), page_options).await?;
+        assert!(!page.has_more);
+        let titles: Vec<&str> = page.items.iter().map(|post| post.title.as_str()).collect();
+        assert_eq!(titles, ["test_list_page_ok-post 01", "test_list_page_ok-post 03"]);
+
+        // -- Exec & Check (invalid)
+        let page_options = PageOptions {
+            cursor: Some("not-a-cursor".to_string()),
+            ..Default::default()
+        };
+        let res = PostBmc::list_page(&ctx, &mm, None, page_options).await;
+        assert!(matches!(res, Err(Error::PageCursorInvalid)));
+        let page_options = PageOptions {
+            order_by: Some("pwd".to_string()),
+            ..Default::default()
+        };
+        let res = PostBmc::list_page(&ctx, &mm, None, page_options).await;
+        assert!(matches!(res, Err(Error::PageOrderByInvalid { .. })));
+        // Note: A column of the entity, but not allow-listed (nullable or not a sort key).
+        let page_options = PageOptions {
+            order_by: Some("description".to_string()),
+            ..Default::default()
+        };
+        let res = PostBmc::list_page(&ctx, &mm, None, page_options).await;
+        assert!(matches!(res, Err(Error::PageOrderByInvalid { .. })));
+        // Note: The cursor of the title order, for the id order.
+        let page_options = PageOptions {
+            limit: Some(1),
+            order_by: Some("title".to_string()),
+            ..Default::default()
+        };
+        let page = PostBmc::list_page(&ctx, &mm, Some(fx_filter()?), page_options).await?;
+        let page_options = PageOptions {
+            cursor: page.next_cursor,
+            order_by: Some("id".to_string()),
+            ..Default::default()
+        };
+        let res = PostBmc::list_page(&ctx, &mm, None, page_options).await;
+        assert!(matches!(res, Err(Error::PageCursorInvalid)));
+        // Note: The cursors of another column of the same type, or of the other direction.
+        for (fx_order_by, fx_replay_order_by) in [("!ctime", "mtime"), ("!ctime", "ctime")] {
+            let page_options = PageOptions {
+                limit: Some(1),
+                order_by: Some(fx_order_by.to_string()),
+                ..Default::default()
+            };
+            let page = PostBmc::list_page(&ctx, &mm, Some(fx_filter()?), page_options).await?;
+            let page_options = PageOptions {
+                cursor: page.next_cursor,
+                order_by: Some(fx_replay_order_by.to_string()),
+                ..Default::default()
+            };
+            let res = PostBmc::list_page(&ctx, &mm, None, page_options).await;
+            assert!(
+                matches!(res, Err(Error::PageCursorInvalid)),
+                "{fx_order_by} cursor replayed as {fx_replay_order_by}"
+            );
+        }
+
+        // -- Clean
+        for post in PostBmc::list(&ctx, &mm, Some(fx_filter()?), None).await? {
+            PostBmc::delete(&ctx, &mm, post.id).await?;
+        }
+
+        Ok(())
+    }
+
     #[serial]
     #[tokio::test]
     async fn test_update_ok() -> Result<()> {
@@ -290,6 +643,293 @@ mod tests {
         Ok(())
     }
 
+    #[serial]
+    #[tokio::test]
+    async fn test_update_if_version_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_title = "test_update_if_version_ok - post 01";
+        let fx_post = _dev_utils::seed_posts(&ctx, &mm, &[fx_title], &[fx_title])
+            .await?
+            .remove(0);
+        let fx_post_u = |title: &str| PostForUpdate {
+            title: Some(title.to_string()),
+            ..Default::default()
+        };
+
+        // -- Exec
+        let version =
+            PostBmc::update_if_version(&ctx, &mm, fx_post.id, fx_post.version, fx_post_u("device 1"))
+                .await?;
+        // the second device still has the first version
+        let res =
+            PostBmc::update_if_version(&ctx, &mm, fx_post.id, fx_post.version, fx_post_u("device 2"))
+                .await;
+
+        // -- Check
+        assert_eq!(version, fx_post.version + 1);
+        assert!(
+            matches!(
+                res,
+                Err(Error::VersionConflict { current_version, .. }) if current_version == version
+            ),
+            "VersionConflict not matching"
+        );
+        let post = PostBmc::get(&ctx, &mm, fx_post.id).await?;
+        assert_eq!(post.title, "device 1");
+        assert_eq!(post.version, version);
+
+        // the blind update bumps the version too
+        PostBmc::update(&ctx, &mm, fx_post.id, fx_post_u("device 3")).await?;
+        let post = PostBmc::get(&ctx, &mm, fx_post.id).await?;
+        assert_eq!(post.version, version + 1);
+
+        // -- Clean
+        PostBmc::delete(&ctx, &mm, fx_post.id).await?;
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_set_places_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_title = "test_set_places_ok - post 01";
+        let fx_post = _dev_utils::seed_posts(&ctx, &mm, &[fx_title], &[fx_title])
+            .await?
+            .remove(0);
+        let fx_place_ids = _dev_utils::seed_places(
+            &ctx,
+            &mm,
+            &["test_set_places_ok B", "test_set_places_ok A", "test_set_places_ok C"],
+        )
+        .await?;
+
+        // -- Exec
+        PostBmc::set_places(&ctx, &mm, fx_post.id, fx_place_ids.clone()).await?;
+        PostBmc::set_places(&ctx, &mm, fx_post.id, fx_place_ids[..2].to_vec()).await?;
+
+        // -- Check
+        let places = PlaceBmc::list_for_post(&ctx, &mm, fx_post.id).await?;
+        let names: Vec<&str> = places.iter().map(|place| place.name.as_str()).collect();
+        assert_eq!(names, ["test_set_places_ok A", "test_set_places_ok B"]);
+
+        // -- Exec & Check (missing place, the tags are kept)
+        let res = PostBmc::set_places(&ctx, &mm, fx_post.id, vec![fx_place_ids[2], 999_999]).await;
+        assert!(matches!(res, Err(Error::ValidationFail(_))));
+        assert_eq!(PlaceBmc::list_for_post(&ctx, &mm, fx_post.id).await?.len(), 2);
+
+        // -- Clean
+        PostBmc::delete(&ctx, &mm, fx_post.id).await?;
+        PlaceBmc::delete_many(&ctx, &mm, fx_place_ids).await?;
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_list_near_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_posts = _dev_utils::seed_posts(
+            &ctx,
+            &mm,
+            &["test_list_near_ok - post 01", "test_list_near_ok - post 02"],
+            &["test_list_near_ok", "test_list_near_ok"],
+        )
+        .await?;
+        let fx_place_ids = _dev_utils::seed_places(
+            &ctx,
+            &mm,
+            &["test_list_near_ok A", "test_list_near_ok B"],
+        )
+        .await?;
+        let fx_near = Near::new(Location::new(1.2839, 103.8515)?, 5.)?;
+        // the first post at the 2 places, the second at none
+        PostBmc::set_places(&ctx, &mm, fx_posts[0].id, fx_place_ids.clone()).await?;
+
+        // -- Exec
+        let posts = PostBmc::list_near(&ctx, &mm, fx_near, None).await?;
+
+        // -- Check
+        let posts: Vec<&PostNear> = posts
+            .iter()
+            .filter(|post| post.post.title.starts_with("test_list_near_ok"))
+            .collect();
+        assert_eq!(posts.len(), 1);
+        assert_eq!(posts[0].post.id, fx_posts[0].id);
+        let places = PlaceBmc::list_for_post(&ctx, &mm, fx_posts[0].id).await?;
+        let distance_min = places
+            .iter()
+            .map(|place| fx_near.center().distance_km(&Location::new(place.lat, place.lon).unwrap()))
+            .fold(f64::MAX, f64::min);
+        assert!((posts[0].distance_km - distance_min).abs() < 1e-6);
+
+        // -- Clean
+        for post in fx_posts {
+            PostBmc::delete(&ctx, &mm, post.id).await?;
+        }
+        PlaceBmc::delete_many(&ctx, &mm, fx_place_ids).await?;
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_update_delete_owner_scoped() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let root_ctx = Ctx::root_ctx();
+        let owner_ctx =
+            _dev_utils::seed_user(&mm, "test_owner_scoped-owner", Role::Creator).await?;
+        let other_ctx =
+            _dev_utils::seed_user(&mm, "test_owner_scoped-other", Role::Creator).await?;
+        let mod_ctx =
+            _dev_utils::seed_user(&mm, "test_owner_scoped-mod", Role::Moderator).await?;
+        let fx_post = _dev_utils::seed_posts(
+            &owner_ctx,
+            &mm,
+            &["test_owner_scoped - post 01"],
+            &["test_owner_scoped - post 01"],
+        )
+        .await?
+        .remove(0);
+        let fx_post_u = || PostForUpdate {
+            is_published: Some(true),
+            ..Default::default()
+        };
+
+        // -- Exec
+        let res_other = PostBmc::update(&other_ctx, &mm, fx_post.id, fx_post_u()).await;
+        let res_other_delete = PostBmc::delete(&other_ctx, &mm, fx_post.id).await;
+        PostBmc::update(&owner_ctx, &mm, fx_post.id, fx_post_u()).await?;
+        PostBmc::update(&mod_ctx, &mm, fx_post.id, fx_post_u()).await?;
+
+        // -- Check
+        assert_eq!(fx_post.owner_id, owner_ctx.user_id());
+        assert!(matches!(res_other, Err(Error::EntityNotFound { .. })));
+        assert!(matches!(res_other_delete, Err(Error::EntityNotFound { .. })));
+
+        // -- Clean
+        PostBmc::delete(&mod_ctx, &mm, fx_post.id).await?;
+        for ctx in [owner_ctx, other_ctx, mod_ctx] {
+            UserBmc::delete(&root_ctx, &mm, ctx.user_id()).await?;
+        }
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_history_audit_log_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let root_ctx = Ctx::root_ctx();
+        let owner_ctx = _dev_utils::seed_user(&mm, "test_history-owner", Role::Creator).await?;
+        let mod_ctx = _dev_utils::seed_user(&mm, "test_history-mod", Role::Moderator).await?;
+        let fx_post = _dev_utils::seed_posts(
+            &owner_ctx,
+            &mm,
+            &["test_history - post 01"],
+            &["test_history - post 01"],
+        )
+        .await?
+        .remove(0);
+
+        // -- Exec
+        let fx_post_u = PostForUpdate {
+            title: Some("test_history - post 01 - edited".to_string()),
+            ..Default::default()
+        };
+        PostBmc::update(&mod_ctx, &mm, fx_post.id, fx_post_u).await?;
+        PostBmc::delete(&owner_ctx, &mm, fx_post.id).await?;
+        let res_owner = PostBmc::history(&owner_ctx, &mm, fx_post.id).await;
+        let history = PostBmc::history(&mod_ctx, &mm, fx_post.id).await?;
+
+        // -- Check
+        assert!(matches!(res_owner, Err(Error::Ctx(_))));
+        let ops: Vec<(&str, i64)> = history
+            .iter()
+            .map(|e| (e.op.as_str(), e.actor_id))
+            .collect();
+        assert_eq!(
+            ops,
+            vec![
+                ("create", owner_ctx.user_id()),
+                ("update", mod_ctx.user_id()),
+                ("delete", owner_ctx.user_id()),
+            ]
+        );
+        assert_eq!(
+            history[1].changes,
+            Some(json!({"title": "test_history - post 01 - edited"}))
+        );
+        assert_eq!(history[2].changes, None);
+
+        // -- Clean
+        for ctx in [owner_ctx, mod_ctx] {
+            UserBmc::delete(&root_ctx, &mm, ctx.user_id()).await?;
+        }
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_unpublished_visible_to_owner_only() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let root_ctx = Ctx::root_ctx();
+        let owner_ctx =
+            _dev_utils::seed_user(&mm, "test_unpublished-owner", Role::Creator).await?;
+        let other_ctx = _dev_utils::seed_user(&mm, "test_unpublished-other", Role::User).await?;
+        let fx_post = _dev_utils::seed_posts(
+            &owner_ctx,
+            &mm,
+            &["test_unpublished - post 01"],
+            &["test_unpublished - post 01"],
+        )
+        .await?
+        .remove(0);
+        let fx_filter = || -> Result<Vec<PostFilter>> {
+            Ok(serde_json::from_value(json!([{
+                "title": {"$startsWith": "test_unpublished"}
+            }]))?)
+        };
+
+        // -- Exec & Check
+        let res = PostBmc::get(&other_ctx, &mm, fx_post.id).await;
+        assert!(matches!(res, Err(Error::EntityNotFound { .. })));
+        let posts = PostBmc::list(&other_ctx, &mm, Some(fx_filter()?), None).await?;
+        assert!(posts.is_empty());
+        let posts = PostBmc::list(&owner_ctx, &mm, Some(fx_filter()?), None).await?;
+        assert_eq!(posts.len(), 1);
+
+        PostBmc::update(
+            &owner_ctx,
+            &mm,
+            fx_post.id,
+            PostForUpdate {
+                is_published: Some(true),
+                ..Default::default()
+            },
+        )
+        .await?;
+        let post = PostBmc::get(&other_ctx, &mm, fx_post.id).await?;
+        assert!(post.is_published);
+
+        // -- Clean
+        for ctx in [owner_ctx, other_ctx] {
+            UserBmc::delete(&root_ctx, &mm, ctx.user_id()).await?;
+        }
+
+        Ok(())
+    }
+
     #[serial]
     #[tokio::test]
     async fn test_delete_err_not_found() -> Result<()> {
@@ -1,15 +1,53 @@
-use crate::ctx::Ctx;
-use crate::model::base::{self, DbBmc};
+use crate::ctx::{Ctx, Permission};
+use crate::model::audit_log::{fields_to_json, AuditLogBmc, AuditOp};
+use crate::model::base::{self, prep_fields_for_update, CommonIden, DbBmc};
+use crate::model::post::PostBmc;
+use chrono::{DateTime, Utc};
+use lib_storage::config::media_config;
+use lib_storage::media::{self, ImageVariant, ProcessedImage};
+use lib_storage::{PresignMethod, PresignRequest, PresignedUpload, StorageBackend};
+use lib_utils::mime::get_mime_from_bytes;
 use modql::filter::{FilterNodes, ListOptions, OpValsInt64, OpValsString};
 use serde::{Deserialize, Serialize};
-use crate::model::{Result, ModelManager};
+use serde_json::Value as Json;
+use crate::model::{Error, Result, ModelManager};
 use sqlx::FromRow;
-use modql::field::Fields;
+use modql::field::{Fields, SeaField, SeaFields};
+use sea_query::{Expr, Iden, PostgresQueryBuilder, Query, SimpleExpr};
+use sea_query_binder::SqlxBinder;
+use std::time::Duration;
+
+// region: --- Consts
+
+const STATUS_PENDING: &str = "pending";
+const STATUS_READY: &str = "ready";
+
+/// The media types of the uploads: (mime type, media type, extension, max size).
+const UPLOAD_TYPES: &[(&str, &str, &str, i64)] = &[
+    ("image/jpeg", "image", "jpg", 25 * 1024 * 1024),
+    ("image/png", "image", "png", 25 * 1024 * 1024),
+    ("image/webp", "image", "webp", 25 * 1024 * 1024),
+    ("video/mp4", "video", "mp4", 4 * 1024 * 1024 * 1024),
+    ("video/quicktime", "video", "mov", 4 * 1024 * 1024 * 1024),
+];
+
+/// The validity of the presigned uploads.
+const UPLOAD_SLOT_TTL: Duration = Duration::from_secs(15 * 60);
+
+/// The time after the slot expiry to complete its upload (e.g., a PUT started just
+/// before the expiry), then the pending media is expired (see `delete_expired_uploads`).
+const UPLOAD_COMPLETE_GRACE: Duration = Duration::from_secs(60 * 60);
+
+/// The bytes read to sniff the type of the uploads (see `get_mime_from_bytes`).
+pub(in crate::model) const SNIFF_LEN: usize = 64;
+
+// endregion: --- Consts
 
 // region: --- PostMedia Types
 #[derive(Debug, Clone, Fields, FromRow, Serialize, Deserialize)]
 pub struct PostMedia {
     pub id: i64,
+    pub owner_id: i64,
     pub post_id: i64,
     pub media_url: String,
     pub media_type: String,  // "image" or "video"
@@ -20,9 +58,13 @@ pub struct PostMedia {
     pub duration: Option<i32>,  // for videos in seconds
     pub sort_order: i32,  // order in carousel
     pub alt_text: Option<String>,
+    /// The image variants, e.g., thumbnail, feed, full (see `lib_storage::media::ImageVariant`).
+    pub variants: Json,
+    /// "pending" from its upload slot until its upload is completed, then "ready".
+    pub status: String,
 }
 
-#[derive(Fields, Deserialize)]
+#[derive(Fields, Clone, Deserialize)]
 pub struct PostMediaForCreate {
     pub post_id: i64,
     pub media_url: String,
@@ -36,6 +78,74 @@ pub struct PostMediaForCreate {
     pub alt_text: Option<String>,
 }
 
+/// An image to process (see `PostMediaBmc::create_image`).
+#[derive(Deserialize)]
+pub struct PostImageForCreate {
+    pub post_id: i64,
+    pub sort_order: i32,
+    pub alt_text: Option<String>,
+}
+
+#[derive(Fields, Clone)]
+struct PostImageForInsert {
+    post_id: i64,
+    media_url: String,
+    media_type: String,
+    mime_type: String,
+    width: Option<i32>,
+    height: Option<i32>,
+    file_size: Option<i64>,
+    sort_order: i32,
+    alt_text: Option<String>,
+    variants: Json,
+}
+
+/// The upload slot to request, for a media uploaded directly to the storage
+/// (see `PostMediaBmc::create_upload_slot`).
+#[derive(Deserialize)]
+pub struct PostMediaForUploadSlot {
+    /// The mime type of the file (e.g., "video/mp4"), checked on completion.
+    pub content_type: String,
+    /// The size of the file, in bytes.
+    pub size: i64,
+    /// PUT by default.
+    pub method: Option<PresignMethod>,
+    pub sort_order: Option<i32>,
+    pub alt_text: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PostMediaUploadSlot {
+    pub media_id: i64,
+    pub upload: PresignedUpload,
+}
+
+#[derive(Fields)]
+struct PostMediaUploadForInsert {
+    post_id: i64,
+    media_url: String,
+    media_type: String,
+    mime_type: String,
+    file_size: Option<i64>,
+    sort_order: i32,
+    alt_text: Option<String>,
+    status: String,
+    storage_key: String,
+    upload_expires_at: DateTime<Utc>,
+}
+
+#[derive(Fields, FromRow)]
+struct PostMediaUpload {
+    id: i64,
+    post_id: i64,
+    media_type: String,
+    mime_type: String,
+    file_size: Option<i64>,
+    status: String,
+    storage_key: Option<String>,
+    upload_expires_at: Option<DateTime<Utc>>,
+}
+
 #[derive(Fields, Default, Deserialize)]
 pub struct PostMediaForUpdate {
     pub media_url: Option<String>,
@@ -58,6 +168,18 @@ pub struct PostMediaFilter {
     sort_order: Option<OpValsInt64>,
 }
 
+#[derive(Iden)]
+enum PostMediaIden {
+    PostId,
+    MediaUrl,
+    Width,
+    Height,
+    Variants,
+    Status,
+    StorageKey,
+    UploadExpiresAt,
+}
+
 // endregion: --- PostMedia Types
 
 // region: --- PostMediaBmc
@@ -65,6 +187,33 @@ pub struct PostMediaBmc;
 
 impl DbBmc for PostMediaBmc {
     const TABLE: &'static str = "post_media";
+
+    fn has_owner_id() -> bool {
+        true
+    }
+
+    fn has_audit_log() -> bool {
+        true
+    }
+
+    /// The media follow the visibility of their post, and the pending ones are only
+    /// visible to their owner.
+    fn visible_cond(ctx: &Ctx) -> Option<SimpleExpr> {
+        let visible_posts = Query::select()
+            .column(CommonIden::Id)
+            .from(PostBmc::table_ref())
+            .and_where(PostBmc::visible_cond(ctx)?)
+            .to_owned();
+        let ready_or_owned = Expr::col(PostMediaIden::Status)
+            .eq(STATUS_READY)
+            .or(Expr::col(CommonIden::OwnerId).eq(ctx.user_id()));
+
+        Some(
+            Expr::col(PostMediaIden::PostId)
+                .in_subquery(visible_posts)
+                .and(ready_or_owned),
+        )
+    }
 }
 
 impl PostMediaBmc {
@@ -73,9 +222,266 @@ impl PostMediaBmc {
         mm: &ModelManager,
         post_media_c: PostMediaForCreate,
     ) -> Result<i64> {
+        // Note: The media are part of the post, so they need the post update permission.
+        ctx.require(Permission::PostUpdate)?;
+        base::check_writable::<PostBmc>(ctx, mm, post_media_c.post_id).await?;
         base::create::<Self, _>(ctx, mm, post_media_c).await
     }
 
+    /// Create the media of an image (JPEG, PNG, or WebP), with its variants stored
+    /// (see `lib_storage::media::process_image`).
+    /// The media url is the largest variant, in WebP. The post gets the media as cover,
+    /// and the smallest variant as thumbnail, if it has none.
+    pub async fn create_image(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        storage: &impl StorageBackend,
+        image_c: PostImageForCreate,
+        data: &[u8],
+    ) -> Result<i64> {
+        ctx.require(Permission::PostUpdate)?;
+        let post_id = image_c.post_id;
+        base::check_writable::<PostBmc>(ctx, mm, post_id).await?;
+
+        // -- Process & store the variants
+        let key_prefix = format!("posts/{post_id}/{}", uuid::Uuid::new_v4());
+        let image = media::process_image(storage, media_config(), &key_prefix, data).await?;
+
+        Self::create_processed_image(ctx, mm, image_c, image, i64::try_from(data.len()).ok()).await
+    }
+
+    /// Create the media of an image, with its variants stored (see `create_image`), and
+    /// the cover of the post, if it has none.
+    /// Note: The permission is checked by the caller.
+    pub(in crate::model) async fn create_processed_image(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        image_c: PostImageForCreate,
+        image: ProcessedImage,
+        file_size: Option<i64>,
+    ) -> Result<i64> {
+        let post_id = image_c.post_id;
+        let (media_url, thumbnail_url) = image_urls(&image)?;
+
+        let image_i = PostImageForInsert {
+            post_id,
+            media_url: media_url.clone(),
+            media_type: "image".to_string(),
+            mime_type: image.mime_type,
+            width: i32::try_from(image.width).ok(),
+            height: i32::try_from(image.height).ok(),
+            file_size,
+            sort_order: image_c.sort_order,
+            alt_text: image_c.alt_text,
+            variants: serde_json::to_value(&image.variants)
+                .map_err(|ex| Error::ValidationFail(ex.to_string()))?,
+        };
+
+        mm.transaction(|mm| {
+            let image_i = image_i.clone();
+            let (media_url, thumbnail_url) = (media_url.clone(), thumbnail_url.clone());
+            async move {
+                let id = base::create::<Self, _>(ctx, &mm, image_i).await?;
+                PostBmc::set_cover_if_none(ctx, &mm, post_id, &media_url, &thumbnail_url).await?;
+                Ok(id)
+            }
+        })
+        .await
+    }
+
+    /// Create a pending media, with the presigned upload of its file, for the client to
+    /// upload it directly to the storage (see `complete_upload`).
+    pub async fn create_upload_slot(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        storage: &impl StorageBackend,
+        post_id: i64,
+        slot_c: PostMediaForUploadSlot,
+    ) -> Result<PostMediaUploadSlot> {
+        ctx.require(Permission::PostUpdate)?;
+        base::check_writable::<PostBmc>(ctx, mm, post_id).await?;
+
+        // -- Check the constraints
+        let (mime_type, media_type, extension) = check_upload(&slot_c.content_type, slot_c.size)?;
+
+        // -- Presign
+        let key = format!("posts/{post_id}/{}/original.{extension}", uuid::Uuid::new_v4());
+        let upload = storage.presign_upload(
+            &key,
+            &PresignRequest {
+                method: slot_c.method.unwrap_or(PresignMethod::Put),
+                content_type: mime_type.to_string(),
+                size: slot_c.size as u64,
+                expires_in: UPLOAD_SLOT_TTL,
+            },
+        )?;
+
+        let upload_i = PostMediaUploadForInsert {
+            post_id,
+            media_url: storage.public_url(&key),
+            media_type: media_type.to_string(),
+            mime_type: mime_type.to_string(),
+            file_size: Some(slot_c.size),
+            sort_order: slot_c.sort_order.unwrap_or_default(),
+            alt_text: slot_c.alt_text,
+            status: STATUS_PENDING.to_string(),
+            storage_key: key,
+            upload_expires_at: upload.expires_at,
+        };
+        let media_id = base::create::<Self, _>(ctx, mm, upload_i).await?;
+
+        Ok(PostMediaUploadSlot { media_id, upload })
+    }
+
+    /// Complete the upload of a pending media: check its file is in the storage, of the
+    /// declared size and type (sniffed), then make it ready.
+    /// An image gets its variants (see `create_image`), then its uploaded file is deleted.
+    /// A file of another size or type is deleted, with its media, as is an expired upload.
+    pub async fn complete_upload(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        storage: &impl StorageBackend,
+        id: i64,
+    ) -> Result<PostMedia> {
+        ctx.require(Permission::PostUpdate)?;
+        base::check_writable::<Self>(ctx, mm, id).await?;
+        let upload: PostMediaUpload = base::get::<Self, _>(ctx, mm, id).await?;
+        let (STATUS_PENDING, Some(key)) = (upload.status.as_str(), upload.storage_key) else {
+            return Err(Error::MediaUploadNotPending { id });
+        };
+
+        // -- Check the expiry
+        let expired = upload
+            .upload_expires_at
+            .is_some_and(|expires_at| expires_at + UPLOAD_COMPLETE_GRACE < Utc::now());
+        if expired {
+            storage.delete(&key).await?;
+            base::delete::<Self>(ctx, mm, id).await?;
+            return Err(Error::MediaUploadExpired { id });
+        }
+
+        // -- Check the file
+        // Note: The POST policies only enforce a max size, so the actual one is checked.
+        let Some(size) = storage.size(&key).await? else {
+            return Err(Error::MediaUploadMissing { id });
+        };
+        if let Some(declared) = upload.file_size.filter(|declared| *declared as u64 != size) {
+            storage.delete(&key).await?;
+            base::delete::<Self>(ctx, mm, id).await?;
+            return Err(Error::MediaSizeMismatch { declared, actual: size });
+        }
+        let prefix = storage.download_prefix(&key, SNIFF_LEN).await?;
+        let sniffed = get_mime_from_bytes(&prefix, "");
+        if sniffed != upload.mime_type {
+            storage.delete(&key).await?;
+            base::delete::<Self>(ctx, mm, id).await?;
+            return Err(Error::MediaTypeMismatch {
+                declared: upload.mime_type,
+                sniffed,
+            });
+        }
+
+        // -- Image variants
+        let image = if upload.media_type == "image" {
+            let data = storage.download(&key).await?;
+            let key_prefix = key.rsplit_once('/').map(|(prefix, _)| prefix).unwrap_or_default();
+            let image = media::process_image(storage, media_config(), key_prefix, &data).await?;
+            let (media_url, thumbnail_url) = image_urls(&image)?;
+            Some((image, media_url, thumbnail_url))
+        } else {
+            None
+        };
+
+        // -- Prep fields
+        let mut fields = SeaFields::new(vec![
+            SeaField::new(PostMediaIden::Status, STATUS_READY),
+            SeaField::new(PostMediaIden::UploadExpiresAt, Option::<DateTime<Utc>>::None),
+        ]);
+        if let Some((image, media_url, _)) = &image {
+            let variants = serde_json::to_value(&image.variants)
+                .map_err(|ex| Error::ValidationFail(ex.to_string()))?;
+            fields.push(SeaField::new(PostMediaIden::MediaUrl, media_url.clone()));
+            fields.push(SeaField::new(PostMediaIden::Width, i32::try_from(image.width).ok()));
+            fields.push(SeaField::new(PostMediaIden::Height, i32::try_from(image.height).ok()));
+            fields.push(SeaField::new(PostMediaIden::Variants, variants));
+            // Note: The uploaded file is deleted below (its metadata, e.g., EXIF GPS, with it).
+            fields.push(SeaField::new(PostMediaIden::StorageKey, Option::<String>::None));
+        }
+        let changes = fields_to_json(&fields);
+        prep_fields_for_update::<Self>(&mut fields, ctx.user_id());
+        let cover = image
+            .as_ref()
+            .map(|(_, media_url, thumbnail_url)| (media_url.clone(), thumbnail_url.clone()));
+
+        mm.transaction(|mm| {
+            let (fields, changes, cover) = (fields.clone(), changes.clone(), cover.clone());
+            async move {
+                // -- Build query
+                // Note: The pending condition makes the completion happen once.
+                let mut query = Query::update();
+                query
+                    .table(Self::table_ref())
+                    .values(fields.for_sea_update())
+                    .and_where(Expr::col(CommonIden::Id).eq(id))
+                    .and_where(Expr::col(PostMediaIden::Status).eq(STATUS_PENDING));
+
+                // -- Exec query
+                let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+                let count = mm.dbx().execute(sqlx::query_with(&sql, values)).await?;
+                if count == 0 {
+                    return Err(Error::MediaUploadNotPending { id });
+                }
+                AuditLogBmc::record::<Self>(ctx, &mm, id, AuditOp::Update, Some(changes)).await?;
+                if let Some((media_url, thumbnail_url)) = cover {
+                    PostBmc::set_cover_if_none(ctx, &mm, upload.post_id, &media_url, &thumbnail_url)
+                        .await?;
+                }
+
+                Ok(())
+            }
+        })
+        .await?;
+
+        if image.is_some() {
+            storage.delete(&key).await?;
+        }
+
+        Self::get(ctx, mm, id).await
+    }
+
+    /// Delete the pending media whose upload slot expired (past `UPLOAD_COMPLETE_GRACE`),
+    /// with their uploaded files, if any. Returns the number of media deleted.
+    pub async fn delete_expired_uploads(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        storage: &impl StorageBackend,
+    ) -> Result<u64> {
+        ctx.require(Permission::ContentModerate)?;
+        let expired_before = Utc::now() - UPLOAD_COMPLETE_GRACE;
+
+        // -- Build query
+        let mut query = Query::delete();
+        query
+            .from_table(Self::table_ref())
+            .and_where(Expr::col(PostMediaIden::Status).eq(STATUS_PENDING))
+            .and_where(Expr::col(PostMediaIden::UploadExpiresAt).lt(expired_before))
+            .returning(Query::returning().columns([PostMediaIden::StorageKey]));
+
+        // -- Exec query
+        let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+        let sqlx_query = sqlx::query_as_with::<_, (Option<String>,), _>(&sql, values);
+        let deleted = mm.dbx().fetch_all(sqlx_query).await?;
+
+        // Note: The rows are deleted first, so a failed file delete is only logged.
+        for key in deleted.iter().filter_map(|(key,)| key.as_deref()) {
+            if let Err(ex) = storage.delete(key).await {
+                tracing::warn!("Failed to delete the expired upload file {key}: {ex:?}");
+            }
+        }
+
+        Ok(deleted.len() as u64)
+    }
+
     pub async fn get(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<PostMedia> {
         base::get::<Self, _>(ctx, mm, id).await
     }
@@ -90,11 +496,450 @@ impl PostMediaBmc {
     }
 
     pub async fn update(ctx: &Ctx, mm: &ModelManager, id: i64, post_media_u: PostMediaForUpdate) -> Result<()> {
+        ctx.require(Permission::PostUpdate)?;
         base::update::<Self, _>(ctx, mm, id, post_media_u).await
     }
 
     pub async fn delete(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
+        ctx.require(Permission::PostUpdate)?;
         base::delete::<Self>(ctx, mm, id).await
     }
 
-}
\ No newline at end of file
+    /// Delete the media of a user (owned, or on the user posts), on user erasure.
+    /// Returns the urls of the deleted media (and of their variants), for the caller
+    /// to remove the files.
+    /// Note: The permission is checked by the caller (see `UserBmc::delete`).
+    pub async fn delete_for_user(_ctx: &Ctx, mm: &ModelManager, user_id: i64) -> Result<Vec<String>> {
+        let user_posts = Query::select()
+            .column(CommonIden::Id)
+            .from(PostBmc::table_ref())
+            .and_where(Expr::col(CommonIden::OwnerId).eq(user_id))
+            .to_owned();
+
+        // -- Build query
+        let mut query = Query::delete();
+        query
+            .from_table(Self::table_ref())
+            .cond_where(
+                Expr::col(CommonIden::OwnerId)
+                    .eq(user_id)
+                    .or(Expr::col(PostMediaIden::PostId).in_subquery(user_posts)),
+            )
+            .returning(Query::returning().columns([PostMediaIden::MediaUrl, PostMediaIden::Variants]));
+
+        // -- Exec query
+        let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+        let sqlx_query = sqlx::query_as_with::<_, (String, Json), _>(&sql, values);
+        let deleted = mm.dbx().fetch_all(sqlx_query).await?;
+
+        let mut media_urls = Vec::new();
+        for (media_url, variants) in deleted {
+            let variants: Vec<ImageVariant> = serde_json::from_value(variants).unwrap_or_default();
+            let variant_urls = variants
+                .into_iter()
+                .flat_map(|variant| [variant.webp_url, variant.fallback_url])
+                .filter(|url| *url != media_url)
+                .collect::<Vec<_>>();
+            media_urls.push(media_url);
+            media_urls.extend(variant_urls);
+        }
+
+        Ok(media_urls)
+    }
+
+}
+
+/// The media url (the largest variant) and the thumbnail url (the smallest one) of
+/// the image, in WebP.
+fn image_urls(image: &ProcessedImage) -> Result<(String, String)> {
+    let (Some(largest), Some(smallest)) = (
+        image.variants.iter().max_by_key(|variant| variant.width * variant.height),
+        image.variants.iter().min_by_key(|variant| variant.width * variant.height),
+    ) else {
+        return Err(Error::ValidationFail("No image variants configured".to_string()));
+    };
+
+    Ok((largest.webp_url.clone(), smallest.webp_url.clone()))
+}
+
+/// Check the type and the size of an upload (see `UPLOAD_TYPES`).
+/// Returns its (mime type, media type, extension).
+pub(in crate::model) fn check_upload(
+    content_type: &str,
+    size: i64,
+) -> Result<(&'static str, &'static str, &'static str)> {
+    let &(mime_type, media_type, extension, max_size) = UPLOAD_TYPES
+        .iter()
+        .find(|(mime_type, ..)| *mime_type == content_type)
+        .ok_or_else(|| Error::ValidationFail(format!("Content type '{content_type}' not accepted")))?;
+    if !(1..=max_size).contains(&size) {
+        return Err(Error::ValidationFail(format!(
+            "Size {size} not between 1 and {max_size} bytes for '{mime_type}'"
+        )));
+    }
+
+    Ok((mime_type, media_type, extension))
+}
+
+// endregion: --- PostMediaBmc
+
+// region: --- Test
+#[cfg(test)]
+mod tests {
+    #[allow(unused)]
+    use crate::_dev_utils;
+
+    use super::*;
+    use crate::ctx::Role;
+    use crate::model::user::{User, UserBmc, UserForCreate};
+    use anyhow::Result;
+    use image::codecs::png::PngEncoder;
+    use image::{DynamicImage, Rgb, RgbImage};
+    use lib_storage::MemStorage;
+    use serial_test::serial;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_create_image_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let storage = MemStorage::default();
+        let fx_post = _dev_utils::seed_posts(
+            &ctx,
+            &mm,
+            &["test_create_image_ok post"],
+            &["test_create_image_ok"],
+        )
+        .await?
+        .remove(0);
+        let mut fx_data = Vec::new();
+        DynamicImage::ImageRgb8(RgbImage::from_pixel(64, 32, Rgb([30, 144, 255])))
+            .write_with_encoder(PngEncoder::new(&mut fx_data))?;
+
+        // -- Exec
+        let image_c = PostImageForCreate {
+            post_id: fx_post.id,
+            sort_order: 0,
+            alt_text: Some("test_create_image_ok".to_string()),
+        };
+        let id = PostMediaBmc::create_image(&ctx, &mm, &storage, image_c, &fx_data).await?;
+
+        // -- Check
+        let media = PostMediaBmc::get(&ctx, &mm, id).await?;
+        assert_eq!(media.media_type, "image");
+        assert_eq!(media.mime_type, "image/png");
+        assert_eq!((media.width, media.height), (Some(64), Some(32)));
+        let variants: Vec<ImageVariant> = serde_json::from_value(media.variants)?;
+        assert_eq!(variants.len(), media_config().MEDIA_VARIANTS.len());
+        assert!(variants.iter().all(|variant| (variant.width, variant.height) == (64, 32)));
+        assert_eq!(media.media_url, variants.last().unwrap().webp_url);
+        assert!(storage.exists(variants[0].fallback_url.trim_start_matches(MemStorage::URL_SCHEME)).await?);
+
+        let post = PostBmc::get(&ctx, &mm, fx_post.id).await?;
+        assert_eq!(post.cover_media_url.as_deref(), Some(media.media_url.as_str()));
+        assert!(post.thumbnail_url.is_some());
+        // the cover is set as an update of the post (versioned and audited)
+        assert_eq!(post.version, fx_post.version + 1);
+        let history = PostBmc::history(&ctx, &mm, fx_post.id).await?;
+        let cover_update = history
+            .iter()
+            .rfind(|entry| entry.op == "update")
+            .and_then(|entry| entry.changes.clone());
+        assert_eq!(cover_update.unwrap()["cover_media_url"], media.media_url.as_str());
+
+        // -- Exec & Check (second image, the cover is kept, not updated)
+        let image_c = PostImageForCreate {
+            post_id: fx_post.id,
+            sort_order: 1,
+            alt_text: None,
+        };
+        PostMediaBmc::create_image(&ctx, &mm, &storage, image_c, &fx_data).await?;
+        let post = PostBmc::get(&ctx, &mm, fx_post.id).await?;
+        assert_eq!(post.cover_media_url.as_deref(), Some(media.media_url.as_str()));
+        assert_eq!(post.version, fx_post.version + 1);
+
+        // -- Clean
+        PostBmc::delete(&ctx, &mm, fx_post.id).await?;
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_create_image_ok_registered_user() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let root_ctx = Ctx::root_ctx();
+        let storage = MemStorage::default();
+        let fx_username = "test_create_image_ok_registered_user-user-01";
+        let user_id = UserBmc::create(
+            &root_ctx,
+            &mm,
+            UserForCreate {
+                username: fx_username.to_string(),
+                pwd_clear: format!("{fx_username} pwd"),
+                email: format!("{fx_username}@example.com"),
+            },
+        )
+        .await?;
+        let user: User = UserBmc::get(&root_ctx, &mm, user_id).await?;
+        assert_eq!(user.role, Role::User);
+        let ctx = Ctx::new(user_id)?.add_role(user.role);
+        let mut fx_data = Vec::new();
+        DynamicImage::ImageRgb8(RgbImage::from_pixel(64, 32, Rgb([30, 144, 255])))
+            .write_with_encoder(PngEncoder::new(&mut fx_data))?;
+
+        // -- Exec
+        let fx_post = _dev_utils::seed_posts(
+            &ctx,
+            &mm,
+            &["test_create_image_ok_registered_user post"],
+            &["test_create_image_ok_registered_user"],
+        )
+        .await?
+        .remove(0);
+        let image_c = PostImageForCreate {
+            post_id: fx_post.id,
+            sort_order: 0,
+            alt_text: None,
+        };
+        let id = PostMediaBmc::create_image(&ctx, &mm, &storage, image_c, &fx_data).await?;
+
+        // -- Check
+        let media = PostMediaBmc::get(&ctx, &mm, id).await?;
+        assert_eq!(media.post_id, fx_post.id);
+        let post = PostBmc::get(&ctx, &mm, fx_post.id).await?;
+        assert_eq!(post.cover_media_url.as_deref(), Some(media.media_url.as_str()));
+
+        // -- Clean
+        UserBmc::delete(&root_ctx, &mm, user_id).await?;
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_complete_upload_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let storage = MemStorage::default();
+        let fx_post = _dev_utils::seed_posts(
+            &ctx,
+            &mm,
+            &["test_complete_upload_ok post"],
+            &["test_complete_upload_ok"],
+        )
+        .await?
+        .remove(0);
+        let mut fx_data = Vec::new();
+        DynamicImage::ImageRgb8(RgbImage::from_pixel(48, 64, Rgb([255, 140, 0])))
+            .write_with_encoder(PngEncoder::new(&mut fx_data))?;
+
+        // -- Exec (slot)
+        let slot_c = PostMediaForUploadSlot {
+            content_type: "image/png".to_string(),
+            size: fx_data.len() as i64,
+            method: None,
+            sort_order: Some(1),
+            alt_text: None,
+        };
+        let slot = PostMediaBmc::create_upload_slot(&ctx, &mm, &storage, fx_post.id, slot_c).await?;
+
+        // -- Check (slot)
+        assert_eq!(slot.upload.method, PresignMethod::Put);
+        assert_eq!(slot.upload.headers["content-type"], "image/png");
+        let media = PostMediaBmc::get(&ctx, &mm, slot.media_id).await?;
+        assert_eq!(media.status, STATUS_PENDING);
+        assert!(matches!(
+            PostMediaBmc::complete_upload(&ctx, &mm, &storage, slot.media_id).await,
+            Err(Error::MediaUploadMissing { .. })
+        ));
+
+        // -- Exec (upload & complete)
+        let key = slot.upload.url.trim_start_matches(MemStorage::URL_SCHEME);
+        storage.upload(key, &fx_data).await?;
+        let media = PostMediaBmc::complete_upload(&ctx, &mm, &storage, slot.media_id).await?;
+
+        // -- Check (complete)
+        assert_eq!(media.status, STATUS_READY);
+        assert_eq!((media.width, media.height), (Some(48), Some(64)));
+        assert!(media.media_url.ends_with(".webp"));
+        assert!(!storage.exists(key).await?, "uploaded file should be deleted");
+        assert!(matches!(
+            PostMediaBmc::complete_upload(&ctx, &mm, &storage, slot.media_id).await,
+            Err(Error::MediaUploadNotPending { .. })
+        ));
+
+        // -- Clean
+        PostBmc::delete(&ctx, &mm, fx_post.id).await?;
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_complete_upload_err_type_mismatch() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let storage = MemStorage::default();
+        let fx_post = _dev_utils::seed_posts(
+            &ctx,
+            &mm,
+            &["test_complete_upload_err_type_mismatch post"],
+            &["test_complete_upload_err_type_mismatch"],
+        )
+        .await?
+        .remove(0);
+        let fx_data = b"<html><script>alert('not a video')</script></html>";
+        let slot_c = PostMediaForUploadSlot {
+            content_type: "video/mp4".to_string(),
+            size: fx_data.len() as i64,
+            method: Some(PresignMethod::Post),
+            sort_order: None,
+            alt_text: None,
+        };
+        let slot = PostMediaBmc::create_upload_slot(&ctx, &mm, &storage, fx_post.id, slot_c).await?;
+        let key = slot.upload.fields["key"].clone();
+        storage.upload(&key, fx_data).await?;
+
+        // -- Exec
+        let res = PostMediaBmc::complete_upload(&ctx, &mm, &storage, slot.media_id).await;
+
+        // -- Check
+        assert!(
+            matches!(&res, Err(Error::MediaTypeMismatch { declared, .. }) if declared == "video/mp4"),
+            "{res:?}"
+        );
+        assert!(!storage.exists(&key).await?);
+        assert!(matches!(
+            PostMediaBmc::get(&ctx, &mm, slot.media_id).await,
+            Err(Error::EntityNotFound { .. })
+        ));
+
+        // -- Check (content type not accepted)
+        let slot_c = PostMediaForUploadSlot {
+            content_type: "text/html".to_string(),
+            size: 10,
+            method: None,
+            sort_order: None,
+            alt_text: None,
+        };
+        assert!(matches!(
+            PostMediaBmc::create_upload_slot(&ctx, &mm, &storage, fx_post.id, slot_c).await,
+            Err(Error::ValidationFail(_))
+        ));
+
+        // -- Clean
+        PostBmc::delete(&ctx, &mm, fx_post.id).await?;
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_complete_upload_err_size_mismatch() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let storage = MemStorage::default();
+        let fx_post = _dev_utils::seed_posts(
+            &ctx,
+            &mm,
+            &["test_complete_upload_err_size_mismatch post"],
+            &["test_complete_upload_err_size_mismatch"],
+        )
+        .await?
+        .remove(0);
+        let fx_data = b"\x00\x00\x00\x18ftypisom\x00\x00\x02\x00isomiso2mp41";
+        let slot_c = PostMediaForUploadSlot {
+            content_type: "video/mp4".to_string(),
+            size: 1024,
+            method: Some(PresignMethod::Post),
+            sort_order: None,
+            alt_text: None,
+        };
+        let slot = PostMediaBmc::create_upload_slot(&ctx, &mm, &storage, fx_post.id, slot_c).await?;
+        let key = slot.upload.fields["key"].clone();
+        storage.upload(&key, fx_data).await?;
+
+        // -- Exec
+        let res = PostMediaBmc::complete_upload(&ctx, &mm, &storage, slot.media_id).await;
+
+        // -- Check
+        assert!(
+            matches!(&res, Err(Error::MediaSizeMismatch { declared: 1024, actual }) if *actual == fx_data.len() as u64),
+            "{res:?}"
+        );
+        assert!(!storage.exists(&key).await?);
+        assert!(matches!(
+            PostMediaBmc::get(&ctx, &mm, slot.media_id).await,
+            Err(Error::EntityNotFound { .. })
+        ));
+
+        // -- Clean
+        PostBmc::delete(&ctx, &mm, fx_post.id).await?;
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_complete_upload_err_expired() -> Result<()> {
+        // -- Setup & Fixtures
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let storage = MemStorage::default();
+        let fx_post = _dev_utils::seed_posts(
+            &ctx,
+            &mm,
+            &["test_complete_upload_err_expired post"],
+            &["test_complete_upload_err_expired"],
+        )
+        .await?
+        .remove(0);
+        let fx_data = b"\x00\x00\x00\x18ftypisom\x00\x00\x02\x00isomiso2mp41";
+        let mut slot_ids = Vec::new();
+        for _ in 0..2 {
+            let slot_c = PostMediaForUploadSlot {
+                content_type: "video/mp4".to_string(),
+                size: fx_data.len() as i64,
+                method: None,
+                sort_order: None,
+                alt_text: None,
+            };
+            let slot = PostMediaBmc::create_upload_slot(&ctx, &mm, &storage, fx_post.id, slot_c).await?;
+            storage.upload(slot.upload.url.trim_start_matches(MemStorage::URL_SCHEME), fx_data).await?;
+            slot_ids.push(slot.media_id);
+        }
+        let expire_sql = "UPDATE post_media SET upload_expires_at = now() - interval '2 hours' WHERE id = $1";
+        for id in &slot_ids {
+            mm.dbx().execute(sqlx::query(expire_sql).bind(id)).await?;
+        }
+
+        // -- Exec & Check (complete)
+        let res = PostMediaBmc::complete_upload(&ctx, &mm, &storage, slot_ids[0]).await;
+        assert!(matches!(&res, Err(Error::MediaUploadExpired { .. })), "{res:?}");
+        assert!(matches!(
+            PostMediaBmc::get(&ctx, &mm, slot_ids[0]).await,
+            Err(Error::EntityNotFound { .. })
+        ));
+
+        // -- Exec & Check (sweep)
+        let count = PostMediaBmc::delete_expired_uploads(&ctx, &mm, &storage).await?;
+        assert_eq!(count, 1);
+        assert!(matches!(
+            PostMediaBmc::get(&ctx, &mm, slot_ids[1]).await,
+            Err(Error::EntityNotFound { .. })
+        ));
+        assert_eq!(PostMediaBmc::delete_expired_uploads(&ctx, &mm, &storage).await?, 0);
+
+        // -- Clean
+        PostBmc::delete(&ctx, &mm, fx_post.id).await?;
+
+        Ok(())
+    }
+}
+// endregion: --- Test
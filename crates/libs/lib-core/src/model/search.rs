@@ -0,0 +1,311 @@
+//! Search of the posts, by full-text (`search_tsv`, with the text search config of the
+//! post language) and trigram word similarity of the title (typo tolerance, `pg_trgm`).
+//! See the `0006_post_search` migration.
+//!
+//! The hits are ranked by relevance, recency, and engagement (see `rank_expr`).
+
+// region: ---- Modules
+use crate::ctx::Ctx;
+use crate::model::base::{self, not_deleted_cond, DbBmc, OffsetCursor, Page, PageOptions};
+use crate::model::post::{validate_lang, Post, PostBmc};
+use crate::model::{Error, ModelManager, Result};
+use modql::field::HasSeaFields;
+use sea_query::{Alias, Expr, Order, PostgresQueryBuilder, Query, SimpleExpr};
+use sea_query_binder::SqlxBinder;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+// endregion: ---- Modules
+
+// region:    --- Consts
+
+/// The weights of the rank (relevance, then recency and engagement as tie breakers).
+const RANK_TEXT_WEIGHT: f64 = 1.0;
+const RANK_SIMILARITY_WEIGHT: f64 = 0.5;
+const RANK_RECENCY_WEIGHT: f64 = 0.2;
+const RANK_ENGAGEMENT_WEIGHT: f64 = 0.05;
+
+/// The age (in seconds) halving the recency score (30 days).
+const RECENCY_HALF_AGE_SEC: i64 = 30 * 24 * 3600;
+
+const SNIPPET_OPTIONS: &str =
+	"StartSel=<mark>, StopSel=</mark>, MaxFragments=2, MaxWords=30, MinWords=10";
+
+/// The description, html escaped, so the only markup of the snippet is the `<mark>`.
+/// Note: The text search parser skips the html entities, so the words are the same.
+const SNIPPET_DOCUMENT: &str = "replace(replace(replace(replace(description, '&', '&amp;'), \
+	'<', '&lt;'), '>', '&gt;'), '\"', '&quot;')";
+
+// endregion: --- Consts
+
+// region:    --- Search Types
+
+/// The search of the posts (e.g., from the `/api/search` query string).
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PostSearch {
+	/// The text to search, in the web search syntax (e.g., `"old town" -hotel`).
+	pub q: String,
+	/// Only the posts in this language (ISO 639-1 code), searched with its config
+	/// (stemming). By default, each post is searched with the config of its language.
+	pub lang: Option<String>,
+}
+
+/// A post matching the search, with its rank and highlighted snippet.
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct PostHit {
+	#[sqlx(flatten)]
+	#[serde(flatten)]
+	pub post: Post,
+	pub rank: f64,
+	/// The matching fragments of the description (html escaped), with the terms in `<mark>`.
+	pub snippet: String,
+}
+
+// endregion: --- Search Types
+
+impl PostBmc {
+	/// Search the posts visible to the ctx, ranked by relevance (then recency and engagement).
+	/// Note: Paged by offset (the rank is computed), so `page_options.order_by` is ignored.
+	pub async fn search(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		search: PostSearch,
+		page_options: PageOptions,
+	) -> Result<Page<PostHit>> {
+		let q = search.q.trim().to_string();
+		if q.is_empty() {
+			return Err(Error::ValidationFail("search query empty".to_string()));
+		}
+		validate_lang(search.lang.as_deref())?;
+		let limit = base::compute_page_limit(page_options.limit)?;
+		let offset = match page_options.cursor {
+			Some(cursor) => OffsetCursor::decode(&cursor)?.offset,
+			None => 0,
+		};
+
+		// -- Build the query
+		let config = match search.lang.clone() {
+			Some(lang) => Expr::cust_with_values("post_search_config($1)", [lang]),
+			None => Expr::cust("post_search_config(lang)"),
+		};
+		let tsquery = Expr::cust_with_exprs(
+			"websearch_to_tsquery($1, $2)",
+			[config.clone(), Expr::val(q.clone()).into()],
+		);
+		let q_expr: SimpleExpr = Expr::val(q).into();
+
+		let mut query = Query::select();
+		query
+			.from(Self::table_ref())
+			.columns(Post::sea_column_refs())
+			.expr_as(rank_expr(tsquery.clone(), q_expr.clone()), Alias::new("rank"))
+			.expr_as(
+				Expr::cust_with_exprs(
+					format!("ts_headline($1, {SNIPPET_DOCUMENT}, $2, '{SNIPPET_OPTIONS}')"),
+					[config, tsquery.clone()],
+				),
+				Alias::new("snippet"),
+			)
+			.and_where(Expr::cust_with_exprs(
+				"(search_tsv @@ $1 OR $2 <% title)",
+				[tsquery, q_expr],
+			));
+		if let Some(lang) = search.lang {
+			query.and_where(Expr::col(Alias::new("lang")).eq(lang));
+		}
+		if let Some(not_deleted) = not_deleted_cond::<Self>() {
+			query.and_where(not_deleted);
+		}
+		if let Some(visible_cond) = Self::visible_cond(ctx) {
+			query.and_where(visible_cond);
+		}
+		// Note: One more row than the limit, to know if there is a next page.
+		query
+			.order_by(Alias::new("rank"), Order::Desc)
+			.order_by(Alias::new("id"), Order::Desc)
+			.limit(limit as u64 + 1)
+			.offset(offset as u64);
+
+		// -- Execute the query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let sqlx_query = sqlx::query_as_with::<_, PostHit, _>(&sql, values);
+		let mut hits = mm.dbx().fetch_all(sqlx_query).await?;
+
+		// -- Build the page
+		let has_more = hits.len() as i64 > limit;
+		hits.truncate(limit as usize);
+		let next_cursor = has_more.then(|| {
+			OffsetCursor {
+				offset: offset + limit,
+			}
+			.encode()
+		});
+
+		Ok(Page {
+			items: hits,
+			next_cursor,
+			has_more,
+		})
+	}
+}
+
+/// The rank of a hit, the weighted sum of:
+/// - the full-text rank (cover density, the title weighted above the description),
+/// - the word similarity of the query to the title (typos),
+/// - the recency (1 when new, halved every `RECENCY_HALF_AGE_SEC`),
+/// - the engagement (log of the media count).
+fn rank_expr(tsquery: SimpleExpr, q: SimpleExpr) -> SimpleExpr {
+	let sql = format!(
+		"({RANK_TEXT_WEIGHT} * ts_rank_cd(search_tsv, $1) \
+		+ {RANK_SIMILARITY_WEIGHT} * word_similarity($2, title) \
+		+ {RANK_RECENCY_WEIGHT} * power(0.5, extract(epoch FROM now() - ctime) / {RECENCY_HALF_AGE_SEC}) \
+		+ {RANK_ENGAGEMENT_WEIGHT} * ln(1 + media_count))::float8"
+	);
+	Expr::cust_with_exprs(sql, [tsquery, q])
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	pub type Result<T> = core::result::Result<T, Error>;
+	pub type Error = Box<dyn std::error::Error>; // For tests.
+
+	use super::*;
+	use crate::_dev_utils;
+	use crate::model::post::{PostFilter, PostForCreate};
+	use serde_json::json;
+	use serial_test::serial;
+
+	fn fx_post_c(title: &str, description: &str, lang: &str) -> PostForCreate {
+		PostForCreate {
+			title: title.to_string(),
+			description: description.to_string(),
+			is_published: Some(true),
+			cover_media_url: None,
+			thumbnail_url: None,
+			media_count: None,
+			has_video: None,
+			lang: Some(lang.to_string()),
+		}
+	}
+
+	fn fx_search(q: &str, lang: Option<&str>) -> PostSearch {
+		PostSearch {
+			q: q.to_string(),
+			lang: lang.map(|lang| lang.to_string()),
+		}
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_search_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_posts = [
+			fx_post_c(
+				"test_search_ok Hiking the Dolomites",
+				"Three days hiking between the mountain huts, with the best views of the trip.",
+				"en",
+			),
+			fx_post_c(
+				"test_search_ok Street food in Penang",
+				"The hawker stalls of George Town, and a short hike up Penang Hill.",
+				"en",
+			),
+			fx_post_c(
+				"test_search_ok Randonnée en Corse",
+				"Le GR20, les randonnées les plus difficiles d'Europe.",
+				"fr",
+			),
+		];
+		for post_c in fx_posts {
+			PostBmc::create(&ctx, &mm, post_c).await?;
+		}
+
+		// -- Exec & Check (stemming, title first)
+		let page = PostBmc::search(&ctx, &mm, fx_search("hikes", None), PageOptions::default())
+			.await?;
+		let titles: Vec<&str> = page.items.iter().map(|hit| hit.post.title.as_str()).collect();
+		assert_eq!(
+			titles,
+			["test_search_ok Hiking the Dolomites", "test_search_ok Street food in Penang"]
+		);
+		assert!(page.items[0].rank > page.items[1].rank);
+		assert!(page.items[0].snippet.contains("<mark>hiking</mark>"));
+		assert!(!page.has_more);
+
+		// -- Exec & Check (typo, by title similarity)
+		let page = PostBmc::search(&ctx, &mm, fx_search("Dolomits", None), PageOptions::default())
+			.await?;
+		assert_eq!(page.items.len(), 1);
+		assert_eq!(page.items[0].post.title, "test_search_ok Hiking the Dolomites");
+
+		// -- Exec & Check (language)
+		let page = PostBmc::search(&ctx, &mm, fx_search("randonnée", Some("fr")), PageOptions::default())
+			.await?;
+		assert_eq!(page.items.len(), 1);
+		assert_eq!(page.items[0].post.lang, "fr");
+
+		// -- Exec & Check (pages)
+		let page_options = PageOptions {
+			limit: Some(1),
+			..Default::default()
+		};
+		let page = PostBmc::search(&ctx, &mm, fx_search("hiking", None), page_options).await?;
+		assert_eq!(page.items.len(), 1);
+		assert!(page.has_more);
+		let page_options = PageOptions {
+			cursor: page.next_cursor,
+			limit: Some(1),
+			..Default::default()
+		};
+		let page = PostBmc::search(&ctx, &mm, fx_search("hiking", None), page_options).await?;
+		assert_eq!(page.items.len(), 1);
+		assert_eq!(page.items[0].post.title, "test_search_ok Street food in Penang");
+		assert!(!page.has_more);
+
+		// -- Clean
+		let filter: Vec<PostFilter> = serde_json::from_value(json!([{
+			"title": {"$startsWith": "test_search_ok"}
+		}]))?;
+		for post in PostBmc::list(&ctx, &mm, Some(filter), None).await? {
+			PostBmc::delete(&ctx, &mm, post.id).await?;
+		}
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_search_ok_snippet_escaped() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_post_c = fx_post_c(
+			"test_search_ok_snippet_escaped Lagoon",
+			"<script>alert('xss')</script> Snorkeling in the lagoon, \"Q&A\" with the guides.",
+			"en",
+		);
+		let post_id = PostBmc::create(&ctx, &mm, fx_post_c).await?;
+
+		// -- Exec
+		let page = PostBmc::search(&ctx, &mm, fx_search("snorkeling", None), PageOptions::default())
+			.await?;
+
+		// -- Check
+		assert_eq!(page.items.len(), 1);
+		let snippet = &page.items[0].snippet;
+		assert!(snippet.contains("<mark>Snorkeling</mark>"));
+		assert!(snippet.contains("&lt;/script&gt;"));
+		assert!(!snippet.contains("script>"));
+		assert!(snippet.contains("&quot;Q&amp;A&quot;"));
+
+		// -- Clean
+		PostBmc::delete(&ctx, &mm, post_id).await?;
+
+		Ok(())
+	}
+}
+
+// endregion: --- Tests
@@ -0,0 +1,655 @@
+// region: ---- Modules
+use crate::ctx::Ctx;
+use crate::model::base::{self, prep_fields_for_update, DbBmc};
+use crate::model::ModelManager;
+use crate::model::{Error, Result};
+use chrono::{DateTime, Utc};
+use lib_auth::auth_config;
+use modql::field::{Fields, HasSeaFields, SeaField, SeaFields};
+use sea_query::{Expr, Iden, Order, PostgresQueryBuilder, Query};
+use sea_query_binder::SqlxBinder;
+use serde::Serialize;
+use sqlx::postgres::PgRow;
+use sqlx::FromRow;
+use uuid::Uuid;
+// endregion: ---- Modules
+
+// region:    --- Session Types
+
+const SESSION_TOUCH_INTERVAL_SEC: i64 = 5 * 60;
+
+// Client info column sizes (in chars, see the `session` table).
+const DEVICE_MAX_LEN: usize = 128;
+const IP_MAX_LEN: usize = 64;
+const LOCATION_MAX_LEN: usize = 128;
+
+/// A session is a refresh token family.
+/// Each refresh rotates `current_jti`, and presenting a previous jti revokes the whole family.
+#[derive(Clone, Fields, FromRow, Debug, Serialize)]
+pub struct Session {
+	pub id: i64,
+	pub user_id: i64,
+
+	// -- Client info
+	pub device: Option<String>,
+	pub user_agent: Option<String>,
+	pub ip: Option<String>,
+	pub location: Option<String>,
+
+	// -- Lifecycle
+	pub last_used_at: DateTime<Utc>,
+	pub expires_at: DateTime<Utc>,
+	pub revoked_at: Option<DateTime<Utc>>,
+	pub ctime: DateTime<Utc>,
+}
+
+/// Session info needed to validate and rotate a refresh token.
+#[derive(Clone, Fields, FromRow, Debug)]
+pub struct SessionForRotate {
+	pub id: i64,
+	pub user_id: i64,
+	pub family_id: Uuid,
+	pub current_jti: Uuid,
+	pub expires_at: DateTime<Utc>,
+	pub revoked_at: Option<DateTime<Utc>>,
+}
+
+/// Session info needed to validate an access token.
+#[derive(Clone, Fields, FromRow, Debug)]
+pub struct SessionForAuth {
+	pub id: i64,
+	pub user_id: i64,
+	pub last_used_at: DateTime<Utc>,
+	pub expires_at: DateTime<Utc>,
+	pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl SessionForAuth {
+	pub fn is_active(&self) -> bool {
+		self.revoked_at.is_none() && Utc::now() < self.expires_at
+	}
+
+	/// True if the last seen info is old enough to be updated
+	/// (avoids a session write on every request).
+	pub fn is_touch_due(&self) -> bool {
+		Utc::now() - self.last_used_at > chrono::Duration::seconds(SESSION_TOUCH_INTERVAL_SEC)
+	}
+}
+
+/// A session as shown to its user (e.g., the "Your devices" list).
+#[derive(Serialize)]
+pub struct SessionDTO {
+	pub id: i64,
+	pub device: Option<String>,
+	pub ip: Option<String>,
+	pub location: Option<String>,
+	pub last_used_at: DateTime<Utc>,
+	pub ctime: DateTime<Utc>,
+	/// True for the session of the request.
+	pub current: bool,
+}
+
+impl SessionDTO {
+	pub fn from_session(session: Session, current_session_id: Option<i64>) -> Self {
+		SessionDTO {
+			current: Some(session.id) == current_session_id,
+			id: session.id,
+			device: session.device,
+			ip: session.ip,
+			location: session.location,
+			last_used_at: session.last_used_at,
+			ctime: session.ctime,
+		}
+	}
+}
+
+pub struct SessionForCreate {
+	pub user_id: i64,
+	pub device: Option<String>,
+	pub user_agent: Option<String>,
+	pub ip: Option<String>,
+	pub location: Option<String>,
+}
+
+#[derive(Fields)]
+struct SessionForInsert {
+	user_id: i64,
+	family_id: Uuid,
+	current_jti: Uuid,
+	device: Option<String>,
+	user_agent: Option<String>,
+	ip: Option<String>,
+	location: Option<String>,
+	last_used_at: DateTime<Utc>,
+	expires_at: DateTime<Utc>,
+}
+
+/// The last seen client info of a session.
+#[derive(Fields, Default)]
+pub struct SessionForTouch {
+	pub ip: Option<String>,
+	pub location: Option<String>,
+}
+
+/// The ids to put in the web tokens of a session
+/// (`family_id` as the token `sid`, and `jti` for the refresh token).
+#[derive(Clone, Debug)]
+pub struct SessionTokenIds {
+	pub id: i64,
+	pub family_id: Uuid,
+	pub jti: Uuid,
+}
+
+/// Marker trait
+pub trait SessionBy: HasSeaFields + for<'r> FromRow<'r, PgRow> + Unpin + Send {}
+
+impl SessionBy for Session {}
+impl SessionBy for SessionForRotate {}
+impl SessionBy for SessionForAuth {}
+
+#[derive(Iden)]
+enum SessionIden {
+	Id,
+	UserId,
+	FamilyId,
+	CurrentJti,
+	LastUsedAt,
+	ExpiresAt,
+	RevokedAt,
+	RevokedReason,
+}
+
+// endregion: --- Session Types
+
+// region:    --- SessionBmc
+pub struct SessionBmc;
+
+impl DbBmc for SessionBmc {
+	const TABLE: &'static str = "session";
+}
+
+impl SessionBmc {
+	/// Create a new session (refresh token family), typically on login.
+	pub async fn create(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		session_c: SessionForCreate,
+	) -> Result<SessionTokenIds> {
+		let SessionForCreate {
+			user_id,
+			device,
+			user_agent,
+			ip,
+			location,
+		} = session_c;
+
+		let family_id = Uuid::new_v4();
+		let jti = Uuid::new_v4();
+		let now = Utc::now();
+
+		let session_fi = SessionForInsert {
+			user_id,
+			family_id,
+			current_jti: jti,
+			device: truncate_chars(device, DEVICE_MAX_LEN),
+			user_agent,
+			ip: truncate_chars(ip, IP_MAX_LEN),
+			location: truncate_chars(location, LOCATION_MAX_LEN),
+			last_used_at: now,
+			expires_at: refresh_expires_at(now),
+		};
+
+		let id = base::create::<Self, _>(ctx, mm, session_fi).await?;
+
+		Ok(SessionTokenIds { id, family_id, jti })
+	}
+
+	pub async fn get(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<Session> {
+		base::get::<Self, _>(ctx, mm, id).await
+	}
+
+	/// List the active (not revoked, not expired) sessions of the ctx user,
+	/// most recently used first.
+	pub async fn list_active(ctx: &Ctx, mm: &ModelManager) -> Result<Vec<Session>> {
+		// -- Build query
+		let mut query = Query::select();
+		query
+			.from(Self::table_ref())
+			.columns(Session::sea_column_refs())
+			.and_where(Expr::col(SessionIden::UserId).eq(ctx.user_id()))
+			.and_where(Expr::col(SessionIden::RevokedAt).is_null())
+			.and_where(Expr::col(SessionIden::ExpiresAt).gt(Utc::now()))
+			.order_by(SessionIden::LastUsedAt, Order::Desc);
+
+		// -- Execute query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let sqlx_query = sqlx::query_as_with::<_, Session, _>(&sql, values);
+		let sessions = mm.dbx().fetch_all(sqlx_query).await?;
+
+		Ok(sessions)
+	}
+
+	pub async fn first_by_family_id<E>(
+		_ctx: &Ctx,
+		mm: &ModelManager,
+		family_id: Uuid,
+	) -> Result<Option<E>>
+	where
+		E: SessionBy,
+	{
+		// -- Build query
+		let mut query = Query::select();
+		query
+			.from(Self::table_ref())
+			.columns(E::sea_idens())
+			.and_where(Expr::col(SessionIden::FamilyId).eq(family_id));
+
+		// -- Execute query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let sqlx_query = sqlx::query_as_with::<_, E, _>(&sql, values);
+		let entity = mm.dbx().fetch_optional(sqlx_query).await?;
+
+		Ok(entity)
+	}
+
+	/// Rotate the refresh token of a session family.
+	///
+	/// - If `jti` is the current one, a new jti is stored and returned.
+	/// - If `jti` is a previous (already rotated) one, the whole family is revoked
+	///   and `Error::SessionTokenReused` is returned.
+	pub async fn rotate(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		family_id: Uuid,
+		jti: Uuid,
+	) -> Result<SessionTokenIds> {
+		let session: SessionForRotate =
+			Self::first_by_family_id(ctx, mm, family_id)
+				.await?
+				.ok_or(Error::SessionNotFound)?;
+
+		if session.revoked_at.is_some() {
+			return Err(Error::SessionRevoked);
+		}
+
+		if Utc::now() > session.expires_at {
+			return Err(Error::SessionExpired);
+		}
+
+		if session.current_jti != jti {
+			tracing::warn!(
+				"Refresh token reuse detected for session {}, revoking family",
+				session.id
+			);
+			Self::revoke_family(ctx, mm, family_id, "token_reuse").await?;
+			return Err(Error::SessionTokenReused {
+				session_id: session.id,
+			});
+		}
+
+		// -- Prep fields
+		let new_jti = Uuid::new_v4();
+		let now = Utc::now();
+		let mut fields = SeaFields::new(vec![
+			SeaField::new(SessionIden::CurrentJti, new_jti),
+			SeaField::new(SessionIden::LastUsedAt, now),
+			SeaField::new(SessionIden::ExpiresAt, refresh_expires_at(now)),
+		]);
+		prep_fields_for_update::<Self>(&mut fields, ctx.user_id());
+
+		// -- Build query
+		// Note: The jti condition makes the rotation atomic. If a concurrent request
+		//       rotated the same token first, this one is a reuse.
+		let fields = fields.for_sea_update();
+		let mut query = Query::update();
+		query
+			.table(Self::table_ref())
+			.values(fields)
+			.and_where(Expr::col(SessionIden::Id).eq(session.id))
+			.and_where(Expr::col(SessionIden::CurrentJti).eq(jti))
+			.and_where(Expr::col(SessionIden::RevokedAt).is_null());
+
+		// -- Exec query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let count = mm.dbx().execute(sqlx::query_with(&sql, values)).await?;
+
+		if count == 0 {
+			Self::revoke_family(ctx, mm, family_id, "token_reuse").await?;
+			return Err(Error::SessionTokenReused {
+				session_id: session.id,
+			});
+		}
+
+		Ok(SessionTokenIds {
+			id: session.id,
+			family_id,
+			jti: new_jti,
+		})
+	}
+
+	/// Update the last seen time and client info of a session.
+	pub async fn touch(
+		_ctx: &Ctx,
+		mm: &ModelManager,
+		id: i64,
+		session_t: SessionForTouch,
+	) -> Result<()> {
+		// -- Prep fields
+		let session_t = SessionForTouch {
+			ip: truncate_chars(session_t.ip, IP_MAX_LEN),
+			location: truncate_chars(session_t.location, LOCATION_MAX_LEN),
+		};
+		let mut fields = session_t.not_none_sea_fields();
+		fields.push(SeaField::new(SessionIden::LastUsedAt, Utc::now()));
+
+		// -- Build query
+		// Note: No mid/mtime update, since this is not a user change of the session.
+		let fields = fields.for_sea_update();
+		let mut query = Query::update();
+		query
+			.table(Self::table_ref())
+			.values(fields)
+			.and_where(Expr::col(SessionIden::Id).eq(id));
+
+		// -- Exec query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		mm.dbx().execute(sqlx::query_with(&sql, values)).await?;
+
+		Ok(())
+	}
+
+	/// Revoke one session of the ctx user.
+	pub async fn revoke(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
+		// -- Prep fields
+		let mut fields = revoke_fields("user_revoked");
+		prep_fields_for_update::<Self>(&mut fields, ctx.user_id());
+
+		// -- Build query
+		let fields = fields.for_sea_update();
+		let mut query = Query::update();
+		query
+			.table(Self::table_ref())
+			.values(fields)
+			.and_where(Expr::col(SessionIden::Id).eq(id))
+			.and_where(Expr::col(SessionIden::UserId).eq(ctx.user_id()))
+			.and_where(Expr::col(SessionIden::RevokedAt).is_null());
+
+		// -- Exec query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let count = mm.dbx().execute(sqlx::query_with(&sql, values)).await?;
+
+		// -- Check result
+		if count == 0 {
+			Err(Error::EntityNotFound {
+				entity: Self::TABLE,
+				id,
+			})
+		} else {
+			Ok(())
+		}
+	}
+
+	/// Revoke all the sessions of the ctx user, except the `except_id` one (if any).
+	/// Returns the number of revoked sessions.
+	pub async fn revoke_all(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		except_id: Option<i64>,
+	) -> Result<u64> {
+		// -- Prep fields
+		let mut fields = revoke_fields("user_revoked_all");
+		prep_fields_for_update::<Self>(&mut fields, ctx.user_id());
+
+		// -- Build query
+		let fields = fields.for_sea_update();
+		let mut query = Query::update();
+		query
+			.table(Self::table_ref())
+			.values(fields)
+			.and_where(Expr::col(SessionIden::UserId).eq(ctx.user_id()))
+			.and_where(Expr::col(SessionIden::RevokedAt).is_null());
+		if let Some(except_id) = except_id {
+			query.and_where(Expr::col(SessionIden::Id).ne(except_id));
+		}
+
+		// -- Exec query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let count = mm.dbx().execute(sqlx::query_with(&sql, values)).await?;
+
+		Ok(count)
+	}
+
+	/// Delete all the sessions of a user (they hold its ip, location, and user agent),
+	/// on user erasure. Returns the number of deleted sessions.
+	/// Note: The permission is checked by the caller (see `UserBmc::delete`).
+	pub async fn delete_for_user(ctx: &Ctx, mm: &ModelManager, user_id: i64) -> Result<u64> {
+		base::delete_where::<Self>(ctx, mm, Expr::col(SessionIden::UserId).eq(user_id)).await
+	}
+
+	/// Revoke all the tokens of a session family.
+	pub async fn revoke_family(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		family_id: Uuid,
+		reason: &str,
+	) -> Result<()> {
+		// -- Prep fields
+		let mut fields = revoke_fields(reason);
+		prep_fields_for_update::<Self>(&mut fields, ctx.user_id());
+
+		// -- Build query
+		let fields = fields.for_sea_update();
+		let mut query = Query::update();
+		query
+			.table(Self::table_ref())
+			.values(fields)
+			.and_where(Expr::col(SessionIden::FamilyId).eq(family_id))
+			.and_where(Expr::col(SessionIden::RevokedAt).is_null());
+
+		// -- Exec query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		mm.dbx().execute(sqlx::query_with(&sql, values)).await?;
+
+		Ok(())
+	}
+}
+
+fn refresh_expires_at(now: DateTime<Utc>) -> DateTime<Utc> {
+	now + chrono::Duration::seconds(auth_config().REFRESH_TOKEN_TTL)
+}
+
+/// Client info comes from request headers or bodies, so it is truncated
+/// to its column size rather than failing the login.
+fn truncate_chars(value: Option<String>, max_len: usize) -> Option<String> {
+	value.map(|v| match v.char_indices().nth(max_len) {
+		Some((idx, _)) => v[..idx].to_string(),
+		None => v,
+	})
+}
+
+fn revoke_fields(reason: &str) -> SeaFields {
+	SeaFields::new(vec![
+		SeaField::new(SessionIden::RevokedAt, Utc::now()),
+		SeaField::new(SessionIden::RevokedReason, reason.to_string()),
+	])
+}
+
+// endregion: --- SessionBmc
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	pub type Result<T> = core::result::Result<T, Error>;
+	pub type Error = Box<dyn std::error::Error>; // For tests.
+
+	use super::*;
+	use crate::_dev_utils;
+	use crate::model::user::{User, UserBmc};
+	use serial_test::serial;
+
+	async fn fx_demo1_ctx(mm: &ModelManager) -> Result<Ctx> {
+		let user: User = UserBmc::first_by_username(&Ctx::root_ctx(), mm, "demo1")
+			.await?
+			.ok_or("Should have user 'demo1'")?;
+
+		Ok(Ctx::new(user.id)?)
+	}
+
+	async fn fx_session(ctx: &Ctx, mm: &ModelManager) -> Result<SessionTokenIds> {
+		let user_ctx = fx_demo1_ctx(mm).await?;
+
+		let ids = SessionBmc::create(
+			ctx,
+			mm,
+			SessionForCreate {
+				user_id: user_ctx.user_id(),
+				device: Some("test device".to_string()),
+				user_agent: None,
+				ip: Some("127.0.0.1".to_string()),
+				location: None,
+			},
+		)
+		.await?;
+
+		Ok(ids)
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_rotate_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_ids = fx_session(&ctx, &mm).await?;
+
+		// -- Exec
+		let ids = SessionBmc::rotate(&ctx, &mm, fx_ids.family_id, fx_ids.jti).await?;
+
+		// -- Check
+		assert_eq!(ids.id, fx_ids.id);
+		assert_ne!(ids.jti, fx_ids.jti);
+		let session: SessionForRotate =
+			SessionBmc::first_by_family_id(&ctx, &mm, fx_ids.family_id)
+				.await?
+				.ok_or("Should have session")?;
+		assert_eq!(session.current_jti, ids.jti);
+		assert!(session.revoked_at.is_none());
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_rotate_err_reused_revokes_family() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_ids = fx_session(&ctx, &mm).await?;
+		let ids = SessionBmc::rotate(&ctx, &mm, fx_ids.family_id, fx_ids.jti).await?;
+
+		// -- Exec
+		// Replay the already rotated token.
+		let res = SessionBmc::rotate(&ctx, &mm, fx_ids.family_id, fx_ids.jti).await;
+
+		// -- Check
+		assert!(
+			matches!(res, Err(crate::model::Error::SessionTokenReused { .. })),
+			"should be SessionTokenReused"
+		);
+		// The latest token of the family must be revoked as well.
+		let res = SessionBmc::rotate(&ctx, &mm, fx_ids.family_id, ids.jti).await;
+		assert!(
+			matches!(res, Err(crate::model::Error::SessionRevoked)),
+			"should be SessionRevoked"
+		);
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_create_ok_device_truncated() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let user_ctx = fx_demo1_ctx(&mm).await?;
+		let fx_device = "é".repeat(DEVICE_MAX_LEN + 10);
+
+		// -- Exec
+		let ids = SessionBmc::create(
+			&ctx,
+			&mm,
+			SessionForCreate {
+				user_id: user_ctx.user_id(),
+				device: Some(fx_device),
+				user_agent: None,
+				ip: None,
+				location: None,
+			},
+		)
+		.await?;
+
+		// -- Check
+		let session = SessionBmc::get(&ctx, &mm, ids.id).await?;
+		assert_eq!(session.device, Some("é".repeat(DEVICE_MAX_LEN)));
+
+		// -- Clean
+		SessionBmc::revoke(&user_ctx, &mm, ids.id).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_revoke_all_except_current_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let root_ctx = Ctx::root_ctx();
+		let fx_current = fx_session(&root_ctx, &mm).await?;
+		let fx_other = fx_session(&root_ctx, &mm).await?;
+		let ctx = fx_demo1_ctx(&mm).await?.add_session_id(fx_current.id);
+
+		// -- Exec
+		SessionBmc::revoke_all(&ctx, &mm, ctx.session_id()).await?;
+
+		// -- Check
+		let sessions = SessionBmc::list_active(&ctx, &mm).await?;
+		assert!(sessions.iter().any(|s| s.id == fx_current.id));
+		assert!(!sessions.iter().any(|s| s.id == fx_other.id));
+
+		// -- Clean
+		SessionBmc::revoke(&ctx, &mm, fx_current.id).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_revoke_err_other_user() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let root_ctx = Ctx::root_ctx();
+		let fx_ids = fx_session(&root_ctx, &mm).await?;
+		let ctx = Ctx::new(9999)?;
+
+		// -- Exec
+		let res = SessionBmc::revoke(&ctx, &mm, fx_ids.id).await;
+
+		// -- Check
+		assert!(
+			matches!(
+				res,
+				Err(crate::model::Error::EntityNotFound {
+					entity: "session",
+					..
+				})
+			),
+			"EntityNotFound not matching"
+		);
+
+		Ok(())
+	}
+}
+
+// endregion: --- Tests
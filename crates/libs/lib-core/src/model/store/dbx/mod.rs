@@ -51,6 +51,11 @@ impl TxnHolder {
 	}
 }
 
+/// The savepoint of a nested begin, by depth (2 for the first nested begin).
+fn savepoint_name(depth: i32) -> String {
+	format!("dbx_sp_{depth}")
+}
+
 impl Deref for TxnHolder {
 	type Target = Transaction<'static, Postgres>;
 
@@ -65,35 +70,75 @@ impl DerefMut for TxnHolder {
 	}
 }
 
+/// The isolation level of a transaction (see `Dbx::begin_txn_with`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IsolationLevel {
+	/// The postgres default.
+	#[default]
+	ReadCommitted,
+	RepeatableRead,
+	/// Might fail on commit with a serialization failure (40001), to be retried.
+	Serializable,
+}
+
+impl IsolationLevel {
+	fn as_sql(&self) -> &'static str {
+		match self {
+			IsolationLevel::ReadCommitted => "READ COMMITTED",
+			IsolationLevel::RepeatableRead => "REPEATABLE READ",
+			IsolationLevel::Serializable => "SERIALIZABLE",
+		}
+	}
+}
+
 impl Dbx {
+	/// Begin a transaction, or a savepoint if one is already open (nested).
 	pub async fn begin_txn(&self) -> Result<()> {
-		println!("DEBUG: commit_txn called, with_txn: {}", self.with_txn);
+		self.begin_txn_inner(None).await
+	}
 
+	/// Same as `begin_txn`, with the isolation level of the transaction.
+	/// Note: Ignored for a nested begin (savepoint), which is in the open transaction.
+	pub async fn begin_txn_with(&self, isolation: IsolationLevel) -> Result<()> {
+		self.begin_txn_inner(Some(isolation)).await
+	}
+
+	async fn begin_txn_inner(&self, isolation: Option<IsolationLevel>) -> Result<()> {
 		if !self.with_txn {
 			return Err(Error::CannotBeginTxnWithTxnFalse);
 		}
 
 		let mut txh_g = self.txn_holder.lock().await;
-		// If we already have a tx holder, then, we increment
+		// If we already have a tx holder, then, we increment and set a savepoint
 		if let Some(txh) = txh_g.as_mut() {
 			txh.inc();
+			let sql = format!("SAVEPOINT {}", savepoint_name(txh.counter));
+			sqlx::query(&sql).execute(txh.txn.as_mut()).await?;
 		}
 		// If not, we create one with a new transaction
 		else {
-			let transaction = self.db_pool.begin().await?;
+			let mut transaction = self.db_pool.begin().await?;
+			if let Some(isolation) = isolation {
+				let sql = format!("SET TRANSACTION ISOLATION LEVEL {}", isolation.as_sql());
+				sqlx::query(&sql).execute(transaction.as_mut()).await?;
+			}
 			let _ = txh_g.insert(TxnHolder::new(transaction));
 		}
 
 		Ok(())
 	}
 
+	/// Rollback the transaction, or the last savepoint if nested.
 	pub async fn rollback_txn(&self) -> Result<()> {
 		let mut txh_g = self.txn_holder.lock().await;
 		if let Some(mut txn_holder) = txh_g.take() {
 			// Take the TxnHolder out of the Option
 			if txn_holder.counter > 1 {
+				let sql = format!("ROLLBACK TO SAVEPOINT {}", savepoint_name(txn_holder.counter));
 				txn_holder.counter -= 1;
+				let res = sqlx::query(&sql).execute(txn_holder.txn.as_mut()).await;
 				let _ = txh_g.replace(txn_holder); // Put it back if not the last reference
+				res?;
 			} else {
 				// Perform the actual rollback
 				txn_holder.txn.rollback().await?;
@@ -105,6 +150,7 @@ impl Dbx {
 		}
 	}
 
+	/// Commit the transaction, or release the last savepoint if nested.
 	pub async fn commit_txn(&self) -> Result<()> {
 		if !self.with_txn {
 			return Err(Error::CannotCommitTxnWithTxnFalse);
@@ -112,6 +158,7 @@ impl Dbx {
 
 		let mut txh_g = self.txn_holder.lock().await;
 		if let Some(txh) = txh_g.as_mut() {
+			let savepoint = savepoint_name(txh.counter);
 			let counter = txh.dec();
 			// If 0, then, it should be matching commit for the first first begin_txn
 			// so we can commit.
@@ -120,7 +167,10 @@ impl Dbx {
 				if let Some(txn) = txh_g.take() {
 					txn.txn.commit().await?;
 				} // TODO: Might want to add a warning on the else.
-			} // TODO: Might want to add a warning on the else.
+			} else {
+				let sql = format!("RELEASE SAVEPOINT {savepoint}");
+				sqlx::query(&sql).execute(txh.txn.as_mut()).await?;
+			}
 
 			Ok(())
 		}
@@ -130,10 +180,20 @@ impl Dbx {
 		}
 	}
 
+	/// If a transaction is open (see `begin_txn`).
+	pub async fn in_txn(&self) -> bool {
+		self.txn_holder.lock().await.is_some()
+	}
+
 	pub fn db(&self) -> &Pool<Postgres> {
 		&self.db_pool
 	}
 
+	/// If the queries can run in a transaction (see `begin_txn`).
+	pub fn with_txn(&self) -> bool {
+		self.with_txn
+	}
+
 	pub async fn fetch_one<'q, O, A>(
 		&self,
 		query: QueryAs<'q, Postgres, O, A>,
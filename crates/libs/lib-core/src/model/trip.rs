@@ -0,0 +1,424 @@
+//! The trips (personalized routes): a trip has ordered days (see `trip_day`), and each
+//! day has ordered stops at places (see `trip_stop`).
+//!
+//! The days and stops are part of their trip, so their writes need the trip to be
+//! writable by the ctx (owned, or `Permission::ContentModerate`).
+
+// region: ---- Modules
+use crate::ctx::Ctx;
+use crate::generate_common_bmc_fns;
+use crate::model::base::{self, owner_scope, CommonIden, DbBmc, PageKeyType};
+use crate::model::trip_day::{TripDay, TripDayBmc};
+use crate::model::trip_stop::{TripStop, TripStopBmc};
+use crate::model::{Error, ModelManager, Result};
+use chrono::NaiveDate;
+use modql::field::Fields;
+use modql::filter::{FilterNodes, ListOptions, OpValsBool, OpValsInt64, OpValsString};
+use sea_query::{Expr, Iden, LockType, PostgresQueryBuilder, Query, SimpleExpr};
+use sea_query_binder::SqlxBinder;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+// endregion: ---- Modules
+
+// region:    --- Trip Types
+
+#[derive(Debug, Clone, Fields, FromRow, Serialize)]
+pub struct Trip {
+	pub id: i64,
+	pub owner_id: i64,
+	pub title: String,
+	pub description: Option<String>,
+	pub start_date: Option<NaiveDate>,
+	/// The currency of the stop budgets (ISO 4217 code).
+	pub currency: String,
+	/// A template is visible to all the users, to duplicate it (see `TripBmc::duplicate`).
+	pub is_template: bool,
+	/// The trip this one was duplicated from, if any.
+	pub source_trip_id: Option<i64>,
+}
+
+#[derive(Fields, Deserialize)]
+pub struct TripForCreate {
+	pub title: String,
+	pub description: Option<String>,
+	pub start_date: Option<NaiveDate>,
+	pub currency: Option<String>,
+	pub is_template: Option<bool>,
+}
+
+#[derive(Fields, Default, Deserialize)]
+pub struct TripForUpdate {
+	pub title: Option<String>,
+	pub description: Option<String>,
+	pub start_date: Option<NaiveDate>,
+	pub currency: Option<String>,
+	pub is_template: Option<bool>,
+}
+
+/// The copy of a trip (see `TripBmc::duplicate`).
+#[derive(Fields)]
+struct TripForDuplicate {
+	title: String,
+	description: Option<String>,
+	currency: String,
+	source_trip_id: i64,
+}
+
+#[derive(FilterNodes, Deserialize, Default, Debug)]
+pub struct TripFilter {
+	id: Option<OpValsInt64>,
+	owner_id: Option<OpValsInt64>,
+	title: Option<OpValsString>,
+	is_template: Option<OpValsBool>,
+}
+
+/// The totals of a trip (see `TripBmc::totals`).
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct TripTotals {
+	pub day_count: i64,
+	pub stop_count: i64,
+	/// The sum of the stop budgets, in the minor unit of the trip currency.
+	pub budget: i64,
+	/// The sum of the planned stays (departure - arrival) at the stops.
+	pub planned_minutes: i64,
+	/// The straight line (great-circle) distance between the consecutive stops.
+	pub distance_km: f64,
+}
+
+/// A trip with its days and their stops, in order.
+#[derive(Debug, Clone, Serialize)]
+pub struct TripItinerary {
+	#[serde(flatten)]
+	pub trip: Trip,
+	pub days: Vec<TripItineraryDay>,
+	pub totals: TripTotals,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TripItineraryDay {
+	#[serde(flatten)]
+	pub day: TripDay,
+	pub stops: Vec<TripStop>,
+}
+
+#[derive(Iden)]
+enum TripIden {
+	IsTemplate,
+}
+
+// endregion: --- Trip Types
+
+// region:    --- TripBmc
+
+pub struct TripBmc;
+
+impl DbBmc for TripBmc {
+	const TABLE: &'static str = "trip";
+
+	fn has_owner_id() -> bool {
+		true
+	}
+
+	fn page_order_columns() -> Vec<(&'static str, PageKeyType)> {
+		vec![
+			("id", PageKeyType::Int),
+			("title", PageKeyType::Text),
+			("ctime", PageKeyType::Time),
+			("mtime", PageKeyType::Time),
+		]
+	}
+
+	/// The trips are only visible to their owner, except the templates.
+	fn visible_cond(ctx: &Ctx) -> Option<SimpleExpr> {
+		Some(
+			Expr::col(TripIden::IsTemplate)
+				.eq(true)
+				.or(Expr::col(CommonIden::OwnerId).eq(ctx.user_id())),
+		)
+	}
+}
+
+generate_common_bmc_fns!(
+	Bmc: TripBmc,
+	Entity: Trip,
+	Filter: TripFilter,
+);
+
+impl TripBmc {
+	pub async fn create(ctx: &Ctx, mm: &ModelManager, trip_c: TripForCreate) -> Result<i64> {
+		if let Some(currency) = &trip_c.currency {
+			validate_currency(currency)?;
+		}
+		base::create::<Self, _>(ctx, mm, trip_c).await
+	}
+
+	pub async fn update(ctx: &Ctx, mm: &ModelManager, id: i64, trip_u: TripForUpdate) -> Result<()> {
+		if let Some(currency) = &trip_u.currency {
+			validate_currency(currency)?;
+		}
+		base::update::<Self, _>(ctx, mm, id, trip_u).await
+	}
+
+	/// The trip with its days and stops, and its totals.
+	pub async fn get_itinerary(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<TripItinerary> {
+		let trip = Self::get(ctx, mm, id).await?;
+		let mut stops = TripStopBmc::list_for_trip(ctx, mm, id).await?.into_iter().peekable();
+
+		// Note: The stops are ordered by day, as the days.
+		let days = TripDayBmc::list_for_trip(ctx, mm, id)
+			.await?
+			.into_iter()
+			.map(|day| {
+				let mut day_stops = Vec::new();
+				while let Some(stop) = stops.next_if(|stop| stop.trip_day_id == day.id) {
+					day_stops.push(stop);
+				}
+				TripItineraryDay {
+					day,
+					stops: day_stops,
+				}
+			})
+			.collect();
+		let totals = Self::totals(ctx, mm, id).await?;
+
+		Ok(TripItinerary { trip, days, totals })
+	}
+
+	/// Duplicate a trip visible to the ctx (e.g., a template), with its days and stops,
+	/// as a new trip of the ctx user. The start date is not copied.
+	/// Returns the id of the new trip.
+	pub async fn duplicate(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		id: i64,
+		title: Option<String>,
+	) -> Result<i64> {
+		let source = Self::get(ctx, mm, id).await?;
+
+		mm.transaction(|mm| {
+			let trip_d = TripForDuplicate {
+				title: title.clone().unwrap_or_else(|| source.title.clone()),
+				description: source.description.clone(),
+				currency: source.currency.clone(),
+				source_trip_id: id,
+			};
+			async move {
+				let new_id = base::create::<Self, _>(ctx, &mm, trip_d).await?;
+
+				// -- Copy the days
+				let sql = "INSERT INTO trip_day (trip_id, position, title, notes, cid, ctime, mid, mtime) \
+					SELECT $1, position, title, notes, $3, now(), $3, now() \
+					FROM trip_day WHERE trip_id = $2";
+				let query = sqlx::query(sql).bind(new_id).bind(id).bind(ctx.user_id());
+				mm.dbx().execute(query).await?;
+
+				// -- Copy the stops (to the new day of the same position)
+				let sql = "INSERT INTO trip_stop (trip_id, trip_day_id, place_id, position, \
+						arrival_time, departure_time, transport_mode, notes, budget, cid, ctime, mid, mtime) \
+					SELECT $1, new_day.id, stop.place_id, stop.position, \
+						stop.arrival_time, stop.departure_time, stop.transport_mode, stop.notes, stop.budget, \
+						$3, now(), $3, now() \
+					FROM trip_stop stop \
+					JOIN trip_day day ON day.id = stop.trip_day_id \
+					JOIN trip_day new_day ON new_day.trip_id = $1 AND new_day.position = day.position \
+					WHERE stop.trip_id = $2";
+				let query = sqlx::query(sql).bind(new_id).bind(id).bind(ctx.user_id());
+				mm.dbx().execute(query).await?;
+
+				Ok(new_id)
+			}
+		})
+		.await
+	}
+
+	/// The totals of a trip (days, stops, budget, planned time, and distance).
+	pub async fn totals(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<TripTotals> {
+		// Note: The trip must be visible to the ctx (not found otherwise).
+		Self::get(ctx, mm, id).await?;
+
+		let sql = "SELECT \
+				(SELECT count(*) FROM trip_day WHERE trip_id = $1) AS day_count, \
+				count(stop.id) AS stop_count, \
+				coalesce(sum(stop.budget), 0)::BIGINT AS budget, \
+				coalesce(sum(extract(epoch FROM stop.departure_time - stop.arrival_time)) / 60, 0)::BIGINT \
+					AS planned_minutes, \
+				coalesce(sum(haversine_km(stop.prev_lat, stop.prev_lon, stop.lat, stop.lon)), 0)::FLOAT8 \
+					AS distance_km \
+			FROM ( \
+				SELECT trip_stop.id, trip_stop.budget, trip_stop.arrival_time, trip_stop.departure_time, \
+					place.lat, place.lon, lag(place.lat) OVER w AS prev_lat, lag(place.lon) OVER w AS prev_lon \
+				FROM trip_stop \
+				JOIN trip_day ON trip_day.id = trip_stop.trip_day_id \
+				JOIN place ON place.id = trip_stop.place_id \
+				WHERE trip_stop.trip_id = $1 \
+				WINDOW w AS (ORDER BY trip_day.position, trip_stop.position) \
+			) AS stop";
+		let query = sqlx::query_as::<_, TripTotals>(sql).bind(id);
+		let totals = mm.dbx().fetch_one(query).await?;
+
+		Ok(totals)
+	}
+
+	/// Delete all the trips of an owner (their days and stops cascade), on user erasure.
+	/// Note: The permission is checked by the caller (see `UserBmc::delete`).
+	pub async fn delete_for_owner(ctx: &Ctx, mm: &ModelManager, owner_id: i64) -> Result<u64> {
+		base::delete_where::<Self>(ctx, mm, Expr::col(CommonIden::OwnerId).eq(owner_id)).await
+	}
+
+	/// Check the trip is writable by the ctx, and lock it until the end of the transaction,
+	/// for the writes of its days and stops (e.g., the positions of a reorder).
+	/// Note: Must be called in a transaction (see `ModelManager::transaction`).
+	pub(in crate::model) async fn lock_writable(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
+		let mut query = Query::select();
+		query
+			.from(Self::table_ref())
+			.column(CommonIden::Id)
+			.and_where(Expr::col(CommonIden::Id).eq(id))
+			.lock(LockType::Update);
+		if let Some(owner_cond) = owner_scope::<Self>(ctx) {
+			query.and_where(owner_cond);
+		}
+
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let sqlx_query = sqlx::query_as_with::<_, (i64,), _>(&sql, values);
+		mm.dbx()
+			.fetch_optional(sqlx_query)
+			.await?
+			.ok_or(Error::EntityNotFound {
+				entity: Self::TABLE,
+				id,
+			})?;
+
+		Ok(())
+	}
+}
+
+/// The currency must be an ISO 4217 code (e.g., "EUR").
+fn validate_currency(currency: &str) -> Result<()> {
+	if currency.len() != 3 || !currency.chars().all(|c| c.is_ascii_uppercase()) {
+		return Err(Error::ValidationFail(format!("currency '{currency}' invalid")));
+	}
+	Ok(())
+}
+
+// endregion: --- TripBmc
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	pub type Result<T> = core::result::Result<T, Error>;
+	pub type Error = Box<dyn std::error::Error>; // For tests.
+
+	use super::*;
+	use crate::_dev_utils;
+	use crate::model::place::PlaceBmc;
+	use crate::model::trip_day::TripDayForCreate;
+	use crate::model::trip_stop::TripStopForCreate;
+	use chrono::NaiveTime;
+	use serial_test::serial;
+
+	fn fx_trip_c(title: &str) -> TripForCreate {
+		TripForCreate {
+			title: title.to_string(),
+			description: None,
+			start_date: None,
+			currency: Some("SGD".to_string()),
+			is_template: None,
+		}
+	}
+
+	fn fx_stop_c(place_id: i64, arrival: (u32, u32), departure: (u32, u32), budget: i64) -> TripStopForCreate {
+		TripStopForCreate {
+			place_id,
+			arrival_time: NaiveTime::from_hms_opt(arrival.0, arrival.1, 0),
+			departure_time: NaiveTime::from_hms_opt(departure.0, departure.1, 0),
+			transport_mode: Some("walk".to_string()),
+			notes: None,
+			budget: Some(budget),
+		}
+	}
+
+	/// A trip of 2 days, the first with 2 stops, the second with 1 stop.
+	async fn seed_trip(ctx: &Ctx, mm: &ModelManager, title: &str, place_ids: &[i64]) -> Result<i64> {
+		let trip_id = TripBmc::create(ctx, mm, fx_trip_c(title)).await?;
+		let day_1 = TripDayBmc::create(ctx, mm, trip_id, TripDayForCreate::default()).await?;
+		let day_2 = TripDayBmc::create(ctx, mm, trip_id, TripDayForCreate::default()).await?;
+		TripStopBmc::create(ctx, mm, day_1, fx_stop_c(place_ids[0], (9, 0), (10, 30), 1500)).await?;
+		TripStopBmc::create(ctx, mm, day_1, fx_stop_c(place_ids[1], (11, 0), (12, 0), 2500)).await?;
+		TripStopBmc::create(ctx, mm, day_2, fx_stop_c(place_ids[2], (9, 0), (9, 15), 0)).await?;
+		Ok(trip_id)
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_totals_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_place_ids =
+			_dev_utils::seed_places(&ctx, &mm, &["test_totals_ok A", "test_totals_ok B", "test_totals_ok C"])
+				.await?;
+		let trip_id = seed_trip(&ctx, &mm, "test_totals_ok", &fx_place_ids).await?;
+
+		// -- Exec
+		let totals = TripBmc::totals(&ctx, &mm, trip_id).await?;
+
+		// -- Check
+		assert_eq!(totals.day_count, 2);
+		assert_eq!(totals.stop_count, 3);
+		assert_eq!(totals.budget, 4000);
+		assert_eq!(totals.planned_minutes, 90 + 60 + 15);
+		// the seeded places are at the same location
+		assert!(totals.distance_km.abs() < 1e-9);
+
+		// -- Clean
+		TripBmc::delete(&ctx, &mm, trip_id).await?;
+		PlaceBmc::delete_many(&ctx, &mm, fx_place_ids).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_duplicate_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_place_ids = _dev_utils::seed_places(
+			&ctx,
+			&mm,
+			&["test_duplicate_ok A", "test_duplicate_ok B", "test_duplicate_ok C"],
+		)
+		.await?;
+		let trip_id = seed_trip(&ctx, &mm, "test_duplicate_ok", &fx_place_ids).await?;
+
+		// -- Exec
+		let copy_id = TripBmc::duplicate(&ctx, &mm, trip_id, Some("test_duplicate_ok copy".to_string()))
+			.await?;
+
+		// -- Check
+		let source = TripBmc::get_itinerary(&ctx, &mm, trip_id).await?;
+		let copy = TripBmc::get_itinerary(&ctx, &mm, copy_id).await?;
+		assert_eq!(copy.trip.title, "test_duplicate_ok copy");
+		assert_eq!(copy.trip.source_trip_id, Some(trip_id));
+		assert_eq!(copy.days.len(), 2);
+		for (source_day, copy_day) in source.days.iter().zip(&copy.days) {
+			assert_ne!(copy_day.day.id, source_day.day.id);
+			let place_ids = |day: &TripItineraryDay| -> Vec<i64> {
+				day.stops.iter().map(|stop| stop.place_id).collect()
+			};
+			assert_eq!(place_ids(copy_day), place_ids(source_day));
+			assert!(copy_day.stops.iter().all(|stop| stop.trip_day_id == copy_day.day.id));
+		}
+		assert_eq!(copy.totals.budget, source.totals.budget);
+
+		// -- Clean
+		TripBmc::delete_many(&ctx, &mm, vec![trip_id, copy_id]).await?;
+		PlaceBmc::delete_many(&ctx, &mm, fx_place_ids).await?;
+
+		Ok(())
+	}
+}
+
+// endregion: --- Tests
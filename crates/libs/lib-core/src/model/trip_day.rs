@@ -0,0 +1,237 @@
+// region: ---- Modules
+use crate::ctx::Ctx;
+use crate::model::base::{self, CommonIden, DbBmc};
+use crate::model::trip::TripBmc;
+use crate::model::{Error, ModelManager, Result};
+use modql::field::{Fields, HasSeaFields};
+use sea_query::{Expr, Iden, Order, PostgresQueryBuilder, Query, SimpleExpr};
+use sea_query_binder::SqlxBinder;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+// endregion: ---- Modules
+
+// region:    --- TripDay Types
+
+/// A day of a trip, with its stops (see `TripStopBmc`).
+#[derive(Debug, Clone, Fields, FromRow, Serialize)]
+pub struct TripDay {
+	pub id: i64,
+	pub trip_id: i64,
+	/// The order of the day in the trip (see `TripDayBmc::reorder`).
+	pub position: i32,
+	pub title: Option<String>,
+	pub notes: Option<String>,
+}
+
+/// Note: The day is added at the end of the trip.
+#[derive(Default, Deserialize)]
+pub struct TripDayForCreate {
+	pub title: Option<String>,
+	pub notes: Option<String>,
+}
+
+#[derive(Fields, Default, Deserialize)]
+pub struct TripDayForUpdate {
+	pub title: Option<String>,
+	pub notes: Option<String>,
+}
+
+#[derive(Fields)]
+struct TripDayForInsert {
+	trip_id: i64,
+	position: i32,
+	title: Option<String>,
+	notes: Option<String>,
+}
+
+#[derive(Iden)]
+enum TripDayIden {
+	TripId,
+	Position,
+}
+
+// endregion: --- TripDay Types
+
+// region:    --- TripDayBmc
+
+pub struct TripDayBmc;
+
+impl DbBmc for TripDayBmc {
+	const TABLE: &'static str = "trip_day";
+
+	/// The days follow the visibility of their trip.
+	fn visible_cond(ctx: &Ctx) -> Option<SimpleExpr> {
+		let visible_trips = Query::select()
+			.column(CommonIden::Id)
+			.from(TripBmc::table_ref())
+			.and_where(TripBmc::visible_cond(ctx)?)
+			.to_owned();
+
+		Some(Expr::col(TripDayIden::TripId).in_subquery(visible_trips))
+	}
+}
+
+impl TripDayBmc {
+	/// Add a day at the end of the trip.
+	pub async fn create(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		trip_id: i64,
+		day_c: TripDayForCreate,
+	) -> Result<i64> {
+		mm.transaction(|mm| {
+			let day_i = TripDayForInsert {
+				trip_id,
+				position: 0,
+				title: day_c.title.clone(),
+				notes: day_c.notes.clone(),
+			};
+			async move {
+				// Note: Locked, so the concurrent adds do not get the same position.
+				TripBmc::lock_writable(ctx, &mm, trip_id).await?;
+				let sql = "SELECT coalesce(max(position) + 1, 0) FROM trip_day WHERE trip_id = $1";
+				let (position,) = mm
+					.dbx()
+					.fetch_one(sqlx::query_as::<_, (i32,)>(sql).bind(trip_id))
+					.await?;
+
+				base::create::<Self, _>(ctx, &mm, TripDayForInsert { position, ..day_i }).await
+			}
+		})
+		.await
+	}
+
+	pub async fn get(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<TripDay> {
+		base::get::<Self, _>(ctx, mm, id).await
+	}
+
+	/// The days of a trip, in order.
+	pub async fn list_for_trip(ctx: &Ctx, mm: &ModelManager, trip_id: i64) -> Result<Vec<TripDay>> {
+		// Note: The trip must be visible to the ctx (not found otherwise).
+		TripBmc::get(ctx, mm, trip_id).await?;
+
+		let mut query = Query::select();
+		query
+			.from(Self::table_ref())
+			.columns(TripDay::sea_column_refs())
+			.and_where(Expr::col(TripDayIden::TripId).eq(trip_id))
+			.order_by(TripDayIden::Position, Order::Asc);
+
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let sqlx_query = sqlx::query_as_with::<_, TripDay, _>(&sql, values);
+		let days = mm.dbx().fetch_all(sqlx_query).await?;
+
+		Ok(days)
+	}
+
+	pub async fn update(ctx: &Ctx, mm: &ModelManager, id: i64, day_u: TripDayForUpdate) -> Result<()> {
+		let day = Self::get(ctx, mm, id).await?;
+		base::check_writable::<TripBmc>(ctx, mm, day.trip_id).await?;
+		base::update::<Self, _>(ctx, mm, id, day_u).await
+	}
+
+	/// Delete a day, with its stops.
+	pub async fn delete(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
+		let day = Self::get(ctx, mm, id).await?;
+		base::check_writable::<TripBmc>(ctx, mm, day.trip_id).await?;
+		base::delete::<Self>(ctx, mm, id).await
+	}
+
+	/// Reorder the days of a trip, all or nothing.
+	/// `day_ids` must be all the days of the trip, in the new order.
+	pub async fn reorder(ctx: &Ctx, mm: &ModelManager, trip_id: i64, day_ids: Vec<i64>) -> Result<()> {
+		mm.transaction(|mm| {
+			let day_ids = day_ids.clone();
+			async move {
+				TripBmc::lock_writable(ctx, &mm, trip_id).await?;
+
+				let mut current_ids: Vec<i64> = Self::list_for_trip(ctx, &mm, trip_id)
+					.await?
+					.into_iter()
+					.map(|day| day.id)
+					.collect();
+				let mut new_ids = day_ids.clone();
+				current_ids.sort_unstable();
+				new_ids.sort_unstable();
+				if new_ids != current_ids {
+					return Err(Error::ValidationFail(format!(
+						"day ids must be the days of the trip {trip_id}"
+					)));
+				}
+
+				// Note: In one update, the unique positions are checked at its end (deferrable).
+				let sql = "UPDATE trip_day SET position = new.position - 1, mid = $3, mtime = now() \
+					FROM unnest($2::BIGINT[]) WITH ORDINALITY AS new(id, position) \
+					WHERE trip_day.id = new.id AND trip_day.trip_id = $1";
+				let query = sqlx::query(sql).bind(trip_id).bind(day_ids).bind(ctx.user_id());
+				mm.dbx().execute(query).await?;
+
+				Ok(())
+			}
+		})
+		.await
+	}
+}
+
+// endregion: --- TripDayBmc
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	pub type Result<T> = core::result::Result<T, Error>;
+	pub type Error = Box<dyn std::error::Error>; // For tests.
+
+	use super::*;
+	use crate::_dev_utils;
+	use crate::model::trip::TripForCreate;
+	use crate::model::Error as ModelError;
+	use serial_test::serial;
+
+	#[serial]
+	#[tokio::test]
+	async fn test_reorder_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_trip_c = TripForCreate {
+			title: "test_reorder_ok".to_string(),
+			description: None,
+			start_date: None,
+			currency: None,
+			is_template: None,
+		};
+		let trip_id = TripBmc::create(&ctx, &mm, fx_trip_c).await?;
+		let mut day_ids = Vec::new();
+		for title in ["day 1", "day 2", "day 3"] {
+			let day_c = TripDayForCreate {
+				title: Some(title.to_string()),
+				notes: None,
+			};
+			day_ids.push(TripDayBmc::create(&ctx, &mm, trip_id, day_c).await?);
+		}
+
+		// -- Exec
+		TripDayBmc::reorder(&ctx, &mm, trip_id, vec![day_ids[2], day_ids[0], day_ids[1]]).await?;
+
+		// -- Check
+		let days = TripDayBmc::list_for_trip(&ctx, &mm, trip_id).await?;
+		let titles: Vec<&str> = days.iter().filter_map(|day| day.title.as_deref()).collect();
+		assert_eq!(titles, ["day 3", "day 1", "day 2"]);
+		let positions: Vec<i32> = days.iter().map(|day| day.position).collect();
+		assert_eq!(positions, [0, 1, 2]);
+
+		// -- Exec & Check (missing day, nothing changed)
+		let res = TripDayBmc::reorder(&ctx, &mm, trip_id, vec![day_ids[0], day_ids[1]]).await;
+		assert!(matches!(res, Err(ModelError::ValidationFail(_))));
+		let days = TripDayBmc::list_for_trip(&ctx, &mm, trip_id).await?;
+		assert_eq!(days[0].title.as_deref(), Some("day 3"));
+
+		// -- Clean
+		TripBmc::delete(&ctx, &mm, trip_id).await?;
+
+		Ok(())
+	}
+}
+
+// endregion: --- Tests
@@ -0,0 +1,463 @@
+// region: ---- Modules
+use crate::ctx::Ctx;
+use crate::model::base::{self, CommonIden, DbBmc};
+use crate::model::place::PlaceBmc;
+use crate::model::trip::TripBmc;
+use crate::model::trip_day::TripDayBmc;
+use crate::model::{Error, ModelManager, Result};
+use chrono::NaiveTime;
+use modql::field::{Fields, HasSeaFields};
+use sea_query::{Alias, Expr, Iden, Order, PostgresQueryBuilder, Query, SimpleExpr};
+use sea_query_binder::SqlxBinder;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+// endregion: ---- Modules
+
+// region:    --- Consts
+
+/// The transport modes to a stop (from the previous one).
+pub const TRANSPORT_MODES: &[&str] = &["walk", "bike", "drive", "transit", "train", "ferry", "flight"];
+
+// endregion: --- Consts
+
+// region:    --- TripStop Types
+
+/// A stop of a trip day, at a place.
+#[derive(Debug, Clone, Fields, FromRow, Serialize)]
+pub struct TripStop {
+	pub id: i64,
+	pub trip_id: i64,
+	pub trip_day_id: i64,
+	pub place_id: i64,
+	/// The order of the stop in the day (see `TripStopBmc::reorder`).
+	pub position: i32,
+
+	// -- Planned times (local time of the day)
+	pub arrival_time: Option<NaiveTime>,
+	pub departure_time: Option<NaiveTime>,
+
+	/// From the previous stop (see `TRANSPORT_MODES`).
+	pub transport_mode: Option<String>,
+	pub notes: Option<String>,
+	/// In the minor unit of the trip currency (e.g., cents).
+	pub budget: Option<i64>,
+}
+
+/// Note: The stop is added at the end of the day.
+#[derive(Deserialize)]
+pub struct TripStopForCreate {
+	pub place_id: i64,
+	pub arrival_time: Option<NaiveTime>,
+	pub departure_time: Option<NaiveTime>,
+	pub transport_mode: Option<String>,
+	pub notes: Option<String>,
+	pub budget: Option<i64>,
+}
+
+#[derive(Fields, Default, Deserialize)]
+pub struct TripStopForUpdate {
+	pub place_id: Option<i64>,
+	pub arrival_time: Option<NaiveTime>,
+	pub departure_time: Option<NaiveTime>,
+	pub transport_mode: Option<String>,
+	pub notes: Option<String>,
+	pub budget: Option<i64>,
+}
+
+#[derive(Fields)]
+struct TripStopForInsert {
+	trip_id: i64,
+	trip_day_id: i64,
+	place_id: i64,
+	position: i32,
+	arrival_time: Option<NaiveTime>,
+	departure_time: Option<NaiveTime>,
+	transport_mode: Option<String>,
+	notes: Option<String>,
+	budget: Option<i64>,
+}
+
+#[derive(Iden)]
+enum TripStopIden {
+	TripId,
+	TripDayId,
+	Position,
+}
+
+// endregion: --- TripStop Types
+
+// region:    --- TripStopBmc
+
+pub struct TripStopBmc;
+
+impl DbBmc for TripStopBmc {
+	const TABLE: &'static str = "trip_stop";
+
+	/// The stops follow the visibility of their trip.
+	fn visible_cond(ctx: &Ctx) -> Option<SimpleExpr> {
+		let visible_trips = Query::select()
+			.column(CommonIden::Id)
+			.from(TripBmc::table_ref())
+			.and_where(TripBmc::visible_cond(ctx)?)
+			.to_owned();
+
+		Some(Expr::col(TripStopIden::TripId).in_subquery(visible_trips))
+	}
+}
+
+impl TripStopBmc {
+	/// Add a stop at the end of the day.
+	pub async fn create(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		trip_day_id: i64,
+		stop_c: TripStopForCreate,
+	) -> Result<i64> {
+		validate_stop(
+			stop_c.arrival_time,
+			stop_c.departure_time,
+			stop_c.transport_mode.as_deref(),
+			stop_c.budget,
+		)?;
+		validate_place(ctx, mm, stop_c.place_id).await?;
+		let day = TripDayBmc::get(ctx, mm, trip_day_id).await?;
+
+		mm.transaction(|mm| {
+			let stop_i = TripStopForInsert {
+				trip_id: day.trip_id,
+				trip_day_id,
+				place_id: stop_c.place_id,
+				position: 0,
+				arrival_time: stop_c.arrival_time,
+				departure_time: stop_c.departure_time,
+				transport_mode: stop_c.transport_mode.clone(),
+				notes: stop_c.notes.clone(),
+				budget: stop_c.budget,
+			};
+			async move {
+				// Note: Locked, so the concurrent adds do not get the same position.
+				TripBmc::lock_writable(ctx, &mm, stop_i.trip_id).await?;
+				let sql = "SELECT coalesce(max(position) + 1, 0) FROM trip_stop WHERE trip_day_id = $1";
+				let (position,) = mm
+					.dbx()
+					.fetch_one(sqlx::query_as::<_, (i32,)>(sql).bind(trip_day_id))
+					.await?;
+
+				base::create::<Self, _>(ctx, &mm, TripStopForInsert { position, ..stop_i }).await
+			}
+		})
+		.await
+	}
+
+	pub async fn get(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<TripStop> {
+		base::get::<Self, _>(ctx, mm, id).await
+	}
+
+	/// The stops of a trip, in order (by day, then in the day).
+	pub async fn list_for_trip(ctx: &Ctx, mm: &ModelManager, trip_id: i64) -> Result<Vec<TripStop>> {
+		// Note: The trip must be visible to the ctx (not found otherwise).
+		TripBmc::get(ctx, mm, trip_id).await?;
+
+		let stop_table = Alias::new(Self::TABLE);
+		let day_table = Alias::new(TripDayBmc::TABLE);
+		let mut query = Query::select();
+		query
+			.from(Self::table_ref())
+			.columns(TripStop::sea_column_refs_with_rel(stop_table.clone()))
+			.inner_join(
+				TripDayBmc::table_ref(),
+				Expr::col((day_table.clone(), CommonIden::Id))
+					.equals((stop_table.clone(), TripStopIden::TripDayId)),
+			)
+			.and_where(Expr::col((stop_table.clone(), TripStopIden::TripId)).eq(trip_id))
+			.order_by((day_table, TripStopIden::Position), Order::Asc)
+			.order_by((stop_table, TripStopIden::Position), Order::Asc);
+
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let sqlx_query = sqlx::query_as_with::<_, TripStop, _>(&sql, values);
+		let stops = mm.dbx().fetch_all(sqlx_query).await?;
+
+		Ok(stops)
+	}
+
+	pub async fn update(ctx: &Ctx, mm: &ModelManager, id: i64, stop_u: TripStopForUpdate) -> Result<()> {
+		let stop = Self::get(ctx, mm, id).await?;
+		validate_stop(
+			stop_u.arrival_time.or(stop.arrival_time),
+			stop_u.departure_time.or(stop.departure_time),
+			stop_u.transport_mode.as_deref(),
+			stop_u.budget,
+		)?;
+		if let Some(place_id) = stop_u.place_id {
+			validate_place(ctx, mm, place_id).await?;
+		}
+		base::check_writable::<TripBmc>(ctx, mm, stop.trip_id).await?;
+		base::update::<Self, _>(ctx, mm, id, stop_u).await
+	}
+
+	pub async fn delete(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
+		let stop = Self::get(ctx, mm, id).await?;
+		base::check_writable::<TripBmc>(ctx, mm, stop.trip_id).await?;
+		base::delete::<Self>(ctx, mm, id).await
+	}
+
+	/// Reorder the stops of a day, all or nothing.
+	/// `stop_ids` must have all the stops of the day, in the new order. The stops of the
+	/// other days of the trip in it are moved to this day (e.g., a drag and drop).
+	pub async fn reorder(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		trip_day_id: i64,
+		stop_ids: Vec<i64>,
+	) -> Result<()> {
+		let day = TripDayBmc::get(ctx, mm, trip_day_id).await?;
+		let trip_id = day.trip_id;
+
+		mm.transaction(|mm| {
+			let stop_ids = stop_ids.clone();
+			async move {
+				TripBmc::lock_writable(ctx, &mm, trip_id).await?;
+
+				// -- Check the stops
+				let stops = Self::list_for_trip(ctx, &mm, trip_id).await?;
+				let mut new_ids = stop_ids.clone();
+				new_ids.sort_unstable();
+				new_ids.dedup();
+				let all_of_trip = new_ids.len() == stop_ids.len()
+					&& new_ids.iter().all(|id| stops.iter().any(|stop| stop.id == *id));
+				let all_of_day = stops
+					.iter()
+					.filter(|stop| stop.trip_day_id == trip_day_id)
+					.all(|stop| new_ids.binary_search(&stop.id).is_ok());
+				if !all_of_trip || !all_of_day {
+					return Err(Error::ValidationFail(format!(
+						"stop ids must have the stops of the day {trip_day_id}, and only stops of its trip"
+					)));
+				}
+
+				// Note: In one update, the unique positions are checked at its end (deferrable).
+				let sql = "UPDATE trip_stop SET trip_day_id = $2, position = new.position - 1, \
+						mid = $4, mtime = now() \
+					FROM unnest($3::BIGINT[]) WITH ORDINALITY AS new(id, position) \
+					WHERE trip_stop.id = new.id AND trip_stop.trip_id = $1";
+				let query = sqlx::query(sql)
+					.bind(trip_id)
+					.bind(trip_day_id)
+					.bind(stop_ids)
+					.bind(ctx.user_id());
+				mm.dbx().execute(query).await?;
+
+				Ok(())
+			}
+		})
+		.await
+	}
+}
+
+fn validate_stop(
+	arrival_time: Option<NaiveTime>,
+	departure_time: Option<NaiveTime>,
+	transport_mode: Option<&str>,
+	budget: Option<i64>,
+) -> Result<()> {
+	if let (Some(arrival_time), Some(departure_time)) = (arrival_time, departure_time)
+		&& departure_time < arrival_time
+	{
+		return Err(Error::ValidationFail(
+			"departure time before arrival time".to_string(),
+		));
+	}
+	if let Some(transport_mode) = transport_mode
+		&& !TRANSPORT_MODES.contains(&transport_mode)
+	{
+		return Err(Error::ValidationFail(format!(
+			"transport mode '{transport_mode}' unknown"
+		)));
+	}
+	if budget.is_some_and(|budget| budget < 0) {
+		return Err(Error::ValidationFail("budget negative".to_string()));
+	}
+	Ok(())
+}
+
+/// The place of a stop must exist (a validation error, not the one of its foreign key).
+/// Note: The stops of a deleted place are deleted with it (see `0015_trip_stop_place_cascade`).
+async fn validate_place(ctx: &Ctx, mm: &ModelManager, place_id: i64) -> Result<()> {
+	match PlaceBmc::get(ctx, mm, place_id).await {
+		Ok(_) => Ok(()),
+		Err(Error::EntityNotFound { .. }) => Err(Error::ValidationFail(format!(
+			"place {place_id} not found"
+		))),
+		Err(err) => Err(err),
+	}
+}
+
+// endregion: --- TripStopBmc
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	pub type Result<T> = core::result::Result<T, Error>;
+	pub type Error = Box<dyn std::error::Error>; // For tests.
+
+	use super::*;
+	use crate::_dev_utils;
+	use crate::model::trip::TripForCreate;
+	use crate::model::trip_day::TripDayForCreate;
+	use crate::model::Error as ModelError;
+	use serial_test::serial;
+
+	fn fx_stop_c(place_id: i64, notes: &str) -> TripStopForCreate {
+		TripStopForCreate {
+			place_id,
+			arrival_time: None,
+			departure_time: None,
+			transport_mode: None,
+			notes: Some(notes.to_string()),
+			budget: None,
+		}
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_reorder_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_place_ids = _dev_utils::seed_places(&ctx, &mm, &["test_reorder_ok A"]).await?;
+		let fx_trip_c = TripForCreate {
+			title: "test_reorder_ok".to_string(),
+			description: None,
+			start_date: None,
+			currency: None,
+			is_template: None,
+		};
+		let trip_id = TripBmc::create(&ctx, &mm, fx_trip_c).await?;
+		let day_1 = TripDayBmc::create(&ctx, &mm, trip_id, TripDayForCreate::default()).await?;
+		let day_2 = TripDayBmc::create(&ctx, &mm, trip_id, TripDayForCreate::default()).await?;
+		let mut stop_ids = Vec::new();
+		for (day_id, notes) in [(day_1, "1a"), (day_1, "1b"), (day_1, "1c"), (day_2, "2a")] {
+			let stop_c = fx_stop_c(fx_place_ids[0], notes);
+			stop_ids.push(TripStopBmc::create(&ctx, &mm, day_id, stop_c).await?);
+		}
+		let notes_by_day = |stops: &[TripStop], day_id: i64| -> Vec<String> {
+			stops
+				.iter()
+				.filter(|stop| stop.trip_day_id == day_id)
+				.filter_map(|stop| stop.notes.clone())
+				.collect()
+		};
+
+		// -- Exec (in the day)
+		TripStopBmc::reorder(&ctx, &mm, day_1, vec![stop_ids[2], stop_ids[0], stop_ids[1]]).await?;
+
+		// -- Check
+		let stops = TripStopBmc::list_for_trip(&ctx, &mm, trip_id).await?;
+		assert_eq!(notes_by_day(&stops, day_1), ["1c", "1a", "1b"]);
+
+		// -- Exec (moved from the first day)
+		TripStopBmc::reorder(&ctx, &mm, day_2, vec![stop_ids[3], stop_ids[0]]).await?;
+
+		// -- Check
+		let stops = TripStopBmc::list_for_trip(&ctx, &mm, trip_id).await?;
+		assert_eq!(notes_by_day(&stops, day_1), ["1c", "1b"]);
+		assert_eq!(notes_by_day(&stops, day_2), ["2a", "1a"]);
+
+		// -- Exec & Check (missing stop of the day, nothing changed)
+		let res = TripStopBmc::reorder(&ctx, &mm, day_2, vec![stop_ids[0]]).await;
+		assert!(matches!(res, Err(ModelError::ValidationFail(_))));
+		let stops = TripStopBmc::list_for_trip(&ctx, &mm, trip_id).await?;
+		assert_eq!(notes_by_day(&stops, day_2), ["2a", "1a"]);
+
+		// -- Clean
+		TripBmc::delete(&ctx, &mm, trip_id).await?;
+		PlaceBmc::delete_many(&ctx, &mm, fx_place_ids).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_create_err_validation() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_stop_c = TripStopForCreate {
+			transport_mode: Some("teleport".to_string()),
+			..fx_stop_c(1000, "test_create_err_validation")
+		};
+
+		// -- Exec
+		let res = TripStopBmc::create(&ctx, &mm, 1000, fx_stop_c).await;
+
+		// -- Check
+		assert!(matches!(res, Err(ModelError::ValidationFail(_))));
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_create_err_place_not_found() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_trip_c = TripForCreate {
+			title: "test_create_err_place_not_found".to_string(),
+			description: None,
+			start_date: None,
+			currency: None,
+			is_template: None,
+		};
+		let trip_id = TripBmc::create(&ctx, &mm, fx_trip_c).await?;
+		let day_id = TripDayBmc::create(&ctx, &mm, trip_id, TripDayForCreate::default()).await?;
+
+		// -- Exec
+		let stop_c = fx_stop_c(999_999, "test_create_err_place_not_found");
+		let res = TripStopBmc::create(&ctx, &mm, day_id, stop_c).await;
+
+		// -- Check
+		assert!(matches!(res, Err(ModelError::ValidationFail(_))));
+
+		// -- Clean
+		TripBmc::delete(&ctx, &mm, trip_id).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_place_delete_ok_stops_deleted() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_place_ids =
+			_dev_utils::seed_places(&ctx, &mm, &["test_place_delete_ok_stops_deleted A"]).await?;
+		let fx_trip_c = TripForCreate {
+			title: "test_place_delete_ok_stops_deleted".to_string(),
+			description: None,
+			start_date: None,
+			currency: None,
+			is_template: None,
+		};
+		let trip_id = TripBmc::create(&ctx, &mm, fx_trip_c).await?;
+		let day_id = TripDayBmc::create(&ctx, &mm, trip_id, TripDayForCreate::default()).await?;
+		let stop_c = fx_stop_c(fx_place_ids[0], "test_place_delete_ok_stops_deleted");
+		TripStopBmc::create(&ctx, &mm, day_id, stop_c).await?;
+
+		// -- Exec
+		PlaceBmc::delete_many(&ctx, &mm, fx_place_ids).await?;
+
+		// -- Check
+		let stops = TripStopBmc::list_for_trip(&ctx, &mm, trip_id).await?;
+		assert!(stops.is_empty());
+
+		// -- Clean
+		TripBmc::delete(&ctx, &mm, trip_id).await?;
+
+		Ok(())
+	}
+}
+
+// endregion: --- Tests
@@ -0,0 +1,283 @@
+//! Closure-based transactions on the ModelManager.
+//!
+//! ```ignore
+//! let id = mm
+//!     .transaction(|mm| async move {
+//!         let id = PostBmc::create(ctx, &mm, post_c).await?;
+//!         PostMediaBmc::create(ctx, &mm, media_c(id)).await?;
+//!         Ok(id)
+//!     })
+//!     .await?;
+//! ```
+//!
+//! - Commits on `Ok`, rolls back on `Err` or panic.
+//! - Nested (called with the mm of a transaction), it runs in a savepoint of it.
+//! - Retried on serialization failures (see `TxnOptions`).
+//!
+//! Note: The closure is called again on retry, so it must not have side effects
+//!       other than through the given mm (e.g., sending an email).
+
+// region:    --- Modules
+
+use crate::model::store::dbx::IsolationLevel;
+use crate::model::{ModelManager, Result};
+use futures::FutureExt;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::time::Duration;
+use tracing::warn;
+
+// endregion: --- Modules
+
+const RETRY_BASE_DELAY_MS: u64 = 10;
+
+/// The options of `ModelManager::transaction_with`.
+#[derive(Debug, Clone, Copy)]
+pub struct TxnOptions {
+	pub isolation: IsolationLevel,
+	/// Number of retries of the whole transaction on a serialization failure (40001).
+	pub max_retries: u32,
+}
+
+impl Default for TxnOptions {
+	fn default() -> Self {
+		TxnOptions {
+			isolation: IsolationLevel::default(),
+			max_retries: 3,
+		}
+	}
+}
+
+impl TxnOptions {
+	pub fn serializable() -> Self {
+		TxnOptions {
+			isolation: IsolationLevel::Serializable,
+			..Default::default()
+		}
+	}
+}
+
+impl ModelManager {
+	/// Run `f` in a transaction, with the default `TxnOptions`.
+	pub async fn transaction<F, Fut, T>(&self, f: F) -> Result<T>
+	where
+		F: Fn(ModelManager) -> Fut,
+		Fut: Future<Output = Result<T>> + Send,
+	{
+		self.transaction_with(TxnOptions::default(), f).await
+	}
+
+	/// Run `f` in a transaction. The mm given to `f` must be used for all its queries.
+	pub async fn transaction_with<F, Fut, T>(&self, options: TxnOptions, f: F) -> Result<T>
+	where
+		F: Fn(ModelManager) -> Fut,
+		Fut: Future<Output = Result<T>> + Send,
+	{
+		// -- Nested, in a savepoint
+		// Note: Not retried here, since the whole transaction must be (by the outer one).
+		if self.dbx().with_txn() && self.dbx().in_txn().await {
+			self.dbx().begin_txn().await?;
+			return run_and_end(self.clone(), &f).await;
+		}
+
+		// -- New transaction
+		let mut retries = 0;
+		loop {
+			let mm = self.new_with_txn()?;
+			mm.dbx().begin_txn_with(options.isolation).await?;
+
+			match run_and_end(mm, &f).await {
+				Err(err) if err.is_serialization_failure() && retries < options.max_retries => {
+					retries += 1;
+					warn!("Transaction serialization failure, retry {retries}");
+					tokio::time::sleep(Duration::from_millis(RETRY_BASE_DELAY_MS * retries as u64))
+						.await;
+				}
+				res => return res,
+			}
+		}
+	}
+}
+
+/// Run `f` with the mm (in a begun transaction), then commit on `Ok`, or rollback
+/// on `Err` or panic (the panic is then resumed).
+async fn run_and_end<F, Fut, T>(mm: ModelManager, f: &F) -> Result<T>
+where
+	F: Fn(ModelManager) -> Fut,
+	Fut: Future<Output = Result<T>> + Send,
+{
+	match AssertUnwindSafe(f(mm.clone())).catch_unwind().await {
+		Ok(Ok(val)) => {
+			mm.dbx().commit_txn().await?;
+			Ok(val)
+		}
+		Ok(Err(err)) => {
+			mm.dbx().rollback_txn().await?;
+			Err(err)
+		}
+		Err(panic) => {
+			let _ = mm.dbx().rollback_txn().await;
+			std::panic::resume_unwind(panic)
+		}
+	}
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	pub type Result<T> = core::result::Result<T, Error>;
+	pub type Error = Box<dyn std::error::Error>; // For tests.
+
+	use super::*;
+	use crate::_dev_utils;
+	use crate::ctx::Ctx;
+	use crate::model::post::{PostBmc, PostForCreate};
+	use serial_test::serial;
+	use std::sync::atomic::{AtomicU32, Ordering};
+
+	fn fx_post_c(title: &str) -> PostForCreate {
+		PostForCreate {
+			title: title.to_string(),
+			description: title.to_string(),
+			is_published: Some(true),
+			cover_media_url: None,
+			thumbnail_url: None,
+			media_count: None,
+			has_video: None,
+			lang: None,
+		}
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_transaction_commit_and_rollback() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let ctx = &ctx;
+
+		// -- Exec
+		let id_ok = mm
+			.transaction(|mm| async move {
+				PostBmc::create(ctx, &mm, fx_post_c("test_transaction - post ok")).await
+			})
+			.await?;
+		let id_err = AtomicU32::new(0);
+		let res_err: crate::model::Result<()> = mm
+			.transaction(|mm| {
+				let id_err = &id_err;
+				async move {
+					let id =
+						PostBmc::create(ctx, &mm, fx_post_c("test_transaction - post err")).await?;
+					id_err.store(id as u32, Ordering::SeqCst);
+					Err(crate::model::Error::ValidationFail("test".to_string()))
+				}
+			})
+			.await;
+
+		// -- Check
+		assert!(res_err.is_err());
+		PostBmc::get(ctx, &mm, id_ok).await?;
+		let res_get = PostBmc::get(ctx, &mm, id_err.load(Ordering::SeqCst) as i64).await;
+		assert!(matches!(
+			res_get,
+			Err(crate::model::Error::EntityNotFound { .. })
+		));
+
+		// -- Clean
+		PostBmc::delete(ctx, &mm, id_ok).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_transaction_nested_savepoint() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let ctx = &ctx;
+
+		// -- Exec
+		let (id_outer, res_inner) = mm
+			.transaction(|mm| async move {
+				let id_outer =
+					PostBmc::create(ctx, &mm, fx_post_c("test_nested - post outer")).await?;
+				let res_inner: crate::model::Result<i64> = mm
+					.transaction(|mm| async move {
+						PostBmc::create(ctx, &mm, fx_post_c("test_nested - post inner")).await?;
+						Err(crate::model::Error::ValidationFail("test".to_string()))
+					})
+					.await;
+				Ok((id_outer, res_inner))
+			})
+			.await?;
+
+		// -- Check
+		assert!(res_inner.is_err());
+		PostBmc::get(ctx, &mm, id_outer).await?;
+		let posts = PostBmc::list(ctx, &mm, None, None).await?;
+		assert!(posts.iter().all(|p| p.title != "test_nested - post inner"));
+
+		// -- Clean
+		PostBmc::delete(ctx, &mm, id_outer).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_transaction_retry_serialization_failure() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let attempts = AtomicU32::new(0);
+		let attempts = &attempts;
+
+		// -- Exec
+		mm.transaction_with(TxnOptions::serializable(), |mm| async move {
+			// Note: Simulates a conflict on the first attempt.
+			if attempts.fetch_add(1, Ordering::SeqCst) == 0 {
+				let sql = "DO $$ BEGIN RAISE EXCEPTION 'conflict' USING ERRCODE = '40001'; END $$";
+				mm.dbx().execute(sqlx::query(sql)).await?;
+			}
+			Ok(())
+		})
+		.await?;
+
+		// -- Check
+		assert_eq!(attempts.load(Ordering::SeqCst), 2);
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_transaction_rollback_on_panic() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let ctx = &ctx;
+		let fx_title = "test_transaction_panic - post 01";
+
+		// -- Exec
+		let res = AssertUnwindSafe(mm.transaction(|mm| async move {
+			PostBmc::create(ctx, &mm, fx_post_c(fx_title)).await?;
+			if fx_title.is_empty() {
+				return Ok(());
+			}
+			panic!("test panic");
+		}))
+		.catch_unwind()
+		.await;
+
+		// -- Check
+		assert!(res.is_err());
+		let posts = PostBmc::list(ctx, &mm, None, None).await?;
+		assert!(posts.iter().all(|p| p.title != fx_title));
+
+		Ok(())
+	}
+}
+
+// endregion: --- Tests
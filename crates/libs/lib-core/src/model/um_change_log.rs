@@ -0,0 +1,83 @@
+// region: ---- Modules
+use crate::ctx::Ctx;
+use crate::model::base::{self, DbBmc};
+use crate::model::ModelManager;
+use crate::model::Result;
+use chrono::{DateTime, Utc};
+use modql::field::{Fields, HasSeaFields};
+use sea_query::{Expr, Iden, Order, PostgresQueryBuilder, Query};
+use sea_query_binder::SqlxBinder;
+use serde::Serialize;
+use sqlx::FromRow;
+// endregion: ---- Modules
+
+// region:    --- UmChangeLog Types
+
+/// A user management change (audit), e.g., a user erasure.
+/// `cid` is the user who performed the change.
+#[derive(Clone, Fields, FromRow, Debug, Serialize)]
+pub struct UmChangeLog {
+	pub id: i64,
+	pub user_id: i64,
+	pub action: String,
+	pub note: Option<String>,
+	pub cid: i64,
+	pub ctime: DateTime<Utc>,
+}
+
+/// Note: The note must not contain PII (kept after the user erasure).
+#[derive(Fields)]
+pub struct UmChangeLogForCreate {
+	pub user_id: i64,
+	pub action: String,
+	pub note: Option<String>,
+}
+
+#[derive(Iden)]
+enum UmChangeLogIden {
+	Id,
+	UserId,
+}
+
+// endregion: --- UmChangeLog Types
+
+// region:    --- UmChangeLogBmc
+pub struct UmChangeLogBmc;
+
+impl DbBmc for UmChangeLogBmc {
+	const TABLE: &'static str = "um_change_log";
+}
+
+impl UmChangeLogBmc {
+	pub async fn create(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		change_c: UmChangeLogForCreate,
+	) -> Result<i64> {
+		base::create::<Self, _>(ctx, mm, change_c).await
+	}
+
+	/// The changes of a user, most recent first.
+	pub async fn list_for_user(
+		_ctx: &Ctx,
+		mm: &ModelManager,
+		user_id: i64,
+	) -> Result<Vec<UmChangeLog>> {
+		// -- Build query
+		let mut query = Query::select();
+		query
+			.from(Self::table_ref())
+			.columns(UmChangeLog::sea_column_refs())
+			.and_where(Expr::col(UmChangeLogIden::UserId).eq(user_id))
+			.order_by(UmChangeLogIden::Id, Order::Desc);
+
+		// -- Exec query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let sqlx_query = sqlx::query_as_with::<_, UmChangeLog, _>(&sql, values);
+		let changes = mm.dbx().fetch_all(sqlx_query).await?;
+
+		Ok(changes)
+	}
+}
+
+// endregion: --- UmChangeLogBmc
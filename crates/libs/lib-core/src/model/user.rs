@@ -1,19 +1,31 @@
 // region: ---- Modules
-use crate::ctx::Ctx;
+use crate::ctx::{Ctx, Permission, Role};
 use crate::model::base::{self, prep_fields_for_update, DbBmc};
 use crate::model::modql_utils::time_to_sea_value;
+use crate::model::audit_log::AuditLogBmc;
+use crate::model::post::PostBmc;
+use crate::model::post_media::PostMediaBmc;
+use crate::model::session::SessionBmc;
+use crate::model::trip::TripBmc;
+use crate::model::um_change_log::{UmChangeLogBmc, UmChangeLogForCreate};
+use crate::model::user_identity::UserIdentityBmc;
 use crate::model::ModelManager;
 use crate::model::{Error, Result};
 use chrono::Utc;
 use lib_auth::pwd::{self, ContentToHash};
-use lib_tmail::email::emails_sender::{send_reset_pwd_email, send_verification_email, send_welcome_email};
+use lib_auth::onetime::{self, TokenPurpose};
+use lib_auth::totp;
+use lib_tmail::email::emails_sender::{
+	send_account_locked_email, send_login_link_email, send_reset_pwd_email,
+	send_verification_email, send_welcome_email,
+};
 use lib_tmail::tmail_config;
 use lib_auth::auth_config;
 use modql::field::{Fields, HasSeaFields, SeaField, SeaFields};
 use modql::filter::{
 	FilterNodes, ListOptions, OpValsInt64, OpValsString, OpValsValue,
 };
-use sea_query::{Expr, Iden, PostgresQueryBuilder, Query};
+use sea_query::{Alias, Expr, Func, Iden, PostgresQueryBuilder, Query, SimpleExpr};
 use sea_query_binder::SqlxBinder;
 use serde::{Deserialize, Serialize};
 use sqlx::postgres::PgRow;
@@ -21,6 +33,10 @@ use sqlx::FromRow;
 use uuid::Uuid;
 // endregion: ---- Modules
 
+/// The unlock link of a locked account is valid longer than the lockout,
+/// since the user may read the email later.
+const ACCOUNT_UNLOCK_TTL_MIN: i64 = 24 * 60;
+
 // region:    --- User Types
 #[derive(Clone, Debug, sqlx::Type, derive_more::Display, Deserialize, Serialize)]
 #[sqlx(type_name = "user_typ")]
@@ -42,6 +58,7 @@ pub struct User {
 	pub username: String,
 	pub email: String,
 	pub typ: UserTyp,
+	pub role: Role,
 	pub email_verified: bool,
 }
 
@@ -64,6 +81,15 @@ pub struct UserForInsert {
 	pub email_verification_expires_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
+/// A user created from an OIDC login (no password, email verified by the provider).
+#[derive(Fields)]
+pub struct UserForInsertOidc {
+	pub username: String,
+	pub email: String,
+	pub token_salt: Uuid,
+	pub email_verified: bool,
+}
+
 #[derive(Clone, FromRow, Fields, Debug)]
 pub struct UserForLogin {
 	pub id: i64,
@@ -74,6 +100,9 @@ pub struct UserForLogin {
 	pub pwd: Option<String>, // encrypted, #_scheme_id_#....
 	pub pwd_salt: Uuid,
 	pub token_salt: Uuid,
+
+	// -- mfa info
+	pub mfa_enabled: bool,
 }
 
 #[derive(Clone, FromRow, Fields, Debug)]
@@ -81,11 +110,40 @@ pub struct UserForAuth {
 	pub id: i64,
 	pub username: String,
 	pub email: String,
+	pub role: Role,
 
 	// -- token info
 	pub token_salt: Uuid,
 }
 
+#[derive(Clone, FromRow, Fields, Debug)]
+pub struct UserForMfa {
+	pub id: i64,
+	pub username: String,
+	pub token_salt: Uuid,
+
+	// -- mfa info
+	pub mfa_enabled: bool,
+	pub mfa_secret: Option<String>, // TOTP secret, base32
+	pub mfa_last_step: Option<i64>,
+	pub mfa_recovery_codes: Option<String>, // comma separated hashes
+}
+
+/// Returned on MFA setup, to be shown (as QR code) to the user authenticator app.
+#[derive(Serialize)]
+pub struct MfaSetup {
+	pub secret: String,
+	pub otpauth_uri: String,
+}
+
+/// The result of a user deletion (see `UserBmc::delete`).
+#[derive(Debug, Serialize)]
+pub struct UserErasure {
+	pub posts_deleted: u64,
+	/// The urls of the deleted media, whose files are to be removed from the storage.
+	pub media_urls: Vec<String>,
+}
+
 #[derive(Serialize)]
 pub struct UserDTO {
 	pub id: i64,
@@ -97,21 +155,33 @@ pub trait UserBy: HasSeaFields + for<'r> FromRow<'r, PgRow> + Unpin + Send {}
 impl UserBy for User {}
 impl UserBy for UserForLogin {}
 impl UserBy for UserForAuth {}
+impl UserBy for UserForMfa {}
 
 // Note: Since the entity properties Iden will be given by modql
 //       UserIden does not have to be exhaustive, but just have the columns
-#[derive(Iden)]
+#[derive(Iden, Clone, Copy)]
 enum UserIden {
 	Id,
 	Username,
+	Role,
 	Email,
 	Pwd,
+	PwdSalt,
+	Deleted,
 	EmailVerified,
 	EmailVerificationToken,
 	EmailVerificationExpiresAt,
 	TokenSalt,
 	ResetToken,
 	ResetTokenExpiresAt,
+	LoginToken,
+	LoginTokenExpiresAt,
+	UnlockToken,
+	UnlockTokenExpiresAt,
+	MfaEnabled,
+	MfaSecret,
+	MfaLastStep,
+	MfaRecoveryCodes,
 }
 
 #[derive(FilterNodes, Deserialize, Default, Debug)]
@@ -135,6 +205,10 @@ pub struct UserBmc;
 
 impl DbBmc for UserBmc {
 	const TABLE: &'static str = "user";
+
+	fn has_soft_delete() -> bool {
+		true
+	}
 }
 
 impl UserBmc {
@@ -158,7 +232,9 @@ impl UserBmc {
 		.await?;
 
 		// -- Generate verification token and expiration
-		let verification_token = uuid::Uuid::new_v4().to_string();
+		let verification_token = onetime::generate_token();
+		let verification_token_hash =
+			onetime::hash_token(TokenPurpose::EmailVerification, &verification_token)?;
 		let config = auth_config();
 		let expires_at = Utc::now() + chrono::Duration::minutes(config.VERIFY_TOKEN_TTL_MIN);
 
@@ -174,7 +250,7 @@ impl UserBmc {
 			pwd_salt,
 			token_salt,
 			email_verified: false,
-			email_verification_token: Some(verification_token.clone()),
+			email_verification_token: Some(verification_token_hash),
 			email_verification_expires_at: Some(expires_at),
 		};
 
@@ -209,6 +285,29 @@ impl UserBmc {
 		Ok(user_id)
 	}
 
+	/// Create a user from a verified OIDC provider email (no password, no emails sent).
+	/// The username is derived from the email, with a suffix if already taken.
+	pub async fn create_from_oidc(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		email: &str,
+	) -> Result<i64> {
+		let mut username = username_from_email(email);
+		if Self::first_by_username::<User>(ctx, mm, &username).await?.is_some() {
+			let suffix = Uuid::new_v4().simple().to_string();
+			username = format!("{username}-{}", &suffix[..6]);
+		}
+
+		let user_fi = UserForInsertOidc {
+			username,
+			email: email.to_string(),
+			token_salt: Uuid::new_v4(),
+			email_verified: true,
+		};
+
+		base::create::<Self, _>(ctx, mm, user_fi).await
+	}
+
 	pub async fn get<E>(
 		ctx: &Ctx, 
 		mm: &ModelManager, 
@@ -233,7 +332,37 @@ impl UserBmc {
 		query
 			.from(Self::table_ref())
 			.columns(E::sea_idens())
-			.and_where(Expr::col(UserIden::Username).eq(username));
+			.and_where(Expr::col(UserIden::Username).eq(username))
+			.and_where(Expr::col(UserIden::Deleted).eq(false));
+
+		// -- Execute query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+
+		let sqlx_query = sqlx::query_as_with::<_, E, _>(&sql, values);
+		let entity = mm.dbx().fetch_optional(sqlx_query).await?;
+
+		Ok(entity)
+	}
+
+	pub async fn first_by_email<E>(
+		_ctx: &Ctx,
+		mm: &ModelManager,
+		email: &str,
+	) -> Result<Option<E>>
+	where
+		E: UserBy,
+	{
+		// -- Build query
+		let mut query = Query::select();
+		query
+			.from(Self::table_ref())
+			.columns(E::sea_idens())
+			// Note: Case insensitive, since the emails are stored as entered.
+			.and_where(
+				Expr::expr(Func::lower(Expr::col(UserIden::Email)))
+					.eq(Func::lower(Expr::val(email))),
+			)
+			.and_where(Expr::col(UserIden::Deleted).eq(false));
 
 		// -- Execute query
 		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
@@ -310,25 +439,54 @@ impl UserBmc {
         Ok(())
     }
 
+	/// Change the role of a user (requires `Permission::UserManage`).
+	pub async fn update_role(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		id: i64,
+		role: Role,
+	) -> Result<()> {
+		ctx.require(Permission::UserManage)?;
+
+		// -- Prep fields
+		// Note: The role is bound as text, so cast to the postgres enum.
+		let role_value = Expr::val(role).as_enum(Alias::new("user_role"));
+		let mut fields = SeaFields::new(vec![SeaField::new(UserIden::Role, role_value)]);
+		prep_fields_for_update::<Self>(&mut fields, ctx.user_id());
+
+		// -- Build query
+		let fields = fields.for_sea_update();
+		let mut query = Query::update();
+		query
+			.table(Self::table_ref())
+			.values(fields)
+			.and_where(Expr::col(UserIden::Id).eq(id));
+
+		// -- Exec query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let count = mm.dbx().execute(sqlx::query_with(&sql, values)).await?;
+		if count == 0 {
+			return Err(Error::EntityNotFound {
+				entity: Self::TABLE,
+				id,
+			});
+		}
+
+		tracing::info!("Role of user_id {} set to {} by user_id {}", id, role, ctx.user_id());
+		Ok(())
+	}
+
 	pub async fn request_password_reset(
-        _ctx: &Ctx,
+        ctx: &Ctx,
         mm: &ModelManager,
         email: &str,
     ) -> Result<()> {
         tracing::debug!("Requesting password reset for email: {}", email);
 
         // -- Find User by email
-        let mut query = Query::select();
-        query
-            .from(Self::table_ref())
-            .columns(vec![UserIden::Id, UserIden::Username])
-            .and_where(Expr::col(UserIden::Email).eq(email));
-
-        let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
-        let sqlx_query = sqlx::query_as_with::<_, (i64, String), _>(&sql, values);
-        let user_opt = mm.dbx().fetch_optional(sqlx_query).await?;
+        let user_opt: Option<UserForAuth> = Self::first_by_email(ctx, mm, email).await?;
 
-        let (user_id, username) = match user_opt {
+        let UserForAuth { id: user_id, username, email, .. } = match user_opt {
             Some(u) => u,
             None => {
                 tracing::warn!("Password reset requested for non-existent email: {}", email);
@@ -336,29 +494,15 @@ impl UserBmc {
             }
         };
 
-        // -- generate token and expiration
-        let reset_token = Uuid::new_v4().to_string();
-        let config = auth_config();
-		let expires_at = Utc::now() + chrono::Duration::minutes(config.RESET_TOKEN_TTL_MIN);
-
-        // -- Save token in DB
-        let mut update = Query::update();
-        update
-            .table(Self::table_ref())
-            .values(vec![
-                (UserIden::ResetToken, Expr::value(reset_token.clone())),
-                (UserIden::ResetTokenExpiresAt, Expr::value(expires_at)),
-            ])
-            .and_where(Expr::col(UserIden::Id).eq(user_id));
-
-        let (sql, values) = update.build_sqlx(PostgresQueryBuilder);
-        mm.dbx().execute(sqlx::query_with(&sql, values)).await?;
+        // -- Generate token and save its hash in DB
+        let reset_token =
+            Self::set_onetime_token(ctx, mm, user_id, TokenPurpose::PasswordReset).await?;
 
         // -- Send email
         let config = tmail_config();
         let reset_link = format!("{}/reset?token={}", config.PASSWORD_RESET_BASE_URL, reset_token);
 
-        if let Err(e) = send_reset_pwd_email(email, &reset_link, &username).await {
+        if let Err(e) = send_reset_pwd_email(&email, &reset_link, &username).await {
             tracing::error!("Failed to send reset email to {}: {:?}", email, e);
         } else {
             tracing::info!("Password reset email sent to {}", email);
@@ -373,26 +517,14 @@ impl UserBmc {
         token: &str,
         new_password: &str,
     ) -> Result<()> {
-        tracing::debug!("Resetting password with token: {}", token);
+        tracing::debug!("Resetting password with token");
 
         // -- Find User by token
-        let mut query = Query::select();
-        query
-            .from(Self::table_ref())
-            .columns(vec![UserIden::Id, UserIden::ResetTokenExpiresAt])
-            .and_where(Expr::col(UserIden::ResetToken).eq(token));
-
-        let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
-        let sqlx_query =
-            sqlx::query_as_with::<_, (i64, Option<chrono::DateTime<Utc>>), _>(&sql, values);
-        let row = mm.dbx().fetch_optional(sqlx_query).await?;
-
-        let (user_id, expires_at) = match row {
-            Some((id, exp)) => (id, exp),
-            None => {
-                tracing::warn!("Invalid reset token used: {}", token);
-                return Err(Error::ResetTokenInvalid);
-            }
+        let Some((user_id, token_hash, expires_at)) =
+            Self::first_by_onetime_token(mm, TokenPurpose::PasswordReset, token).await?
+        else {
+            tracing::warn!("Invalid reset token used");
+            return Err(Error::ResetTokenInvalid);
         };
 
         // -- Check if token is expired
@@ -412,25 +544,36 @@ impl UserBmc {
         .await?;
 
 
-        // -- Update password, token_salt and clear reset_token
-        let mut update = Query::update();
-        update
-            .table(Self::table_ref())
-            .values(vec![
-                (UserIden::Pwd, Expr::value(new_pwd)),
-                (UserIden::ResetToken, Expr::value(Option::<String>::None)),
-                (
-                    UserIden::ResetTokenExpiresAt,
-                    Expr::value(Option::<chrono::DateTime<Utc>>::None),
-                ),
-            ])
-            .and_where(Expr::col(UserIden::Id).eq(user_id));
-
-        let (sql, values) = update.build_sqlx(PostgresQueryBuilder);
-        mm.dbx().execute(sqlx::query_with(&sql, values)).await?;
-
-        // -- Invalidate all tokens creating new token
-		UserBmc::update_token_salt(&ctx, mm, user_id).await?;
+        // -- Update password, token_salt and clear reset_token (all or nothing)
+        // Note: The token condition makes the token single use (concurrent resets).
+        mm.transaction(|mm| {
+            let (new_pwd, token_hash) = (new_pwd.clone(), token_hash.clone());
+            async move {
+                let mut update = Query::update();
+                update
+                    .table(Self::table_ref())
+                    .values(vec![
+                        (UserIden::Pwd, Expr::value(new_pwd)),
+                        (UserIden::ResetToken, Expr::value(Option::<String>::None)),
+                        (
+                            UserIden::ResetTokenExpiresAt,
+                            Expr::value(Option::<chrono::DateTime<Utc>>::None),
+                        ),
+                    ])
+                    .and_where(Expr::col(UserIden::Id).eq(user_id))
+                    .and_where(Expr::col(UserIden::ResetToken).eq(token_hash));
+
+                let (sql, values) = update.build_sqlx(PostgresQueryBuilder);
+                let count = mm.dbx().execute(sqlx::query_with(&sql, values)).await?;
+                if count == 0 {
+                    return Err(Error::ResetTokenInvalid);
+                }
+
+                // -- Invalidate all tokens creating new token
+                UserBmc::update_token_salt(ctx, &mm, user_id).await
+            }
+        })
+        .await?;
 
         tracing::info!(
             "Password reset successful for user_id {}, tokens invalidated",
@@ -445,7 +588,7 @@ impl UserBmc {
 		mm: &ModelManager,
 		token: &str,
 	) -> Result<()> {
-		tracing::debug!("Verifying email with token: {}", token);
+		tracing::debug!("Verifying email with token");
 
 		// check if token is empty
 		if token.trim().is_empty() {
@@ -454,24 +597,11 @@ impl UserBmc {
 		}
 
 		// Find user by verification token
-		let mut query = Query::select();
-		query
-			.from(Self::table_ref())
-			.columns(vec![UserIden::Id, UserIden::EmailVerificationExpiresAt])
-			.and_where(Expr::col(UserIden::EmailVerificationToken).eq(token));
-
-		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
-		let sqlx_query = sqlx::query_as_with::<_, (i64, Option<chrono::DateTime<chrono::Utc>>), _>(&sql, values);
-		let row = mm.dbx().fetch_optional(sqlx_query).await?;
-
-		tracing::debug!("Found user for token: {:?}", row);
-
-		let (user_id, expires_at) = match row {
-			Some((id, expires)) => (id, expires),
-			None => {
-				tracing::error!("No user found for verification token: {}", token);
-				return Err(Error::EmailVerificationTokenInvalid);
-			}
+		let Some((user_id, _token_hash, expires_at)) =
+			Self::first_by_onetime_token(mm, TokenPurpose::EmailVerification, token).await?
+		else {
+			tracing::error!("No user found for verification token");
+			return Err(Error::EmailVerificationTokenInvalid);
 		};
 
 		// Check if token is expired
@@ -503,20 +633,583 @@ impl UserBmc {
 		Ok(())
 	}
 
-	/// TODO: For User, deletion will require a soft-delete approach:
-	///       - Set `deleted: true`.
-	///       - Change `username` to "DELETED-_user_id_".
-	///       - Clear any other UUIDs or PII (Personally Identifiable Information).
-	///       - The automatically set `mid`/`mtime` will record who performed the deletion.
-	///       - It's likely necessary to record this action in a `um_change_log` (a user management change audit table).
-	///       - Remove or clean up any user-specific assets (messages, etc.).
-	pub async fn delete(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
-		base::delete::<Self>(ctx, mm, id).await
+	/// Send a one-time login link to the user with this email (passwordless login).
+	/// Does nothing if no user has this email (no account enumeration).
+	pub async fn request_login_link(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		email: &str,
+	) -> Result<()> {
+		let Some(user) = Self::first_by_email::<User>(ctx, mm, email).await? else {
+			tracing::warn!("Login link requested for non-existent email: {}", email);
+			return Ok(());
+		};
+
+		let token =
+			Self::set_onetime_token(ctx, mm, user.id, TokenPurpose::LoginLink).await?;
+
+		// Note: Sent in the background, so the response time does not tell
+		//       if the account exists.
+		let email = email.to_string();
+		let ttl_min = auth_config().LOGIN_LINK_TTL_MIN;
+		tokio::spawn(async move {
+			if let Err(e) = send_login_link_email(&email, &user.username, &token, ttl_min).await {
+				tracing::warn!("Failed to send login link email: {:?}", e);
+			}
+		});
+
+		Ok(())
+	}
+
+	/// Send the account locked email (after too many login failures), with an unlock link.
+	/// Does nothing if no user has this username.
+	pub async fn notify_account_locked(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		username: &str,
+		locked_min: i64,
+	) -> Result<()> {
+		let Some(user) = Self::first_by_username::<User>(ctx, mm, username).await? else {
+			return Ok(());
+		};
+
+		let token =
+			Self::set_onetime_token(ctx, mm, user.id, TokenPurpose::AccountUnlock).await?;
+
+		tokio::spawn(async move {
+			if let Err(e) =
+				send_account_locked_email(&user.email, &user.username, &token, locked_min).await
+			{
+				tracing::warn!("Failed to send account locked email: {:?}", e);
+			}
+		});
+
+		Ok(())
+	}
+
+	/// Consume an account unlock token, and returns the username to unlock.
+	pub async fn consume_unlock_token(
+		_ctx: &Ctx,
+		mm: &ModelManager,
+		token: &str,
+	) -> Result<String> {
+		// -- Find user by token
+		let (user_id, token_hash, expires_at) =
+			Self::first_by_onetime_token(mm, TokenPurpose::AccountUnlock, token)
+				.await?
+				.ok_or(Error::UnlockTokenInvalid)?;
+
+		// -- Clear the token (single use)
+		let mut update = Query::update();
+		update
+			.table(Self::table_ref())
+			.values(vec![
+				(UserIden::UnlockToken, Expr::value(Option::<String>::None)),
+				(
+					UserIden::UnlockTokenExpiresAt,
+					Expr::value(Option::<chrono::DateTime<Utc>>::None),
+				),
+			])
+			.and_where(Expr::col(UserIden::Id).eq(user_id))
+			.and_where(Expr::col(UserIden::UnlockToken).eq(token_hash))
+			.returning_col(UserIden::Username);
+
+		let (sql, values) = update.build_sqlx(PostgresQueryBuilder);
+		let sqlx_query = sqlx::query_as_with::<_, (String,), _>(&sql, values);
+		let (username,) = mm
+			.dbx()
+			.fetch_optional(sqlx_query)
+			.await?
+			.ok_or(Error::UnlockTokenInvalid)?;
+
+		// -- Check if token is expired
+		if expires_at.is_none_or(|exp| Utc::now() > exp) {
+			tracing::warn!("Unlock token expired for user_id {}", user_id);
+			return Err(Error::UnlockTokenExpired);
+		}
+
+		Ok(username)
+	}
+
+	/// Store a new one-time token (hashed) for a user, and returns the clear token.
+	async fn set_onetime_token(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		id: i64,
+		purpose: TokenPurpose,
+	) -> Result<String> {
+		let config = auth_config();
+		let ttl_min = match purpose {
+			TokenPurpose::EmailVerification => config.VERIFY_TOKEN_TTL_MIN,
+			TokenPurpose::PasswordReset => config.RESET_TOKEN_TTL_MIN,
+			TokenPurpose::LoginLink => config.LOGIN_LINK_TTL_MIN,
+			TokenPurpose::AccountUnlock => ACCOUNT_UNLOCK_TTL_MIN,
+		};
+
+		let token = onetime::generate_token();
+		let token_hash = onetime::hash_token(purpose, &token)?;
+		let expires_at = Utc::now() + chrono::Duration::minutes(ttl_min);
+
+		// -- Prep fields
+		let (token_col, expires_col) = onetime_token_columns(purpose);
+		let mut fields = SeaFields::new(vec![
+			SeaField::new(token_col, token_hash),
+			SeaField::new(expires_col, expires_at),
+		]);
+		prep_fields_for_update::<Self>(&mut fields, ctx.user_id());
+
+		// -- Build query
+		let fields = fields.for_sea_update();
+		let mut query = Query::update();
+		query
+			.table(Self::table_ref())
+			.values(fields)
+			.and_where(Expr::col(UserIden::Id).eq(id));
+
+		// -- Exec query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		mm.dbx().execute(sqlx::query_with(&sql, values)).await?;
+
+		Ok(token)
+	}
+
+	/// Consume a login link token, and returns the user to login.
+	/// The email is marked as verified, since the user received the link.
+	pub async fn consume_login_link<E>(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		token: &str,
+	) -> Result<E>
+	where
+		E: UserBy,
+	{
+		// -- Find user by token
+		let (user_id, token_hash, expires_at) =
+			Self::first_by_onetime_token(mm, TokenPurpose::LoginLink, token)
+				.await?
+				.ok_or(Error::LoginLinkTokenInvalid)?;
+
+		// -- Clear the token (single use)
+		// Note: The token condition prevents a concurrent double use.
+		let mut update = Query::update();
+		update
+			.table(Self::table_ref())
+			.values(vec![
+				(UserIden::LoginToken, Expr::value(Option::<String>::None)),
+				(
+					UserIden::LoginTokenExpiresAt,
+					Expr::value(Option::<chrono::DateTime<Utc>>::None),
+				),
+				(UserIden::EmailVerified, Expr::value(true)),
+			])
+			.and_where(Expr::col(UserIden::Id).eq(user_id))
+			.and_where(Expr::col(UserIden::LoginToken).eq(token_hash));
+
+		let (sql, values) = update.build_sqlx(PostgresQueryBuilder);
+		let count = mm.dbx().execute(sqlx::query_with(&sql, values)).await?;
+		if count == 0 {
+			return Err(Error::LoginLinkTokenInvalid);
+		}
+
+		// -- Check if token is expired
+		if expires_at.is_none_or(|exp| Utc::now() > exp) {
+			tracing::warn!("Login link token expired for user_id {}", user_id);
+			return Err(Error::LoginLinkTokenExpired);
+		}
+
+		Self::get(ctx, mm, user_id).await
+	}
+
+	/// Find the user of a one-time token, as (id, token hash, expires_at).
+	/// The row is found by the token keyed hash, and the token is then verified
+	/// in constant time.
+	async fn first_by_onetime_token(
+		mm: &ModelManager,
+		purpose: TokenPurpose,
+		token: &str,
+	) -> Result<Option<(i64, String, Option<chrono::DateTime<Utc>>)>> {
+		let token = token.trim();
+		if token.is_empty() {
+			return Ok(None);
+		}
+		let (token_col, expires_col) = onetime_token_columns(purpose);
+		let token_hash = onetime::hash_token(purpose, token)?;
+
+		// -- Build query
+		let mut query = Query::select();
+		query
+			.from(Self::table_ref())
+			.columns(vec![UserIden::Id, token_col, expires_col])
+			.and_where(Expr::col(token_col).eq(token_hash));
+
+		// -- Exec query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let sqlx_query = sqlx::query_as_with::<
+			_,
+			(i64, String, Option<chrono::DateTime<Utc>>),
+			_,
+		>(&sql, values);
+		let row = mm.dbx().fetch_optional(sqlx_query).await?;
+
+		Ok(row.filter(|(_, stored_hash, _)| {
+			onetime::verify_token(purpose, token, stored_hash).is_ok()
+		}))
+	}
+
+	/// Migration of the reset and verification tokens stored in plaintext
+	/// (before the keyed hashes). Each legacy token is replaced by its hash, so the
+	/// links already sent keep working until they expire.
+	/// Idempotent, returns the number of tokens hashed (run by `db-migrate hash-legacy-tokens`).
+	pub async fn hash_legacy_tokens(_ctx: &Ctx, mm: &ModelManager) -> Result<u64> {
+		let mut count = 0;
+
+		for purpose in [TokenPurpose::PasswordReset, TokenPurpose::EmailVerification] {
+			let (token_col, _) = onetime_token_columns(purpose);
+
+			// -- Select the legacy (not hashed) tokens
+			let mut query = Query::select();
+			query
+				.from(Self::table_ref())
+				.columns(vec![UserIden::Id, token_col])
+				.and_where(Expr::col(token_col).is_not_null())
+				.and_where(Expr::col(token_col).not_like(format!("{}%", onetime::HASH_PREFIX)));
+
+			let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+			let sqlx_query = sqlx::query_as_with::<_, (i64, String), _>(&sql, values);
+			let legacy_tokens = mm.dbx().fetch_all(sqlx_query).await?;
+
+			// -- Replace each by its hash
+			for (id, legacy_token) in legacy_tokens {
+				let token_hash = onetime::hash_token(purpose, &legacy_token)?;
+
+				let mut update = Query::update();
+				update
+					.table(Self::table_ref())
+					.values(vec![(token_col, Expr::value(token_hash))])
+					.and_where(Expr::col(UserIden::Id).eq(id))
+					.and_where(Expr::col(token_col).eq(legacy_token));
+
+				let (sql, values) = update.build_sqlx(PostgresQueryBuilder);
+				count += mm.dbx().execute(sqlx::query_with(&sql, values)).await?;
+			}
+		}
+
+		if count > 0 {
+			tracing::info!("Hashed {} legacy plaintext user tokens", count);
+		}
+
+		Ok(count)
+	}
+
+	/// Start the MFA enrollment by generating a new TOTP secret.
+	/// MFA is only enabled once a first code is verified (see `mfa_enable`).
+	pub async fn mfa_setup(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<MfaSetup> {
+		let user: UserForMfa = Self::get(ctx, mm, id).await?;
+		if user.mfa_enabled {
+			return Err(Error::MfaAlreadyEnabled);
+		}
+
+		let secret = totp::generate_secret();
+		let otpauth_uri = totp::provisioning_uri(&secret, &user.username);
+
+		let fields = SeaFields::new(vec![
+			SeaField::new(UserIden::MfaSecret, secret.clone()),
+			SeaField::new(UserIden::MfaLastStep, Option::<i64>::None),
+		]);
+		Self::update_mfa_fields(ctx, mm, id, fields, None).await?;
+
+		Ok(MfaSetup {
+			secret,
+			otpauth_uri,
+		})
+	}
+
+	/// Enable MFA, if the code matches the secret from `mfa_setup`.
+	/// Returns the recovery codes (in clear, only time they are available).
+	pub async fn mfa_enable(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		id: i64,
+		code: &str,
+	) -> Result<Vec<String>> {
+		let user: UserForMfa = Self::get(ctx, mm, id).await?;
+		if user.mfa_enabled {
+			return Err(Error::MfaAlreadyEnabled);
+		}
+		let secret = user.mfa_secret.ok_or(Error::MfaNotSetup)?;
+
+		let step = verify_totp_code(&secret, code, None)?;
+
+		let recovery_codes = totp::generate_recovery_codes();
+		let recovery_hashes = recovery_codes
+			.iter()
+			.map(|code| totp::hash_recovery_code(code))
+			.collect::<core::result::Result<Vec<_>, _>>()?;
+
+		let fields = SeaFields::new(vec![
+			SeaField::new(UserIden::MfaEnabled, true),
+			SeaField::new(UserIden::MfaLastStep, step),
+			SeaField::new(UserIden::MfaRecoveryCodes, recovery_hashes.join(",")),
+		]);
+		Self::update_mfa_fields(ctx, mm, id, fields, None).await?;
+
+		Ok(recovery_codes)
+	}
+
+	/// Verify the second factor of a user, either a TOTP code or a recovery code.
+	/// A TOTP code cannot be replayed, and a recovery code is consumed.
+	pub async fn mfa_verify(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		id: i64,
+		code: &str,
+	) -> Result<()> {
+		let user: UserForMfa = Self::get(ctx, mm, id).await?;
+		if !user.mfa_enabled {
+			return Err(Error::MfaNotEnabled);
+		}
+		let secret = user.mfa_secret.ok_or(Error::MfaNotSetup)?;
+
+		let is_totp_code = code.trim().chars().all(|c| c.is_ascii_digit());
+		if is_totp_code {
+			let step = verify_totp_code(&secret, code, user.mfa_last_step)?;
+
+			// Note: The condition on the previous step prevents a concurrent replay
+			//       of the same code.
+			let fields =
+				SeaFields::new(vec![SeaField::new(UserIden::MfaLastStep, step)]);
+			let prev = Expr::col(UserIden::MfaLastStep)
+				.is_null()
+				.or(Expr::col(UserIden::MfaLastStep).lt(step));
+			Self::update_mfa_fields(ctx, mm, id, fields, Some(prev)).await
+		} else {
+			let hashes = user.mfa_recovery_codes.unwrap_or_default();
+			let mut remaining: Vec<&str> = hashes.split(',').filter(|h| !h.is_empty()).collect();
+			let idx = totp::find_recovery_code(code, &remaining)?.ok_or(Error::MfaCodeInvalid)?;
+			remaining.remove(idx);
+
+			// Note: The condition on the previous codes prevents a concurrent double use.
+			let fields = SeaFields::new(vec![SeaField::new(
+				UserIden::MfaRecoveryCodes,
+				remaining.join(","),
+			)]);
+			let prev = Expr::col(UserIden::MfaRecoveryCodes).eq(hashes.as_str());
+			Self::update_mfa_fields(ctx, mm, id, fields, Some(prev)).await?;
+
+			tracing::info!(
+				"Recovery code used for user_id {}, {} remaining",
+				id,
+				remaining.len()
+			);
+			Ok(())
+		}
+	}
+
+	/// Disable MFA, after verifying a last code (TOTP or recovery).
+	pub async fn mfa_disable(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		id: i64,
+		code: &str,
+	) -> Result<()> {
+		Self::mfa_verify(ctx, mm, id, code).await?;
+
+		let fields = SeaFields::new(vec![
+			SeaField::new(UserIden::MfaEnabled, false),
+			SeaField::new(UserIden::MfaSecret, Option::<String>::None),
+			SeaField::new(UserIden::MfaLastStep, Option::<i64>::None),
+			SeaField::new(UserIden::MfaRecoveryCodes, Option::<String>::None),
+		]);
+		Self::update_mfa_fields(ctx, mm, id, fields, None).await
+	}
+
+	/// Update the mfa fields of a user, with an optional extra condition.
+	/// Returns `Error::MfaCodeInvalid` if the condition did not match.
+	async fn update_mfa_fields(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		id: i64,
+		mut fields: SeaFields,
+		cond: Option<SimpleExpr>,
+	) -> Result<()> {
+		prep_fields_for_update::<Self>(&mut fields, ctx.user_id());
+
+		// -- Build query
+		let fields = fields.for_sea_update();
+		let mut query = Query::update();
+		query
+			.table(Self::table_ref())
+			.values(fields)
+			.and_where(Expr::col(UserIden::Id).eq(id));
+		if let Some(cond) = cond {
+			query.and_where(cond);
+		}
+
+		// -- Exec query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let count = mm.dbx().execute(sqlx::query_with(&sql, values)).await?;
+
+		if count == 0 {
+			Err(Error::MfaCodeInvalid)
+		} else {
+			Ok(())
+		}
+	}
+
+	/// Delete a user (GDPR erasure), by the user itself or with `Permission::UserManage`.
+	///
+	/// - The user row is soft deleted (`deleted`), with its username and email set to
+	///   "DELETED-_user_id_", and its other PII, secrets, and tokens cleared.
+	///   The `mid`/`mtime` record who performed the deletion.
+	/// - Its posts, media, sessions, and provider identities are deleted, and the changes
+	///   of its writes cleared from the `audit_log`.
+	/// - The action is recorded in the `um_change_log`.
+	///
+	/// A deleted user cannot login or refresh tokens (not found by username, and the
+	/// token salt and sessions are gone).
+	/// Returns the urls of the deleted media, for the caller to remove the files.
+	pub async fn delete(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<UserErasure> {
+		if ctx.user_id() != id {
+			ctx.require(Permission::UserManage)?;
+		}
+
+		let user: User = Self::get(ctx, mm, id).await?;
+		if matches!(user.typ, UserTyp::Sys) {
+			return Err(Error::ValidationFail(format!("Cannot delete the sys user {id}")));
+		}
+
+		// Note: All or nothing, so a failure does not leave a half erased user.
+		let erasure = mm
+			.transaction(|mm| async move {
+				let media_urls = PostMediaBmc::delete_for_user(ctx, &mm, id).await?;
+				let posts_deleted = PostBmc::delete_for_owner(ctx, &mm, id).await?;
+				let trips_deleted = TripBmc::delete_for_owner(ctx, &mm, id).await?;
+				let sessions_deleted = SessionBmc::delete_for_user(ctx, &mm, id).await?;
+				let identities_deleted = UserIdentityBmc::delete_for_user(ctx, &mm, id).await?;
+				AuditLogBmc::clear_changes_for_actor(ctx, &mm, id).await?;
+
+				Self::anonymize(ctx, &mm, id).await?;
+
+				UmChangeLogBmc::create(
+					ctx,
+					&mm,
+					UmChangeLogForCreate {
+						user_id: id,
+						action: "user_erased".to_string(),
+						note: Some(format!(
+							"posts: {posts_deleted}, media: {}, trips: {trips_deleted}, sessions: {sessions_deleted}, identities: {identities_deleted}",
+							media_urls.len()
+						)),
+					},
+				)
+				.await?;
+
+				Ok(UserErasure {
+					posts_deleted,
+					media_urls,
+				})
+			})
+			.await?;
+
+		tracing::info!("User {} deleted (erased) by user_id {}", id, ctx.user_id());
+		Ok(erasure)
+	}
+
+	/// Soft delete the user row, and clear its PII, secrets, and tokens.
+	async fn anonymize(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
+		let deleted_name = format!("DELETED-{id}");
+		let no_time = Option::<chrono::DateTime<Utc>>::None;
+
+		// -- Prep fields
+		let mut fields = SeaFields::new(vec![
+			SeaField::new(UserIden::Deleted, true),
+			SeaField::new(UserIden::Username, deleted_name.clone()),
+			SeaField::new(UserIden::Email, deleted_name),
+			SeaField::new(UserIden::EmailVerified, false),
+			SeaField::new(UserIden::Pwd, Option::<String>::None),
+			SeaField::new(UserIden::PwdSalt, Uuid::new_v4()),
+			SeaField::new(UserIden::TokenSalt, Uuid::new_v4()),
+			SeaField::new(UserIden::EmailVerificationToken, Option::<String>::None),
+			SeaField::new(UserIden::EmailVerificationExpiresAt, no_time),
+			SeaField::new(UserIden::ResetToken, Option::<String>::None),
+			SeaField::new(UserIden::ResetTokenExpiresAt, no_time),
+			SeaField::new(UserIden::LoginToken, Option::<String>::None),
+			SeaField::new(UserIden::LoginTokenExpiresAt, no_time),
+			SeaField::new(UserIden::UnlockToken, Option::<String>::None),
+			SeaField::new(UserIden::UnlockTokenExpiresAt, no_time),
+			SeaField::new(UserIden::MfaEnabled, false),
+			SeaField::new(UserIden::MfaSecret, Option::<String>::None),
+			SeaField::new(UserIden::MfaLastStep, Option::<i64>::None),
+			SeaField::new(UserIden::MfaRecoveryCodes, Option::<String>::None),
+		]);
+		prep_fields_for_update::<Self>(&mut fields, ctx.user_id());
+
+		// -- Build query
+		let fields = fields.for_sea_update();
+		let mut query = Query::update();
+		query
+			.table(Self::table_ref())
+			.values(fields)
+			.and_where(Expr::col(UserIden::Id).eq(id))
+			.and_where(Expr::col(UserIden::Deleted).eq(false));
+
+		// -- Exec query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let count = mm.dbx().execute(sqlx::query_with(&sql, values)).await?;
+		if count == 0 {
+			return Err(Error::EntityNotFound {
+				entity: Self::TABLE,
+				id,
+			});
+		}
+
+		Ok(())
 	}
 }
 
 // endregion: --- UserBmc
 
+// region:    --- Support
+
+fn verify_totp_code(secret: &str, code: &str, last_step: Option<i64>) -> Result<i64> {
+	totp::verify_code(secret, code, last_step).map_err(|err| match err {
+		totp::Error::CodeInvalid | totp::Error::CodeReused => Error::MfaCodeInvalid,
+		other => Error::Totp(other),
+	})
+}
+
+/// The (token, expires_at) columns of a one-time token purpose.
+fn onetime_token_columns(purpose: TokenPurpose) -> (UserIden, UserIden) {
+	match purpose {
+		TokenPurpose::EmailVerification => (
+			UserIden::EmailVerificationToken,
+			UserIden::EmailVerificationExpiresAt,
+		),
+		TokenPurpose::PasswordReset => (UserIden::ResetToken, UserIden::ResetTokenExpiresAt),
+		TokenPurpose::LoginLink => (UserIden::LoginToken, UserIden::LoginTokenExpiresAt),
+		TokenPurpose::AccountUnlock => (UserIden::UnlockToken, UserIden::UnlockTokenExpiresAt),
+	}
+}
+
+/// The email local part, restricted to `[a-z0-9._-]` (e.g., "john.doe").
+fn username_from_email(email: &str) -> String {
+	let local_part = email.split('@').next().unwrap_or_default();
+	let username: String = local_part
+		.to_lowercase()
+		.chars()
+		.filter(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+		.take(32)
+		.collect();
+
+	if username.is_empty() {
+		"user".to_string()
+	} else {
+		username
+	}
+}
+
+// endregion: --- Support
+
 // region:    --- Tests
 
 #[cfg(test)]
@@ -578,6 +1271,366 @@ mod tests {
 
 		Ok(())
 	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_first_by_email_ok_case_insensitive() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let demo1: User = UserBmc::first_by_username(&ctx, &mm, "demo1")
+			.await?
+			.ok_or("Should have user 'demo1'")?;
+		let fx_email = demo1.email.to_uppercase();
+
+		// -- Exec
+		let user: User = UserBmc::first_by_email(&ctx, &mm, &fx_email)
+			.await?
+			.ok_or("Should have user 'demo1' by email")?;
+
+		// -- Check
+		assert_eq!(user.id, demo1.id);
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_update_role_permission() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let user_id = UserBmc::create(
+			&ctx,
+			&mm,
+			UserForCreate {
+				username: "test_update_role-user-01".to_string(),
+				pwd_clear: "test_update_role pwd 01".to_string(),
+				email: "test_update_role user@example.com".to_string(),
+			},
+		)
+		.await?;
+		let user_ctx = Ctx::new(user_id)?;
+
+		// -- Exec
+		let res = UserBmc::update_role(&user_ctx, &mm, user_id, Role::Admin).await;
+		UserBmc::update_role(&ctx, &mm, user_id, Role::Creator).await?;
+
+		// -- Check
+		assert!(matches!(
+			res,
+			Err(crate::model::Error::Ctx(crate::ctx::Error::PermissionDenied {
+				permission: Permission::UserManage,
+				..
+			}))
+		));
+		let user: User = UserBmc::get(&ctx, &mm, user_id).await?;
+		assert_eq!(user.role, Role::Creator);
+
+		// -- Clean
+		UserBmc::delete(&ctx, &mm, user_id).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_consume_login_link_single_use_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let user_id = UserBmc::create(
+			&ctx,
+			&mm,
+			UserForCreate {
+				username: "test_consume_login_link-user-01".to_string(),
+				pwd_clear: "test_consume_login_link pwd 01".to_string(),
+				email: "test_consume_login_link user@example.com".to_string(),
+			},
+		)
+		.await?;
+
+		// -- Exec
+		let token =
+			UserBmc::set_onetime_token(&ctx, &mm, user_id, TokenPurpose::LoginLink).await?;
+		let user: User = UserBmc::consume_login_link(&ctx, &mm, &token).await?;
+
+		// -- Check
+		assert_eq!(user.id, user_id);
+		assert!(user.email_verified);
+		let res = UserBmc::consume_login_link::<User>(&ctx, &mm, &token).await;
+		assert!(
+			matches!(res, Err(crate::model::Error::LoginLinkTokenInvalid)),
+			"login link should be single use"
+		);
+
+		// -- Clean
+		UserBmc::delete(&ctx, &mm, user_id).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_consume_unlock_token_single_use_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_username = "test_consume_unlock_token-user-01";
+		let user_id = UserBmc::create(
+			&ctx,
+			&mm,
+			UserForCreate {
+				username: fx_username.to_string(),
+				pwd_clear: "test_consume_unlock_token pwd 01".to_string(),
+				email: "test_consume_unlock_token user@example.com".to_string(),
+			},
+		)
+		.await?;
+
+		// -- Exec
+		let token =
+			UserBmc::set_onetime_token(&ctx, &mm, user_id, TokenPurpose::AccountUnlock).await?;
+		let username = UserBmc::consume_unlock_token(&ctx, &mm, &token).await?;
+
+		// -- Check
+		assert_eq!(username, fx_username);
+		let res = UserBmc::consume_unlock_token(&ctx, &mm, &token).await;
+		assert!(
+			matches!(res, Err(crate::model::Error::UnlockTokenInvalid)),
+			"unlock token should be single use"
+		);
+
+		// -- Clean
+		UserBmc::delete(&ctx, &mm, user_id).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_onetime_token_err_other_purpose() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let user_id = UserBmc::create(
+			&ctx,
+			&mm,
+			UserForCreate {
+				username: "test_onetime_token_err_other_purpose-user-01".to_string(),
+				pwd_clear: "test_onetime_token_err_other_purpose pwd 01".to_string(),
+				email: "test_onetime_token_err_other_purpose user@example.com".to_string(),
+			},
+		)
+		.await?;
+		let reset_token =
+			UserBmc::set_onetime_token(&ctx, &mm, user_id, TokenPurpose::PasswordReset).await?;
+
+		// -- Exec
+		let res = UserBmc::verify_email(&ctx, &mm, &reset_token).await;
+
+		// -- Check
+		assert!(
+			matches!(res, Err(crate::model::Error::EmailVerificationTokenInvalid)),
+			"a reset token should not verify an email"
+		);
+		UserBmc::reset_password(&ctx, &mm, &reset_token, "new pwd 01").await?;
+		let res = UserBmc::reset_password(&ctx, &mm, &reset_token, "new pwd 02").await;
+		assert!(matches!(res, Err(crate::model::Error::ResetTokenInvalid)));
+
+		// -- Clean
+		UserBmc::delete(&ctx, &mm, user_id).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_request_password_reset_ok_case_insensitive() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let user_id = UserBmc::create(
+			&ctx,
+			&mm,
+			UserForCreate {
+				username: "test_request_password_reset-user-01".to_string(),
+				pwd_clear: "test_request_password_reset pwd 01".to_string(),
+				email: "test_request_password_reset@example.com".to_string(),
+			},
+		)
+		.await?;
+
+		// -- Exec
+		UserBmc::request_password_reset(&ctx, &mm, "Test_Request_Password_Reset@Example.com")
+			.await?;
+
+		// -- Check
+		let mut query = Query::select();
+		query
+			.from(UserBmc::table_ref())
+			.column(UserIden::ResetToken)
+			.and_where(Expr::col(UserIden::Id).eq(user_id));
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let (reset_token,): (Option<String>,) =
+			mm.dbx().fetch_one(sqlx::query_as_with(&sql, values)).await?;
+		assert!(reset_token.is_some(), "reset token should be set");
+
+		// -- Clean
+		UserBmc::delete(&ctx, &mm, user_id).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_hash_legacy_tokens_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let user_id = UserBmc::create(
+			&ctx,
+			&mm,
+			UserForCreate {
+				username: "test_hash_legacy_tokens_ok-user-01".to_string(),
+				pwd_clear: "test_hash_legacy_tokens_ok pwd 01".to_string(),
+				email: "test_hash_legacy_tokens_ok user@example.com".to_string(),
+			},
+		)
+		.await?;
+		// A plaintext reset token, as stored before the hashes.
+		let fx_legacy_token = Uuid::new_v4().to_string();
+		let mut update = Query::update();
+		update
+			.table(UserBmc::table_ref())
+			.values(vec![
+				(UserIden::ResetToken, Expr::value(fx_legacy_token.clone())),
+				(
+					UserIden::ResetTokenExpiresAt,
+					Expr::value(Utc::now() + chrono::Duration::minutes(5)),
+				),
+			])
+			.and_where(Expr::col(UserIden::Id).eq(user_id));
+		let (sql, values) = update.build_sqlx(PostgresQueryBuilder);
+		mm.dbx().execute(sqlx::query_with(&sql, values)).await?;
+
+		// -- Exec
+		let count = UserBmc::hash_legacy_tokens(&ctx, &mm).await?;
+
+		// -- Check
+		assert!(count >= 1);
+		assert_eq!(UserBmc::hash_legacy_tokens(&ctx, &mm).await?, 0);
+		// The link already sent still works.
+		UserBmc::reset_password(&ctx, &mm, &fx_legacy_token, "new pwd 01").await?;
+
+		// -- Clean
+		UserBmc::delete(&ctx, &mm, user_id).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_mfa_enable_verify_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let user_id = UserBmc::create(
+			&ctx,
+			&mm,
+			UserForCreate {
+				username: "test_mfa_enable_verify_ok-user-01".to_string(),
+				pwd_clear: "test_mfa_enable_verify_ok pwd 01".to_string(),
+				email: "test_mfa_enable_verify_ok user@example.com".to_string(),
+			},
+		)
+		.await?;
+
+		// -- Exec
+		let setup = UserBmc::mfa_setup(&ctx, &mm, user_id).await?;
+		let code = totp::generate_code(&setup.secret, Utc::now().timestamp())?;
+		let recovery_codes = UserBmc::mfa_enable(&ctx, &mm, user_id, &code).await?;
+
+		// -- Check
+		let user: UserForLogin = UserBmc::get(&ctx, &mm, user_id).await?;
+		assert!(user.mfa_enabled);
+		assert!(setup.otpauth_uri.starts_with("otpauth://totp/"));
+
+		// The enable code cannot be replayed.
+		let res = UserBmc::mfa_verify(&ctx, &mm, user_id, &code).await;
+		assert!(matches!(res, Err(crate::model::Error::MfaCodeInvalid)), "replay should fail");
+
+		// A recovery code works once.
+		let fx_recovery_code = &recovery_codes[0];
+		UserBmc::mfa_verify(&ctx, &mm, user_id, fx_recovery_code).await?;
+		let res = UserBmc::mfa_verify(&ctx, &mm, user_id, fx_recovery_code).await;
+		assert!(
+			matches!(res, Err(crate::model::Error::MfaCodeInvalid)),
+			"recovery code should be single use"
+		);
+
+		// -- Clean
+		UserBmc::delete(&ctx, &mm, user_id).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_delete_erasure_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_username = "test_delete_erasure-user-01";
+		let user_ctx = _dev_utils::seed_user(&mm, fx_username, Role::Creator).await?;
+		let user_id = user_ctx.user_id();
+		let posts = _dev_utils::seed_posts(
+			&user_ctx,
+			&mm,
+			&["test_delete_erasure post 01"],
+			&["test_delete_erasure desc 01"],
+		)
+		.await?;
+		let other_ctx =
+			_dev_utils::seed_user(&mm, "test_delete_erasure-user-02", Role::Creator).await?;
+
+		// -- Exec
+		let res_other = UserBmc::delete(&other_ctx, &mm, user_id).await;
+		let erasure = UserBmc::delete(&user_ctx, &mm, user_id).await?;
+		let res_again = UserBmc::delete(&ctx, &mm, user_id).await;
+
+		// -- Check
+		assert!(matches!(
+			res_other,
+			Err(crate::model::Error::Ctx(crate::ctx::Error::PermissionDenied { .. }))
+		));
+		assert_eq!(erasure.posts_deleted, 1);
+		assert!(matches!(
+			res_again,
+			Err(crate::model::Error::EntityNotFound { .. })
+		));
+
+		// hidden from the reads
+		let by_username: Option<User> = UserBmc::first_by_username(&ctx, &mm, fx_username).await?;
+		assert!(by_username.is_none());
+		let res_get = UserBmc::get::<User>(&ctx, &mm, user_id).await;
+		assert!(matches!(res_get, Err(crate::model::Error::EntityNotFound { .. })));
+		let users = UserBmc::list(&ctx, &mm, None, None).await?;
+		assert!(users.iter().all(|u| u.id != user_id));
+		let res_post = PostBmc::get(&ctx, &mm, posts[0].id).await;
+		assert!(matches!(res_post, Err(crate::model::Error::EntityNotFound { .. })));
+
+		// audit row
+		let changes = UmChangeLogBmc::list_for_user(&ctx, &mm, user_id).await?;
+		assert_eq!(changes.len(), 1);
+		assert_eq!(changes[0].action, "user_erased");
+		assert_eq!(changes[0].cid, user_id);
+
+		// -- Clean
+		UserBmc::delete(&ctx, &mm, other_ctx.user_id()).await?;
+
+		Ok(())
+	}
 }
 
-// endregion: --- Tests
\ No newline at end of file
+// endregion: --- Tests
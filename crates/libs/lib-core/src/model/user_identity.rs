@@ -0,0 +1,290 @@
+// region: ---- Modules
+use crate::ctx::Ctx;
+use crate::model::base::{self, prep_fields_for_update, DbBmc};
+use crate::model::user::{User, UserBmc};
+use crate::model::ModelManager;
+use crate::model::{Error, Result};
+use chrono::{DateTime, Utc};
+use lib_auth::oidc::IdTokenClaims;
+use modql::field::{Fields, HasSeaFields, SeaField, SeaFields};
+use sea_query::{Expr, Iden, Order, PostgresQueryBuilder, Query};
+use sea_query_binder::SqlxBinder;
+use serde::Serialize;
+use sqlx::FromRow;
+// endregion: ---- Modules
+
+// region:    --- UserIdentity Types
+
+/// A provider account (OIDC `sub`) linked to a user.
+#[derive(Clone, Fields, FromRow, Debug, Serialize)]
+pub struct UserIdentity {
+	pub id: i64,
+	pub user_id: i64,
+	pub provider: String,
+	pub subject: String,
+	pub email: Option<String>,
+	pub last_login_at: Option<DateTime<Utc>>,
+	pub ctime: DateTime<Utc>,
+}
+
+#[derive(Fields)]
+pub struct UserIdentityForCreate {
+	pub user_id: i64,
+	pub provider: String,
+	pub subject: String,
+	pub email: Option<String>,
+	pub last_login_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Iden)]
+enum UserIdentityIden {
+	Id,
+	UserId,
+	Provider,
+	Subject,
+	LastLoginAt,
+}
+
+// endregion: --- UserIdentity Types
+
+// region:    --- UserIdentityBmc
+pub struct UserIdentityBmc;
+
+impl DbBmc for UserIdentityBmc {
+	const TABLE: &'static str = "user_identity";
+}
+
+impl UserIdentityBmc {
+	pub async fn create(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		identity_c: UserIdentityForCreate,
+	) -> Result<i64> {
+		base::create::<Self, _>(ctx, mm, identity_c)
+			.await
+			.map_err(|model_error| {
+				Error::resolve_unique_violation(
+					model_error,
+					Some(|table: &str, _constraint: &str| {
+						(table == "user_identity").then_some(Error::OidcIdentityAlreadyLinked)
+					}),
+				)
+			})
+	}
+
+	pub async fn first_by_provider_subject(
+		_ctx: &Ctx,
+		mm: &ModelManager,
+		provider: &str,
+		subject: &str,
+	) -> Result<Option<UserIdentity>> {
+		// -- Build query
+		let mut query = Query::select();
+		query
+			.from(Self::table_ref())
+			.columns(UserIdentity::sea_column_refs())
+			.and_where(Expr::col(UserIdentityIden::Provider).eq(provider))
+			.and_where(Expr::col(UserIdentityIden::Subject).eq(subject));
+
+		// -- Execute query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let sqlx_query = sqlx::query_as_with::<_, UserIdentity, _>(&sql, values);
+		let entity = mm.dbx().fetch_optional(sqlx_query).await?;
+
+		Ok(entity)
+	}
+
+	/// List the linked identities of the ctx user.
+	pub async fn list_for_user(ctx: &Ctx, mm: &ModelManager) -> Result<Vec<UserIdentity>> {
+		// -- Build query
+		let mut query = Query::select();
+		query
+			.from(Self::table_ref())
+			.columns(UserIdentity::sea_column_refs())
+			.and_where(Expr::col(UserIdentityIden::UserId).eq(ctx.user_id()))
+			.order_by(UserIdentityIden::Id, Order::Asc);
+
+		// -- Execute query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		let sqlx_query = sqlx::query_as_with::<_, UserIdentity, _>(&sql, values);
+		let identities = mm.dbx().fetch_all(sqlx_query).await?;
+
+		Ok(identities)
+	}
+
+	/// Resolve the user of a validated provider login, and returns its id.
+	///
+	/// - A known identity logs in its user.
+	/// - With `link_user_id` (flow started by a logged in user), the identity is linked to it.
+	/// - Otherwise, the provider email must be verified. It links to the user with the same
+	///   verified email, or creates a new user (without password).
+	///
+	/// Note: A user with the same email but not verified is not linked (it could be an
+	///       account pre-registered by someone else). The user has to login and link it.
+	pub async fn login_or_link(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		provider: &str,
+		claims: &IdTokenClaims,
+		link_user_id: Option<i64>,
+	) -> Result<i64> {
+		// -- Known identity
+		if let Some(identity) =
+			Self::first_by_provider_subject(ctx, mm, provider, &claims.sub).await?
+		{
+			if link_user_id.is_some_and(|id| id != identity.user_id) {
+				return Err(Error::OidcIdentityAlreadyLinked);
+			}
+			Self::update_last_login(ctx, mm, identity.id).await?;
+			return Ok(identity.user_id);
+		}
+
+		let identity_c = |user_id: i64| UserIdentityForCreate {
+			user_id,
+			provider: provider.to_string(),
+			subject: claims.sub.clone(),
+			email: claims.email.clone(),
+			last_login_at: Some(Utc::now()),
+		};
+
+		// -- Link to the logged in user
+		if let Some(user_id) = link_user_id {
+			Self::create(ctx, mm, identity_c(user_id)).await?;
+			return Ok(user_id);
+		}
+
+		// -- Link or create by verified email
+		let email = match claims.email.as_deref() {
+			Some(email) if claims.email_verified => email,
+			_ => return Err(Error::OidcEmailNotVerified),
+		};
+
+		if let Some(user) = UserBmc::first_by_email::<User>(ctx, mm, email).await? {
+			if !user.email_verified {
+				return Err(Error::OidcLinkRequiresLogin);
+			}
+			Self::create(ctx, mm, identity_c(user.id)).await?;
+			return Ok(user.id);
+		}
+
+		// Note: The new user and its identity are created in the same transaction.
+		let user_id = mm
+			.transaction(|mm| async move {
+				let user_id = UserBmc::create_from_oidc(ctx, &mm, email).await?;
+				Self::create(ctx, &mm, identity_c(user_id)).await?;
+				Ok(user_id)
+			})
+			.await?;
+
+		tracing::info!("User {} created from {} login", user_id, provider);
+		Ok(user_id)
+	}
+
+	/// Delete all the identities of a user, on user erasure.
+	/// Returns the number of deleted identities.
+	/// Note: The permission is checked by the caller (see `UserBmc::delete`).
+	pub async fn delete_for_user(ctx: &Ctx, mm: &ModelManager, user_id: i64) -> Result<u64> {
+		base::delete_where::<Self>(ctx, mm, Expr::col(UserIdentityIden::UserId).eq(user_id)).await
+	}
+
+	async fn update_last_login(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
+		// -- Prep fields
+		let mut fields =
+			SeaFields::new(vec![SeaField::new(UserIdentityIden::LastLoginAt, Utc::now())]);
+		prep_fields_for_update::<Self>(&mut fields, ctx.user_id());
+
+		// -- Build query
+		let fields = fields.for_sea_update();
+		let mut query = Query::update();
+		query
+			.table(Self::table_ref())
+			.values(fields)
+			.and_where(Expr::col(UserIdentityIden::Id).eq(id));
+
+		// -- Exec query
+		let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+		mm.dbx().execute(sqlx::query_with(&sql, values)).await?;
+
+		Ok(())
+	}
+}
+
+// endregion: --- UserIdentityBmc
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	pub type Result<T> = core::result::Result<T, Error>;
+	pub type Error = Box<dyn std::error::Error>; // For tests.
+
+	use super::*;
+	use crate::_dev_utils;
+	use serial_test::serial;
+
+	fn fx_claims(sub: &str, email: &str, email_verified: bool) -> IdTokenClaims {
+		IdTokenClaims {
+			iss: "https://idp.example.com".to_string(),
+			sub: sub.to_string(),
+			nonce: None,
+			email: Some(email.to_string()),
+			email_verified,
+			name: None,
+			preferred_username: None,
+		}
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_login_or_link_create_then_login_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_claims =
+			fx_claims("test_login_or_link-sub-01", "test_login_or_link@example.com", true);
+
+		// -- Exec
+		let user_id = UserIdentityBmc::login_or_link(&ctx, &mm, "mock", &fx_claims, None).await?;
+		let user_id_2 =
+			UserIdentityBmc::login_or_link(&ctx, &mm, "mock", &fx_claims, None).await?;
+
+		// -- Check
+		assert_eq!(user_id, user_id_2);
+		let user: User = UserBmc::get(&ctx, &mm, user_id).await?;
+		assert!(user.email_verified);
+		assert_eq!(user.username, "test_login_or_link");
+
+		// -- Clean
+		UserBmc::delete(&ctx, &mm, user_id).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_login_or_link_err_email_not_verified() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		// Note: demo1 exists, with its email not verified.
+		let fx_claims_demo1 = fx_claims("test_login_or_link-sub-02", "demo1@example.com", true);
+		let fx_claims_unverified =
+			fx_claims("test_login_or_link-sub-03", "other@example.com", false);
+
+		// -- Exec
+		let res = UserIdentityBmc::login_or_link(&ctx, &mm, "mock", &fx_claims_demo1, None).await;
+		let res_unverified =
+			UserIdentityBmc::login_or_link(&ctx, &mm, "mock", &fx_claims_unverified, None).await;
+
+		// -- Check
+		assert!(matches!(res, Err(crate::model::Error::OidcLinkRequiresLogin)));
+		assert!(matches!(
+			res_unverified,
+			Err(crate::model::Error::OidcEmailNotVerified)
+		));
+
+		Ok(())
+	}
+}
+
+// endregion: --- Tests
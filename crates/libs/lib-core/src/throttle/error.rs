@@ -0,0 +1,30 @@
+use crate::model;
+use derive_more::From;
+use serde::Serialize;
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[derive(Debug, Serialize, From)]
+pub enum Error {
+	/// Too many recent failures (account or ip), retry after a backoff delay.
+	Throttled { retry_after_sec: i64 },
+	/// Locked after too many account failures (unlock by email link, or wait).
+	AccountLocked { retry_after_sec: i64 },
+
+	// -- Modules
+	#[from]
+	Model(model::Error),
+}
+
+// region:    --- Error Boilerplate
+impl core::fmt::Display for Error {
+	fn fmt(
+		&self,
+		fmt: &mut core::fmt::Formatter,
+	) -> core::result::Result<(), core::fmt::Error> {
+		write!(fmt, "{self:?}")
+	}
+}
+
+impl std::error::Error for Error {}
+// endregion: --- Error Boilerplate
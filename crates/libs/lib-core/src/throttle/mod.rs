@@ -0,0 +1,304 @@
+//! Login brute-force protection.
+//!
+//! - Failure counters per account (username) and per ip, with an exponential backoff
+//!   after a few free attempts.
+//! - Account lockout after `lockout_threshold` failures. The caller sends the unlock
+//!   email when `FailureOutcome::account_locked` is true.
+//! - The counters restart after `reset_window_sec` without failure.
+//!
+//! The counters are in Postgres (`PgThrottleStore`), so the throttling holds across
+//! server instances. `MemThrottleStore` is a local in-memory store for tests.
+
+// region:    --- Modules
+
+mod error;
+mod store;
+
+pub use self::error::{Error, Result};
+pub use self::store::{MemThrottleStore, PgThrottleStore, ThrottleStore};
+
+use crate::model::ModelManager;
+use chrono::{DateTime, Duration, Utc};
+
+// endregion: --- Modules
+
+// region:    --- Policy
+
+pub struct BackoffPolicy {
+	/// Failures allowed before any delay.
+	pub free_attempts: i32,
+	/// First delay, doubled on each further failure.
+	pub base_delay_sec: i64,
+	pub max_delay_sec: i64,
+}
+
+impl BackoffPolicy {
+	/// The delay before the next attempt after `failures` failures (None if no delay).
+	fn delay_sec(&self, failures: i32) -> Option<i64> {
+		let over = failures - self.free_attempts;
+		if over <= 0 {
+			return None;
+		}
+
+		let factor = 2i64.saturating_pow((over - 1).min(62) as u32);
+		Some(self.base_delay_sec.saturating_mul(factor).min(self.max_delay_sec))
+	}
+}
+
+pub struct ThrottlePolicy {
+	pub account: BackoffPolicy,
+	/// Note: Higher, since many users can share an ip (NAT, mobile carriers).
+	pub ip: BackoffPolicy,
+	pub lockout_threshold: i32,
+	pub lockout_sec: i64,
+	pub reset_window_sec: i64,
+}
+
+impl Default for ThrottlePolicy {
+	fn default() -> Self {
+		ThrottlePolicy {
+			account: BackoffPolicy {
+				free_attempts: 3,
+				base_delay_sec: 1,
+				max_delay_sec: 60,
+			},
+			ip: BackoffPolicy {
+				free_attempts: 20,
+				base_delay_sec: 1,
+				max_delay_sec: 5 * 60,
+			},
+			lockout_threshold: 10,
+			lockout_sec: 30 * 60,
+			reset_window_sec: 60 * 60,
+		}
+	}
+}
+
+// endregion: --- Policy
+
+// region:    --- LoginThrottle
+
+pub struct FailureOutcome {
+	/// True when this failure locked the account (to send the unlock email, once).
+	pub account_locked: bool,
+}
+
+pub struct LoginThrottle<S: ThrottleStore> {
+	store: S,
+	policy: ThrottlePolicy,
+}
+
+impl LoginThrottle<PgThrottleStore> {
+	/// The login throttle with the Postgres store and the default policy.
+	pub fn new_pg(mm: &ModelManager) -> Self {
+		Self::new(PgThrottleStore::new(mm.clone()), ThrottlePolicy::default())
+	}
+}
+
+impl<S: ThrottleStore> LoginThrottle<S> {
+	pub fn new(store: S, policy: ThrottlePolicy) -> Self {
+		LoginThrottle { store, policy }
+	}
+
+	pub fn policy(&self) -> &ThrottlePolicy {
+		&self.policy
+	}
+
+	/// Check if a login attempt is allowed, before validating the credentials.
+	pub async fn check(&self, username: &str, ip: Option<&str>) -> Result<()> {
+		let now = Utc::now();
+
+		// -- Account
+		if let Some(counter) = self.store.get(&account_key(username)).await?
+			&& let Some(blocked_until) = counter.blocked_until
+			&& blocked_until > now
+		{
+			let retry_after_sec = retry_after_sec(now, blocked_until);
+			return Err(if counter.failures >= self.policy.lockout_threshold {
+				Error::AccountLocked { retry_after_sec }
+			} else {
+				Error::Throttled { retry_after_sec }
+			});
+		}
+
+		// -- Ip
+		if let Some(ip) = ip
+			&& let Some(counter) = self.store.get(&ip_key(ip)).await?
+			&& let Some(blocked_until) = counter.blocked_until
+			&& blocked_until > now
+		{
+			return Err(Error::Throttled {
+				retry_after_sec: retry_after_sec(now, blocked_until),
+			});
+		}
+
+		Ok(())
+	}
+
+	/// Record a failed attempt (unknown user, wrong password, or wrong second factor).
+	pub async fn record_failure(
+		&self,
+		username: &str,
+		ip: Option<&str>,
+	) -> Result<FailureOutcome> {
+		let now = Utc::now();
+		let reset_before = now - Duration::seconds(self.policy.reset_window_sec);
+
+		// -- Account
+		let key = account_key(username);
+		let failures = self.store.incr_failures(&key, reset_before).await?;
+		let delay_sec = if failures >= self.policy.lockout_threshold {
+			Some(self.policy.lockout_sec)
+		} else {
+			self.policy.account.delay_sec(failures)
+		};
+		if let Some(delay_sec) = delay_sec {
+			self.store
+				.set_blocked_until(&key, now + Duration::seconds(delay_sec))
+				.await?;
+		}
+
+		// -- Ip
+		if let Some(ip) = ip {
+			let key = ip_key(ip);
+			let failures = self.store.incr_failures(&key, reset_before).await?;
+			if let Some(delay_sec) = self.policy.ip.delay_sec(failures) {
+				self.store
+					.set_blocked_until(&key, now + Duration::seconds(delay_sec))
+					.await?;
+			}
+		}
+
+		Ok(FailureOutcome {
+			account_locked: failures == self.policy.lockout_threshold,
+		})
+	}
+
+	/// Record a successful login (resets the account counter).
+	/// Note: The ip counter is not reset, so a valid account does not unlock the
+	///       attempts on the other accounts from the same ip.
+	pub async fn record_success(&self, username: &str) -> Result<()> {
+		self.store.reset(&account_key(username)).await?;
+		Ok(())
+	}
+
+	/// Unlock an account (e.g., from the unlock email link).
+	pub async fn unlock(&self, username: &str) -> Result<()> {
+		self.store.reset(&account_key(username)).await?;
+		Ok(())
+	}
+}
+
+// endregion: --- LoginThrottle
+
+// region:    --- Support
+
+fn account_key(username: &str) -> String {
+	format!("user:{}", username.trim().to_lowercase())
+}
+
+fn ip_key(ip: &str) -> String {
+	format!("ip:{ip}")
+}
+
+fn retry_after_sec(now: DateTime<Utc>, blocked_until: DateTime<Utc>) -> i64 {
+	(blocked_until - now).num_seconds().max(1)
+}
+
+// endregion: --- Support
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	pub type Result<T> = core::result::Result<T, Error>;
+	pub type Error = Box<dyn std::error::Error>; // For tests.
+
+	use super::*;
+
+	fn fx_policy() -> ThrottlePolicy {
+		ThrottlePolicy {
+			account: BackoffPolicy {
+				free_attempts: 2,
+				base_delay_sec: 60,
+				max_delay_sec: 600,
+			},
+			ip: BackoffPolicy {
+				free_attempts: 3,
+				base_delay_sec: 60,
+				max_delay_sec: 600,
+			},
+			lockout_threshold: 4,
+			lockout_sec: 1800,
+			reset_window_sec: 3600,
+		}
+	}
+
+	#[test]
+	fn test_backoff_delay_exponential() {
+		let policy = fx_policy().account;
+
+		assert_eq!(policy.delay_sec(2), None);
+		assert_eq!(policy.delay_sec(3), Some(60));
+		assert_eq!(policy.delay_sec(4), Some(120));
+		assert_eq!(policy.delay_sec(5), Some(240));
+		assert_eq!(policy.delay_sec(100), Some(600));
+	}
+
+	#[tokio::test]
+	async fn test_account_backoff_then_lockout() -> Result<()> {
+		// -- Setup & Fixtures
+		let throttle = LoginThrottle::new(MemThrottleStore::default(), fx_policy());
+		let fx_username = "demo1";
+
+		// -- Exec & Check
+		// Free attempts.
+		for _ in 0..2 {
+			throttle.check(fx_username, None).await?;
+			throttle.record_failure(fx_username, None).await?;
+		}
+		throttle.check(fx_username, None).await?;
+
+		// Backoff.
+		let outcome = throttle.record_failure(fx_username, None).await?;
+		assert!(!outcome.account_locked);
+		let res = throttle.check(fx_username, None).await;
+		assert!(matches!(res, Err(super::Error::Throttled { .. })));
+
+		// Lockout (once).
+		let outcome = throttle.record_failure(fx_username, None).await?;
+		assert!(outcome.account_locked);
+		let res = throttle.check(fx_username, None).await;
+		assert!(matches!(
+			res,
+			Err(super::Error::AccountLocked { retry_after_sec }) if retry_after_sec > 1700
+		));
+
+		// Unlock.
+		throttle.unlock(fx_username).await?;
+		throttle.check(fx_username, None).await?;
+
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn test_ip_throttle_across_accounts() -> Result<()> {
+		// -- Setup & Fixtures
+		let throttle = LoginThrottle::new(MemThrottleStore::default(), fx_policy());
+		let fx_ip = Some("203.0.113.7");
+
+		// -- Exec
+		for i in 0..4 {
+			throttle.record_failure(&format!("user-{i}"), fx_ip).await?;
+		}
+
+		// -- Check
+		let res = throttle.check("other-user", fx_ip).await;
+		assert!(matches!(res, Err(super::Error::Throttled { .. })));
+		throttle.check("other-user", Some("203.0.113.8")).await?;
+
+		Ok(())
+	}
+}
+
+// endregion: --- Tests
@@ -0,0 +1,120 @@
+use crate::ctx::Ctx;
+use crate::model::login_throttle::{LoginThrottleBmc, LoginThrottleCounter};
+use crate::model::{ModelManager, Result};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+/// The storage of the failure counters.
+pub trait ThrottleStore: Send + Sync {
+	fn get(
+		&self,
+		key: &str,
+	) -> impl Future<Output = Result<Option<LoginThrottleCounter>>> + Send;
+
+	/// Increment the failures (restarting at 1 if the last failure is before `reset_before`),
+	/// and returns the new count.
+	fn incr_failures(
+		&self,
+		key: &str,
+		reset_before: DateTime<Utc>,
+	) -> impl Future<Output = Result<i32>> + Send;
+
+	fn set_blocked_until(
+		&self,
+		key: &str,
+		blocked_until: DateTime<Utc>,
+	) -> impl Future<Output = Result<()>> + Send;
+
+	fn reset(&self, key: &str) -> impl Future<Output = Result<()>> + Send;
+}
+
+// region:    --- PgThrottleStore
+
+/// Postgres store (`login_throttle` table), shared by all the server instances.
+#[derive(Clone)]
+pub struct PgThrottleStore {
+	mm: ModelManager,
+}
+
+impl PgThrottleStore {
+	pub fn new(mm: ModelManager) -> Self {
+		PgThrottleStore { mm }
+	}
+}
+
+impl ThrottleStore for PgThrottleStore {
+	async fn get(&self, key: &str) -> Result<Option<LoginThrottleCounter>> {
+		LoginThrottleBmc::get(&Ctx::root_ctx(), &self.mm, key).await
+	}
+
+	async fn incr_failures(&self, key: &str, reset_before: DateTime<Utc>) -> Result<i32> {
+		LoginThrottleBmc::incr_failures(&Ctx::root_ctx(), &self.mm, key, reset_before).await
+	}
+
+	async fn set_blocked_until(&self, key: &str, blocked_until: DateTime<Utc>) -> Result<()> {
+		LoginThrottleBmc::set_blocked_until(&Ctx::root_ctx(), &self.mm, key, blocked_until)
+			.await
+	}
+
+	async fn reset(&self, key: &str) -> Result<()> {
+		LoginThrottleBmc::reset(&Ctx::root_ctx(), &self.mm, key).await
+	}
+}
+
+// endregion: --- PgThrottleStore
+
+// region:    --- MemThrottleStore
+
+/// In-memory store, local to the process (for tests).
+#[derive(Clone, Default)]
+pub struct MemThrottleStore {
+	counters: Arc<Mutex<HashMap<String, LoginThrottleCounter>>>,
+}
+
+impl MemThrottleStore {
+	fn counters(&self) -> std::sync::MutexGuard<'_, HashMap<String, LoginThrottleCounter>> {
+		self.counters.lock().unwrap_or_else(|err| err.into_inner())
+	}
+}
+
+impl ThrottleStore for MemThrottleStore {
+	async fn get(&self, key: &str) -> Result<Option<LoginThrottleCounter>> {
+		Ok(self.counters().get(key).cloned())
+	}
+
+	async fn incr_failures(&self, key: &str, reset_before: DateTime<Utc>) -> Result<i32> {
+		let now = Utc::now();
+		let mut counters = self.counters();
+		let counter = counters
+			.entry(key.to_string())
+			.or_insert(LoginThrottleCounter {
+				failures: 0,
+				last_failure_at: now,
+				blocked_until: None,
+			});
+
+		if counter.last_failure_at < reset_before {
+			counter.failures = 0;
+		}
+		counter.failures += 1;
+		counter.last_failure_at = now;
+
+		Ok(counter.failures)
+	}
+
+	async fn set_blocked_until(&self, key: &str, blocked_until: DateTime<Utc>) -> Result<()> {
+		if let Some(counter) = self.counters().get_mut(key) {
+			counter.blocked_until = Some(blocked_until);
+		}
+		Ok(())
+	}
+
+	async fn reset(&self, key: &str) -> Result<()> {
+		self.counters().remove(key);
+		Ok(())
+	}
+}
+
+// endregion: --- MemThrottleStore
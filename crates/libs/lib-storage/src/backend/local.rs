@@ -0,0 +1,254 @@
+use super::{check_key, PresignRequest, PresignedUpload, StorageBackend, PRIVATE_PREFIX};
+use crate::{Error, Result};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tokio::io::{self, AsyncReadExt};
+use tracing::info;
+
+/// The local filesystem storage, the objects as files in the dir (by key), and the
+/// private ones (see `PRIVATE_PREFIX`) in the private dir (by key, without the prefix).
+/// Note: The web server serves the dir at `ROUTE_PATH`, for their public urls, so the
+///       private dir must not be in it.
+#[derive(Clone)]
+pub struct LocalStorage {
+    dir: PathBuf,
+    private_dir: PathBuf,
+    public_base: String,
+}
+
+impl LocalStorage {
+    /// The path of the objects in the web server.
+    pub const ROUTE_PATH: &'static str = "/storage";
+
+    /// `public_base` is the base url of the web server (e.g., `http://localhost:8080`).
+    pub fn new(
+        dir: impl Into<PathBuf>,
+        private_dir: impl Into<PathBuf>,
+        public_base: impl Into<String>,
+    ) -> Self {
+        Self {
+            dir: dir.into(),
+            private_dir: private_dir.into(),
+            public_base: public_base.into(),
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Append the files of the parts to the file of the path (created).
+    async fn append_parts(&self, path: &Path, parts: &[String]) -> Result<()> {
+        let mut file = fs::File::create(path).await.map_err(io_error)?;
+        for part in parts {
+            let part_path = self.path(part)?;
+            let mut part_file = fs::File::open(part_path).await.map_err(|ex| match ex.kind() {
+                ErrorKind::NotFound => Error::ObjectNotFound(part.to_string()),
+                _ => io_error(ex),
+            })?;
+            io::copy(&mut part_file, &mut file).await.map_err(io_error)?;
+        }
+        file.sync_all().await.map_err(io_error)?;
+
+        Ok(())
+    }
+
+    fn path(&self, key: &str) -> Result<PathBuf> {
+        check_key(key)?;
+        match key.strip_prefix(PRIVATE_PREFIX) {
+            Some(private_key) => Ok(self.private_dir.join(private_key)),
+            None => Ok(self.dir.join(key)),
+        }
+    }
+}
+
+impl StorageBackend for LocalStorage {
+    async fn upload(&self, key: &str, data: &[u8]) -> Result<String> {
+        info!("{:<12} - Uploading file: {}", "LOCAL", key);
+        let path = self.path(key)?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await.map_err(io_error)?;
+        }
+        // Note: Written aside then renamed, so a reader never gets a partial file.
+        let tmp_path = path.with_file_name(format!(".{}.tmp", uuid::Uuid::new_v4()));
+        fs::write(&tmp_path, data).await.map_err(io_error)?;
+        fs::rename(&tmp_path, &path).await.map_err(io_error)?;
+
+        Ok(self.public_url(key))
+    }
+
+    async fn download(&self, key: &str) -> Result<Vec<u8>> {
+        info!("{:<12} - Downloading file: {}", "LOCAL", key);
+
+        fs::read(self.path(key)?).await.map_err(|ex| match ex.kind() {
+            ErrorKind::NotFound => Error::ObjectNotFound(key.to_string()),
+            _ => io_error(ex),
+        })
+    }
+
+    async fn download_prefix(&self, key: &str, len: usize) -> Result<Vec<u8>> {
+        let file = fs::File::open(self.path(key)?).await.map_err(|ex| match ex.kind() {
+            ErrorKind::NotFound => Error::ObjectNotFound(key.to_string()),
+            _ => io_error(ex),
+        })?;
+
+        let mut data = Vec::with_capacity(len);
+        file.take(len as u64).read_to_end(&mut data).await.map_err(io_error)?;
+
+        Ok(data)
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        info!("{:<12} - Deleting file: {}", "LOCAL", key);
+
+        match fs::remove_file(self.path(key)?).await {
+            Ok(()) => Ok(()),
+            Err(ex) if ex.kind() == ErrorKind::NotFound => Ok(()),
+            Err(ex) => Err(io_error(ex)),
+        }
+    }
+
+    async fn exists(&self, key: &str) -> Result<bool> {
+        fs::try_exists(self.path(key)?).await.map_err(io_error)
+    }
+
+    async fn size(&self, key: &str) -> Result<Option<u64>> {
+        match fs::metadata(self.path(key)?).await {
+            Ok(meta) => Ok(Some(meta.len())),
+            Err(ex) if ex.kind() == ErrorKind::NotFound => Ok(None),
+            Err(ex) => Err(io_error(ex)),
+        }
+    }
+
+    async fn assemble(&self, key: &str, parts: &[String]) -> Result<String> {
+        info!("{:<12} - Assembling file: {} ({} parts)", "LOCAL", key, parts.len());
+        let path = self.path(key)?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await.map_err(io_error)?;
+        }
+        // Note: Appended aside then renamed, as `upload`.
+        let tmp_path = path.with_file_name(format!(".{}.tmp", uuid::Uuid::new_v4()));
+        if let Err(err) = self.append_parts(&tmp_path, parts).await {
+            let _ = fs::remove_file(&tmp_path).await;
+            return Err(err);
+        }
+        fs::rename(&tmp_path, &path).await.map_err(io_error)?;
+
+        Ok(self.public_url(key))
+    }
+
+    fn public_url(&self, key: &str) -> String {
+        format!(
+            "{}{}/{}",
+            self.public_base.trim_end_matches('/'),
+            Self::ROUTE_PATH,
+            key.trim_start_matches('/')
+        )
+    }
+
+    /// Note: The web server only serves the objects (no upload), so the clients upload
+    ///       through the API.
+    fn presign_upload(&self, key: &str, _request: &PresignRequest) -> Result<PresignedUpload> {
+        Err(Error::PresignUnsupported(format!("local storage, for '{key}'")))
+    }
+}
+
+fn io_error(ex: std::io::Error) -> Error {
+    Error::Io(ex.to_string())
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    #[tokio::test]
+    async fn test_upload_download_delete_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let dir = std::env::temp_dir().join(format!("lib-storage-{}", uuid::Uuid::new_v4()));
+        let storage =
+            LocalStorage::new(dir.join("public"), dir.join("private"), "http://localhost:8080/");
+        let fx_key = "posts/12/cover.jpg";
+        let fx_data = b"test_upload_download_delete_ok";
+
+        // -- Exec
+        let url = storage.upload(fx_key, fx_data).await?;
+
+        // -- Check
+        assert_eq!(url, "http://localhost:8080/storage/posts/12/cover.jpg");
+        assert!(storage.dir().join(fx_key).exists());
+        assert!(storage.exists(fx_key).await?);
+        assert_eq!(storage.size(fx_key).await?, Some(fx_data.len() as u64));
+        assert_eq!(storage.download(fx_key).await?, fx_data);
+
+        // -- Exec & Check (delete)
+        storage.delete(fx_key).await?;
+        assert!(!storage.exists(fx_key).await?);
+        assert_eq!(storage.size(fx_key).await?, None);
+        assert!(matches!(
+            storage.download(fx_key).await,
+            Err(Error::ObjectNotFound(_))
+        ));
+        storage.delete(fx_key).await?;
+
+        // -- Check (private, not in the served dir)
+        let fx_private_key = format!("{PRIVATE_PREFIX}uploads/12/chunk");
+        storage.upload(&fx_private_key, fx_data).await?;
+        assert_eq!(std::fs::read(dir.join("private/uploads/12/chunk"))?, fx_data);
+        assert!(!storage.dir().join(&fx_private_key).exists());
+        assert_eq!(storage.download(&fx_private_key).await?, fx_data);
+
+        // -- Check (outside of the dir)
+        assert!(matches!(
+            storage.upload("../escape.txt", fx_data).await,
+            Err(Error::KeyInvalid(_))
+        ));
+
+        // -- Clean
+        std::fs::remove_dir_all(&dir)?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_assemble_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let dir = std::env::temp_dir().join(format!("lib-storage-{}", uuid::Uuid::new_v4()));
+        let storage =
+            LocalStorage::new(dir.join("public"), dir.join("private"), "http://localhost:8080/");
+        let fx_key = "posts/12/video.mp4";
+        let fx_parts = [
+            format!("{PRIVATE_PREFIX}uploads/12/0"),
+            format!("{PRIVATE_PREFIX}uploads/12/1"),
+        ];
+        storage.upload(&fx_parts[0], b"test_assemble").await?;
+        storage.upload(&fx_parts[1], b"_ok").await?;
+
+        // -- Exec
+        let url = storage.assemble(fx_key, &fx_parts).await?;
+
+        // -- Check
+        assert_eq!(url, "http://localhost:8080/storage/posts/12/video.mp4");
+        assert_eq!(storage.download(fx_key).await?, b"test_assemble_ok");
+        assert!(storage.exists(&fx_parts[0]).await?, "the parts should be kept");
+
+        // -- Check (a missing part, no file)
+        let res = storage
+            .assemble("posts/12/other.mp4", &[format!("{PRIVATE_PREFIX}uploads/12/none")])
+            .await;
+        assert!(matches!(res, Err(Error::ObjectNotFound(_))));
+        assert_eq!(std::fs::read_dir(dir.join("public/posts/12"))?.count(), 1);
+
+        // -- Clean
+        std::fs::remove_dir_all(&dir)?;
+
+        Ok(())
+    }
+}
+
+// endregion: --- Tests
@@ -0,0 +1,99 @@
+use super::{check_key, check_presign, PresignMethod, PresignRequest, PresignedUpload, StorageBackend};
+use crate::{Error, Result};
+use chrono::Utc;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// The in-memory storage, local to the process (for tests).
+#[derive(Clone, Default)]
+pub struct MemStorage {
+    objects: Arc<Mutex<HashMap<String, Vec<u8>>>>,
+}
+
+impl MemStorage {
+    /// The scheme of the public urls (not served).
+    pub const URL_SCHEME: &'static str = "mem://";
+
+    fn objects(&self) -> MutexGuard<'_, HashMap<String, Vec<u8>>> {
+        self.objects.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl StorageBackend for MemStorage {
+    async fn upload(&self, key: &str, data: &[u8]) -> Result<String> {
+        check_key(key)?;
+        self.objects().insert(key.to_string(), data.to_vec());
+
+        Ok(self.public_url(key))
+    }
+
+    async fn download(&self, key: &str) -> Result<Vec<u8>> {
+        check_key(key)?;
+        self.objects()
+            .get(key)
+            .cloned()
+            .ok_or_else(|| Error::ObjectNotFound(key.to_string()))
+    }
+
+    async fn download_prefix(&self, key: &str, len: usize) -> Result<Vec<u8>> {
+        let mut data = self.download(key).await?;
+        data.truncate(len);
+        Ok(data)
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        check_key(key)?;
+        self.objects().remove(key);
+        Ok(())
+    }
+
+    async fn exists(&self, key: &str) -> Result<bool> {
+        check_key(key)?;
+        Ok(self.objects().contains_key(key))
+    }
+
+    async fn size(&self, key: &str) -> Result<Option<u64>> {
+        check_key(key)?;
+        Ok(self.objects().get(key).map(|data| data.len() as u64))
+    }
+
+    /// Note: In memory, as all the objects.
+    async fn assemble(&self, key: &str, parts: &[String]) -> Result<String> {
+        let mut data = Vec::new();
+        for part in parts {
+            data.extend(self.download(part).await?);
+        }
+
+        self.upload(key, &data).await
+    }
+
+    fn public_url(&self, key: &str) -> String {
+        format!("{}{}", Self::URL_SCHEME, key.trim_start_matches('/'))
+    }
+
+    /// Note: Not served, the tests upload the object with `upload`.
+    fn presign_upload(&self, key: &str, request: &PresignRequest) -> Result<PresignedUpload> {
+        check_presign(key, request)?;
+
+        let mut headers = BTreeMap::new();
+        let mut fields = BTreeMap::new();
+        match request.method {
+            PresignMethod::Put => {
+                headers.insert("content-length".to_string(), request.size.to_string());
+                headers.insert("content-type".to_string(), request.content_type.clone());
+            }
+            PresignMethod::Post => {
+                fields.insert("key".to_string(), key.to_string());
+                fields.insert("Content-Type".to_string(), request.content_type.clone());
+            }
+        }
+
+        Ok(PresignedUpload {
+            method: request.method,
+            url: self.public_url(key),
+            headers,
+            fields,
+            expires_at: Utc::now() + request.expires_in,
+        })
+    }
+}
@@ -0,0 +1,256 @@
+//! The storage backends of the media objects, by key (e.g., `posts/12/cover.jpg`).
+//!
+//! - `OssClient`: Aliyun OSS (public read objects).
+//! - `S3Client`: S3 compatible object store.
+//! - `LocalStorage`: local filesystem, served by the web server, for the offline dev.
+//! - `MemStorage`: in memory, for the tests.
+//!
+//! `Storage` is the one selected by the config (see `storage_config`).
+//!
+//! The objects are public, but the ones under `PRIVATE_PREFIX` (e.g., the staged chunks
+//! of the resumable uploads).
+
+// region:    --- Modules
+
+mod local;
+mod memory;
+mod presign;
+
+pub use self::local::LocalStorage;
+pub use self::memory::MemStorage;
+pub use self::presign::{PresignMethod, PresignRequest, PresignedUpload};
+
+pub(crate) use self::presign::check_presign;
+
+use crate::config::{storage_config, StorageConfig};
+use crate::oss::OssClient;
+use crate::s3::S3Client;
+use crate::{Error, Result};
+use std::future::Future;
+
+// endregion: --- Modules
+
+/// The prefix of the private object keys: stored without the public ACL, and not served
+/// (see `LocalStorage`).
+/// Note: For a bucket public by its policy (see `S3_PUBLIC_ACL`), the policy must exclude it.
+pub const PRIVATE_PREFIX: &str = "private/";
+
+/// The operations on the objects of a storage.
+pub trait StorageBackend: Send + Sync {
+    /// Store the object (replacing any), and returns its public url.
+    fn upload(&self, key: &str, data: &[u8]) -> impl Future<Output = Result<String>> + Send;
+
+    fn download(&self, key: &str) -> impl Future<Output = Result<Vec<u8>>> + Send;
+
+    /// The first `len` bytes of the object (all if smaller), e.g., to sniff its type.
+    fn download_prefix(
+        &self,
+        key: &str,
+        len: usize,
+    ) -> impl Future<Output = Result<Vec<u8>>> + Send;
+
+    /// Delete the object. Ok if there is none.
+    fn delete(&self, key: &str) -> impl Future<Output = Result<()>> + Send;
+
+    fn exists(&self, key: &str) -> impl Future<Output = Result<bool>> + Send;
+
+    /// The size of the object in bytes, None if there is none.
+    fn size(&self, key: &str) -> impl Future<Output = Result<Option<u64>>> + Send;
+
+    /// Store the object (replacing any) as the concatenation of the `parts` objects, in
+    /// order, without loading it whole in memory (the parts are kept). Returns its public url.
+    fn assemble(&self, key: &str, parts: &[String]) -> impl Future<Output = Result<String>> + Send;
+
+    fn public_url(&self, key: &str) -> String;
+
+    /// Presign an upload of the object, for the client to upload it directly to the
+    /// storage (not through the web server).
+    fn presign_upload(&self, key: &str, request: &PresignRequest) -> Result<PresignedUpload>;
+}
+
+// region:    --- Storage
+
+/// The storage backend selected by the config.
+#[derive(Clone)]
+pub enum Storage {
+    Oss(OssClient),
+    S3(S3Client),
+    Local(LocalStorage),
+    Memory(MemStorage),
+}
+
+impl Storage {
+    pub fn from_config() -> Result<Self> {
+        let storage = match storage_config()? {
+            StorageConfig::Oss => Storage::Oss(OssClient::new()?),
+            StorageConfig::S3 => Storage::S3(S3Client::new()?),
+            StorageConfig::Local {
+                dir,
+                private_dir,
+                public_base,
+            } => Storage::Local(LocalStorage::new(dir, private_dir, public_base)),
+            StorageConfig::Memory => Storage::Memory(MemStorage::default()),
+        };
+
+        Ok(storage)
+    }
+
+    /// The key of a public url of this storage (e.g., a stored media url), None if not one.
+    /// Note: The keys of the app (e.g., `posts/12/{uuid}/w320.webp`) need no url encoding.
+    pub fn key_from_url(&self, url: &str) -> Option<String> {
+        url.strip_prefix(&self.public_url(""))
+            .filter(|key| check_key(key).is_ok())
+            .map(str::to_string)
+    }
+}
+
+impl StorageBackend for Storage {
+    async fn upload(&self, key: &str, data: &[u8]) -> Result<String> {
+        match self {
+            Storage::Oss(oss) => oss.upload(key, data).await,
+            Storage::S3(s3) => s3.upload(key, data).await,
+            Storage::Local(local) => local.upload(key, data).await,
+            Storage::Memory(mem) => mem.upload(key, data).await,
+        }
+    }
+
+    async fn download(&self, key: &str) -> Result<Vec<u8>> {
+        match self {
+            Storage::Oss(oss) => oss.download(key).await,
+            Storage::S3(s3) => s3.download(key).await,
+            Storage::Local(local) => local.download(key).await,
+            Storage::Memory(mem) => mem.download(key).await,
+        }
+    }
+
+    async fn download_prefix(&self, key: &str, len: usize) -> Result<Vec<u8>> {
+        match self {
+            Storage::Oss(oss) => oss.download_prefix(key, len).await,
+            Storage::S3(s3) => s3.download_prefix(key, len).await,
+            Storage::Local(local) => local.download_prefix(key, len).await,
+            Storage::Memory(mem) => mem.download_prefix(key, len).await,
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        match self {
+            Storage::Oss(oss) => oss.delete(key).await,
+            Storage::S3(s3) => s3.delete(key).await,
+            Storage::Local(local) => local.delete(key).await,
+            Storage::Memory(mem) => mem.delete(key).await,
+        }
+    }
+
+    async fn exists(&self, key: &str) -> Result<bool> {
+        match self {
+            Storage::Oss(oss) => oss.exists(key).await,
+            Storage::S3(s3) => s3.exists(key).await,
+            Storage::Local(local) => local.exists(key).await,
+            Storage::Memory(mem) => mem.exists(key).await,
+        }
+    }
+
+    async fn size(&self, key: &str) -> Result<Option<u64>> {
+        match self {
+            Storage::Oss(oss) => oss.size(key).await,
+            Storage::S3(s3) => s3.size(key).await,
+            Storage::Local(local) => local.size(key).await,
+            Storage::Memory(mem) => mem.size(key).await,
+        }
+    }
+
+    async fn assemble(&self, key: &str, parts: &[String]) -> Result<String> {
+        match self {
+            Storage::Oss(oss) => oss.assemble(key, parts).await,
+            Storage::S3(s3) => s3.assemble(key, parts).await,
+            Storage::Local(local) => local.assemble(key, parts).await,
+            Storage::Memory(mem) => mem.assemble(key, parts).await,
+        }
+    }
+
+    fn public_url(&self, key: &str) -> String {
+        match self {
+            Storage::Oss(oss) => oss.public_url(key),
+            Storage::S3(s3) => s3.public_url(key),
+            Storage::Local(local) => local.public_url(key),
+            Storage::Memory(mem) => mem.public_url(key),
+        }
+    }
+
+    fn presign_upload(&self, key: &str, request: &PresignRequest) -> Result<PresignedUpload> {
+        match self {
+            Storage::Oss(oss) => oss.presign_upload(key, request),
+            Storage::S3(s3) => s3.presign_upload(key, request),
+            Storage::Local(local) => local.presign_upload(key, request),
+            Storage::Memory(mem) => mem.presign_upload(key, request),
+        }
+    }
+}
+
+// endregion: --- Storage
+
+/// Check the key is a relative path of normal segments (no `..`, so no path traversal
+/// on the local filesystem).
+pub(crate) fn check_key(key: &str) -> Result<()> {
+    let valid = !key.is_empty()
+        && !key.contains(['\\', '\0'])
+        && key
+            .split('/')
+            .all(|segment| !segment.is_empty() && segment != "." && segment != "..");
+
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::KeyInvalid(key.to_string()))
+    }
+}
+
+/// True if the key is of a private object (see `PRIVATE_PREFIX`).
+pub(crate) fn is_private(key: &str) -> bool {
+    key.starts_with(PRIVATE_PREFIX)
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_from_url() {
+        let storage = Storage::Memory(MemStorage::default());
+        let url = storage.public_url("posts/12/cover.jpg");
+
+        assert_eq!(storage.key_from_url(&url).as_deref(), Some("posts/12/cover.jpg"));
+        assert_eq!(storage.key_from_url("https://cdn.example.com/posts/12/cover.jpg"), None);
+        assert_eq!(storage.key_from_url("mem://posts/../secret"), None);
+    }
+
+    #[test]
+    fn test_check_key() {
+        assert!(check_key("posts/12/cover.jpg").is_ok());
+        assert!(check_key("cover.v2.jpg").is_ok());
+
+        for key in ["", "/etc/passwd", "posts/../../secret", "posts//a.jpg", "./a.jpg", "a\\b.jpg"] {
+            assert!(matches!(check_key(key), Err(Error::KeyInvalid(_))), "{key}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mem_storage_err_key_invalid() -> Result<()> {
+        let storage = MemStorage::default();
+        storage.upload("posts/12/cover.jpg", b"cover").await?;
+
+        let key = "posts/12/../12/cover.jpg";
+        assert!(matches!(storage.download(key).await, Err(Error::KeyInvalid(_))));
+        assert!(matches!(storage.download_prefix(key, 2).await, Err(Error::KeyInvalid(_))));
+        assert!(matches!(storage.exists(key).await, Err(Error::KeyInvalid(_))));
+        assert!(matches!(storage.size(key).await, Err(Error::KeyInvalid(_))));
+        assert!(matches!(storage.delete(key).await, Err(Error::KeyInvalid(_))));
+        assert!(storage.exists("posts/12/cover.jpg").await?);
+
+        Ok(())
+    }
+}
+
+// endregion: --- Tests
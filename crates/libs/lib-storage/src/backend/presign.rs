@@ -0,0 +1,71 @@
+use super::{check_key, is_private};
+use crate::{Error, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// The max validity of the presigned uploads (as S3, 7 days).
+const EXPIRES_IN_MAX: Duration = Duration::from_secs(7 * 24 * 3600);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum PresignMethod {
+    /// The object as body, with the given headers.
+    Put,
+    /// A multipart form with the given fields, then the object as `file` (e.g., from a browser).
+    Post,
+}
+
+/// The constraints of a presigned upload, enforced by the storage.
+#[derive(Debug, Clone)]
+pub struct PresignRequest {
+    pub method: PresignMethod,
+    pub content_type: String,
+    /// The size of the object, in bytes: exact for PUT (the signed `content-length`), max for POST.
+    pub size: u64,
+    pub expires_in: Duration,
+}
+
+/// What a client needs to upload an object directly to the storage.
+#[derive(Debug, Clone, Serialize)]
+pub struct PresignedUpload {
+    pub method: PresignMethod,
+    pub url: String,
+    /// The headers to send, as is (PUT).
+    pub headers: BTreeMap<String, String>,
+    /// The form fields to send, before the `file` one (POST).
+    pub fields: BTreeMap<String, String>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Check the key, and the constraints of the presigned upload.
+pub(crate) fn check_presign(key: &str, request: &PresignRequest) -> Result<()> {
+    check_key(key)?;
+
+    if is_private(key) {
+        return Err(Error::PresignInvalid(format!("key '{key}' is private")));
+    }
+    if request.size == 0 {
+        return Err(Error::PresignInvalid("size must be over 0".to_string()));
+    }
+    if request.expires_in.is_zero() || request.expires_in > EXPIRES_IN_MAX {
+        return Err(Error::PresignInvalid(format!(
+            "expires_in must be between 1 sec and {} sec",
+            EXPIRES_IN_MAX.as_secs()
+        )));
+    }
+    let content_type_valid = !request.content_type.is_empty()
+        && request
+            .content_type
+            .bytes()
+            .all(|byte| byte.is_ascii_graphic() || byte == b' ');
+    if !content_type_valid {
+        return Err(Error::PresignInvalid(format!(
+            "content_type '{}' invalid",
+            request.content_type
+        )));
+    }
+
+    Ok(())
+}
@@ -1,14 +1,19 @@
 use std::sync::OnceLock;
-use lib_utils::envs::get_env;
+use crate::media::VariantSpec;
+use crate::{Error, Result};
+use lib_utils::envs::{get_env, get_env_parse};
 
-pub fn oss_config() -> &'static OssConfig {
+/// Note: Fails (not cached) if the envs are missing or invalid, e.g., for the backend
+///       not selected by `STORAGE_BACKEND`.
+pub fn oss_config() -> Result<&'static OssConfig> {
     static INSTANCE: OnceLock<OssConfig> = OnceLock::new();
 
-    INSTANCE.get_or_init(|| {
-        OssConfig::load_from_env().unwrap_or_else(|ex| {
-            panic!("FATAL - WHILE LOADING CONF - Cause: {ex:?}")
-        })
-    })
+    if let Some(config) = INSTANCE.get() {
+        return Ok(config);
+    }
+    let config = OssConfig::load_from_env().map_err(|ex| Error::ConfigInvalid(ex.to_string()))?;
+
+    Ok(INSTANCE.get_or_init(|| config))
 }
 
 #[allow(non_snake_case)]
@@ -33,4 +38,145 @@ impl OssConfig {
             OSS_PUBLIC_BASE: get_env("OSS_PUBLIC_BASE")?,
         })
     }
-}
\ No newline at end of file
+}
+/// Note: Fails (not cached) if the envs are missing or invalid, e.g., for the backend
+///       not selected by `STORAGE_BACKEND`.
+pub fn s3_config() -> Result<&'static S3Config> {
+    static INSTANCE: OnceLock<S3Config> = OnceLock::new();
+
+    if let Some(config) = INSTANCE.get() {
+        return Ok(config);
+    }
+    let config = S3Config::load_from_env().map_err(|ex| Error::ConfigInvalid(ex.to_string()))?;
+
+    Ok(INSTANCE.get_or_init(|| config))
+}
+
+#[allow(non_snake_case)]
+#[derive(Clone)]
+pub struct S3Config {
+    pub S3_ACCESS_KEY_ID: String,
+    pub S3_SECRET_ACCESS_KEY: String,
+    pub S3_BUCKET_NAME: String,
+    /// e.g., `https://s3.eu-west-1.amazonaws.com`, or `http://127.0.0.1:9000` (local stand-in).
+    pub S3_ENDPOINT: String,
+    pub S3_REGION: String,
+    /// The path-style urls (`{endpoint}/{bucket}/{key}`), rather than the virtual-host
+    /// ones (`{bucket}.{endpoint host}/{key}`). Most of the S3 compatible stores need it.
+    pub S3_PATH_STYLE: bool,
+    /// Upload the objects with the public-read ACL. Otherwise, they must be public by the
+    /// bucket policy (e.g., when the bucket has the ACLs disabled).
+    pub S3_PUBLIC_ACL: bool,
+    /// The base of the public urls (e.g., a CDN), the object urls if not set.
+    pub S3_PUBLIC_BASE: Option<String>,
+}
+
+impl S3Config {
+    fn load_from_env() -> lib_utils::envs::Result<S3Config> {
+        Ok(S3Config {
+            S3_ACCESS_KEY_ID: get_env("S3_ACCESS_KEY_ID")?,
+            S3_SECRET_ACCESS_KEY: get_env("S3_SECRET_ACCESS_KEY")?,
+            S3_BUCKET_NAME: get_env("S3_BUCKET_NAME")?,
+            S3_ENDPOINT: get_env("S3_ENDPOINT")?,
+            S3_REGION: get_env("S3_REGION")?,
+            S3_PATH_STYLE: get_env_parse("S3_PATH_STYLE")?,
+            S3_PUBLIC_ACL: get_env_parse("S3_PUBLIC_ACL")?,
+            S3_PUBLIC_BASE: get_env("S3_PUBLIC_BASE").ok(),
+        })
+    }
+}
+
+pub fn media_config() -> &'static MediaConfig {
+    static INSTANCE: OnceLock<MediaConfig> = OnceLock::new();
+
+    INSTANCE.get_or_init(|| {
+        MediaConfig::load_from_env().unwrap_or_else(|ex| {
+            panic!("FATAL - WHILE LOADING CONF - Cause: {ex:?}")
+        })
+    })
+}
+
+#[allow(non_snake_case)]
+#[derive(Clone)]
+pub struct MediaConfig {
+    /// The image variants, from `MEDIA_VARIANTS` as `name:max_size` by comma
+    /// (e.g., "thumbnail:320,feed:1080,full:2048"), the max size of the longest side.
+    pub MEDIA_VARIANTS: Vec<VariantSpec>,
+    /// The quality of the JPEG fallbacks and of the (lossy) WebP variants (1 to 100).
+    pub MEDIA_JPEG_QUALITY: u8,
+}
+
+impl MediaConfig {
+    const DEFAULT_VARIANTS: &'static str = "thumbnail:320,feed:1080,full:2048";
+    const DEFAULT_JPEG_QUALITY: u8 = 82;
+
+    fn load_from_env() -> lib_utils::envs::Result<MediaConfig> {
+        let variants =
+            get_env("MEDIA_VARIANTS").unwrap_or_else(|_| Self::DEFAULT_VARIANTS.to_string());
+        let jpeg_quality = match get_env("MEDIA_JPEG_QUALITY") {
+            Ok(_) => get_env_parse::<u8>("MEDIA_JPEG_QUALITY")?,
+            Err(_) => Self::DEFAULT_JPEG_QUALITY,
+        };
+        if !(1..=100).contains(&jpeg_quality) {
+            return Err(lib_utils::envs::Error::WrongFormat("MEDIA_JPEG_QUALITY"));
+        }
+
+        Ok(MediaConfig {
+            MEDIA_VARIANTS: VariantSpec::parse_list(&variants)
+                .ok_or(lib_utils::envs::Error::WrongFormat("MEDIA_VARIANTS"))?,
+            MEDIA_JPEG_QUALITY: jpeg_quality,
+        })
+    }
+}
+
+/// Note: Fails (not cached) if the envs are missing or invalid, e.g., for the backend
+///       not selected by `STORAGE_BACKEND`.
+pub fn storage_config() -> Result<&'static StorageConfig> {
+    static INSTANCE: OnceLock<StorageConfig> = OnceLock::new();
+
+    if let Some(config) = INSTANCE.get() {
+        return Ok(config);
+    }
+    let config = StorageConfig::load_from_env().map_err(|ex| Error::ConfigInvalid(ex.to_string()))?;
+
+    Ok(INSTANCE.get_or_init(|| config))
+}
+
+/// The storage backend, selected by `STORAGE_BACKEND` ("oss", "s3", "local", or "memory"),
+/// "oss" by default.
+#[derive(Clone)]
+pub enum StorageConfig {
+    /// Aliyun OSS (see `oss_config`).
+    Oss,
+    /// S3 compatible object store (see `s3_config`).
+    S3,
+    /// The local filesystem, for the offline dev. The objects are in `STORAGE_LOCAL_DIR`,
+    /// served by the web server at `STORAGE_LOCAL_PUBLIC_BASE` (e.g., `http://localhost:8080`),
+    /// but the private ones, in `STORAGE_LOCAL_PRIVATE_DIR` (not in the served dir).
+    Local {
+        dir: String,
+        private_dir: String,
+        public_base: String,
+    },
+    /// In memory, lost on restart (for tests).
+    Memory,
+}
+
+impl StorageConfig {
+    fn load_from_env() -> lib_utils::envs::Result<StorageConfig> {
+        let backend = get_env("STORAGE_BACKEND").unwrap_or_else(|_| "oss".to_string());
+        let config = match backend.as_str() {
+            "oss" => StorageConfig::Oss,
+            "s3" => StorageConfig::S3,
+            "local" => StorageConfig::Local {
+                dir: get_env("STORAGE_LOCAL_DIR")?,
+                private_dir: get_env("STORAGE_LOCAL_PRIVATE_DIR")?,
+                public_base: get_env("STORAGE_LOCAL_PUBLIC_BASE")?,
+            },
+            "memory" => StorageConfig::Memory,
+            _ => return Err(lib_utils::envs::Error::WrongFormat("STORAGE_BACKEND")),
+        };
+
+        Ok(config)
+    }
+}
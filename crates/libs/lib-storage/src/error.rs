@@ -0,0 +1,41 @@
+use crate::{media, oss, s3};
+use derive_more::From;
+use serde::Serialize;
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[derive(Debug, Serialize, From)]
+pub enum Error {
+	/// The config of the backend, from the envs (e.g., a missing `OSS_BUCKET_NAME`).
+	ConfigInvalid(String),
+	/// The key is not a relative path of normal segments (e.g., `posts/12/cover.jpg`).
+	KeyInvalid(String),
+	ObjectNotFound(String),
+	Io(String),
+
+	// -- Presign
+	PresignInvalid(String),
+	/// The backend cannot presign this upload (e.g., the local filesystem).
+	PresignUnsupported(String),
+
+	// -- Modules
+	#[from]
+	Media(media::Error),
+	#[from]
+	Oss(oss::Error),
+	#[from]
+	S3(s3::Error),
+}
+
+// region:    --- Error Boilerplate
+impl core::fmt::Display for Error {
+	fn fmt(
+		&self,
+		fmt: &mut core::fmt::Formatter,
+	) -> core::result::Result<(), core::fmt::Error> {
+		write!(fmt, "{self:?}")
+	}
+}
+
+impl std::error::Error for Error {}
+// endregion: --- Error Boilerplate
@@ -1,2 +1,17 @@
+// region:    --- Modules
+
+mod backend;
+mod error;
+
 pub mod config;
-pub mod oss;
\ No newline at end of file
+pub mod media;
+pub mod oss;
+pub mod s3;
+
+pub use self::backend::{
+    LocalStorage, MemStorage, PresignMethod, PresignRequest, PresignedUpload, Storage,
+    StorageBackend, PRIVATE_PREFIX,
+};
+pub use self::error::{Error, Result};
+
+// endregion: --- Modules
@@ -1 +1,11 @@
-mod thumbnail;
\ No newline at end of file
+//! The processing of the uploaded media, before their storage (see `process_image`).
+
+// region:    --- Modules
+
+mod error;
+mod thumbnail;
+
+pub use self::error::{Error, Result};
+pub use self::thumbnail::{process_image, ImageVariant, ProcessedImage, VariantSpec};
+
+// endregion: --- Modules
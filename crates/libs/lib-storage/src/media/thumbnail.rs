@@ -0,0 +1,404 @@
+use super::{Error, Result};
+use crate::config::MediaConfig;
+use crate::StorageBackend;
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::PngEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageDecoder, ImageFormat, ImageReader, Limits};
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+use tracing::info;
+
+/// The max width or height of the decoded images (bounds the memory of a decoding).
+const MAX_DIMENSION: u32 = 16_384;
+
+// region:    --- Types
+
+/// A variant to produce (e.g., "thumbnail"), at most `max_size` on its longest side.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VariantSpec {
+    pub name: String,
+    pub max_size: u32,
+}
+
+impl VariantSpec {
+    pub fn new(name: impl Into<String>, max_size: u32) -> Self {
+        Self {
+            name: name.into(),
+            max_size,
+        }
+    }
+
+    /// Parse the `name:max_size` specs by comma (e.g., "thumbnail:320,full:2048").
+    /// Returns None if a spec is invalid, or if none.
+    pub fn parse_list(value: &str) -> Option<Vec<Self>> {
+        let specs = value
+            .split(',')
+            .map(|spec| {
+                let (name, max_size) = spec.trim().split_once(':')?;
+                let name = name.trim();
+                let max_size: u32 = max_size.trim().parse().ok()?;
+                let valid = !name.is_empty()
+                    && name.bytes().all(|byte| byte.is_ascii_alphanumeric() || byte == b'_')
+                    && max_size > 0;
+
+                valid.then(|| Self::new(name, max_size))
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        (!specs.is_empty()).then_some(specs)
+    }
+}
+
+/// A stored variant of an image, in WebP and in a fallback format (JPEG, or PNG if
+/// transparent) for the clients without WebP.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageVariant {
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    pub webp_url: String,
+    pub fallback_url: String,
+    pub fallback_mime_type: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProcessedImage {
+    /// The mime type of the source (e.g., "image/jpeg").
+    pub mime_type: String,
+    /// The dimensions of the source, once oriented.
+    pub width: u32,
+    pub height: u32,
+    /// In the order of the specs.
+    pub variants: Vec<ImageVariant>,
+}
+
+// endregion: --- Types
+
+/// Decode the image (JPEG, PNG, or WebP), orient it by its EXIF orientation, and store
+/// its variants (as `{key_prefix}/{name}.webp`, and `.jpg` or `.png`).
+/// The variants are never upscaled.
+///
+/// Note: The variants are encoded from the pixels, so without the metadata of the source
+///       (e.g., the EXIF GPS position).
+pub async fn process_image(
+    storage: &impl StorageBackend,
+    config: &MediaConfig,
+    key_prefix: &str,
+    data: &[u8],
+) -> crate::Result<ProcessedImage> {
+    let data = data.to_vec();
+    let specs = config.MEDIA_VARIANTS.clone();
+    let jpeg_quality = config.MEDIA_JPEG_QUALITY;
+
+    // -- Decode & encode (CPU bound, off the async runtime)
+    let encoded = tokio::task::spawn_blocking(move || encode_variants(&data, &specs, jpeg_quality))
+        .await
+        .map_err(|ex| Error::TaskFail(ex.to_string()))??;
+
+    // -- Store
+    let key_prefix = key_prefix.trim_end_matches('/');
+    let mut variants = Vec::with_capacity(encoded.variants.len());
+    for variant in encoded.variants {
+        info!("{:<12} - Storing variant: {}/{}", "MEDIA", key_prefix, variant.name);
+
+        let webp_key = format!("{key_prefix}/{}.webp", variant.name);
+        let webp_url = storage.upload(&webp_key, &variant.webp).await?;
+
+        let fallback_key = format!(
+            "{key_prefix}/{}.{}",
+            variant.name,
+            variant.fallback_format.extensions_str()[0]
+        );
+        let fallback_url = storage.upload(&fallback_key, &variant.fallback).await?;
+
+        variants.push(ImageVariant {
+            name: variant.name,
+            width: variant.width,
+            height: variant.height,
+            webp_url,
+            fallback_url,
+            fallback_mime_type: variant.fallback_format.to_mime_type().to_string(),
+        });
+    }
+
+    Ok(ProcessedImage {
+        mime_type: encoded.format.to_mime_type().to_string(),
+        width: encoded.width,
+        height: encoded.height,
+        variants,
+    })
+}
+
+// region:    --- Encoding
+
+struct EncodedImage {
+    format: ImageFormat,
+    width: u32,
+    height: u32,
+    variants: Vec<EncodedVariant>,
+}
+
+struct EncodedVariant {
+    name: String,
+    width: u32,
+    height: u32,
+    webp: Vec<u8>,
+    fallback: Vec<u8>,
+    fallback_format: ImageFormat,
+}
+
+fn encode_variants(data: &[u8], specs: &[VariantSpec], jpeg_quality: u8) -> Result<EncodedImage> {
+    let (format, image) = decode(data)?;
+
+    let variants = specs
+        .iter()
+        .map(|spec| {
+            let variant = resize_to_fit(&image, spec.max_size);
+            let (fallback, fallback_format) = encode_fallback(&variant, jpeg_quality)?;
+
+            Ok(EncodedVariant {
+                name: spec.name.clone(),
+                width: variant.width(),
+                height: variant.height(),
+                webp: encode_webp(&variant, jpeg_quality)?,
+                fallback,
+                fallback_format,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(EncodedImage {
+        format,
+        width: image.width(),
+        height: image.height(),
+        variants,
+    })
+}
+
+/// Decode the image, oriented, in 8 bits RGB (or RGBA if transparent).
+fn decode(data: &[u8]) -> Result<(ImageFormat, DynamicImage)> {
+    let mut reader = ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .map_err(|ex| Error::Decode(ex.to_string()))?;
+    let format = reader
+        .format()
+        .filter(|format| matches!(format, ImageFormat::Jpeg | ImageFormat::Png | ImageFormat::WebP))
+        .ok_or(Error::FormatUnsupported)?;
+
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_DIMENSION);
+    limits.max_image_height = Some(MAX_DIMENSION);
+    reader.limits(limits);
+
+    let mut decoder = reader.into_decoder().map_err(decode_error)?;
+    let orientation = decoder.orientation().map_err(decode_error)?;
+    let mut image = DynamicImage::from_decoder(decoder).map_err(decode_error)?;
+    image.apply_orientation(orientation);
+
+    let image = if image.color().has_alpha() {
+        DynamicImage::ImageRgba8(image.to_rgba8())
+    } else {
+        DynamicImage::ImageRgb8(image.to_rgb8())
+    };
+
+    Ok((format, image))
+}
+
+fn resize_to_fit(image: &DynamicImage, max_size: u32) -> DynamicImage {
+    if image.width() <= max_size && image.height() <= max_size {
+        image.clone()
+    } else {
+        image.resize(max_size, max_size, FilterType::Lanczos3)
+    }
+}
+
+/// Lossy, at the quality of the JPEG fallback (so smaller than the JPEG for the photos).
+/// Note: Through libwebp, as the `image` crate only encodes lossless WebP, which is
+///       several times the size of the JPEG for the photos.
+fn encode_webp(image: &DynamicImage, quality: u8) -> Result<Vec<u8>> {
+    let encoder = match image {
+        DynamicImage::ImageRgb8(rgb) => webp::Encoder::from_rgb(rgb, rgb.width(), rgb.height()),
+        DynamicImage::ImageRgba8(rgba) => webp::Encoder::from_rgba(rgba, rgba.width(), rgba.height()),
+        // Note: `decode` converts the images to 8 bits RGB or RGBA.
+        _ => return Err(Error::Encode("WebP: image not in 8 bits RGB(A)".to_string())),
+    };
+    let webp = encoder
+        .encode_simple(false, f32::from(quality))
+        .map_err(|ex| Error::Encode(format!("WebP: {ex:?}")))?;
+
+    Ok(webp.to_vec())
+}
+
+fn encode_fallback(image: &DynamicImage, jpeg_quality: u8) -> Result<(Vec<u8>, ImageFormat)> {
+    let mut buf = Vec::new();
+    let format = if image.color().has_alpha() {
+        image.write_with_encoder(PngEncoder::new(&mut buf)).map_err(encode_error)?;
+        ImageFormat::Png
+    } else {
+        image
+            .write_with_encoder(JpegEncoder::new_with_quality(&mut buf, jpeg_quality))
+            .map_err(encode_error)?;
+        ImageFormat::Jpeg
+    };
+
+    Ok((buf, format))
+}
+
+fn decode_error(ex: image::ImageError) -> Error {
+    Error::Decode(ex.to_string())
+}
+
+fn encode_error(ex: image::ImageError) -> Error {
+    Error::Encode(ex.to_string())
+}
+
+// endregion: --- Encoding
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemStorage;
+    use anyhow::Result;
+    use image::{Rgb, RgbImage, Rgba, RgbaImage};
+
+    fn fx_config() -> MediaConfig {
+        MediaConfig {
+            MEDIA_VARIANTS: vec![VariantSpec::new("thumbnail", 10), VariantSpec::new("full", 100)],
+            MEDIA_JPEG_QUALITY: 80,
+        }
+    }
+
+    /// A 40x20 JPEG with the EXIF orientation 6 (to rotate 90° clockwise).
+    fn fx_jpeg_rotated() -> Result<Vec<u8>> {
+        let mut jpeg = Vec::new();
+        DynamicImage::ImageRgb8(RgbImage::from_pixel(40, 20, Rgb([200, 80, 40])))
+            .write_with_encoder(JpegEncoder::new_with_quality(&mut jpeg, 90))?;
+
+        // APP1 Exif, big endian TIFF, one IFD entry: Orientation (0x0112), SHORT, 1, 6
+        let mut exif = b"Exif\0\0MM\0\x2a\0\0\0\x08\0\x01".to_vec();
+        exif.extend_from_slice(&[0x01, 0x12, 0, 3, 0, 0, 0, 1, 0, 6, 0, 0]);
+        exif.extend_from_slice(&[0, 0, 0, 0]);
+        let mut app1 = vec![0xFF, 0xE1];
+        app1.extend_from_slice(&((exif.len() + 2) as u16).to_be_bytes());
+        app1.extend_from_slice(&exif);
+
+        // Right after the SOI marker.
+        jpeg.splice(2..2, app1);
+
+        Ok(jpeg)
+    }
+
+    #[tokio::test]
+    async fn test_process_image_jpeg_oriented_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let storage = MemStorage::default();
+        let fx_data = fx_jpeg_rotated()?;
+
+        // -- Exec
+        let processed = process_image(&storage, &fx_config(), "posts/12/abc", &fx_data).await?;
+
+        // -- Check
+        assert_eq!(processed.mime_type, "image/jpeg");
+        assert_eq!((processed.width, processed.height), (20, 40));
+        let dims: Vec<_> = processed
+            .variants
+            .iter()
+            .map(|variant| (variant.name.as_str(), variant.width, variant.height))
+            .collect();
+        assert_eq!(dims, [("thumbnail", 5, 10), ("full", 20, 40)]);
+
+        let thumbnail = &processed.variants[0];
+        assert_eq!(thumbnail.webp_url, "mem://posts/12/abc/thumbnail.webp");
+        assert_eq!(thumbnail.fallback_url, "mem://posts/12/abc/thumbnail.jpg");
+        assert_eq!(thumbnail.fallback_mime_type, "image/jpeg");
+
+        let webp = storage.download("posts/12/abc/thumbnail.webp").await?;
+        let webp = image::load_from_memory_with_format(&webp, ImageFormat::WebP)?;
+        assert_eq!((webp.width(), webp.height()), (5, 10));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_process_image_ok_webp_smaller_than_jpeg() -> Result<()> {
+        // -- Setup & Fixtures
+        let storage = MemStorage::default();
+        // A photo like image (gradient with noise), where a lossless WebP is the largest.
+        let fx_image = RgbImage::from_fn(100, 80, |x, y| {
+            let noise = (x.wrapping_mul(7919) ^ y.wrapping_mul(104_729)) % 32;
+            Rgb([(x * 2 + noise) as u8, (y * 3 + noise) as u8, (128 + noise) as u8])
+        });
+        let mut fx_data = Vec::new();
+        DynamicImage::ImageRgb8(fx_image).write_with_encoder(PngEncoder::new(&mut fx_data))?;
+
+        // -- Exec
+        process_image(&storage, &fx_config(), "posts/12/jkl", &fx_data).await?;
+
+        // -- Check
+        let webp = storage.download("posts/12/jkl/full.webp").await?;
+        let jpeg = storage.download("posts/12/jkl/full.jpg").await?;
+        assert!(
+            webp.len() < jpeg.len(),
+            "webp {} bytes, jpeg {} bytes",
+            webp.len(),
+            jpeg.len()
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_process_image_png_transparent_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let storage = MemStorage::default();
+        let mut fx_data = Vec::new();
+        DynamicImage::ImageRgba8(RgbaImage::from_pixel(30, 30, Rgba([0, 120, 255, 128])))
+            .write_with_encoder(PngEncoder::new(&mut fx_data))?;
+
+        // -- Exec
+        let processed = process_image(&storage, &fx_config(), "posts/12/def", &fx_data).await?;
+
+        // -- Check
+        assert_eq!(processed.mime_type, "image/png");
+        let thumbnail = &processed.variants[0];
+        assert_eq!((thumbnail.width, thumbnail.height), (10, 10));
+        assert_eq!(thumbnail.fallback_url, "mem://posts/12/def/thumbnail.png");
+        assert_eq!(thumbnail.fallback_mime_type, "image/png");
+        assert!(storage.exists("posts/12/def/full.webp").await?);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_process_image_err_unsupported() -> Result<()> {
+        // -- Setup & Fixtures
+        let storage = MemStorage::default();
+
+        // -- Exec
+        let res = process_image(&storage, &fx_config(), "posts/12/ghi", b"GIF89a-not-supported").await;
+
+        // -- Check
+        assert!(matches!(
+            res,
+            Err(crate::Error::Media(Error::FormatUnsupported))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_variant_spec_parse_list() {
+        assert_eq!(
+            VariantSpec::parse_list("thumbnail:320, feed:1080"),
+            Some(vec![VariantSpec::new("thumbnail", 320), VariantSpec::new("feed", 1080)])
+        );
+        for value in ["", "thumbnail", "thumbnail:0", "thumb/../x:10", "full:big"] {
+            assert_eq!(VariantSpec::parse_list(value), None, "{value}");
+        }
+    }
+}
+
+// endregion: --- Tests
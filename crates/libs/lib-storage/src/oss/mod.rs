@@ -1,16 +1,36 @@
 use std::sync::Arc;
 use ali_oss_rs::Client;
 use ali_oss_rs::acl::ObjectAclOperations;
+use ali_oss_rs::multipart::MultipartUploadsOperations;
+use ali_oss_rs::multipart_common::{
+    CompleteMultipartUploadRequest, InitiateMultipartUploadOptions, UploadPartRequest,
+};
 use ali_oss_rs::object::{ObjectOperations};
-use ali_oss_rs::object_common::GetObjectOptions;
+use ali_oss_rs::object_common::{GetObjectOptions, GetObjectOptionsBuilder};
 use ali_oss_rs::object_common::{ObjectAcl, PutObjectOptions};
+use ali_oss_rs::presign::SignedOssRequest;
+use ali_oss_rs::request::{OssRequest, RequestMethod};
+use chrono::Utc;
 use lib_utils::mime::get_mime_from_bytes;
-use tracing::{debug, info};
+use std::time::Duration;
+use tracing::{debug, info, warn};
+use crate::backend::{check_presign, is_private};
 use crate::config::oss_config;
+use crate::{PresignMethod, PresignRequest, PresignedUpload, StorageBackend};
 
 mod error;
 pub use self::error::{Error, Result};
 
+/// The validity of the header signed requests of OSS (the `x-oss-date` skew), so of
+/// the presigned uploads.
+const SIGNED_REQUEST_TTL: Duration = Duration::from_secs(15 * 60);
+
+/// The size of the parts of the multipart uploads (100 KB min for OSS, but the last one).
+const MULTIPART_PART_SIZE: usize = 8 * 1024 * 1024;
+
+/// The bytes read to sniff the content type of an assembled file.
+const SNIFF_LEN: usize = 64;
+
 #[derive(Clone)]
 pub struct OssClient {
     client: Arc<Client>,
@@ -19,8 +39,8 @@ pub struct OssClient {
 }
 
 impl OssClient {
-    pub fn new() -> Self {
-        let config = oss_config();
+    pub fn new() -> crate::Result<Self> {
+        let config = oss_config()?;
 
         let client = Client::new(
             &config.OSS_ACCESS_KEY_ID,
@@ -29,22 +49,68 @@ impl OssClient {
             &config.OSS_ENDPOINT,
         );
 
-        Self {
+        Ok(Self {
             client: Arc::new(client),
             bucket_name: config.OSS_BUCKET_NAME.clone(),
             public_base: config.OSS_PUBLIC_BASE.clone(),
+        })
+    }
+
+    /// Upload the files of `parts` as the parts of the multipart upload, regrouped by
+    /// `MULTIPART_PART_SIZE` (as the parts of OSS are of 100 KB min, but the last one),
+    /// then complete it.
+    async fn upload_parts(
+        &self,
+        filename: &str,
+        upload_id: &str,
+        parts: &[String],
+    ) -> crate::Result<()> {
+        let mut etags = Vec::new();
+        let mut buffer = Vec::new();
+        for (i, part) in parts.iter().enumerate() {
+            buffer.extend(self.download(part).await?);
+            let is_last = i + 1 == parts.len();
+            if buffer.len() >= MULTIPART_PART_SIZE || is_last {
+                let part_number = etags.len() as u32 + 1;
+                let result = self
+                    .client
+                    .upload_part_from_buffer(
+                        &self.bucket_name,
+                        filename,
+                        std::mem::take(&mut buffer),
+                        UploadPartRequest::new(part_number, upload_id),
+                    )
+                    .await
+                    .map_err(|e| Error::UploadError(format!("upload_part_from_buffer: {}", e)))?;
+                etags.push((part_number, result.etag));
+            }
         }
+
+        let request = CompleteMultipartUploadRequest {
+            upload_id: upload_id.to_string(),
+            parts: etags,
+        };
+        self.client
+            .complete_multipart_uploads(&self.bucket_name, filename, request, None)
+            .await
+            .map_err(|e| Error::UploadError(format!("complete_multipart_uploads: {}", e)))?;
+
+        Ok(())
     }
+}
 
+impl StorageBackend for OssClient {
     /// --- Load file in OSS and make it public
-    pub async fn upload(&self, filename: &str, data: &[u8]) -> Result<String> {
+    async fn upload(&self, filename: &str, data: &[u8]) -> crate::Result<String> {
         info!("{:<12} - Uploading file: {}", "OSS", filename);
         debug!("{:<12} - File size: {} bytes", "OSS", data.len());
                 
         get_mime_from_bytes(data, filename);
 
+        // Note: A private object is private whatever the bucket ACL.
         let put_options = PutObjectOptions {
             content_md5: None,
+            object_acl: is_private(filename).then_some(ObjectAcl::Private),
             ..Default::default()
         };
 
@@ -53,10 +119,12 @@ impl OssClient {
             .await
             .map_err(|e| Error::UploadError(format!("put_object_from_buffer: {}", e)))?;
 
-        self.client
-            .put_object_acl(&self.bucket_name, filename, ObjectAcl::PublicRead, None)
-            .await
-            .map_err(|e| Error::UploadError(format!("put_object_acl: {}", e)))?;
+        if !is_private(filename) {
+            self.client
+                .put_object_acl(&self.bucket_name, filename, ObjectAcl::PublicRead, None)
+                .await
+                .map_err(|e| Error::UploadError(format!("put_object_acl: {}", e)))?;
+        }
 
         info!("{:<12} - File uploaded successfully: {}", "OSS", filename);
 
@@ -65,7 +133,7 @@ impl OssClient {
 
 
     /// --- Download file as bites
-    pub async fn download(&self, filename: &str) -> Result<Vec<u8>> {
+    async fn download(&self, filename: &str) -> crate::Result<Vec<u8>> {
         info!("{:<12} - Downloading file: {}", "OSS", filename);
 
         let result = self
@@ -78,8 +146,27 @@ impl OssClient {
     }
 
 
+    /// --- Download the first bytes of file
+    async fn download_prefix(&self, filename: &str, len: usize) -> crate::Result<Vec<u8>> {
+        if len == 0 {
+            return Ok(Vec::new());
+        }
+        let options = GetObjectOptionsBuilder::new()
+            .range(format!("bytes=0-{}", len - 1))
+            .build();
+
+        let mut result = self
+            .client
+            .get_object_to_buffer(&self.bucket_name, filename, Some(options))
+            .await
+            .map_err(|e| Error::UploadError(format!("get_object_to_buffer: {}", e)))?;
+        result.truncate(len);
+
+        Ok(result)
+    }
+
     /// --- Delete file
-    pub async fn delete(&self, filename: &str) -> Result<()> {
+    async fn delete(&self, filename: &str) -> crate::Result<()> {
         info!("{:<12} - Deleting file: {}", "OSS", filename);
 
         self.client
@@ -93,7 +180,7 @@ impl OssClient {
     }
 
     /// --- Check if object exists
-    pub async fn exists(&self, filename: &str) -> Result<bool> {
+    async fn exists(&self, filename: &str) -> crate::Result<bool> {
         info!("{:<12} - Checking if file exists: {}", "OSS", filename);
 
         match self.client.head_object(&self.bucket_name, filename, None).await {
@@ -103,14 +190,69 @@ impl OssClient {
                 if msg.contains("NoSuchKey") || msg.contains("404") {
                     Ok(false)
                 } else {
-                    Err(Error::UploadError(format!("head_object: {}", msg)))
+                    Err(Error::UploadError(format!("head_object: {}", msg)).into())
                 }
             }
         }
     }
 
+    /// --- Get the object size
+    async fn size(&self, filename: &str) -> crate::Result<Option<u64>> {
+        match self.client.head_object(&self.bucket_name, filename, None).await {
+            Ok(meta) => Ok(Some(meta.content_length)),
+            Err(err) => {
+                let msg = err.to_string();
+                if msg.contains("NoSuchKey") || msg.contains("404") {
+                    Ok(None)
+                } else {
+                    Err(Error::UploadError(format!("head_object: {}", msg)).into())
+                }
+            }
+        }
+    }
+
+    /// --- Assemble the parts as the file, by a multipart upload (a part in memory at most)
+    async fn assemble(&self, filename: &str, parts: &[String]) -> crate::Result<String> {
+        info!("{:<12} - Assembling file: {} ({} parts)", "OSS", filename, parts.len());
+        let Some(first_part) = parts.first() else {
+            return self.upload(filename, &[]).await;
+        };
+
+        let prefix = self.download_prefix(first_part, SNIFF_LEN).await?;
+        let options = InitiateMultipartUploadOptions {
+            mime_type: Some(get_mime_from_bytes(&prefix, filename)),
+            object_acl: Some(if is_private(filename) {
+                ObjectAcl::Private
+            } else {
+                ObjectAcl::PublicRead
+            }),
+            ..Default::default()
+        };
+        let upload_id = self
+            .client
+            .initiate_multipart_uploads(&self.bucket_name, filename, Some(options))
+            .await
+            .map_err(|e| Error::UploadError(format!("initiate_multipart_uploads: {}", e)))?
+            .upload_id;
+
+        if let Err(err) = self.upload_parts(filename, &upload_id, parts).await {
+            if let Err(e) = self
+                .client
+                .abort_multipart_uploads(&self.bucket_name, filename, &upload_id)
+                .await
+            {
+                warn!("{:<12} - Fail to abort the multipart upload of {}: {}", "OSS", filename, e);
+            }
+            return Err(err);
+        }
+
+        info!("{:<12} - File assembled successfully: {}", "OSS", filename);
+
+        Ok(self.public_url(filename))
+    }
+
     /// --- Create URL for object
-    pub fn public_url(&self, filename: &str) -> String {
+    fn public_url(&self, filename: &str) -> String {
         info!("{:<12} - Creating public URL: {}", "OSS", filename);
 
         format!(
@@ -119,4 +261,30 @@ impl OssClient {
             filename.trim_start_matches('/')
         )
     }
+
+    /// --- Presign PUT upload (header signed, so valid `SIGNED_REQUEST_TTL` at most)
+    fn presign_upload(&self, filename: &str, request: &PresignRequest) -> crate::Result<PresignedUpload> {
+        check_presign(filename, request)?;
+        if request.method == PresignMethod::Post {
+            return Err(crate::Error::PresignUnsupported(format!("OSS POST, for '{filename}'")));
+        }
+
+        let oss_request = OssRequest::new()
+            .method(RequestMethod::Put)
+            .bucket(&self.bucket_name)
+            .object(filename)
+            .add_header("content-type", &request.content_type)
+            .add_header("content-length", request.size.to_string())
+            .add_header("x-oss-object-acl", "public-read");
+
+        let SignedOssRequest { url, headers } = self.client.presign_raw_request(oss_request);
+
+        Ok(PresignedUpload {
+            method: PresignMethod::Put,
+            url,
+            headers: headers.into_iter().collect(),
+            fields: Default::default(),
+            expires_at: Utc::now() + request.expires_in.min(SIGNED_REQUEST_TTL),
+        })
+    }
 }
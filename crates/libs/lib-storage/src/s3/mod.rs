@@ -0,0 +1,917 @@
+//! The S3 compatible object store client (AWS S3, MinIO, R2, ...), signed with SigV4.
+//!
+//! The objects are addressed path-style (`{endpoint}/{bucket}/{key}`) or virtual-host
+//! (`{bucket}.{endpoint host}/{key}`), by `S3_PATH_STYLE`.
+
+use crate::backend::{check_key, check_presign, is_private};
+use crate::config::{s3_config, S3Config};
+use crate::{PresignMethod, PresignRequest, PresignedUpload, StorageBackend};
+use chrono::{DateTime, Utc};
+use lib_utils::b64::b64_encode;
+use lib_utils::mime::get_mime_from_bytes;
+use reqwest::header::{AUTHORIZATION, CONTENT_LENGTH, ETAG};
+use reqwest::{Method, Response, StatusCode, Url};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tracing::{debug, info, warn};
+
+mod error;
+mod sigv4;
+
+pub use self::error::{Error, Result};
+
+use self::sigv4::{CanonicalRequest, Credentials, ALGORITHM, EMPTY_PAYLOAD_SHA256, UNSIGNED_PAYLOAD};
+
+/// The size of the parts of the multipart uploads (5 MiB min for S3, but the last one).
+const MULTIPART_PART_SIZE: usize = 8 * 1024 * 1024;
+
+/// The bytes read to sniff the content type of an assembled object.
+const SNIFF_LEN: usize = 64;
+
+#[derive(Clone)]
+pub struct S3Client {
+    http: reqwest::Client,
+    config: Arc<S3Config>,
+    /// The url of the bucket, the objects urls are `{object_base}/{key}`.
+    object_base: String,
+    public_base: String,
+}
+
+impl S3Client {
+    pub fn new() -> crate::Result<Self> {
+        let client = Self::from_config(s3_config()?.clone())?;
+        Ok(client)
+    }
+
+    pub fn from_config(config: S3Config) -> Result<Self> {
+        let endpoint = Url::parse(&config.S3_ENDPOINT)
+            .map_err(|ex| Error::EndpointInvalid(format!("{}: {ex}", config.S3_ENDPOINT)))?;
+        let host = host_header(&endpoint)
+            .ok_or_else(|| Error::EndpointInvalid(config.S3_ENDPOINT.clone()))?;
+        let path = endpoint.path().trim_end_matches('/');
+
+        let object_base = if config.S3_PATH_STYLE {
+            format!("{}://{host}{path}/{}", endpoint.scheme(), config.S3_BUCKET_NAME)
+        } else {
+            format!("{}://{}.{host}{path}", endpoint.scheme(), config.S3_BUCKET_NAME)
+        };
+        let public_base = config
+            .S3_PUBLIC_BASE
+            .as_deref()
+            .map(|base| base.trim_end_matches('/').to_string())
+            .unwrap_or_else(|| object_base.clone());
+
+        Ok(Self {
+            http: reqwest::Client::new(),
+            config: Arc::new(config),
+            object_base,
+            public_base,
+        })
+    }
+
+    fn object_url(&self, key: &str) -> crate::Result<Url> {
+        check_key(key)?;
+        let url = format!("{}/{}", self.object_base, sigv4::uri_encode(key, true));
+
+        Url::parse(&url).map_err(|ex| Error::EndpointInvalid(format!("{url}: {ex}")).into())
+    }
+
+    fn credentials(&self) -> Credentials<'_> {
+        Credentials {
+            access_key_id: &self.config.S3_ACCESS_KEY_ID,
+            secret_access_key: &self.config.S3_SECRET_ACCESS_KEY,
+            region: &self.config.S3_REGION,
+        }
+    }
+
+    /// Send the signed request of the object (the body, if any, is the object data).
+    async fn send(
+        &self,
+        method: Method,
+        key: &str,
+        body: Option<&[u8]>,
+        extra_headers: &[(&'static str, String)],
+    ) -> crate::Result<Response> {
+        self.send_query(method, key, &[], body, extra_headers).await
+    }
+
+    /// Send the signed request of the object, with the query parameters (e.g., of the
+    /// multipart upload operations).
+    async fn send_query(
+        &self,
+        method: Method,
+        key: &str,
+        params: &[(&str, String)],
+        body: Option<&[u8]>,
+        extra_headers: &[(&'static str, String)],
+    ) -> crate::Result<Response> {
+        let mut url = self.object_url(key)?;
+        let query = sigv4::canonical_query(params);
+        if !query.is_empty() {
+            url.set_query(Some(&query));
+        }
+        let now = Utc::now();
+        let payload_sha256 = match body {
+            Some(data) => sigv4::sha256_hex(data),
+            None => EMPTY_PAYLOAD_SHA256.to_string(),
+        };
+
+        let mut headers = vec![
+            ("host", host_header(&url).unwrap_or_default()),
+            ("x-amz-content-sha256", payload_sha256.clone()),
+            ("x-amz-date", sigv4::amz_date(now)),
+        ];
+        headers.extend_from_slice(extra_headers);
+
+        let authorization = sigv4::authorization(
+            &self.credentials(),
+            &CanonicalRequest {
+                method: method.as_str(),
+                uri: url.path(),
+                query: &query,
+                headers: &headers,
+                payload_sha256: &payload_sha256,
+            },
+            now,
+        )?;
+
+        // Note: The `host` header is set by the client (from the url).
+        let mut request = self.http.request(method.clone(), url);
+        for (name, value) in headers.into_iter().filter(|(name, _)| *name != "host") {
+            request = request.header(name, value);
+        }
+        request = request.header(AUTHORIZATION, authorization);
+        if let Some(data) = body {
+            request = request.body(data.to_vec());
+        }
+
+        request
+            .send()
+            .await
+            .map_err(|ex| Error::Request(format!("{method} {key}: {ex}")).into())
+    }
+
+    /// The headers of a new object: its content type, and the public ACL (if configured,
+    /// and not a private object).
+    fn object_headers(&self, key: &str, content_type: String) -> Vec<(&'static str, String)> {
+        let mut headers = vec![("content-type", content_type)];
+        if self.config.S3_PUBLIC_ACL && !is_private(key) {
+            headers.push(("x-amz-acl", "public-read".to_string()));
+        }
+        headers
+    }
+
+    /// Upload the objects of `parts` as the parts of the multipart upload, regrouped by
+    /// `MULTIPART_PART_SIZE` (as the parts of S3 are of 5 MiB min, but the last one),
+    /// then complete it.
+    async fn upload_parts(
+        &self,
+        key: &str,
+        upload_id: &str,
+        parts: &[String],
+    ) -> crate::Result<()> {
+        let mut etags = Vec::new();
+        let mut buffer = Vec::new();
+        for (i, part) in parts.iter().enumerate() {
+            buffer.extend(self.download(part).await?);
+            let is_last = i + 1 == parts.len();
+            if buffer.len() >= MULTIPART_PART_SIZE || is_last {
+                let part_number = (etags.len() + 1).to_string();
+                let params = [("partNumber", part_number), ("uploadId", upload_id.to_string())];
+                let res = self.send_query(Method::PUT, key, &params, Some(&buffer), &[]).await?;
+                let res = check_status("upload_part", res).await?;
+                let etag = res
+                    .headers()
+                    .get(ETAG)
+                    .and_then(|value| value.to_str().ok())
+                    .ok_or_else(|| Error::Request(format!("upload_part {key}: no etag")))?;
+                etags.push(etag.to_string());
+                buffer.clear();
+            }
+        }
+
+        // -- Complete
+        let parts_xml: String = etags
+            .iter()
+            .enumerate()
+            .map(|(i, etag)| {
+                format!("<Part><PartNumber>{}</PartNumber><ETag>{etag}</ETag></Part>", i + 1)
+            })
+            .collect();
+        let body = format!("<CompleteMultipartUpload>{parts_xml}</CompleteMultipartUpload>");
+        let params = [("uploadId", upload_id.to_string())];
+        let headers = [("content-type", "application/xml".to_string())];
+        let res = self
+            .send_query(Method::POST, key, &params, Some(body.as_bytes()), &headers)
+            .await?;
+        let res = check_status("complete_multipart_upload", res).await?;
+        // Note: S3 may answer 200 with an error, once the request is processing.
+        let body = res
+            .text()
+            .await
+            .map_err(|ex| Error::Request(format!("complete_multipart_upload {key}: {ex}")))?;
+        if body.contains("<Error>") {
+            return Err(Error::Status {
+                op: "complete_multipart_upload",
+                status: 200,
+                body,
+            }
+            .into());
+        }
+
+        Ok(())
+    }
+
+    /// The presigned url of a PUT (query signed, with the signed headers to send).
+    fn presign_put(
+        &self,
+        key: &str,
+        request: &PresignRequest,
+        now: DateTime<Utc>,
+    ) -> crate::Result<PresignedUpload> {
+        let url = self.object_url(key)?;
+
+        let mut headers = vec![
+            ("content-length", request.size.to_string()),
+            ("content-type", request.content_type.clone()),
+            ("host", host_header(&url).unwrap_or_default()),
+        ];
+        if self.config.S3_PUBLIC_ACL {
+            headers.push(("x-amz-acl", "public-read".to_string()));
+        }
+
+        let credentials = self.credentials();
+        let query = sigv4::canonical_query(&[
+            ("X-Amz-Algorithm", ALGORITHM.to_string()),
+            ("X-Amz-Credential", sigv4::credential(&credentials, now)),
+            ("X-Amz-Date", sigv4::amz_date(now)),
+            ("X-Amz-Expires", request.expires_in.as_secs().to_string()),
+            ("X-Amz-SignedHeaders", sigv4::signed_headers(&headers)),
+        ]);
+        let signature = sigv4::signature(
+            &credentials,
+            &CanonicalRequest {
+                method: "PUT",
+                uri: url.path(),
+                query: &query,
+                headers: &headers,
+                payload_sha256: UNSIGNED_PAYLOAD,
+            },
+            now,
+        )?;
+
+        Ok(PresignedUpload {
+            method: PresignMethod::Put,
+            url: format!("{url}?{query}&X-Amz-Signature={signature}"),
+            // Note: The `host` header is set by the client (from the url).
+            headers: headers
+                .into_iter()
+                .filter(|(name, _)| *name != "host")
+                .map(|(name, value)| (name.to_string(), value))
+                .collect(),
+            fields: BTreeMap::new(),
+            expires_at: now + request.expires_in,
+        })
+    }
+
+    /// The POST policy of the upload (for the browser forms), with its form fields.
+    fn presign_post(
+        &self,
+        key: &str,
+        request: &PresignRequest,
+        now: DateTime<Utc>,
+    ) -> crate::Result<PresignedUpload> {
+        check_key(key)?;
+        let credentials = self.credentials();
+        let expires_at = now + request.expires_in;
+
+        let mut fields = BTreeMap::from([
+            ("key".to_string(), key.to_string()),
+            ("Content-Type".to_string(), request.content_type.clone()),
+            ("x-amz-algorithm".to_string(), ALGORITHM.to_string()),
+            ("x-amz-credential".to_string(), sigv4::credential(&credentials, now)),
+            ("x-amz-date".to_string(), sigv4::amz_date(now)),
+        ]);
+        if self.config.S3_PUBLIC_ACL {
+            fields.insert("acl".to_string(), "public-read".to_string());
+        }
+
+        let mut conditions = vec![
+            json!({ "bucket": self.config.S3_BUCKET_NAME }),
+            json!(["content-length-range", 1, request.size]),
+        ];
+        conditions.extend(fields.iter().map(|(name, value)| json!({ name: value })));
+        let policy = json!({
+            "expiration": expires_at.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+            "conditions": conditions,
+        });
+        let policy = b64_encode(policy.to_string());
+        let signature = sigv4::policy_signature(&credentials, &policy, now)?;
+        fields.insert("policy".to_string(), policy);
+        fields.insert("x-amz-signature".to_string(), signature);
+
+        Ok(PresignedUpload {
+            method: PresignMethod::Post,
+            url: format!("{}/", self.object_base),
+            headers: BTreeMap::new(),
+            fields,
+            expires_at,
+        })
+    }
+}
+
+impl StorageBackend for S3Client {
+    async fn upload(&self, key: &str, data: &[u8]) -> crate::Result<String> {
+        info!("{:<12} - Uploading file: {}", "S3", key);
+        debug!("{:<12} - File size: {} bytes", "S3", data.len());
+
+        let headers = self.object_headers(key, get_mime_from_bytes(data, key));
+        let res = self.send(Method::PUT, key, Some(data), &headers).await?;
+        check_status("put_object", res).await?;
+
+        Ok(self.public_url(key))
+    }
+
+    async fn download(&self, key: &str) -> crate::Result<Vec<u8>> {
+        info!("{:<12} - Downloading file: {}", "S3", key);
+
+        let res = self.send(Method::GET, key, None, &[]).await?;
+        if res.status() == StatusCode::NOT_FOUND {
+            return Err(crate::Error::ObjectNotFound(key.to_string()));
+        }
+        let res = check_status("get_object", res).await?;
+
+        let data = res
+            .bytes()
+            .await
+            .map_err(|ex| Error::Request(format!("get_object {key}: {ex}")))?;
+
+        Ok(data.to_vec())
+    }
+
+    async fn download_prefix(&self, key: &str, len: usize) -> crate::Result<Vec<u8>> {
+        if len == 0 {
+            return Ok(Vec::new());
+        }
+
+        let range = [("range", format!("bytes=0-{}", len - 1))];
+        let res = self.send(Method::GET, key, None, &range).await?;
+        if res.status() == StatusCode::NOT_FOUND {
+            return Err(crate::Error::ObjectNotFound(key.to_string()));
+        }
+        let res = check_status("get_object", res).await?;
+
+        // Note: The stores without range support answer the whole object.
+        let mut data = res
+            .bytes()
+            .await
+            .map_err(|ex| Error::Request(format!("get_object {key}: {ex}")))?
+            .to_vec();
+        data.truncate(len);
+
+        Ok(data)
+    }
+
+    async fn delete(&self, key: &str) -> crate::Result<()> {
+        info!("{:<12} - Deleting file: {}", "S3", key);
+
+        // Note: S3 answers 204 for a missing object, some compatible stores 404.
+        let res = self.send(Method::DELETE, key, None, &[]).await?;
+        if res.status() != StatusCode::NOT_FOUND {
+            check_status("delete_object", res).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn exists(&self, key: &str) -> crate::Result<bool> {
+        let res = self.send(Method::HEAD, key, None, &[]).await?;
+        if res.status() == StatusCode::NOT_FOUND {
+            return Ok(false);
+        }
+        check_status("head_object", res).await?;
+
+        Ok(true)
+    }
+
+    async fn size(&self, key: &str) -> crate::Result<Option<u64>> {
+        let res = self.send(Method::HEAD, key, None, &[]).await?;
+        if res.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let res = check_status("head_object", res).await?;
+
+        // Note: The header, as the body of a HEAD response is empty.
+        let size = res
+            .headers()
+            .get(CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse().ok())
+            .ok_or_else(|| Error::Request(format!("head_object {key}: no content-length")))?;
+
+        Ok(Some(size))
+    }
+
+    /// Note: A multipart upload, so the object is not in memory (but a part).
+    async fn assemble(&self, key: &str, parts: &[String]) -> crate::Result<String> {
+        info!("{:<12} - Assembling file: {} ({} parts)", "S3", key, parts.len());
+        let Some(first_part) = parts.first() else {
+            return self.upload(key, &[]).await;
+        };
+
+        // -- Create
+        let prefix = self.download_prefix(first_part, SNIFF_LEN).await?;
+        let headers = self.object_headers(key, get_mime_from_bytes(&prefix, key));
+        let params = [("uploads", String::new())];
+        let res = self.send_query(Method::POST, key, &params, None, &headers).await?;
+        let res = check_status("create_multipart_upload", res).await?;
+        let body = res
+            .text()
+            .await
+            .map_err(|ex| Error::Request(format!("create_multipart_upload {key}: {ex}")))?;
+        let upload_id = xml_value(&body, "UploadId")
+            .ok_or_else(|| Error::Request(format!("create_multipart_upload {key}: no UploadId")))?;
+
+        // -- Upload the parts & complete (or abort)
+        if let Err(err) = self.upload_parts(key, &upload_id, parts).await {
+            let params = [("uploadId", upload_id)];
+            if let Err(ex) = self.send_query(Method::DELETE, key, &params, None, &[]).await {
+                warn!("{:<12} - Fail to abort the multipart upload of {key}: {ex:?}", "S3");
+            }
+            return Err(err);
+        }
+
+        Ok(self.public_url(key))
+    }
+
+    fn public_url(&self, key: &str) -> String {
+        format!(
+            "{}/{}",
+            self.public_base,
+            sigv4::uri_encode(key.trim_start_matches('/'), true)
+        )
+    }
+
+    fn presign_upload(&self, key: &str, request: &PresignRequest) -> crate::Result<PresignedUpload> {
+        check_presign(key, request)?;
+
+        match request.method {
+            PresignMethod::Put => self.presign_put(key, request, Utc::now()),
+            PresignMethod::Post => self.presign_post(key, request, Utc::now()),
+        }
+    }
+}
+
+/// The `host` header value of the url (with the port, if not the default one).
+fn host_header(url: &Url) -> Option<String> {
+    let host = url.host_str()?;
+    Some(match url.port() {
+        Some(port) => format!("{host}:{port}"),
+        None => host.to_string(),
+    })
+}
+
+/// The text of the first `tag` element of the xml (not unescaped, e.g., an `UploadId`).
+fn xml_value(xml: &str, tag: &str) -> Option<String> {
+    let start = xml.find(&format!("<{tag}>"))? + tag.len() + 2;
+    let end = start + xml[start..].find(&format!("</{tag}>"))?;
+    Some(xml[start..end].to_string())
+}
+
+async fn check_status(op: &'static str, res: Response) -> Result<Response> {
+    let status = res.status();
+    if status.is_success() {
+        return Ok(res);
+    }
+    let body = res.text().await.unwrap_or_default();
+
+    Err(Error::Status {
+        op,
+        status: status.as_u16(),
+        body,
+    })
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use axum::body::Bytes;
+    use axum::extract::{DefaultBodyLimit, Path, State};
+    use axum::http::{HeaderMap, Uri};
+    use axum::routing::any;
+    use axum::Router;
+    use chrono::{NaiveDateTime, TimeZone};
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    type Objects = Arc<Mutex<HashMap<String, Vec<u8>>>>;
+
+    const FX_ACCESS_KEY_ID: &str = "test-access-key";
+    const FX_SECRET_ACCESS_KEY: &str = "test-secret-key";
+    const FX_REGION: &str = "us-east-1";
+
+    fn fx_config(endpoint: &str, path_style: bool) -> S3Config {
+        S3Config {
+            S3_ACCESS_KEY_ID: FX_ACCESS_KEY_ID.to_string(),
+            S3_SECRET_ACCESS_KEY: FX_SECRET_ACCESS_KEY.to_string(),
+            S3_BUCKET_NAME: "mapster".to_string(),
+            S3_ENDPOINT: endpoint.to_string(),
+            S3_REGION: FX_REGION.to_string(),
+            S3_PATH_STYLE: path_style,
+            S3_PUBLIC_ACL: true,
+            S3_PUBLIC_BASE: None,
+        }
+    }
+
+    #[test]
+    fn test_object_url_addressing() -> Result<()> {
+        // -- Exec
+        let path_style = S3Client::from_config(fx_config("http://127.0.0.1:9000/", true))?;
+        let virtual_host =
+            S3Client::from_config(fx_config("https://s3.eu-west-1.amazonaws.com", false))?;
+        let mut config = fx_config("https://s3.eu-west-1.amazonaws.com", false);
+        config.S3_PUBLIC_BASE = Some("https://cdn.example.com/".to_string());
+        let cdn = S3Client::from_config(config)?;
+
+        // -- Check
+        assert_eq!(
+            path_style.object_url("posts/12/a b.jpg")?.as_str(),
+            "http://127.0.0.1:9000/mapster/posts/12/a%20b.jpg"
+        );
+        assert_eq!(
+            virtual_host.object_url("posts/12/cover.jpg")?.as_str(),
+            "https://mapster.s3.eu-west-1.amazonaws.com/posts/12/cover.jpg"
+        );
+        assert_eq!(
+            virtual_host.public_url("posts/12/cover.jpg"),
+            "https://mapster.s3.eu-west-1.amazonaws.com/posts/12/cover.jpg"
+        );
+        assert_eq!(
+            cdn.public_url("posts/12/cover.jpg"),
+            "https://cdn.example.com/posts/12/cover.jpg"
+        );
+        assert!(matches!(
+            path_style.object_url("../secret"),
+            Err(crate::Error::KeyInvalid(_))
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_stand_in_round_trip_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let (endpoint, objects) = start_stand_in().await?;
+        let client = S3Client::from_config(fx_config(&endpoint, true))?;
+        let fx_key = "posts/12/cover photo.jpg";
+        let fx_data = b"test_stand_in_round_trip_ok";
+
+        // -- Exec
+        let url = client.upload(fx_key, fx_data).await?;
+
+        // -- Check
+        assert_eq!(url, format!("{endpoint}/mapster/posts/12/cover%20photo.jpg"));
+        assert!(objects.lock().unwrap().contains_key("mapster/posts/12/cover photo.jpg"));
+        assert!(client.exists(fx_key).await?);
+        assert_eq!(client.download(fx_key).await?, fx_data);
+
+        // -- Exec & Check (delete)
+        client.delete(fx_key).await?;
+        assert!(!client.exists(fx_key).await?);
+        assert!(matches!(
+            client.download(fx_key).await,
+            Err(crate::Error::ObjectNotFound(_))
+        ));
+        client.delete(fx_key).await?;
+
+        // -- Check (bad credentials)
+        let mut config = fx_config(&endpoint, true);
+        config.S3_SECRET_ACCESS_KEY = "wrong-secret-key".to_string();
+        let res = S3Client::from_config(config)?.upload(fx_key, fx_data).await;
+        assert!(matches!(
+            res,
+            Err(crate::Error::S3(Error::Status { status: 403, .. }))
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_stand_in_assemble_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let (endpoint, objects) = start_stand_in().await?;
+        let client = S3Client::from_config(fx_config(&endpoint, true))?;
+        let fx_key = "posts/12/video.mp4";
+        let mut fx_data = b"\x00\x00\x00\x18ftypisom".to_vec();
+        fx_data.resize(13 * 1024 * 1024, 7);
+        // the parts (as the staged chunks) of 6, 3 (so regrouped), then 4 MiB
+        let mut fx_parts = Vec::new();
+        for (i, range) in [0..6, 6..9, 9..13].into_iter().enumerate() {
+            let part_key = format!("private/uploads/12/{i}");
+            let (start, end) = (range.start * 1024 * 1024, range.end * 1024 * 1024);
+            client.upload(&part_key, &fx_data[start..end]).await?;
+            fx_parts.push(part_key);
+        }
+
+        // -- Exec
+        let url = client.assemble(fx_key, &fx_parts).await?;
+
+        // -- Check
+        assert_eq!(url, format!("{endpoint}/mapster/{fx_key}"));
+        assert_eq!(client.size(fx_key).await?, Some(fx_data.len() as u64));
+        assert!(client.download(fx_key).await? == fx_data);
+        assert!(client.exists(&fx_parts[0]).await?, "the parts should be kept");
+        assert!(!objects.lock().unwrap().keys().any(|key| key.contains('#')));
+
+        // -- Check (a missing part, the upload aborted)
+        let fx_missing_parts = [fx_parts[0].clone(), "private/uploads/12/none".to_string()];
+        let res = client.assemble("posts/12/other.mp4", &fx_missing_parts).await;
+        assert!(matches!(res, Err(crate::Error::ObjectNotFound(_))), "{res:?}");
+        assert!(!client.exists("posts/12/other.mp4").await?);
+        assert!(!objects.lock().unwrap().keys().any(|key| key.contains('#')));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_presign_put_stand_in_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let (endpoint, objects) = start_stand_in().await?;
+        let client = S3Client::from_config(fx_config(&endpoint, true))?;
+        let fx_key = "posts/12/video.mp4";
+        let fx_data = b"test_presign_put_stand_in_ok";
+        let fx_request = PresignRequest {
+            method: PresignMethod::Put,
+            content_type: "video/mp4".to_string(),
+            size: fx_data.len() as u64,
+            expires_in: std::time::Duration::from_secs(300),
+        };
+
+        // -- Exec
+        let upload = client.presign_upload(fx_key, &fx_request)?;
+        let mut put = reqwest::Client::new().put(&upload.url).body(fx_data.to_vec());
+        for (name, value) in &upload.headers {
+            put = put.header(name, value);
+        }
+        let res = put.send().await?;
+
+        // -- Check
+        assert_eq!(res.status(), 200);
+        assert_eq!(upload.headers["x-amz-acl"], "public-read");
+        assert_eq!(
+            objects.lock().unwrap().get("mapster/posts/12/video.mp4").map(Vec::as_slice),
+            Some(&fx_data[..])
+        );
+        assert_eq!(client.download_prefix(fx_key, 4).await?, b"test");
+
+        // -- Check (other content type than the signed one)
+        let res = reqwest::Client::new()
+            .put(&upload.url)
+            .header("content-type", "text/html")
+            .header("x-amz-acl", "public-read")
+            .body(fx_data.to_vec())
+            .send()
+            .await?;
+        assert_eq!(res.status(), 403);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_presign_post_policy() -> Result<()> {
+        // -- Setup & Fixtures
+        let client = S3Client::from_config(fx_config("https://s3.eu-west-1.amazonaws.com", false))?;
+        let fx_request = PresignRequest {
+            method: PresignMethod::Post,
+            content_type: "image/jpeg".to_string(),
+            size: 10_000_000,
+            expires_in: std::time::Duration::from_secs(600),
+        };
+
+        // -- Exec
+        let upload = client.presign_upload("posts/12/photo.jpg", &fx_request)?;
+
+        // -- Check
+        assert_eq!(upload.url, "https://mapster.s3.eu-west-1.amazonaws.com/");
+        assert_eq!(upload.fields["key"], "posts/12/photo.jpg");
+        assert_eq!(upload.fields["x-amz-algorithm"], ALGORITHM);
+        let policy = lib_utils::b64::b64_decode(&upload.fields["policy"])?;
+        let policy: serde_json::Value = serde_json::from_slice(&policy)?;
+        let conditions = policy["conditions"].as_array().unwrap();
+        assert!(conditions.contains(&json!(["content-length-range", 1, 10_000_000])));
+        assert!(conditions.contains(&json!({ "Content-Type": "image/jpeg" })));
+        assert!(conditions.contains(&json!({ "acl": "public-read" })));
+
+        // -- Check (invalid constraints)
+        let res = client.presign_upload("private/uploads/12/chunk", &fx_request);
+        assert!(matches!(res, Err(crate::Error::PresignInvalid(_))));
+        let res = client.presign_upload(
+            "posts/12/photo.jpg",
+            &PresignRequest {
+                size: 0,
+                ..fx_request
+            },
+        );
+        assert!(matches!(res, Err(crate::Error::PresignInvalid(_))));
+
+        Ok(())
+    }
+
+    // region:    --- Stand-in
+
+    /// A minimal S3 compatible store (path-style), which verifies the signatures.
+    async fn start_stand_in() -> Result<(String, Objects)> {
+        let objects = Objects::default();
+        let app = Router::new()
+            .route("/{bucket}/{*key}", any(stand_in_handler))
+            .layer(DefaultBodyLimit::disable())
+            .with_state(objects.clone());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        Ok((format!("http://{addr}"), objects))
+    }
+
+    async fn stand_in_handler(
+        State(objects): State<Objects>,
+        Path((bucket, key)): Path<(String, String)>,
+        method: axum::http::Method,
+        uri: Uri,
+        headers: HeaderMap,
+        body: Bytes,
+    ) -> (axum::http::StatusCode, HeaderMap, Vec<u8>) {
+        use axum::http::{Method, StatusCode};
+
+        if !stand_in_signature_valid(&method, &uri, &headers, &body) {
+            return (StatusCode::FORBIDDEN, HeaderMap::new(), b"SignatureDoesNotMatch".to_vec());
+        }
+
+        let key = format!("{bucket}/{key}");
+        let params = stand_in_params(&uri);
+        let mut objects = objects.lock().unwrap();
+        match (method, params.get("uploadId")) {
+            // -- Multipart upload (the parts as the objects `{upload id}#{part number}`)
+            (Method::POST, None) if params.contains_key("uploads") => {
+                let xml = format!(
+                    "<InitiateMultipartUploadResult><UploadId>{key}.upload</UploadId></InitiateMultipartUploadResult>"
+                );
+                (StatusCode::OK, HeaderMap::new(), xml.into_bytes())
+            }
+            (Method::PUT, Some(upload_id)) => {
+                let part_number = params.get("partNumber").cloned().unwrap_or_default();
+                objects.insert(format!("{upload_id}#{part_number:0>5}"), body.to_vec());
+                let mut headers = HeaderMap::new();
+                headers.insert("etag", format!("\"part-{part_number}\"").parse().unwrap());
+                (StatusCode::OK, headers, Vec::new())
+            }
+            (Method::POST, Some(upload_id)) => {
+                let mut part_keys: Vec<String> = objects
+                    .keys()
+                    .filter(|part_key| part_key.starts_with(&format!("{upload_id}#")))
+                    .cloned()
+                    .collect();
+                part_keys.sort();
+                let parts: Vec<Vec<u8>> = part_keys
+                    .iter()
+                    .filter_map(|part_key| objects.remove(part_key))
+                    .collect();
+                // as S3, the parts of 5 MiB min, but the last one
+                if parts.iter().rev().skip(1).any(|part| part.len() < 5 * 1024 * 1024) {
+                    return (StatusCode::BAD_REQUEST, HeaderMap::new(), b"EntityTooSmall".to_vec());
+                }
+                objects.insert(key, parts.concat());
+                (StatusCode::OK, HeaderMap::new(), b"<CompleteMultipartUploadResult/>".to_vec())
+            }
+            (Method::DELETE, Some(upload_id)) => {
+                objects.retain(|part_key, _| !part_key.starts_with(&format!("{upload_id}#")));
+                (StatusCode::NO_CONTENT, HeaderMap::new(), Vec::new())
+            }
+
+            // -- Object
+            (Method::PUT, None) => {
+                objects.insert(key, body.to_vec());
+                (StatusCode::OK, HeaderMap::new(), Vec::new())
+            }
+            (Method::GET | Method::HEAD, None) => match objects.get(&key) {
+                Some(data) => (StatusCode::OK, HeaderMap::new(), data.clone()),
+                None => (StatusCode::NOT_FOUND, HeaderMap::new(), b"NoSuchKey".to_vec()),
+            },
+            (Method::DELETE, None) => {
+                objects.remove(&key);
+                (StatusCode::NO_CONTENT, HeaderMap::new(), Vec::new())
+            }
+            _ => (StatusCode::METHOD_NOT_ALLOWED, HeaderMap::new(), Vec::new()),
+        }
+    }
+
+    fn stand_in_params(uri: &Uri) -> BTreeMap<String, String> {
+        Url::parse(&format!("http://stand-in{uri}"))
+            .map(|url| url.query_pairs().into_owned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Recompute the signature from the request as received.
+    fn stand_in_signature_valid(
+        method: &axum::http::Method,
+        uri: &Uri,
+        headers: &HeaderMap,
+        body: &[u8],
+    ) -> bool {
+        let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+        let credentials = Credentials {
+            access_key_id: FX_ACCESS_KEY_ID,
+            secret_access_key: FX_SECRET_ACCESS_KEY,
+            region: FX_REGION,
+        };
+
+        // -- Presigned (query signed)
+        if uri.query().is_some_and(|query| query.contains("X-Amz-Signature=")) {
+            let Ok(url) = Url::parse(&format!("http://stand-in{uri}")) else {
+                return false;
+            };
+            let params: BTreeMap<String, String> = url.query_pairs().into_owned().collect();
+            let param = |name: &str| params.get(name).map(String::as_str).unwrap_or_default();
+            let Ok(date) = NaiveDateTime::parse_from_str(param("X-Amz-Date"), "%Y%m%dT%H%M%SZ")
+            else {
+                return false;
+            };
+            let date = Utc.from_utc_datetime(&date);
+            let expires_in = param("X-Amz-Expires").parse::<i64>().unwrap_or_default();
+            if Utc::now() > date + chrono::Duration::seconds(expires_in) {
+                return false;
+            }
+            let signed: Vec<(&str, String)> = param("X-Amz-SignedHeaders")
+                .split(';')
+                .map(|name| (name, header(name).unwrap_or_default().to_string()))
+                .collect();
+            let query_params: Vec<(&str, String)> = params
+                .iter()
+                .filter(|(name, _)| *name != "X-Amz-Signature")
+                .map(|(name, value)| (name.as_str(), value.clone()))
+                .collect();
+            let query = sigv4::canonical_query(&query_params);
+            let request = CanonicalRequest {
+                method: method.as_str(),
+                uri: uri.path(),
+                query: &query,
+                headers: &signed,
+                payload_sha256: UNSIGNED_PAYLOAD,
+            };
+
+            return sigv4::signature(&credentials, &request, date)
+                .is_ok_and(|expected| expected == param("X-Amz-Signature"));
+        }
+
+        // -- Authorization header
+        let (Some(authorization), Some(payload_sha256), Some(amz_date)) = (
+            header("authorization"),
+            header("x-amz-content-sha256"),
+            header("x-amz-date"),
+        ) else {
+            return false;
+        };
+        if payload_sha256 != sigv4::sha256_hex(body) {
+            return false;
+        }
+        let Ok(now) = NaiveDateTime::parse_from_str(amz_date, "%Y%m%dT%H%M%SZ") else {
+            return false;
+        };
+        let Some(signed_headers) = authorization
+            .split(", ")
+            .find_map(|part| part.strip_prefix("SignedHeaders="))
+        else {
+            return false;
+        };
+        let signed: Vec<(&str, String)> = signed_headers
+            .split(';')
+            .map(|name| (name, header(name).unwrap_or_default().to_string()))
+            .collect();
+        let params = stand_in_params(uri);
+        let query_params: Vec<(&str, String)> = params
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.clone()))
+            .collect();
+        let query = sigv4::canonical_query(&query_params);
+
+        let request = CanonicalRequest {
+            method: method.as_str(),
+            uri: uri.path(),
+            query: &query,
+            headers: &signed,
+            payload_sha256,
+        };
+
+        sigv4::authorization(&credentials, &request, Utc.from_utc_datetime(&now))
+            .is_ok_and(|expected| expected == authorization)
+    }
+
+    // endregion: --- Stand-in
+}
+
+// endregion: --- Tests
@@ -21,19 +21,31 @@ pub struct EmailConfig {
     pub SMTP_PORT: u16,
     pub USE_TLS: bool,
     pub EMAIL_VERIFICATION_BASE_URL: String,
+    /// `LOGIN_LINK_BASE_URL`, `{PASSWORD_RESET_BASE_URL}/login/link` by default.
+    pub LOGIN_LINK_BASE_URL: String,
+    /// `ACCOUNT_UNLOCK_BASE_URL`, `{PASSWORD_RESET_BASE_URL}/login/unlock` by default.
+    pub ACCOUNT_UNLOCK_BASE_URL: String,
     pub SUPPORT_EMAIL: String,
 }
 
 impl EmailConfig {
     fn load_from_env() -> lib_utils::envs::Result<Self> {
+        let password_reset_base_url = get_env("PASSWORD_RESET_BASE_URL")?;
+        let login_link_base_url = get_env("LOGIN_LINK_BASE_URL")
+            .unwrap_or_else(|_| format!("{password_reset_base_url}/login/link"));
+        let account_unlock_base_url = get_env("ACCOUNT_UNLOCK_BASE_URL")
+            .unwrap_or_else(|_| format!("{password_reset_base_url}/login/unlock"));
+
         Ok(Self {
-            PASSWORD_RESET_BASE_URL: get_env("PASSWORD_RESET_BASE_URL")?,
+            PASSWORD_RESET_BASE_URL: password_reset_base_url,
             SMTP_USERNAME: get_env("SMTP_USERNAME")?,
             SMTP_PWD: get_env("SMTP_PWD")?,
             SMTP_SERVER: get_env("SMTP_SERVER")?,
             SMTP_PORT: get_env_parse("SMTP_PORT")?,
             USE_TLS: get_env_parse("SMTP_USE_TLS")?,
             EMAIL_VERIFICATION_BASE_URL: get_env_parse("EMAIL_VERIFICATION_BASE_URL")?,
+            LOGIN_LINK_BASE_URL: login_link_base_url,
+            ACCOUNT_UNLOCK_BASE_URL: account_unlock_base_url,
             SUPPORT_EMAIL: get_env_parse("SUPPORT_EMAIL")?,
         })
     }
@@ -8,6 +8,8 @@ use crate::tmail_config;
 const VERIFICATION_EMAIL_TEMPLATE: &str = include_str!("templates/verification-email.html");
 const WELCOME_EMAIL_TEMPLATE: &str = include_str!("templates/welcome-email.html");
 const RESET_PWD_EMAIL_TEMPLATE: &str = include_str!("templates/reset-pwd-email.html");
+const LOGIN_LINK_EMAIL_TEMPLATE: &str = include_str!("templates/login-link-email.html");
+const ACCOUNT_LOCKED_EMAIL_TEMPLATE: &str = include_str!("templates/account-locked-email.html");
 
 // region:    --- Email Verification
 pub async fn send_verification_email(
@@ -72,6 +74,64 @@ pub async fn send_reset_pwd_email(
 }
 // endregion: --- Password Reset Email
 
+// region:    --- Login Link Email
+pub async fn send_login_link_email(
+    to_email: &str,
+    username: &str,
+    token: &str,
+    expires_in_min: i64,
+) -> Result<()> {
+    let config = tmail_config();
+    let subject = "Your sign-in link";
+    let login_link = create_login_link(&config.LOGIN_LINK_BASE_URL, token);
+    let placeholders = vec![
+        ("{{username}}".to_string(), username.to_string()),
+        ("{{login_link}}".to_string(), login_link),
+        ("{{expires_in_min}}".to_string(), expires_in_min.to_string()),
+        ("{{support_email}}".to_string(), config.SUPPORT_EMAIL.clone())
+    ];
+
+    send_email_with_template(to_email, subject, LOGIN_LINK_EMAIL_TEMPLATE, &placeholders).await
+}
+
+// helper for Login Link
+fn create_login_link(
+    base_url: &str,
+    token: &str
+) -> String {
+    format!("{}?token={}", base_url, token)
+}
+// endregion: --- Login Link Email
+
+// region:    --- Account Locked Email
+pub async fn send_account_locked_email(
+    to_email: &str,
+    username: &str,
+    unlock_token: &str,
+    locked_min: i64,
+) -> Result<()> {
+    let config = tmail_config();
+    let subject = "Your account has been locked";
+    let unlock_link = create_unlock_link(&config.ACCOUNT_UNLOCK_BASE_URL, unlock_token);
+    let placeholders = vec![
+        ("{{username}}".to_string(), username.to_string()),
+        ("{{unlock_link}}".to_string(), unlock_link),
+        ("{{locked_minutes}}".to_string(), locked_min.to_string()),
+        ("{{support_email}}".to_string(), config.SUPPORT_EMAIL.clone())
+    ];
+
+    send_email_with_template(to_email, subject, ACCOUNT_LOCKED_EMAIL_TEMPLATE, &placeholders).await
+}
+
+// helper for Account Unlock
+fn create_unlock_link(
+    base_url: &str,
+    token: &str
+) -> String {
+    format!("{}?token={}", base_url, token)
+}
+// endregion: --- Account Locked Email
+
 // region: ---- Tests
 #[cfg(test)]
 mod tests {
@@ -128,11 +188,39 @@ mod tests {
         assert!(result.is_ok() || result.is_err());
     }
 
+    #[tokio::test]
+    async fn test_send_login_link_email_template_ok() {
+        init();
+        let result = send_login_link_email(
+            "test@example.com",
+            "testuser",
+            "test-token-123",
+            15
+        ).await;
+
+        assert!(result.is_ok() || result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_send_account_locked_email_template_ok() {
+        init();
+        let result = send_account_locked_email(
+            "test@example.com",
+            "testuser",
+            "test-token-123",
+            30
+        ).await;
+
+        assert!(result.is_ok() || result.is_err());
+    }
+
     #[test]
     fn test_email_templates_loaded_ok() {
         assert!(!VERIFICATION_EMAIL_TEMPLATE.is_empty());
         assert!(!WELCOME_EMAIL_TEMPLATE.is_empty()); 
         assert!(!RESET_PWD_EMAIL_TEMPLATE.is_empty());
+        assert!(!LOGIN_LINK_EMAIL_TEMPLATE.is_empty());
+        assert!(!ACCOUNT_LOCKED_EMAIL_TEMPLATE.is_empty());
         
         assert!(VERIFICATION_EMAIL_TEMPLATE.contains("{{username}}"));
         assert!(VERIFICATION_EMAIL_TEMPLATE.contains("{{verification_link}}"));
@@ -142,6 +230,13 @@ mod tests {
         
         assert!(RESET_PWD_EMAIL_TEMPLATE.contains("{{username}}"));
         assert!(RESET_PWD_EMAIL_TEMPLATE.contains("{{reset_link}}"));
+
+        assert!(LOGIN_LINK_EMAIL_TEMPLATE.contains("{{username}}"));
+        assert!(LOGIN_LINK_EMAIL_TEMPLATE.contains("{{login_link}}"));
+
+        assert!(ACCOUNT_LOCKED_EMAIL_TEMPLATE.contains("{{username}}"));
+        assert!(ACCOUNT_LOCKED_EMAIL_TEMPLATE.contains("{{unlock_link}}"));
+        assert!(ACCOUNT_LOCKED_EMAIL_TEMPLATE.contains("{{locked_minutes}}"));
     }
 
     #[test]
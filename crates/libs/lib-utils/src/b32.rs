@@ -0,0 +1,106 @@
+//! Base32 (RFC 4648, no padding), as used by the TOTP authenticator apps.
+
+const ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+pub fn b32_encode(content: impl AsRef<[u8]>) -> String {
+	let content = content.as_ref();
+	let mut res = String::with_capacity(content.len().div_ceil(5) * 8);
+
+	let mut buffer: u16 = 0;
+	let mut bits: u8 = 0;
+	for &byte in content {
+		buffer = (buffer << 8) | byte as u16;
+		bits += 8;
+		while bits >= 5 {
+			let idx = (buffer >> (bits - 5)) & 0x1f;
+			res.push(ALPHABET[idx as usize] as char);
+			bits -= 5;
+		}
+	}
+	if bits > 0 {
+		let idx = (buffer << (5 - bits)) & 0x1f;
+		res.push(ALPHABET[idx as usize] as char);
+	}
+
+	res
+}
+
+/// Decode a base32 string. Case insensitive, and ignores padding, spaces and dashes
+/// (as users might type them when entering a secret manually).
+pub fn b32_decode(b32: &str) -> Result<Vec<u8>> {
+	let mut res = Vec::with_capacity(b32.len() * 5 / 8);
+
+	let mut buffer: u16 = 0;
+	let mut bits: u8 = 0;
+	for c in b32.chars().filter(|c| !matches!(c, '=' | ' ' | '-')) {
+		let val = ALPHABET
+			.iter()
+			.position(|&a| a as char == c.to_ascii_uppercase())
+			.ok_or(Error::FailToB32Decode)?;
+		buffer = (buffer << 5) | val as u16;
+		bits += 5;
+		if bits >= 8 {
+			res.push((buffer >> (bits - 8)) as u8);
+			bits -= 8;
+		}
+	}
+
+	Ok(res)
+}
+
+// region:    --- Error
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[derive(Debug)]
+pub enum Error {
+	FailToB32Decode,
+}
+
+// endregion: --- Error
+
+// region:    --- Error Boilerplate
+impl core::fmt::Display for Error {
+	fn fmt(
+		&self,
+		fmt: &mut core::fmt::Formatter,
+	) -> core::result::Result<(), core::fmt::Error> {
+		write!(fmt, "{self:?}")
+	}
+}
+
+impl std::error::Error for Error {}
+// endregion: --- Error Boilerplate
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	pub type Result<T> = core::result::Result<T, Error>;
+	pub type Error = Box<dyn std::error::Error>; // For tests.
+
+	use super::*;
+
+	#[test]
+	fn test_b32_rfc4648_vectors_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let fx_vectors = [
+			("", ""),
+			("f", "MY"),
+			("fo", "MZXQ"),
+			("foo", "MZXW6"),
+			("foob", "MZXW6YQ"),
+			("fooba", "MZXW6YTB"),
+			("foobar", "MZXW6YTBOI"),
+		];
+
+		// -- Exec & Check
+		for (content, b32) in fx_vectors {
+			assert_eq!(b32_encode(content), b32);
+			assert_eq!(b32_decode(b32)?, content.as_bytes());
+			assert_eq!(b32_decode(&b32.to_lowercase())?, content.as_bytes());
+		}
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
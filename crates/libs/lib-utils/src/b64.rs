@@ -1,5 +1,16 @@
 use base64::engine::{general_purpose, Engine};
 
+/// The standard base64 (with padding), e.g., for the S3 POST policies.
+pub fn b64_encode(content: impl AsRef<[u8]>) -> String {
+	general_purpose::STANDARD.encode(content)
+}
+
+pub fn b64_decode(b64: &str) -> Result<Vec<u8>> {
+	general_purpose::STANDARD
+		.decode(b64)
+		.map_err(|_| Error::FailToB64Decode)
+}
+
 pub fn b64u_encode(content: impl AsRef<[u8]>) -> String {
 	general_purpose::URL_SAFE_NO_PAD.encode(content)
 }
@@ -23,6 +34,7 @@ pub type Result<T> = core::result::Result<T, Error>;
 
 #[derive(Debug)]
 pub enum Error {
+	FailToB64Decode,
 	FailToB64uDecode,
 }
 
@@ -1,3 +1,4 @@
+pub mod b32;
 pub mod b64;
 pub mod envs;
 pub mod time;
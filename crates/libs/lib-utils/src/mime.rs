@@ -35,7 +35,61 @@ pub fn get_mime_from_bytes(data: &[u8], filename: &str) -> String {
         [0xFF, 0xD8, ..] => "image/jpeg".to_string(),         // JPEG
         [0x89, b'P', b'N', b'G', ..] => "image/png".to_string(), // PNG
         [b'G', b'I', b'F', ..] => "image/gif".to_string(),     // GIF
-        [b'f', b't', b'y', b'p', ..] => "video/mp4".to_string(), // MP4
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P'] => "image/webp".to_string(), // WebP
+        // ISO-BMFF (box size, then "ftyp" and the major brand)
+        [_, _, _, _, b'f', b't', b'y', b'p', b0, b1, b2, b3] => {
+            ftyp_brand_mime(&[*b0, *b1, *b2, *b3]).to_string()
+        }
         _ => get_mime_from_extension(filename),
     }
 }
+
+/// --- Return MIME of an ISO-BMFF major brand (the `ftyp` box)
+/// --- The other formats of the container (HEIC, AVIF, M4A) are not video.
+/// --- If unknown — `application/octet-stream` (not by the extension, the container is sniffed)
+fn ftyp_brand_mime(brand: &[u8; 4]) -> &'static str {
+    match brand {
+        b"qt  " => "video/quicktime", // MOV
+        b"isom" | b"iso2" | b"iso3" | b"iso4" | b"iso5" | b"iso6" | b"mp41" | b"mp42"
+        | b"avc1" | b"dash" | b"M4V " | b"M4VH" | b"M4VP" | b"f4v " | b"MSNV" => "video/mp4",
+        b"3gp4" | b"3gp5" | b"3gp6" | b"3ge6" | b"3gg6" => "video/3gpp",
+        b"heic" | b"heix" | b"heim" | b"heis" | b"mif1" | b"msf1" => "image/heic",
+        b"avif" | b"avis" => "image/avif",
+        b"M4A " | b"M4B " | b"M4P " => "audio/mp4",
+        _ => "application/octet-stream",
+    }
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fx_ftyp(brand: &[u8; 4]) -> Vec<u8> {
+        let mut data = vec![0x00, 0x00, 0x00, 0x20];
+        data.extend_from_slice(b"ftyp");
+        data.extend_from_slice(brand);
+        data.extend_from_slice(&[0x00; 8]);
+        data
+    }
+
+    #[test]
+    fn test_get_mime_from_bytes_ftyp_brands() {
+        let fx_cases: [(&[u8; 4], &str); 8] = [
+            (b"isom", "video/mp4"),
+            (b"mp42", "video/mp4"),
+            (b"avc1", "video/mp4"),
+            (b"qt  ", "video/quicktime"),
+            (b"heic", "image/heic"),
+            (b"avif", "image/avif"),
+            (b"M4A ", "audio/mp4"),
+            (b"abcd", "application/octet-stream"),
+        ];
+
+        for (brand, mime) in fx_cases {
+            // Note: The extension does not matter for a sniffed container.
+            assert_eq!(get_mime_from_bytes(&fx_ftyp(brand), "clip.mp4"), mime, "{brand:?}");
+        }
+    }
+}
+// endregion: --- Tests
@@ -0,0 +1,50 @@
+//! The fixtures of the handler and route tests (requests through the web-server layers).
+
+pub type Result<T> = core::result::Result<T, Error>;
+pub type Error = Box<dyn std::error::Error>; // For tests.
+
+use crate::middleware::mw_auth::{CtxExtResult, CtxW};
+use crate::middleware::mw_req_stamp::mw_req_stamp_resolver;
+use crate::middleware::mw_res_map::mw_reponse_map;
+use axum::body::{to_bytes, Body};
+use axum::http::{HeaderMap, Request, StatusCode};
+use axum::{middleware, Router};
+use lib_core::_dev_utils;
+use lib_core::ctx::Ctx;
+use lib_core::model::ModelManager;
+use serde_json::Value;
+use tower::ServiceExt;
+
+/// A model manager for this test runtime
+/// (the pool of `init_test` is bound to the runtime of the first test).
+pub async fn fx_mm() -> Result<ModelManager> {
+	_dev_utils::init_test().await;
+
+	Ok(ModelManager::new().await?)
+}
+
+/// The request stamp and response map layers, as in the web-server `main`.
+pub fn fx_app(routes: Router) -> Router {
+	routes
+		.layer(middleware::map_response(mw_reponse_map))
+		.layer(middleware::from_fn(mw_req_stamp_resolver))
+}
+
+/// The request ctx, as set by `mw_ctx_resolver` for an authenticated request.
+pub fn with_ctx(mut req: Request<Body>, ctx: Ctx) -> Request<Body> {
+	let ctx_ext_result: CtxExtResult = Ok(CtxW(ctx));
+	req.extensions_mut().insert(ctx_ext_result);
+
+	req
+}
+
+/// Returns the status, headers and json body (`Null` if none).
+pub async fn exec(app: &Router, req: Request<Body>) -> Result<(StatusCode, HeaderMap, Value)> {
+	let res = app.clone().oneshot(req).await?;
+	let status = res.status();
+	let headers = res.headers().clone();
+	let body = to_bytes(res.into_body(), usize::MAX).await?;
+	let body = serde_json::from_slice(&body).unwrap_or(Value::Null);
+
+	Ok((status, headers, body))
+}
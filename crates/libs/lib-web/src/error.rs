@@ -1,8 +1,10 @@
 use std::sync::Arc;
 
 use axum::{http::StatusCode, response::{IntoResponse, Response}};
-use lib_auth::token;
-use lib_core::model;
+use lib_auth::{oidc, token};
+use lib_core::ctx::{self, Permission};
+use lib_core::{model, throttle};
+use lib_storage::media;
 use serde::Serialize;
 use tracing::debug;
 use derive_more::From;
@@ -17,22 +19,52 @@ pub enum Error {
     LoginFailUsernameNotFound,
     LoginFailUserHasNoPwd { user_id: i64 },
     LoginFailPwdNotMatching { user_id: i64 },
+    /// Too many recent failures for this account or ip (backoff).
+    LoginThrottled { retry_after_sec: i64 },
+    /// Too many failures for this account, locked until unlocked by email or expired.
+    LoginAccountLocked { retry_after_sec: i64 },
+
+    // -- OIDC
+    /// The callback state does not match the browser state cookie.
+    OidcStateMismatch,
     
     // -- CtxExtError
     CtxExt(middleware::mw_auth::CtxExtError),
+    PermissionDenied { user_id: i64, permission: Permission },
 
     // -- Extractors
 	ReqStampNotInReqExt,
 
+    // -- Headers
+    /// The `If-Match` header is not an entity version (see `utils::etag`).
+    IfMatchInvalid,
+
+    // -- Tus
+    /// The `Tus-Resumable` header is missing, or not a supported version.
+    TusVersionUnsupported,
+    TusHeaderInvalid(&'static str),
+    /// The PATCH body is not `application/offset+octet-stream`.
+    TusContentTypeInvalid,
+    /// Over the `Tus-Max-Size` (creation), or the `Upload-Length` (PATCH).
+    TusSizeExceeded,
+    TusChecksumAlgorithmUnsupported(String),
+    TusChecksumMismatch,
+    /// The PATCH body was interrupted (the bytes received are kept, if not checksummed).
+    TusBodyInterrupted,
+
     // -- Config
     ConfigMissingEnv(&'static str),
     ConfigWrongFormat(&'static str),
 
     #[from]
 	Token(token::Error),
+    #[from]
+	Oidc(oidc::Error),
     
     // - Modules
 	Model(model::Error),
+    #[from]
+	Storage(lib_storage::Error),
 }
 
 // region: ---- Froms
@@ -41,6 +73,20 @@ impl From<model::Error> for Error {
         Self::Model(val)
     }
 }
+
+impl From<throttle::Error> for Error {
+    fn from(val: throttle::Error) -> Self {
+        match val {
+            throttle::Error::Throttled { retry_after_sec } => {
+                Self::LoginThrottled { retry_after_sec }
+            }
+            throttle::Error::AccountLocked { retry_after_sec } => {
+                Self::LoginAccountLocked { retry_after_sec }
+            }
+            throttle::Error::Model(model_error) => Self::Model(model_error),
+        }
+    }
+}
 // endregion: ---- Froms
 
 // region:    --- Error Boilerplate
@@ -72,6 +118,15 @@ impl IntoResponse for Error {
 
 // region: ---- Error Boilerplate
 impl Error {
+    /// The delay for the `Retry-After` header, if the request can be retried later.
+    pub fn retry_after_sec(&self) -> Option<i64> {
+        match self {
+            Self::LoginThrottled { retry_after_sec }
+            | Self::LoginAccountLocked { retry_after_sec } => Some(*retry_after_sec),
+            _ => None,
+        }
+    }
+
     pub fn client_status_and_error(&self) -> (StatusCode, ClientError) {
         use Error::*;
 
@@ -83,15 +138,200 @@ impl Error {
             | Self::LoginFailPwdNotMatching { user_id: _ } => {
                 (StatusCode::FORBIDDEN, ClientError::LOGIN_FAIL)
             }
+            Self::LoginThrottled { retry_after_sec } => (
+                StatusCode::TOO_MANY_REQUESTS,
+                ClientError::LOGIN_THROTTLED { retry_after_sec: *retry_after_sec },
+            ),
+            Self::LoginAccountLocked { retry_after_sec } => (
+                StatusCode::TOO_MANY_REQUESTS,
+                ClientError::LOGIN_LOCKED { retry_after_sec: *retry_after_sec },
+            ),
+            Self::Model(
+                model::Error::UnlockTokenInvalid | model::Error::UnlockTokenExpired,
+            ) => (StatusCode::FORBIDDEN, ClientError::UNLOCK_LINK_INVALID),
 
             // -- Auth
             CtxExt(_) => (StatusCode::FORBIDDEN, ClientError::LOGIN_FAIL),
+            PermissionDenied { .. }
+            | Self::Model(model::Error::Ctx(ctx::Error::PermissionDenied { .. })) => {
+                (StatusCode::FORBIDDEN, ClientError::NO_AUTH)
+            }
+
+            Token(
+                token::Error::InvalidToken
+                | token::Error::CannotDecodeIdent
+                | token::Error::CannotDecodeExp
+                | token::Error::TokenSignatureMismatch
+                | token::Error::ExpNotIso
+                | token::Error::ExpiredToken
+                | token::Error::TokenDecodeFailed
+                | token::Error::Unauthorized
+                | token::Error::InvalidSubject,
+            ) => (StatusCode::UNAUTHORIZED, ClientError::NO_AUTH),
+
+            // -- Session
+            Self::Model(
+                model::Error::SessionNotFound
+                | model::Error::SessionRevoked
+                | model::Error::SessionExpired
+                | model::Error::SessionTokenReused { .. },
+            ) => (StatusCode::UNAUTHORIZED, ClientError::NO_AUTH),
+
+            // -- Login Link
+            Self::Model(
+                model::Error::LoginLinkTokenInvalid | model::Error::LoginLinkTokenExpired,
+            ) => (StatusCode::FORBIDDEN, ClientError::LOGIN_LINK_INVALID),
+
+            // -- MFA
+            Self::Model(model::Error::MfaCodeInvalid) => {
+                (StatusCode::FORBIDDEN, ClientError::MFA_CODE_INVALID)
+            }
+            Self::Model(
+                model::Error::MfaAlreadyEnabled
+                | model::Error::MfaNotEnabled
+                | model::Error::MfaNotSetup,
+            ) => (StatusCode::BAD_REQUEST, ClientError::MFA_STATE_INVALID),
+
+            // -- OIDC
+            Oidc(oidc::Error::ProviderUnknown(_)) => {
+                (StatusCode::NOT_FOUND, ClientError::OIDC_PROVIDER_UNKNOWN)
+            }
+            Oidc(oidc::Error::ConfigMissingEnv(_)) => {
+                (StatusCode::INTERNAL_SERVER_ERROR, ClientError::SERVICE_ERROR)
+            }
+            Oidc(_)
+            | OidcStateMismatch
+            | Self::Model(model::Error::OidcStateInvalid) => {
+                (StatusCode::FORBIDDEN, ClientError::LOGIN_FAIL)
+            }
+            Self::Model(model::Error::OidcEmailNotVerified) => {
+                (StatusCode::FORBIDDEN, ClientError::OIDC_EMAIL_NOT_VERIFIED)
+            }
+            Self::Model(
+                model::Error::OidcLinkRequiresLogin | model::Error::OidcIdentityAlreadyLinked,
+            ) => (StatusCode::CONFLICT, ClientError::OIDC_LINK_CONFLICT),
+
+            // -- Model
+            Self::Model(model::Error::EntityNotFound { entity, id }) => (
+                StatusCode::NOT_FOUND,
+                ClientError::ENTITY_NOT_FOUND { entity, id: *id },
+            ),
+
+            Self::Model(model::Error::VersionConflict {
+                entity,
+                id,
+                current_version,
+            }) => (
+                StatusCode::CONFLICT,
+                ClientError::VERSION_CONFLICT {
+                    entity,
+                    id: *id,
+                    current_version: *current_version,
+                },
+            ),
+            IfMatchInvalid => (
+                StatusCode::BAD_REQUEST,
+                ClientError::RPC_REQUEST_INVALID("If-Match invalid".to_string()),
+            ),
 
             // -- Validation
             Self::Model(model::Error::ValidationFail(_)) => {
                 (StatusCode::BAD_REQUEST, ClientError::RPC_PARAMS_INVALID("Validation failed".to_string()))
             }
 
+            // -- Geo
+            Self::Model(model::Error::Geo(_)) => (
+                StatusCode::BAD_REQUEST,
+                ClientError::RPC_PARAMS_INVALID("location invalid".to_string()),
+            ),
+
+            // -- Media
+            Self::Model(model::Error::MediaUploadMissing { .. }) => (
+                StatusCode::CONFLICT,
+                ClientError::RPC_REQUEST_INVALID("media file not uploaded".to_string()),
+            ),
+            Self::Model(model::Error::MediaUploadNotPending { .. }) => (
+                StatusCode::CONFLICT,
+                ClientError::RPC_REQUEST_INVALID("media upload already completed".to_string()),
+            ),
+            Self::Model(model::Error::MediaTypeMismatch { declared, sniffed }) => (
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                ClientError::RPC_PARAMS_INVALID(format!("media file is '{sniffed}', not '{declared}'")),
+            ),
+            Self::Model(model::Error::MediaSizeMismatch { declared, actual }) => (
+                StatusCode::BAD_REQUEST,
+                ClientError::RPC_PARAMS_INVALID(format!("media file is {actual} bytes, not {declared}")),
+            ),
+            Self::Model(model::Error::MediaUploadExpired { .. }) => (
+                StatusCode::GONE,
+                ClientError::RPC_REQUEST_INVALID("media upload expired".to_string()),
+            ),
+            Self::Model(model::Error::Storage(
+                lib_storage::Error::Media(media::Error::FormatUnsupported | media::Error::Decode(_)),
+            )) => (
+                StatusCode::BAD_REQUEST,
+                ClientError::RPC_PARAMS_INVALID("image invalid".to_string()),
+            ),
+            Self::Model(model::Error::Storage(lib_storage::Error::PresignInvalid(_))) => (
+                StatusCode::BAD_REQUEST,
+                ClientError::RPC_PARAMS_INVALID("upload constraints invalid".to_string()),
+            ),
+            Self::Model(model::Error::Storage(lib_storage::Error::PresignUnsupported(_))) => (
+                StatusCode::NOT_IMPLEMENTED,
+                ClientError::RPC_REQUEST_INVALID("direct upload not supported by the storage".to_string()),
+            ),
+
+            // -- Tus
+            TusVersionUnsupported => (
+                StatusCode::PRECONDITION_FAILED,
+                ClientError::RPC_REQUEST_INVALID("Tus-Resumable version unsupported".to_string()),
+            ),
+            TusHeaderInvalid(name) => (
+                StatusCode::BAD_REQUEST,
+                ClientError::RPC_REQUEST_INVALID(format!("{name} invalid")),
+            ),
+            TusContentTypeInvalid => (
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                ClientError::RPC_REQUEST_INVALID(
+                    "Content-Type must be application/offset+octet-stream".to_string(),
+                ),
+            ),
+            TusSizeExceeded => (
+                StatusCode::PAYLOAD_TOO_LARGE,
+                ClientError::RPC_REQUEST_INVALID("upload size exceeded".to_string()),
+            ),
+            TusChecksumAlgorithmUnsupported(algorithm) => (
+                StatusCode::BAD_REQUEST,
+                ClientError::RPC_REQUEST_INVALID(format!("checksum algorithm '{algorithm}' unsupported")),
+            ),
+            // Note: 460 is the tus "Checksum Mismatch" status.
+            TusChecksumMismatch => (
+                StatusCode::from_u16(460).unwrap_or(StatusCode::BAD_REQUEST),
+                ClientError::RPC_REQUEST_INVALID("checksum mismatch".to_string()),
+            ),
+            TusBodyInterrupted => (
+                StatusCode::BAD_REQUEST,
+                ClientError::RPC_REQUEST_INVALID("body interrupted".to_string()),
+            ),
+            Self::Model(model::Error::MediaUploadOffsetConflict { .. }) => (
+                StatusCode::CONFLICT,
+                ClientError::RPC_REQUEST_INVALID("Upload-Offset conflict".to_string()),
+            ),
+
+            // -- List
+            Self::Model(model::Error::ListLimitOverMax { max, .. }) => (
+                StatusCode::BAD_REQUEST,
+                ClientError::RPC_PARAMS_INVALID(format!("limit over max ({max})")),
+            ),
+            Self::Model(model::Error::PageCursorInvalid) => (
+                StatusCode::BAD_REQUEST,
+                ClientError::RPC_PARAMS_INVALID("cursor invalid".to_string()),
+            ),
+            Self::Model(model::Error::PageOrderByInvalid { order_by }) => (
+                StatusCode::BAD_REQUEST,
+                ClientError::RPC_PARAMS_INVALID(format!("order_by '{order_by}' invalid")),
+            ),
+
             // -- Fallback
             _ => (
                 StatusCode::INTERNAL_SERVER_ERROR, 
@@ -109,8 +349,22 @@ impl Error {
 pub enum ClientError {
 	LOGIN_FAIL,
 	NO_AUTH,
+	LOGIN_LINK_INVALID,
+	LOGIN_THROTTLED { retry_after_sec: i64 },
+	/// Too many failures, an unlock link was sent to the account email.
+	LOGIN_LOCKED { retry_after_sec: i64 },
+	UNLOCK_LINK_INVALID,
+	MFA_CODE_INVALID,
+	MFA_STATE_INVALID,
+	OIDC_PROVIDER_UNKNOWN,
+	OIDC_EMAIL_NOT_VERIFIED,
+	/// The provider account is linked to another user, or its email belongs to
+	/// an existing account (to login first, then link).
+	OIDC_LINK_CONFLICT,
     // SERVICE_ERROR,
 	ENTITY_NOT_FOUND { entity: &'static str, id: i64 },
+	/// Updated in between (e.g., by another device), to reload and retry with this version.
+	VERSION_CONFLICT { entity: &'static str, id: i64, current_version: i64 },
 
 	RPC_REQUEST_INVALID(String),
 	RPC_REQUEST_METHOD_UNKNOWN(String),
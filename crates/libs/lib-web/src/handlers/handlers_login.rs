@@ -1,19 +1,26 @@
 use crate::error::{Error, Result};
+use crate::middleware::mw_auth::CtxW;
+use crate::utils::client_info::ClientInfo;
 use crate::utils::token;
 use axum::extract::State;
 use axum::Json;
 use lib_auth::pwd::{self, ContentToHash, SchemeStatus};
+use lib_auth::token::{generate_mfa_pending_token, generate_web_tokens, validate_web_token};
 use lib_core::ctx::Ctx;
-use lib_core::model::user::{UserBmc, UserDTO, UserForLogin};
+use lib_core::model::session::{SessionBmc, SessionForCreate};
+use lib_core::model::user::{UserBmc, UserDTO, UserForLogin, UserForMfa};
 use lib_core::model::ModelManager;
+use lib_core::throttle::{LoginThrottle, PgThrottleStore};
 use serde::{Deserialize, Serialize};
 use tower_cookies::Cookies;
 use tracing::debug;
+use uuid::Uuid;
 
 // region:    --- Login
 pub async fn api_login_handler(
 	State(mm): State<ModelManager>,
 	cookies: Cookies,
+	client_info: ClientInfo,
 	Json(payload): Json<LoginPayload>,
 ) -> Result<Json<LoginResponse>> {
 	debug!("{:<12} - api_login_handler", "HANDLER");
@@ -21,30 +28,53 @@ pub async fn api_login_handler(
 	let LoginPayload {
 		username,
 		pwd: pwd_clear,
+		device,
 	} = payload;
 
 	let root_ctx = Ctx::root_ctx();
+	let ip = client_info.ip.clone();
+	let throttle = LoginThrottle::new_pg(&mm);
 
-	// -- Get the user.
-	let user: UserForLogin = UserBmc::first_by_username(&root_ctx, &mm, &username)
-		.await?
-		.ok_or(Error::LoginFailUsernameNotFound)?;
-	let user_id = user.id;
+	// -- Check the throttle (before any credential check).
+	throttle.check(&username, ip.as_deref()).await?;
+
+	// -- Get the user and validate the password.
+	let res = async {
+		let user: UserForLogin = UserBmc::first_by_username(&root_ctx, &mm, &username)
+			.await?
+			.ok_or(Error::LoginFailUsernameNotFound)?;
+		let user_id = user.id;
+
+		let Some(pwd) = user.pwd.clone() else {
+			return Err(Error::LoginFailUserHasNoPwd { user_id });
+		};
+
+		let scheme_status = pwd::validate_pwd(
+			ContentToHash {
+				salt: user.pwd_salt,
+				content: pwd_clear.clone(),
+			},
+			pwd,
+		)
+		.await
+		.map_err(|_| Error::LoginFailPwdNotMatching { user_id })?;
+
+		Ok((user, scheme_status))
+	}
+	.await;
 
-	// -- Validate the password.
-	let Some(pwd) = user.pwd else {
-		return Err(Error::LoginFailUserHasNoPwd { user_id });
+	let (user, scheme_status) = match res {
+		Ok(res) => res,
+		Err(err) => {
+			record_login_failure(&mm, &throttle, &username, ip.as_deref()).await?;
+			return Err(err);
+		}
 	};
 
-	let scheme_status = pwd::validate_pwd(
-		ContentToHash {
-			salt: user.pwd_salt,
-			content: pwd_clear.clone(),
-		},
-		pwd,
-	)
-	.await
-	.map_err(|_| Error::LoginFailPwdNotMatching { user_id })?;
+	// Note: With mfa, the counter is reset after the second factor.
+	if !user.mfa_enabled {
+		throttle.record_success(&username).await?;
+	}
 
 	// -- Update password scheme if needed
 	if let SchemeStatus::Outdated = scheme_status {
@@ -52,22 +82,18 @@ pub async fn api_login_handler(
 		UserBmc::update_pwd(&root_ctx, &mm, user.id, &pwd_clear).await?;
 	}
 
-	// -- Set web token.
-	let access_token = token::set_token_cookie(&cookies, &user.username, user.token_salt)?;
+	let response = login_response(&mm, &cookies, client_info, device, user).await?;
 
-	// Create the success body.
-	 Ok(Json(LoginResponse {
-        success: true,
-        message: format!("Welcome back, {}!", username),
-        user: UserDTO { id: user_id, username },
-        token: Some(access_token),
-    }))
+	Ok(Json(response))
 }
 
 #[derive(Debug, Deserialize)]
 pub struct LoginPayload {
 	username: String,
 	pwd: String,
+	/// Optional device label from the client app (e.g., "Alice's iPhone").
+	#[serde(default)]
+	device: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -76,12 +102,307 @@ pub struct LoginResponse {
 	message: String,
 	user: UserDTO,
 	token: Option<String>,
+	refresh_token: Option<String>,
+	/// When true, no session was created yet, and `mfa_token` must be sent
+	/// with the TOTP (or recovery) code to `/api/login/mfa`.
+	mfa_required: bool,
+	mfa_token: Option<String>,
 }
 // endregion: --- Login
 
+// region:    --- Login MFA
+pub async fn api_login_mfa_handler(
+	State(mm): State<ModelManager>,
+	cookies: Cookies,
+	client_info: ClientInfo,
+	Json(payload): Json<LoginMfaPayload>,
+) -> Result<Json<LoginResponse>> {
+	debug!("{:<12} - api_login_mfa_handler", "HANDLER");
+
+	let LoginMfaPayload {
+		mfa_token,
+		code,
+		device,
+	} = payload;
+
+	let root_ctx = Ctx::root_ctx();
+
+	// -- Check the mfa pending token.
+	let claims = validate_web_token(&mfa_token)?;
+	if claims.typ != "mfa_pending" {
+		return Err(Error::Token(lib_auth::token::Error::InvalidToken));
+	}
+
+	let user: UserForMfa = UserBmc::first_by_username(&root_ctx, &mm, &claims.sub)
+		.await?
+		.ok_or(Error::LoginFailUsernameNotFound)?;
+
+	if claims.salt != user.token_salt.to_string() {
+		return Err(Error::Token(lib_auth::token::Error::InvalidToken));
+	}
+
+	// -- Verify the second factor.
+	let ip = client_info.ip.clone();
+	let throttle = LoginThrottle::new_pg(&mm);
+	throttle.check(&user.username, ip.as_deref()).await?;
+
+	if let Err(err) = UserBmc::mfa_verify(&root_ctx, &mm, user.id, &code).await {
+		record_login_failure(&mm, &throttle, &user.username, ip.as_deref()).await?;
+		return Err(err.into());
+	}
+	throttle.record_success(&user.username).await?;
+
+	// -- Create the session and set web token.
+	let (access_token, refresh_token) = create_session_and_tokens(
+		&mm,
+		&cookies,
+		client_info,
+		device,
+		user.id,
+		&user.username,
+		user.token_salt,
+	)
+	.await?;
+
+	Ok(Json(LoginResponse {
+		success: true,
+		message: format!("Welcome back, {}!", user.username),
+		user: UserDTO {
+			id: user.id,
+			username: user.username,
+		},
+		token: Some(access_token),
+		refresh_token: Some(refresh_token),
+		mfa_required: false,
+		mfa_token: None,
+	}))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LoginMfaPayload {
+	mfa_token: String,
+	/// TOTP code, or one of the recovery codes.
+	code: String,
+	#[serde(default)]
+	device: Option<String>,
+}
+// endregion: --- Login MFA
+
+// region:    --- Login Link
+/// Send a one-time login link by email (passwordless login).
+/// Always succeeds, so the response does not tell if the account exists.
+pub async fn api_login_link_request_handler(
+	State(mm): State<ModelManager>,
+	Json(payload): Json<LoginLinkRequestPayload>,
+) -> Result<Json<LoginLinkRequestResponse>> {
+	debug!("{:<12} - api_login_link_request_handler", "HANDLER");
+
+	let root_ctx = Ctx::root_ctx();
+
+	UserBmc::request_login_link(&root_ctx, &mm, payload.email.trim()).await?;
+
+	Ok(Json(LoginLinkRequestResponse {
+		success: true,
+		message: "If an account exists for this email, a sign-in link was sent.".to_string(),
+	}))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LoginLinkRequestPayload {
+	email: String,
+}
+
+#[derive(Serialize)]
+pub struct LoginLinkRequestResponse {
+	success: bool,
+	message: String,
+}
+
+/// Login with the token of a login link (single use).
+pub async fn api_login_link_handler(
+	State(mm): State<ModelManager>,
+	cookies: Cookies,
+	client_info: ClientInfo,
+	Json(payload): Json<LoginLinkPayload>,
+) -> Result<Json<LoginResponse>> {
+	debug!("{:<12} - api_login_link_handler", "HANDLER");
+
+	let LoginLinkPayload { token, device } = payload;
+
+	let root_ctx = Ctx::root_ctx();
+
+	let user: UserForLogin = UserBmc::consume_login_link(&root_ctx, &mm, &token).await?;
+
+	let response = login_response(&mm, &cookies, client_info, device, user).await?;
+
+	Ok(Json(response))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LoginLinkPayload {
+	token: String,
+	#[serde(default)]
+	device: Option<String>,
+}
+// endregion: --- Login Link
+
+// region:    --- Account Unlock
+/// Unlock an account locked after too many login failures, with the token
+/// of the account locked email (single use).
+pub async fn api_login_unlock_handler(
+	State(mm): State<ModelManager>,
+	Json(payload): Json<LoginUnlockPayload>,
+) -> Result<Json<LoginUnlockResponse>> {
+	debug!("{:<12} - api_login_unlock_handler", "HANDLER");
+
+	let root_ctx = Ctx::root_ctx();
+
+	let username = UserBmc::consume_unlock_token(&root_ctx, &mm, &payload.token).await?;
+	LoginThrottle::new_pg(&mm).unlock(&username).await?;
+
+	Ok(Json(LoginUnlockResponse {
+		success: true,
+		message: "Your account is unlocked, you can sign in again.".to_string(),
+	}))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LoginUnlockPayload {
+	token: String,
+}
+
+#[derive(Serialize)]
+pub struct LoginUnlockResponse {
+	success: bool,
+	message: String,
+}
+// endregion: --- Account Unlock
+
+// region:    --- Support
+
+/// Record a failed login attempt, and send the unlock email when it locks the account.
+async fn record_login_failure(
+	mm: &ModelManager,
+	throttle: &LoginThrottle<PgThrottleStore>,
+	username: &str,
+	ip: Option<&str>,
+) -> Result<()> {
+	let outcome = throttle.record_failure(username, ip).await?;
+
+	if outcome.account_locked {
+		tracing::warn!("Account '{}' locked after too many login failures", username);
+		let locked_min = throttle.policy().lockout_sec / 60;
+		UserBmc::notify_account_locked(&Ctx::root_ctx(), mm, username, locked_min).await?;
+	}
+
+	Ok(())
+}
+
+/// Complete the login of an authenticated user (password or OIDC).
+/// Returns the mfa pending token if the second factor is enabled, otherwise
+/// creates the session and sets the web token.
+pub(crate) async fn login_response(
+	mm: &ModelManager,
+	cookies: &Cookies,
+	client_info: ClientInfo,
+	device: Option<String>,
+	user: UserForLogin,
+) -> Result<LoginResponse> {
+	let UserForLogin {
+		id: user_id,
+		username,
+		token_salt,
+		mfa_enabled,
+		..
+	} = user;
+
+	// -- Second factor (verified by `api_login_mfa_handler`).
+	if mfa_enabled {
+		let mfa_token = generate_mfa_pending_token(&username, token_salt)?;
+
+		return Ok(LoginResponse {
+			success: true,
+			message: "Second factor required".to_string(),
+			user: UserDTO { id: user_id, username },
+			token: None,
+			refresh_token: None,
+			mfa_required: true,
+			mfa_token: Some(mfa_token),
+		});
+	}
+
+	// -- Create the session and set web token.
+	let (access_token, refresh_token) = create_session_and_tokens(
+		mm,
+		cookies,
+		client_info,
+		device,
+		user_id,
+		&username,
+		token_salt,
+	)
+	.await?;
+
+	Ok(LoginResponse {
+		success: true,
+		message: format!("Welcome back, {}!", username),
+		user: UserDTO { id: user_id, username },
+		token: Some(access_token),
+		refresh_token: Some(refresh_token),
+		mfa_required: false,
+		mfa_token: None,
+	})
+}
+
+/// Create the session (refresh token family), set the access token cookie,
+/// and return the (access, refresh) tokens.
+async fn create_session_and_tokens(
+	mm: &ModelManager,
+	cookies: &Cookies,
+	client_info: ClientInfo,
+	device: Option<String>,
+	user_id: i64,
+	username: &str,
+	token_salt: Uuid,
+) -> Result<(String, String)> {
+	let ClientInfo {
+		device: ua_device,
+		user_agent,
+		ip,
+		location,
+	} = client_info;
+	let session_ids = SessionBmc::create(
+		&Ctx::root_ctx(),
+		mm,
+		SessionForCreate {
+			user_id,
+			device: device.or(ua_device),
+			user_agent,
+			ip,
+			location,
+		},
+	)
+	.await?;
+
+	let access_token =
+		token::set_token_cookie(cookies, username, token_salt, session_ids.family_id)?;
+	let (_, refresh_token) = generate_web_tokens(
+		username,
+		token_salt,
+		session_ids.family_id,
+		session_ids.jti,
+	)?;
+
+	Ok((access_token, refresh_token))
+}
+
+// endregion: --- Support
+
 // region:    --- Logout
 pub async fn api_logout_handler(
+	State(mm): State<ModelManager>,
 	cookies: Cookies,
+	ctx: Result<CtxW>,
 	Json(payload): Json<LogoutPayload>,
 ) -> Result<Json<LogoutResponse>> {
 	debug!("{:<12} - api_logout_handler", "HANDLER");
@@ -89,6 +410,13 @@ pub async fn api_logout_handler(
 	let should_logout = payload.logout;
 
 	if should_logout {
+		// -- Revoke the current session (if logged in with one).
+		if let Ok(CtxW(ctx)) = ctx
+			&& let Some(session_id) = ctx.session_id()
+		{
+			SessionBmc::revoke(&ctx, &mm, session_id).await?;
+		}
+
 		token::remove_token_cookie(&cookies)?;
 	}
 
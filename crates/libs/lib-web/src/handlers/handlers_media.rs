@@ -0,0 +1,49 @@
+use crate::error::Result;
+use crate::middleware::mw_auth::CtxW;
+use axum::extract::{Path, State};
+use axum::Json;
+use lib_core::model::post_media::{
+	PostMedia, PostMediaBmc, PostMediaForUploadSlot, PostMediaUploadSlot,
+};
+use lib_core::model::ModelManager;
+use lib_storage::Storage;
+use tracing::debug;
+
+/// The state of the media handlers (they need the storage of the files).
+#[derive(Clone)]
+pub struct MediaState {
+	pub mm: ModelManager,
+	pub storage: Storage,
+}
+
+// region:    --- Direct Uploads
+/// An upload slot for a media of the post: a pending media, and the presigned upload
+/// of its file, for the client to upload it directly to the storage. Then, to complete
+/// it (see `api_complete_media_upload_handler`).
+pub async fn api_create_media_upload_handler(
+	State(state): State<MediaState>,
+	CtxW(ctx): CtxW,
+	Path(post_id): Path<i64>,
+	Json(slot_c): Json<PostMediaForUploadSlot>,
+) -> Result<Json<PostMediaUploadSlot>> {
+	debug!("{:<12} - api_create_media_upload_handler", "HANDLER");
+
+	let slot =
+		PostMediaBmc::create_upload_slot(&ctx, &state.mm, &state.storage, post_id, slot_c).await?;
+
+	Ok(Json(slot))
+}
+
+/// Complete the upload of the media, once its file is uploaded (checked, then ready).
+pub async fn api_complete_media_upload_handler(
+	State(state): State<MediaState>,
+	CtxW(ctx): CtxW,
+	Path(id): Path<i64>,
+) -> Result<Json<PostMedia>> {
+	debug!("{:<12} - api_complete_media_upload_handler", "HANDLER");
+
+	let media = PostMediaBmc::complete_upload(&ctx, &state.mm, &state.storage, id).await?;
+
+	Ok(Json(media))
+}
+// endregion: --- Direct Uploads
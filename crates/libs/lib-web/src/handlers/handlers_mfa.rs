@@ -0,0 +1,72 @@
+use crate::error::Result;
+use crate::middleware::mw_auth::CtxW;
+use axum::extract::State;
+use axum::Json;
+use lib_core::model::user::{MfaSetup, UserBmc};
+use lib_core::model::ModelManager;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+// region:    --- MFA Setup
+/// Generate a new TOTP secret for the ctx user. The returned `otpauth_uri` is
+/// typically displayed as a QR code for the authenticator app.
+pub async fn api_mfa_setup_handler(
+	State(mm): State<ModelManager>,
+	CtxW(ctx): CtxW,
+) -> Result<Json<MfaSetup>> {
+	debug!("{:<12} - api_mfa_setup_handler", "HANDLER");
+
+	let setup = UserBmc::mfa_setup(&ctx, &mm, ctx.user_id()).await?;
+
+	Ok(Json(setup))
+}
+// endregion: --- MFA Setup
+
+// region:    --- MFA Enable
+pub async fn api_mfa_enable_handler(
+	State(mm): State<ModelManager>,
+	CtxW(ctx): CtxW,
+	Json(payload): Json<MfaCodePayload>,
+) -> Result<Json<MfaEnableResponse>> {
+	debug!("{:<12} - api_mfa_enable_handler", "HANDLER");
+
+	let recovery_codes = UserBmc::mfa_enable(&ctx, &mm, ctx.user_id(), &payload.code).await?;
+
+	Ok(Json(MfaEnableResponse {
+		success: true,
+		recovery_codes,
+	}))
+}
+
+#[derive(Serialize)]
+pub struct MfaEnableResponse {
+	success: bool,
+	/// Shown only once, the user must store them.
+	recovery_codes: Vec<String>,
+}
+// endregion: --- MFA Enable
+
+// region:    --- MFA Disable
+pub async fn api_mfa_disable_handler(
+	State(mm): State<ModelManager>,
+	CtxW(ctx): CtxW,
+	Json(payload): Json<MfaCodePayload>,
+) -> Result<Json<MfaDisableResponse>> {
+	debug!("{:<12} - api_mfa_disable_handler", "HANDLER");
+
+	UserBmc::mfa_disable(&ctx, &mm, ctx.user_id(), &payload.code).await?;
+
+	Ok(Json(MfaDisableResponse { success: true }))
+}
+
+#[derive(Serialize)]
+pub struct MfaDisableResponse {
+	success: bool,
+}
+// endregion: --- MFA Disable
+
+#[derive(Debug, Deserialize)]
+pub struct MfaCodePayload {
+	/// TOTP code (or a recovery code, for disable).
+	code: String,
+}
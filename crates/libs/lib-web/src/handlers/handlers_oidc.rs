@@ -0,0 +1,130 @@
+use crate::error::{Error, Result};
+use crate::handlers::handlers_login::{login_response, LoginResponse};
+use crate::middleware::mw_auth::CtxW;
+use crate::utils::client_info::ClientInfo;
+use axum::extract::{Path, Query, State};
+use axum::response::Redirect;
+use axum::Json;
+use lib_auth::oidc::{self, oidc_config};
+use lib_core::ctx::Ctx;
+use lib_core::model::oidc_login_state::{OidcLoginStateBmc, OidcLoginStateForCreate};
+use lib_core::model::user::{UserBmc, UserForLogin};
+use lib_core::model::user_identity::UserIdentityBmc;
+use lib_core::model::ModelManager;
+use serde::Deserialize;
+use tower_cookies::cookie::SameSite;
+use tower_cookies::{Cookie, Cookies};
+use tracing::debug;
+
+const OIDC_STATE_COOKIE: &str = "oidc-state";
+const OIDC_COOKIE_PATH: &str = "/api/oidc";
+
+// region:    --- OIDC Start
+/// Redirect to the provider authorization page.
+/// When called by a logged in user, the provider account is linked to this user.
+pub async fn api_oidc_start_handler(
+	State(mm): State<ModelManager>,
+	cookies: Cookies,
+	ctx: Result<CtxW>,
+	Path(provider): Path<String>,
+) -> Result<Redirect> {
+	debug!("{:<12} - api_oidc_start_handler", "HANDLER");
+
+	let provider = oidc_config()?.provider(&provider)?;
+	let auth_req = oidc::authorization_request(provider)?;
+
+	OidcLoginStateBmc::create(
+		&Ctx::root_ctx(),
+		&mm,
+		OidcLoginStateForCreate {
+			state: auth_req.state.clone(),
+			provider: provider.name.clone(),
+			nonce: auth_req.nonce,
+			pkce_verifier: auth_req.pkce_verifier,
+			link_user_id: ctx.ok().map(|CtxW(ctx)| ctx.user_id()),
+		},
+	)
+	.await?;
+
+	// Note: The state cookie binds the callback to this browser (login CSRF).
+	let mut cookie = Cookie::new(OIDC_STATE_COOKIE, auth_req.state);
+	cookie.set_http_only(true);
+	cookie.set_secure(!cfg!(debug_assertions)); // true only in release
+	cookie.set_same_site(SameSite::Lax); // sent on the provider redirect
+	cookie.set_path(OIDC_COOKIE_PATH);
+	cookie.set_max_age(time::Duration::minutes(10));
+	cookies.add(cookie);
+
+	Ok(Redirect::to(&auth_req.url))
+}
+// endregion: --- OIDC Start
+
+// region:    --- OIDC Callback
+pub async fn api_oidc_callback_handler(
+	State(mm): State<ModelManager>,
+	cookies: Cookies,
+	client_info: ClientInfo,
+	Path(provider): Path<String>,
+	Query(params): Query<OidcCallbackParams>,
+) -> Result<Json<LoginResponse>> {
+	debug!("{:<12} - api_oidc_callback_handler", "HANDLER");
+
+	let root_ctx = Ctx::root_ctx();
+
+	if let Some(error) = params.error {
+		return Err(Error::Oidc(oidc::Error::ProviderError(error)));
+	}
+	let (Some(code), Some(state)) = (params.code, params.state) else {
+		return Err(Error::Oidc(oidc::Error::ProviderError(
+			"missing code or state".to_string(),
+		)));
+	};
+
+	// -- Check the state (same browser, single use, same provider).
+	let cookie_state = cookies.get(OIDC_STATE_COOKIE).map(|c| c.value().to_string());
+	let mut cookie = Cookie::from(OIDC_STATE_COOKIE);
+	cookie.set_path(OIDC_COOKIE_PATH);
+	cookies.remove(cookie);
+
+	if cookie_state.as_deref() != Some(state.as_str()) {
+		return Err(Error::OidcStateMismatch);
+	}
+	let login_state = OidcLoginStateBmc::take(&root_ctx, &mm, &state).await?;
+	if login_state.provider != provider {
+		return Err(Error::OidcStateMismatch);
+	}
+
+	// -- Exchange the code and validate the ID token.
+	let provider = oidc_config()?.provider(&provider)?;
+	let claims = oidc::exchange_code(
+		provider,
+		&code,
+		&login_state.pkce_verifier,
+		&login_state.nonce,
+	)
+	.await?;
+
+	// -- Get (link or create) the user.
+	let user_id = UserIdentityBmc::login_or_link(
+		&root_ctx,
+		&mm,
+		&provider.name,
+		&claims,
+		login_state.link_user_id,
+	)
+	.await?;
+	let user: UserForLogin = UserBmc::get(&root_ctx, &mm, user_id).await?;
+
+	let response = login_response(&mm, &cookies, client_info, None, user).await?;
+
+	Ok(Json(response))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OidcCallbackParams {
+	code: Option<String>,
+	state: Option<String>,
+	/// Set by the provider when the user denied the access (e.g., "access_denied").
+	error: Option<String>,
+}
+// endregion: --- OIDC Callback
@@ -0,0 +1,97 @@
+use crate::error::Result;
+use crate::middleware::mw_auth::{perm, CtxPerm, CtxW};
+use crate::utils::etag::{if_match_version, version_etag};
+use axum::extract::{Path, Query, State};
+use axum::http::header::ETAG;
+use axum::http::{HeaderMap, HeaderName};
+use axum::Json;
+use lib_core::model::audit_log::AuditLogEntry;
+use lib_core::model::post::{Post, PostBmc, PostForUpdate};
+use lib_core::model::search::{PostHit, PostSearch};
+use lib_core::model::{ModelManager, Page, PageOptions};
+use tracing::debug;
+
+// region:    --- Post Feed
+/// A page of the posts visible to the user, e.g., `?order_by=!id&limit=20`, then
+/// `&cursor=<next_cursor>` for the next one (infinite scroll).
+pub async fn api_list_posts_handler(
+	State(mm): State<ModelManager>,
+	CtxW(ctx): CtxW,
+	Query(page_options): Query<PageOptions>,
+) -> Result<Json<Page<Post>>> {
+	debug!("{:<12} - api_list_posts_handler", "HANDLER");
+
+	let page = PostBmc::list_page(&ctx, &mm, None, page_options).await?;
+
+	Ok(Json(page))
+}
+// endregion: --- Post Feed
+
+// region:    --- Post Search
+/// Search the posts, e.g., `?q=hiking dolomites&lang=en&limit=20`, then
+/// `&cursor=<next_cursor>` for the next page.
+pub async fn api_search_posts_handler(
+	State(mm): State<ModelManager>,
+	CtxW(ctx): CtxW,
+	Query(search): Query<PostSearch>,
+	Query(page_options): Query<PageOptions>,
+) -> Result<Json<Page<PostHit>>> {
+	debug!("{:<12} - api_search_posts_handler", "HANDLER");
+
+	let page = PostBmc::search(&ctx, &mm, search, page_options).await?;
+
+	Ok(Json(page))
+}
+// endregion: --- Post Search
+
+// region:    --- Post Get & Update
+/// The post, with its version as `ETag` (for the `If-Match` of the update).
+pub async fn api_get_post_handler(
+	State(mm): State<ModelManager>,
+	CtxW(ctx): CtxW,
+	Path(id): Path<i64>,
+) -> Result<([(HeaderName, String); 1], Json<Post>)> {
+	debug!("{:<12} - api_get_post_handler", "HANDLER");
+
+	let post = PostBmc::get(&ctx, &mm, id).await?;
+
+	Ok(([(ETAG, version_etag(post.version))], Json(post)))
+}
+
+/// Update the post. With an `If-Match` version, only if not updated in between
+/// (e.g., by another device), otherwise 409 with the current version.
+pub async fn api_update_post_handler(
+	State(mm): State<ModelManager>,
+	CtxW(ctx): CtxW,
+	Path(id): Path<i64>,
+	headers: HeaderMap,
+	Json(post_u): Json<PostForUpdate>,
+) -> Result<([(HeaderName, String); 1], Json<Post>)> {
+	debug!("{:<12} - api_update_post_handler", "HANDLER");
+
+	match if_match_version(&headers)? {
+		Some(version) => {
+			PostBmc::update_if_version(&ctx, &mm, id, version, post_u).await?;
+		}
+		None => PostBmc::update(&ctx, &mm, id, post_u).await?,
+	}
+	let post = PostBmc::get(&ctx, &mm, id).await?;
+
+	Ok(([(ETAG, version_etag(post.version))], Json(post)))
+}
+// endregion: --- Post Get & Update
+
+// region:    --- Post History
+/// The history of the writes of a post (who edited it, and when), for the moderators.
+pub async fn api_post_history_handler(
+	State(mm): State<ModelManager>,
+	CtxPerm(ctx, _): CtxPerm<perm::ContentModerate>,
+	Path(id): Path<i64>,
+) -> Result<Json<Vec<AuditLogEntry>>> {
+	debug!("{:<12} - api_post_history_handler", "HANDLER");
+
+	let history = PostBmc::history(&ctx, &mm, id).await?;
+
+	Ok(Json(history))
+}
+// endregion: --- Post History
@@ -0,0 +1,63 @@
+use crate::error::Result;
+use crate::middleware::mw_auth::CtxW;
+use axum::extract::{Path, State};
+use axum::Json;
+use lib_core::model::session::{SessionBmc, SessionDTO};
+use lib_core::model::ModelManager;
+use serde::Serialize;
+use tracing::debug;
+
+// region:    --- List Sessions
+pub async fn api_list_sessions_handler(
+	State(mm): State<ModelManager>,
+	CtxW(ctx): CtxW,
+) -> Result<Json<Vec<SessionDTO>>> {
+	debug!("{:<12} - api_list_sessions_handler", "HANDLER");
+
+	let sessions = SessionBmc::list_active(&ctx, &mm)
+		.await?
+		.into_iter()
+		.map(|session| SessionDTO::from_session(session, ctx.session_id()))
+		.collect();
+
+	Ok(Json(sessions))
+}
+// endregion: --- List Sessions
+
+// region:    --- Revoke Sessions
+pub async fn api_revoke_session_handler(
+	State(mm): State<ModelManager>,
+	CtxW(ctx): CtxW,
+	Path(id): Path<i64>,
+) -> Result<Json<RevokeSessionsResponse>> {
+	debug!("{:<12} - api_revoke_session_handler", "HANDLER");
+
+	SessionBmc::revoke(&ctx, &mm, id).await?;
+
+	Ok(Json(RevokeSessionsResponse {
+		success: true,
+		revoked: 1,
+	}))
+}
+
+/// Revoke all the sessions of the user, except the current one.
+pub async fn api_revoke_other_sessions_handler(
+	State(mm): State<ModelManager>,
+	CtxW(ctx): CtxW,
+) -> Result<Json<RevokeSessionsResponse>> {
+	debug!("{:<12} - api_revoke_other_sessions_handler", "HANDLER");
+
+	let revoked = SessionBmc::revoke_all(&ctx, &mm, ctx.session_id()).await?;
+
+	Ok(Json(RevokeSessionsResponse {
+		success: true,
+		revoked,
+	}))
+}
+
+#[derive(Serialize)]
+pub struct RevokeSessionsResponse {
+	success: bool,
+	revoked: u64,
+}
+// endregion: --- Revoke Sessions
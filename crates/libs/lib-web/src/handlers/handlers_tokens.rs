@@ -1,8 +1,11 @@
 use axum::{extract::State, http::HeaderMap, Json};
+use axum::http::header::CACHE_CONTROL;
+use axum::response::IntoResponse;
 use serde_json::json;
+use lib_core::model::session::SessionBmc;
 use lib_core::model::user::{UserBmc, UserForAuth};
 use lib_core::ctx::Ctx;
-use lib_auth::token::{validate_web_token, generate_web_tokens};
+use lib_auth::token::{keyring, validate_web_token, generate_web_tokens};
 use lib_auth::auth_config;
 use lib_core::model::ModelManager;
 use crate::error::{Error, Result};
@@ -39,8 +42,19 @@ pub async fn api_refresh_token_handler(
         return Err(Error::Token(lib_auth::token::Error::InvalidToken));
     }
 
+    // -- Rotate the refresh token of the session
+    // (a replayed, already rotated, token revokes the whole session family)
+    let sid = claims.sid_uuid()?;
+    let jti = claims.jti_uuid()?;
+    let session_ids = SessionBmc::rotate(&Ctx::root_ctx(), &mm, sid, jti).await?;
+
     // -- Ggenerate new tokens
-    let (access_token, refresh_token) = generate_web_tokens(&user.username, user.token_salt)?;
+    let (access_token, refresh_token) = generate_web_tokens(
+        &user.username,
+        user.token_salt,
+        session_ids.family_id,
+        session_ids.jti,
+    )?;
 
     Ok(Json(json!({
         "access_token": access_token,
@@ -49,3 +63,11 @@ pub async fn api_refresh_token_handler(
     })))
 
 }
+
+/// The public keys (JWKS) to validate the access tokens, for the other services.
+pub async fn api_jwks_handler() -> impl IntoResponse {
+    (
+        [(CACHE_CONTROL, "public, max-age=300")],
+        Json(keyring().jwks()),
+    )
+}
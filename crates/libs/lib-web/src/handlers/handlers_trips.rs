@@ -0,0 +1,241 @@
+use crate::error::Result;
+use crate::middleware::mw_auth::CtxW;
+use axum::extract::{Path, Query, State};
+use axum::Json;
+use lib_core::model::trip::{
+	Trip, TripBmc, TripForCreate, TripForUpdate, TripItinerary, TripTotals,
+};
+use lib_core::model::trip_day::{TripDay, TripDayBmc, TripDayForCreate, TripDayForUpdate};
+use lib_core::model::trip_stop::{TripStop, TripStopBmc, TripStopForCreate, TripStopForUpdate};
+use lib_core::model::{ModelManager, Page, PageOptions};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+// region:    --- Trips
+/// A page of the trips visible to the user (its trips, and the templates).
+pub async fn api_list_trips_handler(
+	State(mm): State<ModelManager>,
+	CtxW(ctx): CtxW,
+	Query(page_options): Query<PageOptions>,
+) -> Result<Json<Page<Trip>>> {
+	debug!("{:<12} - api_list_trips_handler", "HANDLER");
+
+	let page = TripBmc::list_page(&ctx, &mm, None, page_options).await?;
+
+	Ok(Json(page))
+}
+
+pub async fn api_create_trip_handler(
+	State(mm): State<ModelManager>,
+	CtxW(ctx): CtxW,
+	Json(trip_c): Json<TripForCreate>,
+) -> Result<Json<Trip>> {
+	debug!("{:<12} - api_create_trip_handler", "HANDLER");
+
+	let id = TripBmc::create(&ctx, &mm, trip_c).await?;
+	let trip = TripBmc::get(&ctx, &mm, id).await?;
+
+	Ok(Json(trip))
+}
+
+/// The trip with its days and stops, in order, and its totals (the itinerary screen).
+pub async fn api_get_trip_handler(
+	State(mm): State<ModelManager>,
+	CtxW(ctx): CtxW,
+	Path(id): Path<i64>,
+) -> Result<Json<TripItinerary>> {
+	debug!("{:<12} - api_get_trip_handler", "HANDLER");
+
+	let itinerary = TripBmc::get_itinerary(&ctx, &mm, id).await?;
+
+	Ok(Json(itinerary))
+}
+
+pub async fn api_update_trip_handler(
+	State(mm): State<ModelManager>,
+	CtxW(ctx): CtxW,
+	Path(id): Path<i64>,
+	Json(trip_u): Json<TripForUpdate>,
+) -> Result<Json<Trip>> {
+	debug!("{:<12} - api_update_trip_handler", "HANDLER");
+
+	TripBmc::update(&ctx, &mm, id, trip_u).await?;
+	let trip = TripBmc::get(&ctx, &mm, id).await?;
+
+	Ok(Json(trip))
+}
+
+pub async fn api_delete_trip_handler(
+	State(mm): State<ModelManager>,
+	CtxW(ctx): CtxW,
+	Path(id): Path<i64>,
+) -> Result<Json<DeleteResponse>> {
+	debug!("{:<12} - api_delete_trip_handler", "HANDLER");
+
+	TripBmc::delete(&ctx, &mm, id).await?;
+
+	Ok(Json(DeleteResponse { success: true }))
+}
+
+/// Duplicate a trip (e.g., a template) as a new trip of the user.
+pub async fn api_duplicate_trip_handler(
+	State(mm): State<ModelManager>,
+	CtxW(ctx): CtxW,
+	Path(id): Path<i64>,
+	Json(payload): Json<DuplicateTripPayload>,
+) -> Result<Json<Trip>> {
+	debug!("{:<12} - api_duplicate_trip_handler", "HANDLER");
+
+	let new_id = TripBmc::duplicate(&ctx, &mm, id, payload.title).await?;
+	let trip = TripBmc::get(&ctx, &mm, new_id).await?;
+
+	Ok(Json(trip))
+}
+
+pub async fn api_trip_totals_handler(
+	State(mm): State<ModelManager>,
+	CtxW(ctx): CtxW,
+	Path(id): Path<i64>,
+) -> Result<Json<TripTotals>> {
+	debug!("{:<12} - api_trip_totals_handler", "HANDLER");
+
+	let totals = TripBmc::totals(&ctx, &mm, id).await?;
+
+	Ok(Json(totals))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DuplicateTripPayload {
+	/// The title of the new trip (the same by default).
+	title: Option<String>,
+}
+// endregion: --- Trips
+
+// region:    --- Trip Days
+/// Add a day at the end of the trip.
+pub async fn api_create_trip_day_handler(
+	State(mm): State<ModelManager>,
+	CtxW(ctx): CtxW,
+	Path(trip_id): Path<i64>,
+	Json(day_c): Json<TripDayForCreate>,
+) -> Result<Json<TripDay>> {
+	debug!("{:<12} - api_create_trip_day_handler", "HANDLER");
+
+	let id = TripDayBmc::create(&ctx, &mm, trip_id, day_c).await?;
+	let day = TripDayBmc::get(&ctx, &mm, id).await?;
+
+	Ok(Json(day))
+}
+
+/// Reorder the days of the trip, with all its day ids in the new order.
+pub async fn api_reorder_trip_days_handler(
+	State(mm): State<ModelManager>,
+	CtxW(ctx): CtxW,
+	Path(trip_id): Path<i64>,
+	Json(payload): Json<ReorderPayload>,
+) -> Result<Json<Vec<TripDay>>> {
+	debug!("{:<12} - api_reorder_trip_days_handler", "HANDLER");
+
+	TripDayBmc::reorder(&ctx, &mm, trip_id, payload.ids).await?;
+	let days = TripDayBmc::list_for_trip(&ctx, &mm, trip_id).await?;
+
+	Ok(Json(days))
+}
+
+pub async fn api_update_trip_day_handler(
+	State(mm): State<ModelManager>,
+	CtxW(ctx): CtxW,
+	Path(id): Path<i64>,
+	Json(day_u): Json<TripDayForUpdate>,
+) -> Result<Json<TripDay>> {
+	debug!("{:<12} - api_update_trip_day_handler", "HANDLER");
+
+	TripDayBmc::update(&ctx, &mm, id, day_u).await?;
+	let day = TripDayBmc::get(&ctx, &mm, id).await?;
+
+	Ok(Json(day))
+}
+
+pub async fn api_delete_trip_day_handler(
+	State(mm): State<ModelManager>,
+	CtxW(ctx): CtxW,
+	Path(id): Path<i64>,
+) -> Result<Json<DeleteResponse>> {
+	debug!("{:<12} - api_delete_trip_day_handler", "HANDLER");
+
+	TripDayBmc::delete(&ctx, &mm, id).await?;
+
+	Ok(Json(DeleteResponse { success: true }))
+}
+// endregion: --- Trip Days
+
+// region:    --- Trip Stops
+/// Add a stop at the end of the day.
+pub async fn api_create_trip_stop_handler(
+	State(mm): State<ModelManager>,
+	CtxW(ctx): CtxW,
+	Path(trip_day_id): Path<i64>,
+	Json(stop_c): Json<TripStopForCreate>,
+) -> Result<Json<TripStop>> {
+	debug!("{:<12} - api_create_trip_stop_handler", "HANDLER");
+
+	let id = TripStopBmc::create(&ctx, &mm, trip_day_id, stop_c).await?;
+	let stop = TripStopBmc::get(&ctx, &mm, id).await?;
+
+	Ok(Json(stop))
+}
+
+/// Reorder the stops of the day, with all its stop ids in the new order. The ids of
+/// stops of the other days of the trip move them to this day.
+/// Returns the itinerary, since the stops may come from other days.
+pub async fn api_reorder_trip_stops_handler(
+	State(mm): State<ModelManager>,
+	CtxW(ctx): CtxW,
+	Path(trip_day_id): Path<i64>,
+	Json(payload): Json<ReorderPayload>,
+) -> Result<Json<TripItinerary>> {
+	debug!("{:<12} - api_reorder_trip_stops_handler", "HANDLER");
+
+	TripStopBmc::reorder(&ctx, &mm, trip_day_id, payload.ids).await?;
+	let day = TripDayBmc::get(&ctx, &mm, trip_day_id).await?;
+	let itinerary = TripBmc::get_itinerary(&ctx, &mm, day.trip_id).await?;
+
+	Ok(Json(itinerary))
+}
+
+pub async fn api_update_trip_stop_handler(
+	State(mm): State<ModelManager>,
+	CtxW(ctx): CtxW,
+	Path(id): Path<i64>,
+	Json(stop_u): Json<TripStopForUpdate>,
+) -> Result<Json<TripStop>> {
+	debug!("{:<12} - api_update_trip_stop_handler", "HANDLER");
+
+	TripStopBmc::update(&ctx, &mm, id, stop_u).await?;
+	let stop = TripStopBmc::get(&ctx, &mm, id).await?;
+
+	Ok(Json(stop))
+}
+
+pub async fn api_delete_trip_stop_handler(
+	State(mm): State<ModelManager>,
+	CtxW(ctx): CtxW,
+	Path(id): Path<i64>,
+) -> Result<Json<DeleteResponse>> {
+	debug!("{:<12} - api_delete_trip_stop_handler", "HANDLER");
+
+	TripStopBmc::delete(&ctx, &mm, id).await?;
+
+	Ok(Json(DeleteResponse { success: true }))
+}
+// endregion: --- Trip Stops
+
+#[derive(Debug, Deserialize)]
+pub struct ReorderPayload {
+	ids: Vec<i64>,
+}
+
+#[derive(Serialize)]
+pub struct DeleteResponse {
+	success: bool,
+}
@@ -0,0 +1,153 @@
+use crate::error::Result;
+use crate::middleware::mw_auth::{perm, CtxPerm, CtxW};
+use axum::extract::{Path, State};
+use axum::Json;
+use lib_core::ctx::Role;
+use lib_core::model::user::UserBmc;
+use lib_core::model::ModelManager;
+use lib_storage::{Storage, StorageBackend};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+/// The state of the user routes (the storage, to remove the media of a deleted user).
+#[derive(Clone)]
+pub struct UserState {
+	pub mm: ModelManager,
+	pub storage: Storage,
+}
+
+// region:    --- User Role
+/// Change the role of a user (admin only).
+pub async fn api_update_user_role_handler(
+	State(UserState { mm, .. }): State<UserState>,
+	CtxPerm(ctx, _): CtxPerm<perm::UserManage>,
+	Path(id): Path<i64>,
+	Json(payload): Json<UpdateRolePayload>,
+) -> Result<Json<UpdateRoleResponse>> {
+	debug!("{:<12} - api_update_user_role_handler", "HANDLER");
+
+	UserBmc::update_role(&ctx, &mm, id, payload.role).await?;
+
+	Ok(Json(UpdateRoleResponse {
+		success: true,
+		role: payload.role,
+	}))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateRolePayload {
+	role: Role,
+}
+
+#[derive(Serialize)]
+pub struct UpdateRoleResponse {
+	success: bool,
+	role: Role,
+}
+// endregion: --- User Role
+
+// region:    --- User Delete
+/// Delete (erase) a user, by the user itself or an admin.
+/// The user cannot login anymore, and its tokens are invalidated.
+pub async fn api_delete_user_handler(
+	State(UserState { mm, storage }): State<UserState>,
+	CtxW(ctx): CtxW,
+	Path(id): Path<i64>,
+) -> Result<Json<DeleteUserResponse>> {
+	debug!("{:<12} - api_delete_user_handler", "HANDLER");
+
+	let erasure = UserBmc::delete(&ctx, &mm, id).await?;
+
+	// -- Remove the media files (once the erasure is committed)
+	// Note: A failure only leaves an orphan file (its row is deleted), so it is logged, not returned.
+	for media_url in &erasure.media_urls {
+		let Some(key) = storage.key_from_url(media_url) else {
+			warn!("user {id} erasure - media url not of the storage: {media_url}");
+			continue;
+		};
+		if let Err(ex) = storage.delete(&key).await {
+			warn!("user {id} erasure - cannot delete media '{key}': {ex:?}");
+		}
+	}
+
+	Ok(Json(DeleteUserResponse {
+		success: true,
+		posts_deleted: erasure.posts_deleted,
+	}))
+}
+
+#[derive(Serialize)]
+pub struct DeleteUserResponse {
+	success: bool,
+	posts_deleted: u64,
+}
+// endregion: --- User Delete
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	pub type Result<T> = core::result::Result<T, Error>;
+	pub type Error = Box<dyn std::error::Error>; // For tests.
+
+	use super::*;
+	use crate::_test_utils::{exec, fx_app, fx_mm, with_ctx};
+	use axum::body::Body;
+	use axum::http::{Request, StatusCode};
+	use axum::routing::delete;
+	use axum::Router;
+	use lib_core::_dev_utils;
+	use lib_core::model::post_media::{PostMediaBmc, PostMediaForCreate};
+	use lib_storage::MemStorage;
+	use serial_test::serial;
+
+	#[serial]
+	#[tokio::test]
+	async fn test_api_delete_user_handler_ok_media_removed() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = fx_mm().await?;
+		let storage = MemStorage::default();
+		let app = fx_app(
+			Router::new()
+				.route("/api/users/{id}", delete(api_delete_user_handler))
+				.with_state(UserState {
+					mm: mm.clone(),
+					storage: Storage::Memory(storage.clone()),
+				}),
+		);
+		let ctx = _dev_utils::seed_user(&mm, "test_api_delete_user_handler-user-01", Role::Creator).await?;
+		let post = _dev_utils::seed_posts(&ctx, &mm, &["post with media"], &["desc"]).await?.remove(0);
+		let fx_key = format!("posts/{}/fx/original.mp4", post.id);
+		let media_url = storage.upload(&fx_key, b"fx video").await?;
+		PostMediaBmc::create(
+			&ctx,
+			&mm,
+			PostMediaForCreate {
+				post_id: post.id,
+				media_url,
+				media_type: "video".to_string(),
+				mime_type: "video/mp4".to_string(),
+				width: None,
+				height: None,
+				file_size: Some(8),
+				duration: None,
+				sort_order: 0,
+				alt_text: None,
+			},
+		)
+		.await?;
+
+		// -- Exec
+		let req = Request::delete(format!("/api/users/{}", ctx.user_id())).body(Body::empty())?;
+		let (status, _, body) = exec(&app, with_ctx(req, ctx)).await?;
+
+		// -- Check
+		assert_eq!(status, StatusCode::OK);
+		assert_eq!(body["posts_deleted"], 1);
+		assert!(!storage.exists(&fx_key).await?);
+
+		Ok(())
+	}
+}
+
+// endregion: --- Tests
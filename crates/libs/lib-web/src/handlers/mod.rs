@@ -1,5 +1,11 @@
 pub mod handlers_login;
+pub mod handlers_media;
+pub mod handlers_mfa;
+pub mod handlers_oidc;
+pub mod handlers_posts;
 pub mod handlers_email;
 pub mod handlers_register;
+pub mod handlers_sessions;
 pub mod handlers_tokens;
-pub mod mw_req_stamp;
+pub mod handlers_trips;
+pub mod handlers_users;
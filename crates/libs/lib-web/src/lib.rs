@@ -6,4 +6,7 @@ pub use error::Error;
 pub mod handlers;
 pub mod middleware;
 pub mod routes;
-pub mod utils;
\ No newline at end of file
+pub mod utils;
+
+#[cfg(test)]
+mod _test_utils;
\ No newline at end of file
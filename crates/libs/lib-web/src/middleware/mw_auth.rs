@@ -8,9 +8,12 @@ use tower_cookies::{Cookie, Cookies};
 use tracing::debug;
 use serde::Serialize;
 use lib_auth::token::validate_web_token;
-use lib_core::ctx::Ctx;
+use lib_core::ctx::{Ctx, Permission};
+use std::marker::PhantomData;
+use lib_core::model::session::{SessionBmc, SessionForAuth, SessionForTouch};
 use lib_core::model::user::{UserBmc, UserForAuth};
 use lib_core::model::ModelManager;
+use crate::utils::client_info::ClientInfo;
 use crate::utils::token::{extract_token, set_token_cookie, AUTH_TOKEN};
 use crate::error::{Error, Result};
 
@@ -30,6 +33,7 @@ pub async fn mw_ctx_require(
 pub async fn mw_ctx_resolver(
     State(mm): State<ModelManager>,
     cookies: Cookies,
+    client_info: ClientInfo,
     mut req: Request<Body>,
     next: Next,
 ) -> Response {
@@ -38,7 +42,7 @@ pub async fn mw_ctx_resolver(
     let token = extract_token(&req, &cookies);
 
     let ctx_ext_result = match token {
-        Some(token) => ctx_resolve(mm, &cookies, token).await,
+        Some(token) => ctx_resolve(mm, &cookies, client_info, token).await,
         None => Err(CtxExtError::TokenMissing)
     };
 
@@ -59,12 +63,16 @@ pub async fn mw_ctx_resolver(
 async fn ctx_resolve(
     mm: ModelManager, 
     cookies: &Cookies,
+    client_info: ClientInfo,
     token: String,
 ) -> CtxExtResult {
 
     // -- Check token
     let claims = validate_web_token(&token)
         .map_err(|_| CtxExtError::FailValidate)?;
+    if claims.typ != "access" {
+        return Err(CtxExtError::FailValidate);
+    }
 
     // -- Get UserForAuth
     let user: UserForAuth = 
@@ -78,15 +86,35 @@ async fn ctx_resolve(
         return Err(CtxExtError::FailValidate);
     }
 
+    // -- Validate Session (not revoked, e.g., from another device)
+    let sid = claims.sid_uuid().map_err(|_| CtxExtError::FailValidate)?;
+    let session: SessionForAuth =
+        SessionBmc::first_by_family_id(&Ctx::root_ctx(), &mm, sid)
+            .await
+            .map_err(|ex| CtxExtError::ModelAccessError(ex.to_string()))?
+            .ok_or(CtxExtError::FailValidate)?;
+
+    if session.user_id != user.id || !session.is_active() {
+        return Err(CtxExtError::FailValidate);
+    }
+
+    // -- Update the session last seen info
+    if session.is_touch_due() {
+        let ClientInfo { ip, location, .. } = client_info;
+        SessionBmc::touch(&Ctx::root_ctx(), &mm, session.id, SessionForTouch { ip, location })
+            .await
+            .map_err(|ex| CtxExtError::ModelAccessError(ex.to_string()))?;
+    }
+
     // -- Update Token if we get get it from Cookie
     if cookies.get(AUTH_TOKEN).is_some() {
-        set_token_cookie(cookies, &user.username, user.token_salt)
+        set_token_cookie(cookies, &user.username, user.token_salt, sid)
             .map_err(|_| CtxExtError::CannotSetTokenCookie)?;
     }
 
     // -- Create CtxExtResult
     Ctx::new(user.id)
-        .map(CtxW)
+        .map(|ctx| CtxW(ctx.add_session_id(session.id).add_role(user.role)))
         .map_err(|ex| CtxExtError::CtxCreateFail(ex.to_string()))
 }
 
@@ -111,8 +139,70 @@ impl<S: Send + Sync> FromRequestParts<S> for CtxW {
 
 // endregion: ---- Ctx Extractor
 
+// region:    --- Permission Extractor
+
+/// A permission required by a handler, see `CtxPerm`.
+pub trait RequiredPermission {
+	const PERMISSION: Permission;
+}
+
+/// Ctx extractor that also requires a permission of the ctx role,
+/// e.g., `CtxPerm(ctx, _): CtxPerm<perm::UserManage>`.
+/// Rejects with `Error::PermissionDenied` (`ClientError::NO_AUTH`).
+#[derive(Debug, Clone)]
+pub struct CtxPerm<P: RequiredPermission>(pub Ctx, pub PhantomData<P>);
+
+impl<S: Send + Sync, P: RequiredPermission> FromRequestParts<S> for CtxPerm<P> {
+	type Rejection = Error;
+
+	async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self> {
+		debug!("{:<12} - CtxPerm", "EXTRACTOR");
+
+		let CtxW(ctx) = CtxW::from_request_parts(parts, state).await?;
+		if !ctx.has_permission(P::PERMISSION) {
+			return Err(Error::PermissionDenied {
+				user_id: ctx.user_id(),
+				permission: P::PERMISSION,
+			});
+		}
+
+		Ok(CtxPerm(ctx, PhantomData))
+	}
+}
+
+/// The permission markers for `CtxPerm`, one per `Permission` variant.
+pub mod perm {
+	use super::RequiredPermission;
+	use lib_core::ctx::Permission;
+
+	macro_rules! perm_markers {
+		($($name:ident),*) => {
+			$(
+				#[derive(Debug, Clone)]
+				pub struct $name;
+
+				impl RequiredPermission for $name {
+					const PERMISSION: Permission = Permission::$name;
+				}
+			)*
+		};
+	}
+
+	perm_markers!(
+		PostCreate,
+		PostUpdate,
+		PostDelete,
+		ContentModerate,
+		PlaceCreate,
+		PlaceManage,
+		UserManage
+	);
+}
+
+// endregion: --- Permission Extractor
+
 // region:    --- Ctx Extractor Result/Error
-type CtxExtResult = core::result::Result<CtxW, CtxExtError>;
+pub(crate) type CtxExtResult = core::result::Result<CtxW, CtxExtError>;
 
 #[derive(Clone, Serialize, Debug)]
 pub enum CtxExtError {
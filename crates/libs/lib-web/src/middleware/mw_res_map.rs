@@ -3,7 +3,8 @@ use crate::log::log_request;
 use crate::middleware::mw_auth::CtxW;
 use crate::middleware::mw_req_stamp::ReqStamp;
 
-use axum::http::{Method, Uri};
+use axum::http::header::RETRY_AFTER;
+use axum::http::{HeaderValue, Method, Uri};
 use axum::response::{IntoResponse, Response};
 use axum::Json;
 use serde_json::{json, to_value};
@@ -48,7 +49,18 @@ pub async fn mw_reponse_map(
 
             debug!("CLIENT ERROR BODY:\n{client_error_body}");
 
-            (*status_code, Json(client_error_body)).into_response()
+            let mut response = (*status_code, Json(client_error_body)).into_response();
+            if let Some(retry_after_sec) = web_error.and_then(Error::retry_after_sec) {
+                response.headers_mut().insert(RETRY_AFTER, HeaderValue::from(retry_after_sec));
+            }
+            // Note: The tus clients need the protocol headers on the errors too (see `routes_tus`).
+            for (name, value) in res.headers() {
+                if name.as_str().starts_with("tus-") {
+                    response.headers_mut().insert(name.clone(), value.clone());
+                }
+            }
+
+            response
         });
 
 	// -- Build and log the server log line.
@@ -69,3 +81,179 @@ pub async fn mw_reponse_map(
 
 	error_response.unwrap_or(res)
 }
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	pub type Result<T> = core::result::Result<T, Error>;
+	pub type Error = Box<dyn std::error::Error>; // For tests.
+
+	use super::*;
+	use crate::_test_utils::{exec, fx_app, fx_mm};
+	use crate::error::Error as WebError;
+	use crate::handlers::handlers_tokens::api_refresh_token_handler;
+	use axum::body::Body;
+	use axum::http::header::AUTHORIZATION;
+	use axum::http::{Request, StatusCode};
+	use axum::routing::{get, post};
+	use axum::Router;
+	use lib_auth::token::generate_web_tokens;
+	use lib_core::ctx::Ctx;
+	use lib_core::model::session::{SessionBmc, SessionForCreate};
+	use lib_core::model::user::{UserBmc, UserForAuth};
+	use lib_core::model::{self, ModelManager};
+	use serial_test::serial;
+
+	fn refresh_req(refresh_token: &str) -> Result<Request<Body>> {
+		let req = Request::post("/api/auth/refresh")
+			.header(AUTHORIZATION, format!("Bearer {refresh_token}"))
+			.body(Body::empty())?;
+
+		Ok(req)
+	}
+
+	async fn fx_refresh_token(mm: &ModelManager) -> Result<String> {
+		let ctx = Ctx::root_ctx();
+		let user: UserForAuth = UserBmc::first_by_username(&ctx, mm, "demo1")
+			.await?
+			.ok_or("Should have user 'demo1'")?;
+		let ids = SessionBmc::create(
+			&ctx,
+			mm,
+			SessionForCreate {
+				user_id: user.id,
+				device: None,
+				user_agent: None,
+				ip: None,
+				location: None,
+			},
+		)
+		.await?;
+		let (_, refresh_token) =
+			generate_web_tokens(&user.username, user.token_salt, ids.family_id, ids.jti)?;
+
+		Ok(refresh_token)
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_mw_reponse_map_refresh_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = fx_mm().await?;
+		let app = fx_app(
+			Router::new()
+				.route("/api/auth/refresh", post(api_refresh_token_handler))
+				.with_state(mm.clone()),
+		);
+		let fx_refresh_token = fx_refresh_token(&mm).await?;
+
+		// -- Exec
+		let req = refresh_req(&fx_refresh_token)?;
+		let (status, _, body) = exec(&app, req).await?;
+
+		// -- Check
+		assert_eq!(status, StatusCode::OK);
+		assert!(body["refresh_token"].is_string());
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_mw_reponse_map_refresh_err_reused() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = fx_mm().await?;
+		let app = fx_app(
+			Router::new()
+				.route("/api/auth/refresh", post(api_refresh_token_handler))
+				.with_state(mm.clone()),
+		);
+		let fx_refresh_token = fx_refresh_token(&mm).await?;
+		let req = refresh_req(&fx_refresh_token)?;
+		exec(&app, req).await?;
+
+		// -- Exec
+		let req = refresh_req(&fx_refresh_token)?;
+		let (status, _, body) = exec(&app, req).await?;
+
+		// -- Check
+		assert_eq!(status, StatusCode::UNAUTHORIZED);
+		assert_eq!(body["error"]["message"], "NO_AUTH");
+		assert!(body["error"]["data"]["req_uuid"].is_string());
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_mw_reponse_map_refresh_err_token_invalid() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = fx_mm().await?;
+		let app = fx_app(
+			Router::new()
+				.route("/api/auth/refresh", post(api_refresh_token_handler))
+				.with_state(mm.clone()),
+		);
+
+		// -- Exec
+		let req = refresh_req("not-a-token")?;
+		let (status, _, body) = exec(&app, req).await?;
+
+		// -- Check
+		assert_eq!(status, StatusCode::UNAUTHORIZED);
+		assert_eq!(body["error"]["message"], "NO_AUTH");
+
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn test_mw_reponse_map_err_throttled_retry_after() -> Result<()> {
+		// -- Setup & Fixtures
+		let app = fx_app(Router::new().route(
+			"/throttled",
+			get(|| async {
+				crate::error::Result::<()>::Err(WebError::LoginThrottled { retry_after_sec: 30 })
+			}),
+		));
+
+		// -- Exec
+		let req = Request::get("/throttled").body(Body::empty())?;
+		let (status, headers, body) = exec(&app, req).await?;
+
+		// -- Check
+		assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+		assert_eq!(headers.get(RETRY_AFTER).ok_or("Should have Retry-After")?, "30");
+		assert_eq!(body["error"]["message"], "LOGIN_THROTTLED");
+		assert_eq!(body["error"]["data"]["detail"]["retry_after_sec"], 30);
+
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn test_mw_reponse_map_err_not_found() -> Result<()> {
+		// -- Setup & Fixtures
+		let app = fx_app(Router::new().route(
+			"/not-found",
+			get(|| async {
+				crate::error::Result::<()>::Err(WebError::Model(model::Error::EntityNotFound {
+					entity: "post",
+					id: 42,
+				}))
+			}),
+		));
+
+		// -- Exec
+		let req = Request::get("/not-found").body(Body::empty())?;
+		let (status, _, body) = exec(&app, req).await?;
+
+		// -- Check
+		assert_eq!(status, StatusCode::NOT_FOUND);
+		assert_eq!(body["error"]["message"], "ENTITY_NOT_FOUND");
+		assert_eq!(body["error"]["data"]["detail"]["id"], 42);
+
+		Ok(())
+	}
+}
+
+// endregion: --- Tests
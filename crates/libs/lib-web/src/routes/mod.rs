@@ -1 +1,2 @@
-pub mod routes_static;
\ No newline at end of file
+pub mod routes_static;
+pub mod routes_tus;
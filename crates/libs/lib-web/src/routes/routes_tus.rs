@@ -0,0 +1,705 @@
+//! The resumable uploads of the post media, by the tus 1.0 protocol, with the creation,
+//! termination, and checksum extensions.
+//! See https://tus.io/protocols/resumable-upload
+//!
+//! - Creation: `POST /api/uploads`, with the `Upload-Metadata` keys `post_id` and
+//!   `filetype` (required), `sort_order`, and `alt_text`.
+//! - The PATCH bodies are staged in the storage (private), by chunks of `CHUNK_SIZE` max. An
+//!   interrupted PATCH keeps the bytes received (but a checksummed one, all or nothing).
+//! - On the last byte, the chunks are assembled as the file of a new post media
+//!   (see `MediaUploadBmc::complete`), its id in the `Post-Media-Id` header.
+
+use crate::error::{Error, Result};
+use crate::handlers::handlers_media::MediaState;
+use crate::middleware::mw_auth::{mw_ctx_require, CtxW};
+use axum::body::Body;
+use axum::extract::{Path, Request, State};
+use axum::http::header::{CACHE_CONTROL, LOCATION};
+use axum::http::{HeaderMap, HeaderValue, Method, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{head, options, post};
+use axum::Router;
+use futures::StreamExt;
+use lib_core::model::media_upload::{
+	MediaUpload, MediaUploadBmc, MediaUploadChunk, MediaUploadForCreate,
+};
+use lib_core::model::{self, ModelManager};
+use lib_storage::{Storage, StorageBackend, PRIVATE_PREFIX};
+use lib_utils::b64::b64_decode;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use tracing::debug;
+use uuid::Uuid;
+
+// region:    --- Consts
+
+const TUS_PATH: &str = "/api/uploads";
+const TUS_UPLOAD_PATH: &str = "/api/uploads/{id}";
+
+const TUS_VERSION_1_0: &str = "1.0.0";
+const TUS_EXTENSIONS: &str = "creation,termination,checksum";
+const TUS_CHECKSUM_ALGORITHMS: &str = "sha1,sha256";
+/// The max size of an upload.
+const TUS_MAX_SIZE: i64 = 512 * 1024 * 1024;
+/// The content type of the PATCH bodies.
+const TUS_CONTENT_TYPE: &str = "application/offset+octet-stream";
+
+/// The max size of the staged chunks.
+const CHUNK_SIZE: usize = 8 * 1024 * 1024;
+
+// -- Headers
+const TUS_RESUMABLE: &str = "tus-resumable";
+const TUS_VERSION: &str = "tus-version";
+const TUS_EXTENSION: &str = "tus-extension";
+const TUS_MAX_SIZE_HEADER: &str = "tus-max-size";
+const TUS_CHECKSUM_ALGORITHM: &str = "tus-checksum-algorithm";
+const UPLOAD_LENGTH: &str = "upload-length";
+const UPLOAD_OFFSET: &str = "upload-offset";
+const UPLOAD_METADATA: &str = "upload-metadata";
+const UPLOAD_CHECKSUM: &str = "upload-checksum";
+/// The id of the media created on completion (not from the protocol).
+const POST_MEDIA_ID: &str = "post-media-id";
+
+// endregion: --- Consts
+
+pub fn routes(mm: ModelManager, storage: Storage) -> Router {
+	Router::new()
+		.route(TUS_PATH, post(tus_create_handler))
+		.route(
+			TUS_UPLOAD_PATH,
+			head(tus_head_handler)
+				.patch(tus_patch_handler)
+				.delete(tus_delete_handler),
+		)
+		.route_layer(middleware::from_fn(mw_ctx_require))
+		// Note: After the ctx require layer, as the protocol discovery needs no auth.
+		.route(TUS_PATH, options(tus_options_handler))
+		.layer(middleware::from_fn(mw_tus))
+		.with_state(MediaState { mm, storage })
+}
+
+/// Check the protocol version of the requests (but OPTIONS), and add it to the responses.
+async fn mw_tus(req: Request<Body>, next: Next) -> Response {
+	debug!("{:<12} - mw_tus", "MIDDLEWARE");
+
+	let version_supported = req.method() == Method::OPTIONS
+		|| req
+			.headers()
+			.get(TUS_RESUMABLE)
+			.is_some_and(|version| version == TUS_VERSION_1_0);
+
+	let mut res = if version_supported {
+		next.run(req).await
+	} else {
+		Error::TusVersionUnsupported.into_response()
+	};
+
+	let headers = res.headers_mut();
+	headers.insert(TUS_RESUMABLE, HeaderValue::from_static(TUS_VERSION_1_0));
+	if !version_supported {
+		headers.insert(TUS_VERSION, HeaderValue::from_static(TUS_VERSION_1_0));
+	}
+
+	res
+}
+
+// region:    --- Handlers
+
+async fn tus_options_handler() -> impl IntoResponse {
+	debug!("{:<12} - tus_options_handler", "HANDLER");
+
+	(
+		StatusCode::NO_CONTENT,
+		[
+			(TUS_VERSION, TUS_VERSION_1_0.to_string()),
+			(TUS_EXTENSION, TUS_EXTENSIONS.to_string()),
+			(TUS_MAX_SIZE_HEADER, TUS_MAX_SIZE.to_string()),
+			(TUS_CHECKSUM_ALGORITHM, TUS_CHECKSUM_ALGORITHMS.to_string()),
+		],
+	)
+}
+
+async fn tus_create_handler(
+	State(state): State<MediaState>,
+	CtxW(ctx): CtxW,
+	headers: HeaderMap,
+) -> Result<Response> {
+	debug!("{:<12} - tus_create_handler", "HANDLER");
+
+	let upload_length = header_i64(&headers, UPLOAD_LENGTH)?;
+	if upload_length > TUS_MAX_SIZE {
+		return Err(Error::TusSizeExceeded);
+	}
+
+	// -- Metadata
+	let mut metadata = parse_metadata(header_str(&headers, UPLOAD_METADATA).unwrap_or_default())?;
+	let post_id = metadata
+		.get("post_id")
+		.and_then(|post_id| post_id.parse().ok())
+		.ok_or(Error::TusHeaderInvalid(UPLOAD_METADATA))?;
+	let mime_type = metadata
+		.remove("filetype")
+		.ok_or(Error::TusHeaderInvalid(UPLOAD_METADATA))?;
+	let sort_order = metadata
+		.get("sort_order")
+		.map(|sort_order| sort_order.parse())
+		.transpose()
+		.map_err(|_| Error::TusHeaderInvalid(UPLOAD_METADATA))?;
+
+	let upload_c = MediaUploadForCreate {
+		post_id,
+		mime_type,
+		upload_length,
+		sort_order,
+		alt_text: metadata.remove("alt_text"),
+	};
+	let id = MediaUploadBmc::create(&ctx, &state.mm, upload_c).await?;
+
+	Ok((StatusCode::CREATED, [(LOCATION, format!("{TUS_PATH}/{id}"))]).into_response())
+}
+
+async fn tus_head_handler(
+	State(state): State<MediaState>,
+	CtxW(ctx): CtxW,
+	Path(id): Path<i64>,
+) -> Result<Response> {
+	debug!("{:<12} - tus_head_handler", "HANDLER");
+
+	let upload = MediaUploadBmc::get(&ctx, &state.mm, id).await?;
+
+	let mut headers = progress_headers(&upload);
+	headers.insert(CACHE_CONTROL, HeaderValue::from_static("no-store"));
+
+	Ok((StatusCode::OK, headers).into_response())
+}
+
+async fn tus_patch_handler(
+	State(state): State<MediaState>,
+	CtxW(ctx): CtxW,
+	Path(id): Path<i64>,
+	headers: HeaderMap,
+	body: Body,
+) -> Result<Response> {
+	debug!("{:<12} - tus_patch_handler", "HANDLER");
+
+	if header_str(&headers, "content-type") != Some(TUS_CONTENT_TYPE) {
+		return Err(Error::TusContentTypeInvalid);
+	}
+	let offset = header_i64(&headers, UPLOAD_OFFSET)?;
+	let mut checksum = header_str(&headers, UPLOAD_CHECKSUM)
+		.map(Checksum::from_header)
+		.transpose()?;
+
+	let upload = MediaUploadBmc::get(&ctx, &state.mm, id).await?;
+	if offset != upload.upload_offset {
+		return Err(model::Error::MediaUploadOffsetConflict { id, offset }.into());
+	}
+
+	// -- Stage the body
+	let max_size = upload.upload_length - offset;
+	let (chunks, body_complete) =
+		stage_body(&state.storage, id, body, max_size, checksum.as_mut()).await?;
+	if let Some(checksum) = checksum
+		&& (!body_complete || !checksum.matches())
+	{
+		MediaUploadBmc::delete_chunks(&state.storage, &chunks).await;
+		return Err(if body_complete {
+			Error::TusChecksumMismatch
+		} else {
+			Error::TusBodyInterrupted
+		});
+	}
+
+	// -- Append the chunks
+	let upload_offset =
+		match MediaUploadBmc::append_chunks(&ctx, &state.mm, id, offset, &chunks).await {
+			Ok(upload_offset) => upload_offset,
+			Err(err) => {
+				MediaUploadBmc::delete_chunks(&state.storage, &chunks).await;
+				return Err(err.into());
+			}
+		};
+	// Note: The bytes received are kept, for the client to resume from the new offset.
+	if !body_complete {
+		return Err(Error::TusBodyInterrupted);
+	}
+
+	// -- Complete
+	// Note: Also on an empty PATCH at the end, if the completion failed before.
+	let mut headers = HeaderMap::new();
+	headers.insert(UPLOAD_OFFSET, HeaderValue::from(upload_offset));
+	if upload_offset == upload.upload_length && upload.post_media_id.is_none() {
+		let post_media_id = MediaUploadBmc::complete(&ctx, &state.mm, &state.storage, id).await?;
+		headers.insert(POST_MEDIA_ID, HeaderValue::from(post_media_id));
+	}
+
+	Ok((StatusCode::NO_CONTENT, headers).into_response())
+}
+
+/// Terminate the upload (its media, if completed, is kept).
+async fn tus_delete_handler(
+	State(state): State<MediaState>,
+	CtxW(ctx): CtxW,
+	Path(id): Path<i64>,
+) -> Result<StatusCode> {
+	debug!("{:<12} - tus_delete_handler", "HANDLER");
+
+	let upload = MediaUploadBmc::get(&ctx, &state.mm, id).await?;
+	MediaUploadBmc::delete(&ctx, &state.mm, id).await?;
+	MediaUploadBmc::delete_chunks(&state.storage, &upload.chunks()?).await;
+
+	Ok(StatusCode::NO_CONTENT)
+}
+
+// endregion: --- Handlers
+
+// region:    --- Upload Support
+
+/// Stage the body in the storage, by chunks of `CHUNK_SIZE` max, up to `max_size` bytes.
+/// Returns the chunks, and if the body is complete (not interrupted).
+/// On error, the chunks already staged are deleted.
+async fn stage_body(
+	storage: &Storage,
+	id: i64,
+	body: Body,
+	max_size: i64,
+	checksum: Option<&mut Checksum>,
+) -> Result<(Vec<MediaUploadChunk>, bool)> {
+	let mut chunks = Vec::new();
+	let res = stage_body_chunks(storage, id, body, max_size, checksum, &mut chunks).await;
+	if res.is_err() {
+		MediaUploadBmc::delete_chunks(storage, &chunks).await;
+	}
+
+	res.map(|body_complete| (chunks, body_complete))
+}
+
+async fn stage_body_chunks(
+	storage: &Storage,
+	id: i64,
+	body: Body,
+	max_size: i64,
+	mut checksum: Option<&mut Checksum>,
+	chunks: &mut Vec<MediaUploadChunk>,
+) -> Result<bool> {
+	let mut stream = body.into_data_stream();
+	let mut buffer = Vec::new();
+	let mut size = 0;
+	let mut body_complete = true;
+
+	while let Some(data) = stream.next().await {
+		let Ok(data) = data else {
+			body_complete = false;
+			break;
+		};
+		size += data.len() as i64;
+		if size > max_size {
+			return Err(Error::TusSizeExceeded);
+		}
+		if let Some(checksum) = checksum.as_deref_mut() {
+			checksum.update(&data);
+		}
+
+		buffer.extend_from_slice(&data);
+		if buffer.len() >= CHUNK_SIZE {
+			chunks.push(stage_chunk(storage, id, &buffer).await?);
+			buffer.clear();
+		}
+	}
+	if !buffer.is_empty() {
+		chunks.push(stage_chunk(storage, id, &buffer).await?);
+	}
+
+	Ok(body_complete)
+}
+
+async fn stage_chunk(storage: &Storage, id: i64, data: &[u8]) -> Result<MediaUploadChunk> {
+	// Note: Private, until assembled as the (public) file of the media.
+	let key = format!("{PRIVATE_PREFIX}uploads/{id}/{}", Uuid::new_v4());
+	storage.upload(&key, data).await?;
+
+	Ok(MediaUploadChunk {
+		key,
+		size: data.len() as i64,
+	})
+}
+
+fn progress_headers(upload: &MediaUpload) -> HeaderMap {
+	let mut headers = HeaderMap::new();
+	headers.insert(UPLOAD_OFFSET, HeaderValue::from(upload.upload_offset));
+	headers.insert(UPLOAD_LENGTH, HeaderValue::from(upload.upload_length));
+	if let Some(post_media_id) = upload.post_media_id {
+		headers.insert(POST_MEDIA_ID, HeaderValue::from(post_media_id));
+	}
+
+	headers
+}
+
+// endregion: --- Upload Support
+
+// region:    --- Headers Support
+
+fn header_str<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+	headers.get(name).and_then(|value| value.to_str().ok())
+}
+
+/// The value of a size or offset header (not negative).
+fn header_i64(headers: &HeaderMap, name: &'static str) -> Result<i64> {
+	header_str(headers, name)
+		.and_then(|value| value.parse::<i64>().ok())
+		.filter(|value| *value >= 0)
+		.ok_or(Error::TusHeaderInvalid(name))
+}
+
+/// The `Upload-Metadata` pairs, by `,`: the key, and the base64 value (optional), by space.
+fn parse_metadata(metadata: &str) -> Result<HashMap<String, String>> {
+	metadata
+		.split(',')
+		.map(str::trim)
+		.filter(|pair| !pair.is_empty())
+		.map(|pair| {
+			let (key, value_b64) = pair.split_once(' ').unwrap_or((pair, ""));
+			let value = b64_decode(value_b64.trim())
+				.ok()
+				.and_then(|value| String::from_utf8(value).ok())
+				.ok_or(Error::TusHeaderInvalid(UPLOAD_METADATA))?;
+			Ok((key.to_string(), value))
+		})
+		.collect()
+}
+
+/// The `Upload-Checksum` of a PATCH (e.g., `sha1 Kq5sNclPz7QV2+lfQIuc6R7oRu0=`),
+/// with the hash of the body received.
+struct Checksum {
+	expected: Vec<u8>,
+	hasher: ChecksumHasher,
+}
+
+enum ChecksumHasher {
+	Sha1(Sha1),
+	Sha256(Sha256),
+}
+
+impl Checksum {
+	fn from_header(value: &str) -> Result<Self> {
+		let (algorithm, digest_b64) = value
+			.split_once(' ')
+			.ok_or(Error::TusHeaderInvalid(UPLOAD_CHECKSUM))?;
+		let hasher = match algorithm {
+			"sha1" => ChecksumHasher::Sha1(Sha1::new()),
+			"sha256" => ChecksumHasher::Sha256(Sha256::new()),
+			_ => return Err(Error::TusChecksumAlgorithmUnsupported(algorithm.to_string())),
+		};
+		let expected =
+			b64_decode(digest_b64.trim()).map_err(|_| Error::TusHeaderInvalid(UPLOAD_CHECKSUM))?;
+
+		Ok(Self { expected, hasher })
+	}
+
+	fn update(&mut self, data: &[u8]) {
+		match &mut self.hasher {
+			ChecksumHasher::Sha1(hasher) => hasher.update(data),
+			ChecksumHasher::Sha256(hasher) => hasher.update(data),
+		}
+	}
+
+	fn matches(self) -> bool {
+		let digest = match self.hasher {
+			ChecksumHasher::Sha1(hasher) => hasher.finalize().to_vec(),
+			ChecksumHasher::Sha256(hasher) => hasher.finalize().to_vec(),
+		};
+
+		digest == self.expected
+	}
+}
+
+// endregion: --- Headers Support
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	pub type Result<T> = core::result::Result<T, Error>;
+	pub type Error = Box<dyn std::error::Error>; // For tests.
+
+	use super::*;
+	use crate::_test_utils::{exec, fx_app, fx_mm, with_ctx};
+	use axum::http::request::Builder;
+	use lib_core::_dev_utils;
+	use lib_core::ctx::{Ctx, Role};
+	use lib_core::model::post_media::PostMediaBmc;
+	use lib_core::model::user::UserBmc;
+	use lib_storage::MemStorage;
+	use lib_utils::b64::b64_encode;
+	use serial_test::serial;
+
+	/// A 100 bytes mp4 (its `ftyp` box, then zeros).
+	fn fx_mp4() -> Vec<u8> {
+		let mut data = b"\x00\x00\x00\x18ftypisom\x00\x00\x02\x00isomiso2mp41".to_vec();
+		data.resize(100, 0);
+		data
+	}
+
+	/// A tus request (with the `Tus-Resumable` header).
+	fn fx_tus(method: Method, uri: &str) -> Builder {
+		Request::builder()
+			.method(method)
+			.uri(uri)
+			.header(TUS_RESUMABLE, TUS_VERSION_1_0)
+	}
+
+	fn fx_patch(location: &str, offset: i64, data: &[u8]) -> Builder {
+		fx_tus(Method::PATCH, location)
+			.header("content-type", TUS_CONTENT_TYPE)
+			.header(UPLOAD_OFFSET, offset)
+			.header("content-length", data.len())
+	}
+
+	/// Create an upload of `length` bytes of mp4, and returns its location.
+	async fn fx_create(app: &Router, ctx: &Ctx, post_id: i64, length: usize) -> Result<String> {
+		let metadata = format!(
+			"post_id {},filetype {}",
+			b64_encode(post_id.to_string()),
+			b64_encode("video/mp4")
+		);
+		let req = fx_tus(Method::POST, TUS_PATH)
+			.header(UPLOAD_LENGTH, length)
+			.header(UPLOAD_METADATA, metadata)
+			.body(Body::empty())?;
+		let (status, headers, _) = exec(app, with_ctx(req, ctx.clone())).await?;
+		assert_eq!(status, StatusCode::CREATED);
+
+		Ok(headers[LOCATION].to_str()?.to_string())
+	}
+
+	#[test]
+	fn test_parse_metadata_ok() -> Result<()> {
+		// -- Exec
+		let metadata = parse_metadata("post_id MTI=, filetype dmlkZW8vbXA0,is_draft")?;
+
+		// -- Check
+		assert_eq!(metadata["post_id"], "12");
+		assert_eq!(metadata["filetype"], "video/mp4");
+		assert_eq!(metadata["is_draft"], "");
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_parse_metadata_err_invalid() -> Result<()> {
+		// -- Exec & Check
+		// Not base64, then not utf-8.
+		for fx_metadata in ["post_id 12", "filetype //8="] {
+			assert!(
+				matches!(
+					parse_metadata(fx_metadata),
+					Err(crate::Error::TusHeaderInvalid(UPLOAD_METADATA))
+				),
+				"{fx_metadata}"
+			);
+		}
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_tus_upload_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = fx_mm().await?;
+		let app = fx_app(routes(mm.clone(), Storage::Memory(MemStorage::default())));
+		let ctx = _dev_utils::seed_user(&mm, "test_tus_upload_ok-user-01", Role::Creator).await?;
+		let post = _dev_utils::seed_posts(&ctx, &mm, &["tus post"], &["desc"]).await?.remove(0);
+		let fx_data = fx_mp4();
+
+		// -- Exec & Check (create & head)
+		let location = fx_create(&app, &ctx, post.id, fx_data.len()).await?;
+		let req = fx_tus(Method::HEAD, &location).body(Body::empty())?;
+		let (status, headers, _) = exec(&app, with_ctx(req, ctx.clone())).await?;
+		assert_eq!(status, StatusCode::OK);
+		assert_eq!(headers[UPLOAD_OFFSET], "0");
+		assert_eq!(headers[UPLOAD_LENGTH], "100");
+
+		// -- Exec & Check (patch, in two parts)
+		let req = fx_patch(&location, 0, &fx_data[..60]).body(Body::from(fx_data[..60].to_vec()))?;
+		let (status, headers, _) = exec(&app, with_ctx(req, ctx.clone())).await?;
+		assert_eq!(status, StatusCode::NO_CONTENT);
+		assert_eq!(headers[UPLOAD_OFFSET], "60");
+		assert!(!headers.contains_key(POST_MEDIA_ID));
+
+		let req = fx_patch(&location, 60, &fx_data[60..]).body(Body::from(fx_data[60..].to_vec()))?;
+		let (status, headers, _) = exec(&app, with_ctx(req, ctx.clone())).await?;
+		assert_eq!(status, StatusCode::NO_CONTENT);
+		assert_eq!(headers[UPLOAD_OFFSET], "100");
+		let post_media_id: i64 = headers[POST_MEDIA_ID].to_str()?.parse()?;
+
+		// -- Check (media)
+		let media = PostMediaBmc::get(&ctx, &mm, post_media_id).await?;
+		assert_eq!(media.post_id, post.id);
+		assert_eq!(media.media_type, "video");
+		assert_eq!(media.file_size, Some(100));
+		let req = fx_tus(Method::HEAD, &location).body(Body::empty())?;
+		let (_, headers, _) = exec(&app, with_ctx(req, ctx.clone())).await?;
+		assert_eq!(headers[POST_MEDIA_ID], post_media_id.to_string().as_str());
+
+		// -- Clean
+		UserBmc::delete(&Ctx::root_ctx(), &mm, ctx.user_id()).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_tus_patch_handler_err_offset_conflict() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = fx_mm().await?;
+		let app = fx_app(routes(mm.clone(), Storage::Memory(MemStorage::default())));
+		let ctx = _dev_utils::seed_user(&mm, "test_tus_patch_offset-user-01", Role::Creator).await?;
+		let post = _dev_utils::seed_posts(&ctx, &mm, &["tus post"], &["desc"]).await?.remove(0);
+		let fx_data = fx_mp4();
+		let location = fx_create(&app, &ctx, post.id, fx_data.len()).await?;
+		let req = fx_patch(&location, 0, &fx_data[..60]).body(Body::from(fx_data[..60].to_vec()))?;
+		exec(&app, with_ctx(req, ctx.clone())).await?;
+
+		// -- Exec & Check
+		// Already appended at 0, then not the offset of the upload.
+		for offset in [0, 30] {
+			let data = &fx_data[offset as usize..offset as usize + 30];
+			let req = fx_patch(&location, offset, data).body(Body::from(data.to_vec()))?;
+			let (status, _, _) = exec(&app, with_ctx(req, ctx.clone())).await?;
+			assert_eq!(status, StatusCode::CONFLICT, "offset {offset}");
+		}
+
+		let req = fx_tus(Method::HEAD, &location).body(Body::empty())?;
+		let (_, headers, _) = exec(&app, with_ctx(req, ctx.clone())).await?;
+		assert_eq!(headers[UPLOAD_OFFSET], "60");
+
+		// -- Clean
+		UserBmc::delete(&Ctx::root_ctx(), &mm, ctx.user_id()).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_tus_patch_handler_err_checksum_mismatch() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = fx_mm().await?;
+		let app = fx_app(routes(mm.clone(), Storage::Memory(MemStorage::default())));
+		let ctx = _dev_utils::seed_user(&mm, "test_tus_checksum-user-01", Role::Creator).await?;
+		let post = _dev_utils::seed_posts(&ctx, &mm, &["tus post"], &["desc"]).await?.remove(0);
+		let fx_data = fx_mp4();
+		let location = fx_create(&app, &ctx, post.id, fx_data.len()).await?;
+		let data = &fx_data[..60];
+		let fx_checksum = |data: &[u8]| format!("sha1 {}", b64_encode(Sha1::digest(data)));
+
+		// -- Exec & Check (mismatch)
+		let req = fx_patch(&location, 0, data)
+			.header(UPLOAD_CHECKSUM, fx_checksum(b"other data"))
+			.body(Body::from(data.to_vec()))?;
+		let (status, _, _) = exec(&app, with_ctx(req, ctx.clone())).await?;
+		assert_eq!(status.as_u16(), 460);
+
+		let req = fx_tus(Method::HEAD, &location).body(Body::empty())?;
+		let (_, headers, _) = exec(&app, with_ctx(req, ctx.clone())).await?;
+		assert_eq!(headers[UPLOAD_OFFSET], "0");
+
+		// -- Exec & Check (match)
+		let req = fx_patch(&location, 0, data)
+			.header(UPLOAD_CHECKSUM, fx_checksum(data))
+			.body(Body::from(data.to_vec()))?;
+		let (status, headers, _) = exec(&app, with_ctx(req, ctx.clone())).await?;
+		assert_eq!(status, StatusCode::NO_CONTENT);
+		assert_eq!(headers[UPLOAD_OFFSET], "60");
+
+		// -- Clean
+		UserBmc::delete(&Ctx::root_ctx(), &mm, ctx.user_id()).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_mw_tus_err_version_unsupported() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = fx_mm().await?;
+		let app = fx_app(routes(mm, Storage::Memory(MemStorage::default())));
+		let ctx = Ctx::new(1)?.add_role(Role::Creator);
+
+		// -- Exec & Check
+		for fx_version in [None, Some("0.2.2")] {
+			let mut req = Request::post(TUS_PATH).header(UPLOAD_LENGTH, "100");
+			if let Some(version) = fx_version {
+				req = req.header(TUS_RESUMABLE, version);
+			}
+			let req = req.body(Body::empty())?;
+			let (status, headers, _) = exec(&app, with_ctx(req, ctx.clone())).await?;
+			assert_eq!(status, StatusCode::PRECONDITION_FAILED, "{fx_version:?}");
+			assert_eq!(headers[TUS_VERSION], TUS_VERSION_1_0);
+		}
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_tus_create_handler_err_metadata_invalid() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = fx_mm().await?;
+		let app = fx_app(routes(mm.clone(), Storage::Memory(MemStorage::default())));
+		let ctx = _dev_utils::seed_user(&mm, "test_tus_metadata-user-01", Role::Creator).await?;
+
+		// -- Exec & Check
+		// Not base64, no filetype, then a post_id not a number.
+		let fx_filetype = b64_encode("video/mp4");
+		for fx_metadata in [
+			format!("post_id 1,filetype {fx_filetype}"),
+			format!("post_id {}", b64_encode("1")),
+			format!("post_id {},filetype {fx_filetype}", b64_encode("one")),
+		] {
+			let req = fx_tus(Method::POST, TUS_PATH)
+				.header(UPLOAD_LENGTH, "100")
+				.header(UPLOAD_METADATA, &fx_metadata)
+				.body(Body::empty())?;
+			let (status, _, _) = exec(&app, with_ctx(req, ctx.clone())).await?;
+			assert_eq!(status, StatusCode::BAD_REQUEST, "{fx_metadata}");
+		}
+
+		// -- Clean
+		UserBmc::delete(&Ctx::root_ctx(), &mm, ctx.user_id()).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_tus_options_handler_ok_no_auth() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = fx_mm().await?;
+		let app = fx_app(routes(mm, Storage::Memory(MemStorage::default())));
+
+		// -- Exec
+		let req = Request::options(TUS_PATH).body(Body::empty())?;
+		let (status, headers, _) = exec(&app, req).await?;
+
+		// -- Check
+		assert_eq!(status, StatusCode::NO_CONTENT);
+		assert_eq!(headers[TUS_VERSION], TUS_VERSION_1_0);
+		assert_eq!(headers[TUS_EXTENSION], TUS_EXTENSIONS);
+		assert_eq!(headers[TUS_RESUMABLE], TUS_VERSION_1_0);
+
+		// -- Check (the other requests need the auth)
+		let req = Request::post(TUS_PATH)
+			.header(TUS_RESUMABLE, TUS_VERSION_1_0)
+			.header(UPLOAD_LENGTH, "10")
+			.body(Body::empty())?;
+		let (status, _, _) = exec(&app, req).await?;
+		assert_eq!(status, StatusCode::FORBIDDEN);
+
+		Ok(())
+	}
+}
+
+// endregion: --- Tests
@@ -0,0 +1,258 @@
+use std::net::{IpAddr, SocketAddr};
+
+use axum::extract::{ConnectInfo, FromRequestParts};
+use axum::http::header::USER_AGENT;
+use axum::http::request::Parts;
+use axum::http::HeaderMap;
+use ipnet::IpNet;
+use lib_core::config::core_config;
+use tracing::debug;
+
+use crate::error::{Error, Result};
+
+/// Client information taken from the request, recorded on sessions.
+#[derive(Debug, Clone, Default)]
+pub struct ClientInfo {
+	pub device: Option<String>,
+	pub user_agent: Option<String>,
+	/// The peer address, or the `X-Forwarded-For` client when the peer is a trusted proxy
+	/// (see `client_ip`).
+	pub ip: Option<String>,
+	/// Approximate location (e.g., "Singapore, SG"), from the geo headers
+	/// set by the CDN / reverse proxy, only when the peer is a trusted proxy.
+	pub location: Option<String>,
+}
+
+// region:    --- ClientInfo Extractor
+impl<S: Send + Sync> FromRequestParts<S> for ClientInfo {
+	type Rejection = Error;
+
+	async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self> {
+		debug!("{:<12} - ClientInfo", "EXTRACTOR");
+
+		let headers = &parts.headers;
+		let user_agent = header_str(headers, USER_AGENT.as_str());
+		let device = user_agent.as_deref().map(device_label_from_user_agent);
+
+		let peer_ip = parts
+			.extensions
+			.get::<ConnectInfo<SocketAddr>>()
+			.map(|ConnectInfo(addr)| addr.ip());
+		let trusted_proxies = &core_config().TRUSTED_PROXIES;
+		let ip = client_ip(headers, peer_ip, trusted_proxies).map(|ip| ip.to_string());
+
+		// Note: The geo headers can be sent by any client, so only the proxies' ones are read.
+		let location = peer_ip
+			.filter(|ip| is_trusted_proxy(ip, trusted_proxies))
+			.and_then(|_| location_from_headers(headers));
+
+		Ok(ClientInfo {
+			device,
+			user_agent,
+			ip,
+			location,
+		})
+	}
+}
+// endregion: --- ClientInfo Extractor
+
+/// The client ip, from the `X-Forwarded-For` hops only when the peer is a trusted proxy.
+///
+/// Note: The hops are read from the right (each proxy appends the address it got the request from),
+///       and the first one not trusted is the client. The left ones can be anything the client sent.
+fn client_ip(headers: &HeaderMap, peer_ip: Option<IpAddr>, trusted_proxies: &[IpNet]) -> Option<IpAddr> {
+	let is_trusted = |ip: &IpAddr| is_trusted_proxy(ip, trusted_proxies);
+
+	let mut client_ip = peer_ip?;
+	if !is_trusted(&client_ip) {
+		return Some(client_ip);
+	}
+
+	let hops = headers
+		.get_all("x-forwarded-for")
+		.iter()
+		.filter_map(|v| v.to_str().ok())
+		.flat_map(|v| v.split(','))
+		.collect::<Vec<_>>();
+	for hop in hops.into_iter().rev() {
+		// Note: An invalid hop was not set by a trusted proxy, so the last trusted one is kept.
+		let Ok(hop_ip) = hop.trim().parse::<IpAddr>() else {
+			break;
+		};
+		client_ip = hop_ip;
+		if !is_trusted(&hop_ip) {
+			break;
+		}
+	}
+
+	Some(client_ip)
+}
+
+fn is_trusted_proxy(ip: &IpAddr, trusted_proxies: &[IpNet]) -> bool {
+	trusted_proxies.iter().any(|net| net.contains(ip))
+}
+
+fn header_str(headers: &HeaderMap, name: &str) -> Option<String> {
+	headers
+		.get(name)
+		.and_then(|v| v.to_str().ok())
+		.map(|v| v.to_string())
+}
+
+/// Best effort location from the common geo headers (Cloudflare, or the reverse proxy).
+/// Returns "City, Country" when both are known.
+fn location_from_headers(headers: &HeaderMap) -> Option<String> {
+	let country = header_str(headers, "cf-ipcountry")
+		.or_else(|| header_str(headers, "x-geo-country"))
+		// Note: "XX" is Cloudflare for unknown, and "T1" for Tor.
+		.filter(|c| !c.is_empty() && c != "XX" && c != "T1");
+	let city = header_str(headers, "x-geo-city").filter(|c| !c.is_empty());
+
+	match (city, country) {
+		(Some(city), Some(country)) => Some(format!("{city}, {country}")),
+		(city, country) => city.or(country),
+	}
+}
+
+/// Best effort, human readable device label (e.g., "iPhone", "Android", "Mac").
+fn device_label_from_user_agent(user_agent: &str) -> String {
+	const DEVICES: &[(&str, &str)] = &[
+		("iPhone", "iPhone"),
+		("iPad", "iPad"),
+		("Android", "Android"),
+		("Windows", "Windows"),
+		("Macintosh", "Mac"),
+		("CrOS", "Chromebook"),
+		("Linux", "Linux"),
+	];
+
+	DEVICES
+		.iter()
+		.find(|(pattern, _)| user_agent.contains(pattern))
+		.map(|(_, label)| label.to_string())
+		.unwrap_or_else(|| "Unknown device".to_string())
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	pub type Result<T> = core::result::Result<T, Error>;
+	pub type Error = Box<dyn std::error::Error>; // For tests.
+
+	use super::*;
+	use axum::http::Request;
+
+	fn fx_headers(x_forwarded_for: &str) -> Result<HeaderMap> {
+		let mut headers = HeaderMap::new();
+		headers.insert("x-forwarded-for", x_forwarded_for.parse()?);
+
+		Ok(headers)
+	}
+
+	#[tokio::test]
+	async fn test_client_info_ip_ok_spoofed_header_ignored() -> Result<()> {
+		// -- Setup & Fixtures
+		// Note: No trusted proxy configured in the tests env.
+		let fx_peer: SocketAddr = "203.0.113.7:5000".parse()?;
+		let req = Request::get("/")
+			.header("x-forwarded-for", "1.2.3.4")
+			.extension(ConnectInfo(fx_peer))
+			.body(())?;
+		let (mut parts, _) = req.into_parts();
+
+		// -- Exec
+		let client_info = ClientInfo::from_request_parts(&mut parts, &()).await?;
+
+		// -- Check
+		// The ip is the throttle key of the logins (see `api_login_handler`).
+		assert_eq!(client_info.ip.as_deref(), Some("203.0.113.7"));
+
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn test_client_info_location_ok_spoofed_header_ignored() -> Result<()> {
+		// -- Setup & Fixtures
+		// Note: No trusted proxy configured in the tests env.
+		let fx_peer: SocketAddr = "203.0.113.7:5000".parse()?;
+		let req = Request::get("/")
+			.header("cf-ipcountry", "SG")
+			.header("x-geo-city", "Singapore")
+			.extension(ConnectInfo(fx_peer))
+			.body(())?;
+		let (mut parts, _) = req.into_parts();
+
+		// -- Exec
+		let client_info = ClientInfo::from_request_parts(&mut parts, &()).await?;
+
+		// -- Check
+		assert_eq!(client_info.location, None);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_location_from_headers_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let mut fx_headers = HeaderMap::new();
+		fx_headers.insert("cf-ipcountry", "SG".parse()?);
+		fx_headers.insert("x-geo-city", "Singapore".parse()?);
+
+		// -- Exec
+		let location = location_from_headers(&fx_headers);
+
+		// -- Check
+		assert_eq!(location.as_deref(), Some("Singapore, SG"));
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_client_ip_ok_right_most_untrusted() -> Result<()> {
+		// -- Setup & Fixtures
+		let fx_trusted: Vec<IpNet> = vec!["10.0.0.0/8".parse()?];
+		let fx_headers = fx_headers("1.2.3.4, 198.51.100.9, 10.0.0.2")?;
+
+		// -- Exec
+		let ip = client_ip(&fx_headers, Some("10.0.0.1".parse()?), &fx_trusted);
+
+		// -- Check
+		// "1.2.3.4" is whatever the client sent.
+		assert_eq!(ip, Some("198.51.100.9".parse()?));
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_client_ip_ok_untrusted_peer() -> Result<()> {
+		// -- Setup & Fixtures
+		let fx_trusted: Vec<IpNet> = vec!["10.0.0.0/8".parse()?];
+		let fx_headers = fx_headers("1.2.3.4")?;
+
+		// -- Exec
+		let ip = client_ip(&fx_headers, Some("198.51.100.9".parse()?), &fx_trusted);
+
+		// -- Check
+		assert_eq!(ip, Some("198.51.100.9".parse()?));
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_client_ip_ok_invalid_hop() -> Result<()> {
+		// -- Setup & Fixtures
+		let fx_trusted: Vec<IpNet> = vec!["10.0.0.0/8".parse()?];
+		let fx_headers = fx_headers("not-an-ip, 10.0.0.2")?;
+
+		// -- Exec
+		let ip = client_ip(&fx_headers, Some("10.0.0.1".parse()?), &fx_trusted);
+
+		// -- Check
+		assert_eq!(ip, Some("10.0.0.2".parse()?));
+
+		Ok(())
+	}
+}
+
+// endregion: --- Tests
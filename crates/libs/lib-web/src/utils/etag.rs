@@ -0,0 +1,34 @@
+use axum::http::header::IF_MATCH;
+use axum::http::HeaderMap;
+
+use crate::error::{Error, Result};
+
+/// The `ETag` of an entity version (e.g., `"3"`).
+pub fn version_etag(version: i64) -> String {
+	format!("\"{version}\"")
+}
+
+/// The entity version of the `If-Match` header, for an update only if not updated in
+/// between (see `PostBmc::update_if_version`).
+/// Returns None if no header, or `*` (any version).
+pub fn if_match_version(headers: &HeaderMap) -> Result<Option<i64>> {
+	let Some(if_match) = headers.get(IF_MATCH) else {
+		return Ok(None);
+	};
+
+	let if_match = if_match.to_str().map_err(|_| Error::IfMatchInvalid)?.trim();
+	if if_match == "*" {
+		return Ok(None);
+	}
+
+	// Note: The quotes are optional, for the clients sending the bare version.
+	let version = if_match
+		.strip_prefix('"')
+		.and_then(|v| v.strip_suffix('"'))
+		.unwrap_or(if_match);
+
+	version
+		.parse::<i64>()
+		.map(Some)
+		.map_err(|_| Error::IfMatchInvalid)
+}
@@ -1 +1,3 @@
+pub mod client_info;
+pub mod etag;
 pub mod token;
\ No newline at end of file
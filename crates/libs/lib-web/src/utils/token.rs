@@ -1,18 +1,19 @@
 use axum::{body::Body, extract::Request, http::HeaderMap};
 use tower_cookies::{Cookies, Cookie};
 
-use lib_auth::token::generate_web_tokens;
+use lib_auth::token::generate_access_token;
 use uuid::Uuid;
 pub use crate::error::{Error, Result};
 
 pub(crate) const AUTH_TOKEN: &str = "auth-token";
 
 pub(crate) fn set_token_cookie(
-    cookies: &Cookies, 
-    user: &str, 
-    salt: Uuid
+    cookies: &Cookies,
+    user: &str,
+    salt: Uuid,
+    sid: Uuid,
 ) -> Result<String> {
-    let (access_token, _refresh_token) = generate_web_tokens(user, salt)?;
+    let access_token = generate_access_token(user, salt, sid)?;
 
     let mut cookie = Cookie::new(AUTH_TOKEN, access_token.clone());
     cookie.set_http_only(true);
@@ -1,4 +1,5 @@
 use lib_storage::oss::OssClient;
+use lib_storage::StorageBackend;
 use serde_json::json;
 use std::str;
 use tokio;
@@ -18,7 +19,7 @@ async fn main() -> Result<()> {
 
     println!("\n=== Testing OSS Client ===");
 
-    let oss = OssClient::new();
+    let oss = OssClient::new()?;
     let filename = "tests/hello_test.txt";
     let content = b"Hello from Rust!";
 
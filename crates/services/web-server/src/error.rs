@@ -1,5 +1,6 @@
 use derive_more::From;
-use lib_core::model;
+use lib_core::{migration, model};
+use lib_storage as storage;
 
 pub type Result<T> = core::result::Result<T, Error>;
 
@@ -8,6 +9,10 @@ pub enum Error {
 	// -- Modules
 	#[from]
 	Model(model::Error),
+	#[from]
+	Migration(migration::Error),
+	#[from]
+	Storage(storage::Error),
 }
 
 // region:    --- Error Boilerplate
@@ -0,0 +1,40 @@
+//! The background jobs of the web server.
+
+use lib_core::ctx::Ctx;
+use lib_core::model::media_upload::MediaUploadBmc;
+use lib_core::model::post_media::PostMediaBmc;
+use lib_core::model::ModelManager;
+use lib_storage::Storage;
+use std::time::Duration;
+use tokio::time::{interval, MissedTickBehavior};
+use tracing::{info, warn};
+
+/// The period of the sweep of the expired uploads.
+const UPLOAD_SWEEP_PERIOD: Duration = Duration::from_secs(15 * 60);
+
+/// Spawn the periodic sweep of the expired uploads: the pending media of the upload
+/// slots never completed, with their files, and the resumable uploads, with their
+/// staged chunks.
+pub fn spawn_upload_sweep(mm: ModelManager, storage: Storage) {
+    tokio::spawn(async move {
+        let mut ticker = interval(UPLOAD_SWEEP_PERIOD);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let ctx = Ctx::root_ctx();
+
+        loop {
+            ticker.tick().await;
+
+            match PostMediaBmc::delete_expired_uploads(&ctx, &mm, &storage).await {
+                Ok(0) => (),
+                Ok(count) => info!("{:<12} - expired pending media deleted: {count}", "JOBS"),
+                Err(ex) => warn!("{:<12} - expired pending media sweep failed: {ex:?}", "JOBS"),
+            }
+
+            match MediaUploadBmc::delete_expired(&ctx, &mm, &storage).await {
+                Ok(0) => (),
+                Ok(count) => info!("{:<12} - expired resumable uploads deleted: {count}", "JOBS"),
+                Err(ex) => warn!("{:<12} - expired resumable uploads sweep failed: {ex:?}", "JOBS"),
+            }
+        }
+    });
+}
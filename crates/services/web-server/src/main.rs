@@ -2,6 +2,7 @@
 
 mod config;
 mod error;
+mod jobs;
 mod web;
 
 use std::net::SocketAddr;
@@ -10,17 +11,23 @@ pub use self::error::{Error, Result};
 use axum::response::Html;
 use config::web_config;
 
-use lib_web::handlers::mw_req_stamp::mw_req_stamp_resolver;
+use lib_web::middleware::mw_req_stamp::mw_req_stamp_resolver;
 use lib_web::middleware::mw_auth::mw_ctx_resolver;
-// use lib_web::middleware::mw_res_map::mw_reponse_map;
-use lib_web::routes::routes_static;
+use lib_web::middleware::mw_res_map::mw_reponse_map;
+use lib_web::routes::{routes_static, routes_tus};
 
-use crate::web::{routes_email, routes_login, routes_register, routes_token};
+use crate::web::{
+    routes_email, routes_login, routes_media, routes_mfa, routes_oidc, routes_post, routes_register,
+    routes_session, routes_storage, routes_token, routes_trip, routes_user,
+};
 
 use axum::{middleware, Router};
 use axum::routing::get;
 use lib_core::_dev_utils;
+use lib_core::config::core_config;
+use lib_core::migration::Migrator;
 use lib_core::model::ModelManager;
+use lib_storage::Storage;
 use tokio::net::TcpListener;
 use tower_cookies::CookieManagerLayer;
 use tracing_subscriber::EnvFilter;
@@ -43,6 +50,15 @@ async fn main() -> Result<()> {
     // Initialize ModelManager
     let mm = ModelManager::new().await?;
 
+    // -- Check the schema is up to date (see `cargo run -p db-migrate -- up`)
+    Migrator::from_dir(&core_config().MIGRATIONS_DIR)?
+        .check_no_pending(&mm)
+        .await?;
+
+    // -- Storage of the media (see `STORAGE_BACKEND`)
+    let storage = Storage::from_config()?;
+    jobs::spawn_upload_sweep(mm.clone(), storage.clone());
+
     let routes_hello = Router::new()
         .route("/hello", get(|| async { Html("Hello world") }));
         // .route_layer(middleware::from_fn(mw_ctx_require));
@@ -53,8 +69,17 @@ async fn main() -> Result<()> {
         .merge(routes_login::routes(mm.clone()))   
         .merge(routes_email::routes(mm.clone()))
         .merge(routes_token::routes(mm.clone()))
+        .merge(routes_session::routes(mm.clone()))
+        .merge(routes_mfa::routes(mm.clone()))
+        .merge(routes_oidc::routes(mm.clone()))
+        .merge(routes_user::routes(mm.clone(), storage.clone()))
+        .merge(routes_post::routes(mm.clone()))
+        .merge(routes_trip::routes(mm.clone()))
+        .merge(routes_media::routes(mm.clone(), storage.clone()))
+        .merge(routes_tus::routes(mm.clone(), storage.clone()))
         .merge(routes_hello)
-        // .layer(middleware::map_response(mw_reponse_map))
+        .merge(routes_storage::routes(&storage))
+        .layer(middleware::map_response(mw_reponse_map))
         .layer(middleware::from_fn_with_state(
             mm.clone(),
             mw_ctx_resolver
@@ -67,9 +92,12 @@ async fn main() -> Result<()> {
     println!("{:12} - {addr}\n", "LISTENING");
 
     let listener = TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, routes_all.into_make_service())
-        .await
-        .unwrap();
+    axum::serve(
+        listener,
+        routes_all.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .unwrap();
 
     Ok(())
 }
@@ -1,4 +1,12 @@
 pub mod routes_login;
+pub mod routes_media;
+pub mod routes_mfa;
+pub mod routes_oidc;
+pub mod routes_post;
 pub mod routes_register;
+pub mod routes_session;
+pub mod routes_storage;
 pub mod routes_email;
-pub mod routes_token;
\ No newline at end of file
+pub mod routes_token;
+pub mod routes_trip;
+pub mod routes_user;
\ No newline at end of file
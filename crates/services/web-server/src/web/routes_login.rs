@@ -6,6 +6,19 @@ use lib_web::handlers::handlers_login;
 pub fn routes(mm: ModelManager) -> Router {
 	Router::new()
 		.route("/api/login", post(handlers_login::api_login_handler))
+		.route("/api/login/mfa", post(handlers_login::api_login_mfa_handler))
+		.route(
+			"/api/login/link",
+			post(handlers_login::api_login_link_request_handler),
+		)
+		.route(
+			"/api/login/link/verify",
+			post(handlers_login::api_login_link_handler),
+		)
+		.route(
+			"/api/login/unlock",
+			post(handlers_login::api_login_unlock_handler),
+		)
 		.route("/api/logout", post(handlers_login::api_logout_handler))
 		.with_state(mm)
 }
\ No newline at end of file
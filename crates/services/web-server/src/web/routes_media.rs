@@ -0,0 +1,20 @@
+use axum::routing::post;
+use axum::{middleware, Router};
+use lib_core::model::ModelManager;
+use lib_storage::Storage;
+use lib_web::handlers::handlers_media::{self, MediaState};
+use lib_web::middleware::mw_auth::mw_ctx_require;
+
+pub fn routes(mm: ModelManager, storage: Storage) -> Router {
+	Router::new()
+		.route(
+			"/api/posts/{id}/media/uploads",
+			post(handlers_media::api_create_media_upload_handler),
+		)
+		.route(
+			"/api/media/{id}/complete",
+			post(handlers_media::api_complete_media_upload_handler),
+		)
+		.route_layer(middleware::from_fn(mw_ctx_require))
+		.with_state(MediaState { mm, storage })
+}
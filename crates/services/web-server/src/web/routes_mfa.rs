@@ -0,0 +1,14 @@
+use axum::routing::post;
+use axum::{middleware, Router};
+use lib_core::model::ModelManager;
+use lib_web::handlers::handlers_mfa;
+use lib_web::middleware::mw_auth::mw_ctx_require;
+
+pub fn routes(mm: ModelManager) -> Router {
+	Router::new()
+		.route("/api/mfa/setup", post(handlers_mfa::api_mfa_setup_handler))
+		.route("/api/mfa/enable", post(handlers_mfa::api_mfa_enable_handler))
+		.route("/api/mfa/disable", post(handlers_mfa::api_mfa_disable_handler))
+		.route_layer(middleware::from_fn(mw_ctx_require))
+		.with_state(mm)
+}
@@ -0,0 +1,17 @@
+use axum::routing::get;
+use axum::Router;
+use lib_core::model::ModelManager;
+use lib_web::handlers::handlers_oidc;
+
+pub fn routes(mm: ModelManager) -> Router {
+	Router::new()
+		.route(
+			"/api/oidc/{provider}/start",
+			get(handlers_oidc::api_oidc_start_handler),
+		)
+		.route(
+			"/api/oidc/{provider}/callback",
+			get(handlers_oidc::api_oidc_callback_handler),
+		)
+		.with_state(mm)
+}
@@ -0,0 +1,22 @@
+use axum::routing::get;
+use axum::{middleware, Router};
+use lib_core::model::ModelManager;
+use lib_web::handlers::handlers_posts;
+use lib_web::middleware::mw_auth::mw_ctx_require;
+
+pub fn routes(mm: ModelManager) -> Router {
+	Router::new()
+		.route("/api/posts", get(handlers_posts::api_list_posts_handler))
+		.route("/api/search", get(handlers_posts::api_search_posts_handler))
+		.route(
+			"/api/posts/{id}",
+			get(handlers_posts::api_get_post_handler)
+				.patch(handlers_posts::api_update_post_handler),
+		)
+		.route(
+			"/api/posts/{id}/history",
+			get(handlers_posts::api_post_history_handler),
+		)
+		.route_layer(middleware::from_fn(mw_ctx_require))
+		.with_state(mm)
+}
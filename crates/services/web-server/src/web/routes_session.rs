@@ -0,0 +1,20 @@
+use axum::routing::{delete, get, post};
+use axum::{middleware, Router};
+use lib_core::model::ModelManager;
+use lib_web::handlers::handlers_sessions;
+use lib_web::middleware::mw_auth::mw_ctx_require;
+
+pub fn routes(mm: ModelManager) -> Router {
+	Router::new()
+		.route("/api/sessions", get(handlers_sessions::api_list_sessions_handler))
+		.route(
+			"/api/sessions/revoke-others",
+			post(handlers_sessions::api_revoke_other_sessions_handler),
+		)
+		.route(
+			"/api/sessions/{id}",
+			delete(handlers_sessions::api_revoke_session_handler),
+		)
+		.route_layer(middleware::from_fn(mw_ctx_require))
+		.with_state(mm)
+}
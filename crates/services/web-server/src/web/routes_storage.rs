@@ -0,0 +1,14 @@
+use axum::Router;
+use lib_storage::{LocalStorage, Storage};
+use tower_http::services::ServeDir;
+
+/// The objects of the local filesystem storage (none for the other backends, since
+/// their objects are served by their own public urls).
+pub fn routes(storage: &Storage) -> Router {
+	match storage {
+		Storage::Local(local) => {
+			Router::new().nest_service(LocalStorage::ROUTE_PATH, ServeDir::new(local.dir()))
+		}
+		_ => Router::new(),
+	}
+}
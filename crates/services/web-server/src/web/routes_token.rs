@@ -1,9 +1,10 @@
-use axum::{Router, routing::post};
+use axum::{Router, routing::{get, post}};
 use lib_core::model::ModelManager;
 use lib_web::handlers::handlers_tokens;
 
 pub fn routes(mm: ModelManager) -> Router {
     Router::new()
         .route("/api/auth/refresh", post(handlers_tokens::api_refresh_token_handler))
+        .route("/.well-known/jwks.json", get(handlers_tokens::api_jwks_handler))
         .with_state(mm)
 }
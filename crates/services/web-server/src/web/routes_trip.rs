@@ -0,0 +1,55 @@
+use axum::routing::{get, patch, post, put};
+use axum::{middleware, Router};
+use lib_core::model::ModelManager;
+use lib_web::handlers::handlers_trips;
+use lib_web::middleware::mw_auth::mw_ctx_require;
+
+pub fn routes(mm: ModelManager) -> Router {
+	Router::new()
+		// -- Trips
+		.route(
+			"/api/trips",
+			get(handlers_trips::api_list_trips_handler).post(handlers_trips::api_create_trip_handler),
+		)
+		.route(
+			"/api/trips/{id}",
+			get(handlers_trips::api_get_trip_handler)
+				.patch(handlers_trips::api_update_trip_handler)
+				.delete(handlers_trips::api_delete_trip_handler),
+		)
+		.route(
+			"/api/trips/{id}/duplicate",
+			post(handlers_trips::api_duplicate_trip_handler),
+		)
+		.route("/api/trips/{id}/totals", get(handlers_trips::api_trip_totals_handler))
+		// -- Days
+		.route(
+			"/api/trips/{id}/days",
+			post(handlers_trips::api_create_trip_day_handler),
+		)
+		.route(
+			"/api/trips/{id}/days/order",
+			put(handlers_trips::api_reorder_trip_days_handler),
+		)
+		.route(
+			"/api/trip-days/{id}",
+			patch(handlers_trips::api_update_trip_day_handler)
+				.delete(handlers_trips::api_delete_trip_day_handler),
+		)
+		// -- Stops
+		.route(
+			"/api/trip-days/{id}/stops",
+			post(handlers_trips::api_create_trip_stop_handler),
+		)
+		.route(
+			"/api/trip-days/{id}/stops/order",
+			put(handlers_trips::api_reorder_trip_stops_handler),
+		)
+		.route(
+			"/api/trip-stops/{id}",
+			patch(handlers_trips::api_update_trip_stop_handler)
+				.delete(handlers_trips::api_delete_trip_stop_handler),
+		)
+		.route_layer(middleware::from_fn(mw_ctx_require))
+		.with_state(mm)
+}
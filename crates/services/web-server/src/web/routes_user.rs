@@ -0,0 +1,17 @@
+use axum::routing::{delete, put};
+use axum::{middleware, Router};
+use lib_core::model::ModelManager;
+use lib_storage::Storage;
+use lib_web::handlers::handlers_users::{self, UserState};
+use lib_web::middleware::mw_auth::mw_ctx_require;
+
+pub fn routes(mm: ModelManager, storage: Storage) -> Router {
+	Router::new()
+		.route(
+			"/api/users/{id}/role",
+			put(handlers_users::api_update_user_role_handler),
+		)
+		.route("/api/users/{id}", delete(handlers_users::api_delete_user_handler))
+		.route_layer(middleware::from_fn(mw_ctx_require))
+		.with_state(UserState { mm, storage })
+}
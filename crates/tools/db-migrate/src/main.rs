@@ -0,0 +1,71 @@
+pub type Result<T> = core::result::Result<T, Error>;
+pub type Error = Box<dyn std::error::Error>; // Ok for tools
+
+use lib_core::config::core_config;
+use lib_core::ctx::Ctx;
+use lib_core::migration::Migrator;
+use lib_core::model::user::UserBmc;
+use lib_core::model::ModelManager;
+use tracing_subscriber::EnvFilter;
+
+/// Usage (migrations from SERVICE_MIGRATIONS_DIR, db from SERVICE_DB_URL):
+/// - `db-migrate status` - list the migrations, applied or pending
+/// - `db-migrate up [version]` - apply the pending migrations (up to version)
+/// - `db-migrate down [steps]` - roll back the last applied migrations (default 1)
+/// - `db-migrate hash-legacy-tokens` - one-off data migration of the plaintext one-time tokens
+///   to their keyed hashes (needs the token keys, so not a sql migration; idempotent)
+#[tokio::main]
+async fn main() -> Result<()> {
+    dotenvy::dotenv().ok();
+
+    tracing_subscriber::fmt()
+        .without_time()
+        .with_target(false)
+        .with_env_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| "info".into()))
+        .init();
+
+    let mut args = std::env::args().skip(1);
+    let command = args.next();
+    let arg = args.next();
+
+    let migrator = Migrator::from_dir(&core_config().MIGRATIONS_DIR)?;
+    let mm = ModelManager::new().await?;
+
+    match command.as_deref() {
+        None | Some("status") => print_status(&migrator, &mm).await,
+        Some("up") => {
+            let to_version = arg.map(|v| v.parse::<i64>()).transpose()?;
+            let applied = migrator.up(&mm, to_version).await?;
+            println!("\nApplied {} migration(s): {applied:?}", applied.len());
+            Ok(())
+        }
+        Some("down") => {
+            let steps = arg.map(|v| v.parse::<usize>()).transpose()?.unwrap_or(1);
+            let rolled_back = migrator.down(&mm, steps).await?;
+            println!("\nRolled back {} migration(s): {rolled_back:?}", rolled_back.len());
+            Ok(())
+        }
+        Some("hash-legacy-tokens") => {
+            let count = UserBmc::hash_legacy_tokens(&Ctx::root_ctx(), &mm).await?;
+            println!("\nHashed {count} legacy token(s)");
+            Ok(())
+        }
+        Some(other) => Err(format!(
+            "Unknown command '{other}' (expected 'status', 'up', 'down' or 'hash-legacy-tokens')"
+        )
+        .into()),
+    }
+}
+
+async fn print_status(migrator: &Migrator, mm: &ModelManager) -> Result<()> {
+    println!();
+    for status in migrator.status(mm).await? {
+        let applied = status
+            .applied_at
+            .map(|at| format!("applied {at}"))
+            .unwrap_or_else(|| "PENDING".to_string());
+        println!("{:04}_{:<40} {applied}", status.version, status.name);
+    }
+
+    Ok(())
+}
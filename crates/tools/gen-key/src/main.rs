@@ -1,10 +1,28 @@
 pub type Result<T> = core::result::Result<T, Error>;
 pub type Error = Box<dyn std::error::Error>; // Ok for tools
 
+use lib_auth::token::keyring::{generate_ed25519_keypair, generate_rsa_keypair, KeyPairDer};
 use lib_utils::b64::b64u_encode;
 use rand::RngCore;
 
+const RSA_BITS: usize = 2048;
+
+/// Usage:
+/// - `gen-key` - 512 bits symmetric key (e.g., SERVICE_PWD_KEY, SERVICE_TOKEN_KEY)
+/// - `gen-key ed25519 <kid>` - EdDSA keypair for SERVICE_JWT_KEYS
+/// - `gen-key rsa <kid>` - RS256 keypair for SERVICE_JWT_KEYS
 fn main() -> Result<()> {
+    let mut args = std::env::args().skip(1);
+
+    match args.next().as_deref() {
+        None => gen_symmetric_key(),
+        Some("ed25519") => print_keypair(&kid_arg(args.next())?, generate_ed25519_keypair()?),
+        Some("rsa") => print_keypair(&kid_arg(args.next())?, generate_rsa_keypair(RSA_BITS)?),
+        Some(other) => Err(format!("Unknown key type '{other}' (expected 'ed25519' or 'rsa')").into()),
+    }
+}
+
+fn gen_symmetric_key() -> Result<()> {
     let mut key = [0u8; 64]; // 512 bits = 64 bytes
     rand::rng().fill_bytes(&mut key);
     println!("\nGenerated key from rand::rng():\n{key:?}");
@@ -14,3 +32,18 @@ fn main() -> Result<()> {
 
     Ok(())
 }
+
+fn print_keypair(kid: &str, keypair: KeyPairDer) -> Result<()> {
+    println!("\nPrivate key (PKCS#8), SERVICE_JWT_KEYS entry (keep secret):");
+    println!("{kid}={}", b64u_encode(&keypair.private_pkcs8));
+
+    println!("\nPublic key (SPKI), SERVICE_JWT_KEYS entry once retired (verification only):");
+    println!("{kid}={}", b64u_encode(&keypair.public_spki));
+
+    Ok(())
+}
+
+fn kid_arg(kid: Option<String>) -> Result<String> {
+    kid.filter(|kid| !kid.is_empty() && !kid.contains([',', '=']))
+        .ok_or_else(|| "Missing or invalid key id, e.g., 'gen-key ed25519 k-2025-01'".into())
+}
@@ -0,0 +1,6 @@
+-- Note: The root user (id 0) is created by the migrations.
+
+-- Create demo1 User
+INSERT INTO "user" 
+    (username, email, cid, ctime, mid, mtime) VALUES 
+    ('demo1', 'demo1@example.com', 0, now(), 0, now());
\ No newline at end of file
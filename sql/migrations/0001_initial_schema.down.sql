@@ -0,0 +1,11 @@
+---- Base app schema (rollback)
+
+DROP TABLE IF EXISTS login_throttle;
+DROP TABLE IF EXISTS oidc_login_state;
+DROP TABLE IF EXISTS user_identity;
+DROP TABLE IF EXISTS session;
+DROP TABLE IF EXISTS post;
+DROP TABLE IF EXISTS "user";
+
+DROP TYPE IF EXISTS user_role;
+DROP TYPE IF EXISTS user_typ;
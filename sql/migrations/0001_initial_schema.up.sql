@@ -0,0 +1,141 @@
+---- Base app schema
+
+-- User
+CREATE TYPE user_typ AS ENUM ('Sys', 'User');
+CREATE TYPE user_role AS ENUM ('Admin', 'Moderator', 'Creator', 'User');
+
+CREATE TABLE "user" (
+    id BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+    typ user_typ NOT NULL DEFAULT 'User',
+    role user_role NOT NULL DEFAULT 'User',
+    username VARCHAR(128) NOT NULL UNIQUE,
+    email VARCHAR(255) NOT NULL UNIQUE,
+
+    -- Auth
+    pwd VARCHAR(256),
+    pwd_salt uuid NOT NULL DEFAULT gen_random_uuid(),
+
+    -- Token
+    token_salt UUID NOT NULL DEFAULT gen_random_uuid(),
+    reset_token TEXT,
+    reset_token_expires_at TIMESTAMPTZ,
+    login_token TEXT, -- keyed hash of the login link token, single use
+    login_token_expires_at TIMESTAMPTZ,
+    unlock_token TEXT, -- keyed hash of the account unlock token (after a lockout)
+    unlock_token_expires_at TIMESTAMPTZ,
+
+    -- Email verification
+    email_verified BOOLEAN NOT NULL DEFAULT FALSE,
+    email_verification_token VARCHAR(255),
+    email_verification_expires_at TIMESTAMPTZ,
+
+    -- MFA (TOTP)
+    mfa_enabled BOOLEAN NOT NULL DEFAULT FALSE,
+    mfa_secret VARCHAR(64),
+    mfa_last_step BIGINT, -- last accepted TOTP time step (replay guard)
+    mfa_recovery_codes TEXT, -- comma separated keyed hashes, single use
+
+     -- Timestamps
+    cid BIGINT NOT NULL REFERENCES "user"(id),
+    ctime TIMESTAMPTZ NOT NULL DEFAULT now(),
+    mid BIGINT NOT NULL REFERENCES "user"(id),
+    mtime TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- Post
+CREATE TABLE post (
+    id BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+    owner_id BIGINT NOT NULL REFERENCES "user"(id) ON DELETE CASCADE,
+    title VARCHAR(256) NOT NULL,
+    description TEXT NOT NULL,
+    is_published BOOLEAN NOT NULL DEFAULT FALSE,
+
+    -- Audit fields
+    cid BIGINT NOT NULL REFERENCES "user"(id),
+    ctime TIMESTAMPTZ NOT NULL DEFAULT now(),
+    mid BIGINT NOT NULL REFERENCES "user"(id), 
+    mtime TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX post_owner_id_idx ON post(owner_id);
+
+-- Session (refresh token family)
+CREATE TABLE session (
+    id BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+    user_id BIGINT NOT NULL REFERENCES "user"(id) ON DELETE CASCADE,
+
+    -- Token family
+    family_id UUID NOT NULL UNIQUE,
+    current_jti UUID NOT NULL,
+
+    -- Client info
+    device VARCHAR(128),
+    user_agent TEXT,
+    ip VARCHAR(64),
+    location VARCHAR(128),
+
+    -- Lifecycle
+    last_used_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    expires_at TIMESTAMPTZ NOT NULL,
+    revoked_at TIMESTAMPTZ,
+    revoked_reason VARCHAR(64),
+
+    -- Timestamps
+    cid BIGINT NOT NULL REFERENCES "user"(id),
+    ctime TIMESTAMPTZ NOT NULL DEFAULT now(),
+    mid BIGINT NOT NULL REFERENCES "user"(id),
+    mtime TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX session_user_id_idx ON session(user_id);
+
+-- User identity (account linked from an OIDC provider)
+CREATE TABLE user_identity (
+    id BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+    user_id BIGINT NOT NULL REFERENCES "user"(id) ON DELETE CASCADE,
+
+    -- Provider account
+    provider VARCHAR(64) NOT NULL,
+    subject VARCHAR(255) NOT NULL, -- provider `sub` claim
+    email VARCHAR(255),
+    last_login_at TIMESTAMPTZ,
+
+    -- Timestamps
+    cid BIGINT NOT NULL REFERENCES "user"(id),
+    ctime TIMESTAMPTZ NOT NULL DEFAULT now(),
+    mid BIGINT NOT NULL REFERENCES "user"(id),
+    mtime TIMESTAMPTZ NOT NULL DEFAULT now(),
+
+    UNIQUE (provider, subject),
+    UNIQUE (user_id, provider)
+);
+
+-- OIDC login state (between the provider redirect and the callback)
+CREATE TABLE oidc_login_state (
+    id BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+    state VARCHAR(64) NOT NULL UNIQUE,
+    provider VARCHAR(64) NOT NULL,
+    nonce VARCHAR(64) NOT NULL,
+    pkce_verifier VARCHAR(128) NOT NULL,
+    link_user_id BIGINT REFERENCES "user"(id) ON DELETE CASCADE, -- set when linking to a logged in user
+    expires_at TIMESTAMPTZ NOT NULL,
+
+    -- Timestamps
+    cid BIGINT NOT NULL REFERENCES "user"(id),
+    ctime TIMESTAMPTZ NOT NULL DEFAULT now(),
+    mid BIGINT NOT NULL REFERENCES "user"(id),
+    mtime TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- Login throttle (failure counters, by account or ip)
+CREATE TABLE login_throttle (
+    id BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+    key VARCHAR(255) NOT NULL UNIQUE, -- "user:_username_" or "ip:_ip_"
+    failures INT NOT NULL DEFAULT 0,
+    last_failure_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    blocked_until TIMESTAMPTZ,
+
+    -- Timestamps
+    ctime TIMESTAMPTZ NOT NULL DEFAULT now(),
+    mtime TIMESTAMPTZ NOT NULL DEFAULT now()
+);
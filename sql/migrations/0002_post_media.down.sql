@@ -0,0 +1,9 @@
+---- Post media (rollback)
+
+DROP TABLE IF EXISTS post_media;
+
+ALTER TABLE post
+    DROP COLUMN IF EXISTS has_video,
+    DROP COLUMN IF EXISTS media_count,
+    DROP COLUMN IF EXISTS thumbnail_url,
+    DROP COLUMN IF EXISTS cover_media_url;
@@ -0,0 +1,32 @@
+---- Post media (carousel images and videos, and their summary on the post)
+
+-- Post media summary
+ALTER TABLE post
+    ADD COLUMN cover_media_url TEXT,
+    ADD COLUMN thumbnail_url TEXT,
+    ADD COLUMN media_count INT NOT NULL DEFAULT 0,
+    ADD COLUMN has_video BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Post media (carousel images and videos of a post)
+CREATE TABLE post_media (
+    id BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+    owner_id BIGINT NOT NULL REFERENCES "user"(id) ON DELETE CASCADE,
+    post_id BIGINT NOT NULL REFERENCES post(id) ON DELETE CASCADE,
+    media_url TEXT NOT NULL,
+    media_type VARCHAR(16) NOT NULL, -- "image" or "video"
+    mime_type VARCHAR(128) NOT NULL,
+    width INT,
+    height INT,
+    file_size BIGINT,
+    duration INT, -- for videos, in seconds
+    sort_order INT NOT NULL DEFAULT 0, -- order in carousel
+    alt_text TEXT,
+
+    -- Audit fields
+    cid BIGINT NOT NULL REFERENCES "user"(id),
+    ctime TIMESTAMPTZ NOT NULL DEFAULT now(),
+    mid BIGINT NOT NULL REFERENCES "user"(id),
+    mtime TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX post_media_post_id_idx ON post_media(post_id);
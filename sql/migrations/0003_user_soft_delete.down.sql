@@ -0,0 +1,5 @@
+---- User soft delete and erasure audit (rollback)
+
+DROP TABLE IF EXISTS um_change_log;
+
+ALTER TABLE "user" DROP COLUMN IF EXISTS deleted;
@@ -0,0 +1,20 @@
+---- User soft delete and erasure audit
+
+-- User
+ALTER TABLE "user" ADD COLUMN deleted BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- User management change log (audit of the user management actions, e.g., erasure)
+CREATE TABLE um_change_log (
+    id BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+    user_id BIGINT NOT NULL REFERENCES "user"(id), -- the changed user
+    action VARCHAR(64) NOT NULL, -- e.g., "user_erased"
+    note TEXT, -- summary of the change, without PII
+
+    -- Timestamps (cid is the user performing the action)
+    cid BIGINT NOT NULL REFERENCES "user"(id),
+    ctime TIMESTAMPTZ NOT NULL DEFAULT now(),
+    mid BIGINT NOT NULL REFERENCES "user"(id),
+    mtime TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX um_change_log_user_id_idx ON um_change_log(user_id);
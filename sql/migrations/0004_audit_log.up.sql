@@ -0,0 +1,13 @@
+---- Audit log (the writes of the entities opting in, see `DbBmc::has_audit_log`)
+
+CREATE TABLE audit_log (
+    id BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+    entity VARCHAR(64) NOT NULL, -- table of the entity, e.g., "post"
+    entity_id BIGINT NOT NULL,
+    op VARCHAR(16) NOT NULL, -- "create", "update", or "delete"
+    changes JSONB, -- the fields sent (column -> value), none for delete
+    actor_id BIGINT NOT NULL REFERENCES "user"(id), -- the ctx user
+    ctime TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX audit_log_entity_idx ON audit_log(entity, entity_id);
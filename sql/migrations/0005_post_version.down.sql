@@ -0,0 +1,3 @@
+---- Post version (rollback)
+
+ALTER TABLE post DROP COLUMN IF EXISTS version;
@@ -0,0 +1,3 @@
+---- Post version (optimistic concurrency, see `DbBmc::has_version`)
+
+ALTER TABLE post ADD COLUMN version BIGINT NOT NULL DEFAULT 1;
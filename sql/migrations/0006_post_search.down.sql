@@ -0,0 +1,8 @@
+---- Post search (rollback)
+
+DROP INDEX IF EXISTS post_title_trgm_idx;
+DROP INDEX IF EXISTS post_search_tsv_idx;
+ALTER TABLE post DROP COLUMN IF EXISTS search_tsv;
+ALTER TABLE post DROP COLUMN IF EXISTS lang;
+DROP FUNCTION IF EXISTS post_search_config(TEXT);
+-- Note: pg_trgm is kept, as it might be used by other objects.
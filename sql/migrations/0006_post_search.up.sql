@@ -0,0 +1,34 @@
+---- Post search (full-text by language, and trigram similarity for the typos)
+
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+-- The text search config of a post language (ISO 639-1 code), 'simple' if unknown.
+-- Note: IMMUTABLE, so usable by the generated `search_tsv` column.
+CREATE FUNCTION post_search_config(lang TEXT) RETURNS regconfig AS $$
+    SELECT CASE lang
+        WHEN 'da' THEN 'danish'
+        WHEN 'de' THEN 'german'
+        WHEN 'en' THEN 'english'
+        WHEN 'es' THEN 'spanish'
+        WHEN 'fi' THEN 'finnish'
+        WHEN 'fr' THEN 'french'
+        WHEN 'id' THEN 'indonesian'
+        WHEN 'it' THEN 'italian'
+        WHEN 'nl' THEN 'dutch'
+        WHEN 'pt' THEN 'portuguese'
+        WHEN 'ru' THEN 'russian'
+        WHEN 'sv' THEN 'swedish'
+        WHEN 'tr' THEN 'turkish'
+        ELSE 'simple'
+    END::regconfig
+$$ LANGUAGE SQL IMMUTABLE PARALLEL SAFE;
+
+ALTER TABLE post ADD COLUMN lang VARCHAR(8) NOT NULL DEFAULT 'simple';
+
+ALTER TABLE post ADD COLUMN search_tsv tsvector GENERATED ALWAYS AS (
+    setweight(to_tsvector(post_search_config(lang), title), 'A') ||
+    setweight(to_tsvector(post_search_config(lang), description), 'B')
+) STORED;
+
+CREATE INDEX post_search_tsv_idx ON post USING GIN (search_tsv);
+CREATE INDEX post_title_trgm_idx ON post USING GIN (title gin_trgm_ops);
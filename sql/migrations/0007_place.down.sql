@@ -0,0 +1,4 @@
+---- Places (rollback)
+
+DROP TABLE IF EXISTS post_place;
+DROP TABLE IF EXISTS place;
@@ -0,0 +1,46 @@
+---- Places (points of interest), and their tags on the posts
+
+CREATE TABLE place (
+    id BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+    name VARCHAR(256) NOT NULL,
+    category VARCHAR(64) NOT NULL, -- e.g., "restaurant", "museum", "viewpoint"
+
+    -- Coordinates (WGS 84, degrees)
+    lat DOUBLE PRECISION NOT NULL CHECK (lat BETWEEN -90 AND 90),
+    lon DOUBLE PRECISION NOT NULL CHECK (lon BETWEEN -180 AND 180),
+
+    -- Address components
+    street VARCHAR(256),
+    city VARCHAR(128),
+    region VARCHAR(128),
+    postal_code VARCHAR(32),
+    country_code VARCHAR(2), -- ISO 3166-1 alpha-2
+
+    opening_hours JSONB, -- e.g., {"mon": [["09:00", "17:00"]], "sun": []}
+    descriptions JSONB NOT NULL DEFAULT '{}', -- by language, e.g., {"en": "...", "fr": "..."}
+
+    -- External source (e.g., "osm" and its node id), for the imports
+    source VARCHAR(32),
+    source_id VARCHAR(128),
+
+    -- Audit fields
+    cid BIGINT NOT NULL REFERENCES "user"(id),
+    ctime TIMESTAMPTZ NOT NULL DEFAULT now(),
+    mid BIGINT NOT NULL REFERENCES "user"(id),
+    mtime TIMESTAMPTZ NOT NULL DEFAULT now(),
+
+    UNIQUE (source, source_id)
+);
+
+CREATE INDEX place_lat_lon_idx ON place(lat, lon);
+CREATE INDEX place_category_idx ON place(category);
+
+CREATE TABLE post_place (
+    post_id BIGINT NOT NULL REFERENCES post(id) ON DELETE CASCADE,
+    place_id BIGINT NOT NULL REFERENCES place(id) ON DELETE CASCADE,
+    ctime TIMESTAMPTZ NOT NULL DEFAULT now(),
+
+    PRIMARY KEY (post_id, place_id)
+);
+
+CREATE INDEX post_place_place_id_idx ON post_place(place_id);
@@ -0,0 +1,6 @@
+---- Geohash (rollback)
+
+DROP INDEX IF EXISTS place_geohash_idx;
+ALTER TABLE place DROP COLUMN IF EXISTS geohash;
+DROP FUNCTION IF EXISTS haversine_km(DOUBLE PRECISION, DOUBLE PRECISION, DOUBLE PRECISION, DOUBLE PRECISION);
+DROP FUNCTION IF EXISTS geohash_encode(DOUBLE PRECISION, DOUBLE PRECISION, INT);
@@ -0,0 +1,68 @@
+---- Geohash index of the places, and the haversine distance (see `lib_core::geo`)
+-- Note: Plain sql/plpgsql, no extension needed.
+
+-- The geohash of a location, same as `geo::geohash_encode`.
+CREATE FUNCTION geohash_encode(lat DOUBLE PRECISION, lon DOUBLE PRECISION, len INT)
+RETURNS TEXT AS $$
+DECLARE
+    base32 CONSTANT TEXT := '0123456789bcdefghjkmnpqrstuvwxyz';
+    lat_min DOUBLE PRECISION := -90;
+    lat_max DOUBLE PRECISION := 90;
+    lon_min DOUBLE PRECISION := -180;
+    lon_max DOUBLE PRECISION := 180;
+    mid DOUBLE PRECISION;
+    hash TEXT := '';
+    ch INT := 0;
+    bits INT := 0;
+    even BOOLEAN := TRUE;
+BEGIN
+    WHILE length(hash) < len LOOP
+        IF even THEN
+            mid := (lon_min + lon_max) / 2;
+            IF lon >= mid THEN
+                ch := ch * 2 + 1;
+                lon_min := mid;
+            ELSE
+                ch := ch * 2;
+                lon_max := mid;
+            END IF;
+        ELSE
+            mid := (lat_min + lat_max) / 2;
+            IF lat >= mid THEN
+                ch := ch * 2 + 1;
+                lat_min := mid;
+            ELSE
+                ch := ch * 2;
+                lat_max := mid;
+            END IF;
+        END IF;
+        even := NOT even;
+
+        bits := bits + 1;
+        IF bits = 5 THEN
+            hash := hash || substr(base32, ch + 1, 1);
+            ch := 0;
+            bits := 0;
+        END IF;
+    END LOOP;
+
+    RETURN hash;
+END;
+$$ LANGUAGE plpgsql IMMUTABLE STRICT PARALLEL SAFE;
+
+-- The great-circle distance (km) between two locations, same as `Location::distance_km`.
+CREATE FUNCTION haversine_km(
+    lat1 DOUBLE PRECISION, lon1 DOUBLE PRECISION,
+    lat2 DOUBLE PRECISION, lon2 DOUBLE PRECISION
+) RETURNS DOUBLE PRECISION AS $$
+    SELECT 2 * 6371.0088 * asin(sqrt(
+        power(sin(radians(lat2 - lat1) / 2), 2)
+        + cos(radians(lat1)) * cos(radians(lat2)) * power(sin(radians(lon2 - lon1) / 2), 2)
+    ))
+$$ LANGUAGE SQL IMMUTABLE STRICT PARALLEL SAFE;
+
+ALTER TABLE place ADD COLUMN geohash VARCHAR(12)
+    GENERATED ALWAYS AS (geohash_encode(lat, lon, 12)) STORED;
+
+-- Note: text_pattern_ops, for the prefix scans (`geohash LIKE 'w21z%'`).
+CREATE INDEX place_geohash_idx ON place(geohash text_pattern_ops);
@@ -0,0 +1,5 @@
+---- Trips (rollback)
+
+DROP TABLE IF EXISTS trip_stop;
+DROP TABLE IF EXISTS trip_day;
+DROP TABLE IF EXISTS trip;
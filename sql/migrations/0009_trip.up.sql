@@ -0,0 +1,74 @@
+---- Trips (personalized routes): the days of a trip, and the stops (places) of each day
+
+CREATE TABLE trip (
+    id BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+    owner_id BIGINT NOT NULL REFERENCES "user"(id) ON DELETE CASCADE,
+    title VARCHAR(256) NOT NULL,
+    description TEXT,
+    start_date DATE,
+    currency VARCHAR(3) NOT NULL DEFAULT 'USD', -- ISO 4217, of the stop budgets
+
+    -- A template is visible to all the users, to duplicate it
+    is_template BOOLEAN NOT NULL DEFAULT FALSE,
+    -- The trip this one was duplicated from, if any
+    source_trip_id BIGINT REFERENCES trip(id) ON DELETE SET NULL,
+
+    -- Audit fields
+    cid BIGINT NOT NULL REFERENCES "user"(id),
+    ctime TIMESTAMPTZ NOT NULL DEFAULT now(),
+    mid BIGINT NOT NULL REFERENCES "user"(id),
+    mtime TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX trip_owner_id_idx ON trip(owner_id);
+
+CREATE TABLE trip_day (
+    id BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+    trip_id BIGINT NOT NULL REFERENCES trip(id) ON DELETE CASCADE,
+    position INT NOT NULL,
+    title VARCHAR(256),
+    notes TEXT,
+
+    -- Audit fields
+    cid BIGINT NOT NULL REFERENCES "user"(id),
+    ctime TIMESTAMPTZ NOT NULL DEFAULT now(),
+    mid BIGINT NOT NULL REFERENCES "user"(id),
+    mtime TIMESTAMPTZ NOT NULL DEFAULT now(),
+
+    -- Note: Deferrable, checked at the end of the statement, for the reorders in one update.
+    UNIQUE (trip_id, position) DEFERRABLE INITIALLY IMMEDIATE,
+    UNIQUE (id, trip_id)
+);
+
+CREATE TABLE trip_stop (
+    id BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+    trip_id BIGINT NOT NULL,
+    trip_day_id BIGINT NOT NULL,
+    place_id BIGINT NOT NULL REFERENCES place(id),
+    position INT NOT NULL,
+
+    -- Planned times (local time of the day)
+    arrival_time TIME,
+    departure_time TIME,
+    -- From the previous stop, e.g., "walk", "transit"
+    transport_mode VARCHAR(16) CHECK (
+        transport_mode IN ('walk', 'bike', 'drive', 'transit', 'train', 'ferry', 'flight')
+    ),
+    notes TEXT,
+    -- In the minor unit of the trip currency (e.g., cents)
+    budget BIGINT CHECK (budget >= 0),
+
+    -- Audit fields
+    cid BIGINT NOT NULL REFERENCES "user"(id),
+    ctime TIMESTAMPTZ NOT NULL DEFAULT now(),
+    mid BIGINT NOT NULL REFERENCES "user"(id),
+    mtime TIMESTAMPTZ NOT NULL DEFAULT now(),
+
+    -- Note: The day must be of the same trip.
+    FOREIGN KEY (trip_day_id, trip_id) REFERENCES trip_day(id, trip_id) ON DELETE CASCADE,
+    UNIQUE (trip_day_id, position) DEFERRABLE INITIALLY IMMEDIATE,
+    CHECK (departure_time IS NULL OR arrival_time IS NULL OR departure_time >= arrival_time)
+);
+
+CREATE INDEX trip_stop_trip_id_idx ON trip_stop(trip_id);
+CREATE INDEX trip_stop_place_id_idx ON trip_stop(place_id);
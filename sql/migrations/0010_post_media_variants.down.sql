@@ -0,0 +1,3 @@
+---- Image variants of the post media (rollback)
+
+ALTER TABLE post_media DROP COLUMN IF EXISTS variants;
@@ -0,0 +1,5 @@
+---- Image variants of the post media (see `lib_storage::media::process_image`)
+
+-- e.g., [{"name": "thumbnail", "width": 320, "height": 240, "webp_url": "...",
+--         "fallback_url": "...", "fallback_mime_type": "image/jpeg"}, ...]
+ALTER TABLE post_media ADD COLUMN variants JSONB NOT NULL DEFAULT '[]';
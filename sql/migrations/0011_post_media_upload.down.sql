@@ -0,0 +1,6 @@
+---- Direct uploads of the post media (rollback)
+
+DROP INDEX IF EXISTS post_media_pending_idx;
+ALTER TABLE post_media DROP COLUMN IF EXISTS upload_expires_at;
+ALTER TABLE post_media DROP COLUMN IF EXISTS storage_key;
+ALTER TABLE post_media DROP COLUMN IF EXISTS status;
@@ -0,0 +1,10 @@
+---- Direct uploads of the post media, to the storage (see `PostMediaBmc::create_upload_slot`)
+
+-- 'pending' from the upload slot until the upload is completed.
+ALTER TABLE post_media ADD COLUMN status VARCHAR(16) NOT NULL DEFAULT 'ready'
+    CHECK (status IN ('pending', 'ready'));
+-- The object of the upload, and the expiration of its presigned upload.
+ALTER TABLE post_media ADD COLUMN storage_key TEXT;
+ALTER TABLE post_media ADD COLUMN upload_expires_at TIMESTAMPTZ;
+
+CREATE INDEX post_media_pending_idx ON post_media(upload_expires_at) WHERE status = 'pending';
@@ -0,0 +1,3 @@
+---- Resumable uploads of the post media (rollback)
+
+DROP TABLE IF EXISTS media_upload;
@@ -0,0 +1,32 @@
+---- Resumable uploads of the post media (tus protocol, see `MediaUploadBmc`)
+
+CREATE TABLE media_upload (
+    id BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+    owner_id BIGINT NOT NULL REFERENCES "user"(id) ON DELETE CASCADE,
+    post_id BIGINT NOT NULL REFERENCES post(id) ON DELETE CASCADE,
+    media_type VARCHAR(16) NOT NULL, -- "image" or "video"
+    mime_type VARCHAR(128) NOT NULL, -- declared, checked on completion
+    sort_order INT NOT NULL DEFAULT 0,
+    alt_text TEXT,
+    -- The object of the file, assembled on completion
+    storage_key TEXT NOT NULL,
+
+    -- The size of the file, and the bytes received so far
+    upload_length BIGINT NOT NULL CHECK (upload_length > 0),
+    upload_offset BIGINT NOT NULL DEFAULT 0,
+    -- The staged chunks, in order: [{"key": "...", "size": 123}, ...]
+    chunks JSONB NOT NULL DEFAULT '[]',
+    expires_at TIMESTAMPTZ NOT NULL,
+    -- The media created on completion
+    post_media_id BIGINT REFERENCES post_media(id) ON DELETE SET NULL,
+
+    -- Audit fields
+    cid BIGINT NOT NULL REFERENCES "user"(id),
+    ctime TIMESTAMPTZ NOT NULL DEFAULT now(),
+    mid BIGINT NOT NULL REFERENCES "user"(id),
+    mtime TIMESTAMPTZ NOT NULL DEFAULT now(),
+
+    CHECK (upload_offset BETWEEN 0 AND upload_length)
+);
+
+CREATE INDEX media_upload_owner_id_idx ON media_upload(owner_id);
@@ -0,0 +1,3 @@
+---- User email lookup, case insensitive (rollback)
+
+DROP INDEX IF EXISTS user_email_lower_idx;
@@ -0,0 +1,3 @@
+---- User email lookup, case insensitive (see `UserBmc::first_by_email`)
+
+CREATE INDEX user_email_lower_idx ON "user"(lower(email));
@@ -0,0 +1,14 @@
+---- Haversine distance, clamped for the (near) antipodal locations
+
+-- The great-circle distance (km) between two locations, same as `Location::distance_km`.
+-- Note: The haversine term is clamped to 1, as the rounding can exceed it for
+--       near antipodal locations (asin "input is out of range").
+CREATE OR REPLACE FUNCTION haversine_km(
+    lat1 DOUBLE PRECISION, lon1 DOUBLE PRECISION,
+    lat2 DOUBLE PRECISION, lon2 DOUBLE PRECISION
+) RETURNS DOUBLE PRECISION AS $$
+    SELECT 2 * 6371.0088 * asin(least(1.0, sqrt(
+        power(sin(radians(lat2 - lat1) / 2), 2)
+        + cos(radians(lat1)) * cos(radians(lat2)) * power(sin(radians(lon2 - lon1) / 2), 2)
+    )))
+$$ LANGUAGE SQL IMMUTABLE STRICT PARALLEL SAFE;
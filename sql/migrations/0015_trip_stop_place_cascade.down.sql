@@ -0,0 +1,5 @@
+---- Trip stops of a deleted place (rollback)
+
+ALTER TABLE trip_stop DROP CONSTRAINT trip_stop_place_id_fkey;
+ALTER TABLE trip_stop ADD CONSTRAINT trip_stop_place_id_fkey
+    FOREIGN KEY (place_id) REFERENCES place(id);
@@ -0,0 +1,6 @@
+---- Trip stops of a deleted place (see `TripStopBmc`)
+
+-- Note: The stops of a deleted place are removed, as its post tags (`post_place`).
+ALTER TABLE trip_stop DROP CONSTRAINT trip_stop_place_id_fkey;
+ALTER TABLE trip_stop ADD CONSTRAINT trip_stop_place_id_fkey
+    FOREIGN KEY (place_id) REFERENCES place(id) ON DELETE CASCADE;
@@ -0,0 +1,4 @@
+---- Sys root user (rollback)
+
+-- Note: The root user is kept, as the rows written with the root ctx reference it,
+--       and it might predate this migration.
@@ -0,0 +1,8 @@
+---- Sys root user (see `Ctx::root_ctx`)
+
+-- Note: The `cid`/`mid` of the rows written with the root ctx reference it.
+--       It might already be there, from the dev seed of the dbs created before.
+INSERT INTO "user"
+    (id, typ, role, username, email, cid, ctime, mid, mtime) VALUES
+    (0, 'Sys', 'Admin', 'root', 'root@system.com', 0, now(), 0, now())
+ON CONFLICT (id) DO NOTHING;